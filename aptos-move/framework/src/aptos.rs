@@ -3,7 +3,7 @@
 
 #![forbid(unsafe_code)]
 
-use crate::docgen::DocgenOptions;
+use crate::docgen::{DocFormat, DocgenOptions};
 use crate::release_builder::RELEASE_BUNDLE_EXTENSION;
 use crate::release_bundle::ReleaseBundle;
 use crate::{path_in_crate, BuildOptions, ReleaseOptions};
@@ -113,6 +113,9 @@ impl ReleaseTarget {
                     collapsed_sections: true,
                     landing_page_template: Some("doc_template/overview.md".to_string()),
                     references_file: Some("doc_template/references.md".to_string()),
+                    module_template: None,
+                    partials_dir: None,
+                    output_format: DocFormat::Markdown,
                 }),
             },
             packages: packages.iter().map(|(path, _)| path.to_owned()).collect(),
@@ -202,6 +202,20 @@ impl BuiltPackage {
             .collect()
     }
 
+    /// Extracts the bytecode for the modules of the dependencies, with their package names.
+    pub fn extract_dependency_code(&self) -> Vec<(String, Vec<u8>)> {
+        self.package
+            .deps_compiled_units
+            .iter()
+            .map(|(name, unit_with_source)| {
+                (
+                    name.as_str().to_string(),
+                    unit_with_source.unit.serialize(None),
+                )
+            })
+            .collect()
+    }
+
     /// Returns the abis for this package, if available.
     pub fn extract_abis(&self) -> Option<Vec<EntryABI>> {
         self.package.compiled_abis.as_ref().map(|abis| {
@@ -211,6 +225,11 @@ impl BuiltPackage {
         })
     }
 
+    /// Returns the compiled package, e.g. for the source maps and sources of its modules.
+    pub fn package(&self) -> &CompiledPackage {
+        &self.package
+    }
+
     /// Returns an iterator for all compiled proper (non-script) modules.
     pub fn modules(&self) -> impl Iterator<Item = &CompiledModule> {
         self.package
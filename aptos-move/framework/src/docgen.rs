@@ -5,9 +5,56 @@ use anyhow::{anyhow, Context};
 use codespan_reporting::diagnostic::Severity;
 use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
 use move_model::model::GlobalEnv;
-use std::path::PathBuf;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Mutex;
 
+/// Format the documentation is written in
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, clap::ArgEnum, serde::Serialize, serde::Deserialize,
+)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+impl Default for DocFormat {
+    fn default() -> Self {
+        DocFormat::Markdown
+    }
+}
+
+impl Display for DocFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocFormat::Markdown => f.write_str("markdown"),
+            DocFormat::Html => f.write_str("html"),
+        }
+    }
+}
+
+impl FromStr for DocFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" => Ok(DocFormat::Markdown),
+            "html" => Ok(DocFormat::Html),
+            _ => Err("unknown variant"),
+        }
+    }
+}
+
+impl DocFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            DocFormat::Markdown => "md",
+            DocFormat::Html => "html",
+        }
+    }
+}
+
 #[derive(Debug, Clone, clap::Parser, serde::Serialize, serde::Deserialize, Default)]
 pub struct DocgenOptions {
     /// Whether to include private declarations and implementations into the generated
@@ -47,6 +94,24 @@ pub struct DocgenOptions {
     /// This can contain common markdown references fpr this package (e.g. `[move-book]: <url>`).
     #[clap(long)]
     pub references_file: Option<String>,
+
+    /// Package-relative path to a template for the page of each module. `{{title}}` is
+    /// replaced by the name of the module and `{{content}}` by its documentation, which is
+    /// HTML if the output format is HTML.
+    #[clap(long)]
+    pub module_template: Option<String>,
+
+    /// Package-relative path to a directory of partials. The landing page and module templates
+    /// include the partial `NAME` with `{{> NAME}}`, which is the file `NAME.md`, or
+    /// `NAME.html` if the output format is HTML.
+    #[clap(long)]
+    pub partials_dir: Option<String>,
+
+    /// Format of the documentation, `markdown` or `html`. HTML pages link to each other rather
+    /// than to markdown files, links into the documentation of dependencies are kept as is.
+    #[clap(long, default_value_t = DocFormat::Markdown)]
+    #[serde(default)]
+    pub output_format: DocFormat,
 }
 
 impl DocgenOptions {
@@ -65,6 +130,25 @@ impl DocgenOptions {
         let current_dir = std::env::current_dir()?.canonicalize()?;
         std::env::set_current_dir(&package_path)?;
         let output_directory = PathBuf::from("doc");
+        // Partials are expanded into a copy of the landing page template, with the same file
+        // name so the page keeps its name
+        let template_dir = tempfile::tempdir()?;
+        let landing_page_template = match &self.landing_page_template {
+            Some(template) => {
+                let expanded = template_dir.path().join(
+                    Path::new(template)
+                        .file_name()
+                        .ok_or_else(|| anyhow!("invalid landing page template `{}`", template))?,
+                );
+                std::fs::write(&expanded, self.expand_partials(&read(template)?)?)?;
+                Some(expanded)
+            }
+            None => None,
+        };
+        let module_template = match &self.module_template {
+            Some(template) => Some(self.expand_partials(&read(template)?)?),
+            None => None,
+        };
         let doc_path = doc_path
             .into_iter()
             .filter_map(|s| {
@@ -84,10 +168,9 @@ impl DocgenOptions {
             collapsed_sections: self.collapsed_sections,
             output_directory: output_directory.display().to_string(),
             doc_path,
-            root_doc_templates: self
-                .landing_page_template
+            root_doc_templates: landing_page_template
                 .as_ref()
-                .map(|s| vec![s.clone()])
+                .map(|s| vec![s.display().to_string()])
                 .unwrap_or_else(Vec::new),
             references_file: self.references_file.clone(),
             include_dep_diagrams: self.include_dep_diagram,
@@ -104,8 +187,33 @@ impl DocgenOptions {
         } else {
             // Write the generated output files
             std::fs::create_dir_all(&output_directory)?;
+            let landing_page = landing_page_template
+                .as_ref()
+                .and_then(|template| template.file_name())
+                .map(|name| output_directory.join(name));
             for (file_name, content) in output {
                 let dest = PathBuf::from(file_name);
+                let mut content = match self.output_format {
+                    DocFormat::Markdown => content,
+                    DocFormat::Html => markdown_to_html(&content),
+                };
+                let title = dest
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let is_landing_page = landing_page.as_ref() == Some(&dest);
+                if let (Some(template), false) = (&module_template, is_landing_page) {
+                    content = template
+                        .replace("{{title}}", &title)
+                        .replace("{{content}}", &content);
+                } else if self.output_format == DocFormat::Html {
+                    content = format!(
+                        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                         <title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+                        title, content
+                    );
+                }
+                let dest = dest.with_extension(self.output_format.extension());
                 std::fs::write(dest.as_path(), content)
                     .with_context(|| format!("writing `{}`", dest.display()))?;
             }
@@ -114,4 +222,69 @@ impl DocgenOptions {
         std::env::set_current_dir(current_dir)?;
         res
     }
+
+    /// Replaces `{{> NAME}}` in a template with the partial `NAME`
+    fn expand_partials(&self, template: &str) -> anyhow::Result<String> {
+        let mut out = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{>") {
+            let end = rest[start..]
+                .find("}}")
+                .map(|end| start + end)
+                .ok_or_else(|| anyhow!("unterminated partial `{}`", &rest[start..]))?;
+            let name = rest[start + 3..end].trim();
+            let partials_dir = self
+                .partials_dir
+                .as_ref()
+                .ok_or_else(|| anyhow!("partial `{}` used without `--partials-dir`", name))?;
+            out.push_str(&rest[..start]);
+            out.push_str(&read(
+                &Path::new(partials_dir)
+                    .join(format!("{}.{}", name, self.output_format.extension()))
+                    .display()
+                    .to_string(),
+            )?);
+            rest = &rest[end + 2..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+fn read(path: &str) -> anyhow::Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("reading `{}`", path))
+}
+
+/// Renders a page as HTML, with links between the pages of the package pointing at their HTML
+fn markdown_to_html(markdown: &str) -> String {
+    let mut options = pulldown_cmark::Options::empty();
+    options.insert(pulldown_cmark::Options::ENABLE_TABLES);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(
+        &mut html,
+        pulldown_cmark::Parser::new_ext(markdown, options),
+    );
+
+    // Pages of the package are in the same directory, pages of dependencies aren't
+    let mut out = String::new();
+    let mut rest = html.as_str();
+    while let Some(start) = rest.find("href=\"") {
+        let start = start + "href=\"".len();
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find('"').unwrap_or(rest.len());
+        let link = &rest[..end];
+        let (page, anchor) = link.split_at(link.find('#').unwrap_or(link.len()));
+        match page.strip_suffix(".md") {
+            Some(name) if !name.contains('/') && !name.contains(':') => {
+                out.push_str(name);
+                out.push_str(".html");
+                out.push_str(anchor);
+            }
+            _ => out.push_str(link),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
 }
@@ -2,13 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::build_model;
+use anyhow::anyhow;
 use codespan_reporting::diagnostic::Severity;
-use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
+use codespan_reporting::term::termcolor::{Buffer, ColorChoice, StandardStream, WriteColor};
 use log::LevelFilter;
 use move_core_types::account_address::AccountAddress;
-use std::collections::BTreeMap;
-use std::path::Path;
-use std::time::Instant;
+use move_model::model::{GlobalEnv, ModuleEnv};
+use move_stackless_bytecode::options::VerificationScope;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tempfile::TempDir;
 
 #[derive(Debug, Clone, clap::Parser, serde::Serialize, serde::Deserialize)]
@@ -18,10 +25,27 @@ pub struct ProverOptions {
     pub verbosity: Option<LevelFilter>,
 
     /// Filters targets out from the package. Any module with a matching file name will
-    /// be a target, similar as with `cargo test`.
+    /// be a target, similar as with `cargo test`. With `<module>::<function>`, only the
+    /// function is verified.
     #[clap(long, short)]
     pub filter: Option<String>,
 
+    /// Verifies each module of the package separately, running this many at a time, and
+    /// prints a summary of which modules were verified, failed, or timed out.
+    #[clap(long, short)]
+    pub jobs: Option<usize>,
+
+    /// A hard timeout, in seconds, for verifying a target: the module with `--jobs`, or else
+    /// the package. Zero for no timeout.
+    #[clap(long, default_value_t = 0)]
+    pub timeout: u64,
+
+    /// Verify all targets, even those which were verified before and haven't changed since.
+    /// Results are cached in the package's build directory, keyed on the sources and specs of
+    /// a target and everything it depends on.
+    #[clap(long)]
+    pub no_cache: bool,
+
     /// Whether to display additional information in error reports. This may help
     /// debugging but also can make verification slower.
     #[clap(long, short)]
@@ -78,6 +102,9 @@ impl Default for ProverOptions {
         Self {
             verbosity: None,
             filter: None,
+            jobs: None,
+            timeout: 0,
+            no_cache: false,
             trace: false,
             cvc5: false,
             stratification_depth: 6,
@@ -102,12 +129,215 @@ impl ProverOptions {
         named_addresses: BTreeMap<String, AccountAddress>,
     ) -> anyhow::Result<()> {
         let now = Instant::now();
+        self.setup_logging();
+        let model = build_model(package_path, named_addresses.clone(), self.module_filter())?;
+        // Tests verify the framework's sources, which shouldn't get a cache
+        let mut cache = if self.no_cache || self.for_test {
+            None
+        } else {
+            Some(ProverCache::load(package_path))
+        };
+
+        let jobs = match self.jobs {
+            Some(jobs) => jobs,
+            None => {
+                let targets: Vec<_> = model
+                    .get_modules()
+                    .filter(|module| module.is_target())
+                    .collect();
+                let digest = self.digest(&model, &targets, "package")?;
+                if let Some(cache) = &cache {
+                    if cache.is_verified("package", &digest) {
+                        eprintln!("Nothing changed since the package was last verified");
+                        return Ok(());
+                    }
+                }
+                let mut writer = StandardStream::stderr(ColorChoice::Auto);
+                self.prove_target(&model, None, &mut writer, Some(now))?;
+                if let Some(cache) = &mut cache {
+                    cache.verified("package", digest);
+                    cache.save(package_path)?;
+                }
+                return Ok(());
+            }
+        };
+
+        // A function is verified with the digest of its module
+        let targets = match &self.filter {
+            Some(filter) if filter.contains("::") => {
+                let module = filter.rsplit_once("::").map(|(module, _)| module);
+                let modules: Vec<_> = model
+                    .get_modules()
+                    .filter(|env| {
+                        let name = env.get_name().display(model.symbol_pool()).to_string();
+                        env.is_target()
+                            && (module == Some(name.as_str())
+                                || module == Some(env.get_full_name_str().as_str()))
+                    })
+                    .collect();
+                vec![(filter.clone(), self.digest(&model, &modules, filter)?)]
+            }
+            _ => model
+                .get_modules()
+                .filter(|module| module.is_target())
+                .map(|module| {
+                    let name = module.get_full_name_str();
+                    let digest = self.digest(&model, &[module], &name)?;
+                    Ok((name, digest))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        };
+        drop(model);
+
+        // The model isn't thread safe, so each job builds its own
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs.max(1))
+            .build()?;
+        let results: Vec<TargetResult> = pool.install(|| {
+            targets
+                .par_iter()
+                .map(|(target, digest)| {
+                    let start = Instant::now();
+                    if let Some(cache) = &cache {
+                        if cache.is_verified(target, digest) {
+                            return TargetResult {
+                                target: target.clone(),
+                                status: TargetStatus::Cached,
+                                duration: start.elapsed(),
+                                output: String::new(),
+                            };
+                        }
+                    }
+                    let mut writer = Buffer::no_color();
+                    let result =
+                        build_model(package_path, named_addresses.clone(), self.module_filter())
+                            .and_then(|model| {
+                                self.prove_target(&model, Some(target.clone()), &mut writer, None)
+                            });
+                    let output = String::from_utf8_lossy(writer.as_slice()).to_string();
+                    let status = match result {
+                        Ok(()) => TargetStatus::Verified,
+                        Err(err)
+                            if output.contains("timeout")
+                                || err.to_string().contains("timeout") =>
+                        {
+                            TargetStatus::TimedOut
+                        }
+                        Err(_) => TargetStatus::Failed,
+                    };
+                    TargetResult {
+                        target: target.clone(),
+                        status,
+                        duration: start.elapsed(),
+                        output,
+                    }
+                })
+                .collect()
+        });
+
+        if let Some(cache) = &mut cache {
+            for ((_, digest), result) in targets.into_iter().zip(&results) {
+                if result.status == TargetStatus::Verified {
+                    cache.verified(&result.target, digest);
+                }
+            }
+            cache.save(package_path)?;
+        }
+
+        let mut writer = StandardStream::stderr(ColorChoice::Auto);
+        for result in &results {
+            writer.write_all(result.output.as_bytes())?;
+        }
+        write_summary(&mut writer, &results)?;
+        let failed = results
+            .iter()
+            .filter(|result| !result.status.is_success())
+            .count();
+        if failed > 0 {
+            return Err(anyhow!(
+                "{} of {} targets not verified",
+                failed,
+                results.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Digest of the sources, including specs, of the modules and everything they depend on,
+    /// together with the options affecting verification
+    fn digest(
+        &self,
+        model: &GlobalEnv,
+        modules: &[ModuleEnv],
+        scope: &str,
+    ) -> anyhow::Result<String> {
+        let mut files = BTreeSet::new();
+        let mut visited = BTreeSet::new();
+        let mut pending: Vec<_> = modules.iter().map(|module| module.get_id()).collect();
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let module = model.get_module(id);
+            let source = PathBuf::from(module.get_source_path());
+            // Specs may also be in a separate `<module>.spec.move` next to the module
+            let spec = source.with_extension("spec.move");
+            if spec.exists() {
+                files.insert(spec);
+            }
+            files.insert(source);
+            pending.extend(module.get_used_modules(true));
+        }
+
+        let options = ProverOptions {
+            verbosity: None,
+            jobs: None,
+            no_cache: false,
+            ..self.clone()
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update(serde_json::to_vec(&options)?);
+        hasher.update(scope);
+        for file in files {
+            hasher.update(file.display().to_string());
+            hasher.update(std::fs::read(&file)?);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// The part of `--filter` which selects modules
+    fn module_filter(&self) -> Option<String> {
+        self.filter
+            .as_ref()
+            .map(|filter| match filter.split_once("::") {
+                Some((module, _)) => module.to_string(),
+                None => filter.clone(),
+            })
+    }
+
+    /// Verifies the target module, or the whole package if there's none
+    fn prove_target<W: WriteColor>(
+        &self,
+        model: &move_model::model::GlobalEnv,
+        target: Option<String>,
+        writer: &mut W,
+        timer: Option<Instant>,
+    ) -> anyhow::Result<()> {
         let for_test = self.for_test;
-        let model = build_model(package_path, named_addresses, self.filter.clone())?;
-        let mut options = self.convert_options();
+        let mut options = self.clone().convert_options();
+        match (&self.filter, target) {
+            (Some(filter), _) if filter.contains("::") => {
+                options.prover.verify_scope = VerificationScope::Only(filter.clone())
+            }
+            (_, Some(target)) => {
+                options.prover.verify_scope = VerificationScope::OnlyModule(target)
+            }
+            _ => {}
+        }
         // Need to ensure a distinct output.bpl file for concurrent execution. In non-test
         // mode, we actually want to use the static output.bpl for debugging purposes
-        let _temp_holder = if for_test {
+        let _temp_holder = if for_test || self.jobs.is_some() {
             let temp_dir = TempDir::new()?;
             std::fs::create_dir_all(temp_dir.path())?;
             options.output_path = temp_dir
@@ -123,8 +353,7 @@ impl ProverOptions {
                 .to_string();
             None
         };
-        let mut writer = StandardStream::stderr(ColorChoice::Auto);
-        move_prover::run_move_prover_with_model(&model, &mut writer, options, Some(now))?;
+        move_prover::run_move_prover_with_model(model, writer, options, timer)?;
         Ok(())
     }
 
@@ -169,16 +398,21 @@ impl ProverOptions {
                 },
                 custom_natives: None,
                 loop_unroll: self.loop_unroll,
+                hard_timeout_secs: self.timeout,
                 ..Default::default()
             },
             ..Default::default()
         };
+        opts
+    }
+
+    fn setup_logging(&self) {
+        let opts = self.clone().convert_options();
         if self.for_test {
             opts.setup_logging_for_test();
         } else {
             opts.setup_logging()
         }
-        opts
     }
 
     pub fn default_for_test() -> Self {
@@ -188,3 +422,113 @@ impl ProverOptions {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TargetStatus {
+    Verified,
+    /// Verified before, and unchanged since
+    Cached,
+    Failed,
+    TimedOut,
+}
+
+impl TargetStatus {
+    fn is_success(self) -> bool {
+        matches!(self, TargetStatus::Verified | TargetStatus::Cached)
+    }
+}
+
+impl Display for TargetStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetStatus::Verified => f.write_str("verified"),
+            TargetStatus::Cached => f.write_str("cached"),
+            TargetStatus::Failed => f.write_str("failed"),
+            TargetStatus::TimedOut => f.write_str("timed out"),
+        }
+    }
+}
+
+struct TargetResult {
+    target: String,
+    status: TargetStatus,
+    duration: Duration,
+    output: String,
+}
+
+fn write_summary<W: Write>(writer: &mut W, results: &[TargetResult]) -> std::io::Result<()> {
+    let width = results
+        .iter()
+        .map(|result| result.target.len())
+        .chain(std::iter::once("target".len()))
+        .max()
+        .unwrap_or_default();
+    writeln!(
+        writer,
+        "\n{:<width$}  {:<9}  time",
+        "target",
+        "status",
+        width = width
+    )?;
+    for result in results {
+        writeln!(
+            writer,
+            "{:<width$}  {:<9}  {:.1}s",
+            result.target,
+            result.status.to_string(),
+            result.duration.as_secs_f64(),
+            width = width
+        )?;
+    }
+    let count = |status| {
+        results
+            .iter()
+            .filter(|result| result.status == status)
+            .count()
+    };
+    writeln!(
+        writer,
+        "\n{} verified, {} cached, {} failed, {} timed out",
+        count(TargetStatus::Verified),
+        count(TargetStatus::Cached),
+        count(TargetStatus::Failed),
+        count(TargetStatus::TimedOut)
+    )
+}
+
+/// Digests of the targets last verified, see [`ProverOptions::digest`]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct ProverCache {
+    verified: BTreeMap<String, String>,
+}
+
+impl ProverCache {
+    fn path(package_path: &Path) -> PathBuf {
+        package_path.join("build").join("prover_cache.json")
+    }
+
+    /// Loads the cache, which is empty if it doesn't exist or can't be read
+    fn load(package_path: &Path) -> Self {
+        std::fs::read(Self::path(package_path))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, package_path: &Path) -> anyhow::Result<()> {
+        let path = Self::path(package_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn is_verified(&self, target: &str, digest: &str) -> bool {
+        self.verified.get(target).map(String::as_str) == Some(digest)
+    }
+
+    fn verified(&mut self, target: &str, digest: String) {
+        self.verified.insert(target.to_string(), digest);
+    }
+}
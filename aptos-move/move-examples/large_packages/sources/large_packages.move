@@ -0,0 +1,80 @@
+/// Publishes packages too large for a single transaction, by staging them over several.
+///
+/// Each call of `stage_code` appends a chunk of the package's serialized metadata, and chunks of
+/// its modules by their index, to a staging area under the sender.  The call with `publish` set
+/// then publishes what's staged and removes the staging area, while `cleanup` removes it
+/// without publishing.  `aptos move publish --chunked-publish` drives this.
+module large_packages::large_packages {
+    use std::error;
+    use std::signer;
+    use std::vector;
+    use aptos_framework::code;
+
+    struct StagingArea has key {
+        metadata_serialized: vector<u8>,
+        code: vector<vector<u8>>,
+    }
+
+    /// The code indices and chunks differ in length
+    const ECODE_MISMATCH: u64 = 1;
+
+    public entry fun stage_code(
+        owner: &signer,
+        metadata_chunk: vector<u8>,
+        code_indices: vector<u16>,
+        code_chunks: vector<vector<u8>>,
+        publish: bool,
+    ) acquires StagingArea {
+        assert!(
+            vector::length(&code_indices) == vector::length(&code_chunks),
+            error::invalid_argument(ECODE_MISMATCH),
+        );
+        let owner_address = signer::address_of(owner);
+        if (!exists<StagingArea>(owner_address)) {
+            move_to(owner, StagingArea {
+                metadata_serialized: vector::empty(),
+                code: vector::empty(),
+            });
+        };
+
+        let staging_area = borrow_global_mut<StagingArea>(owner_address);
+        vector::append(&mut staging_area.metadata_serialized, metadata_chunk);
+        let i = 0;
+        while (i < vector::length(&code_chunks)) {
+            let index = (*vector::borrow(&code_indices, i) as u64);
+            while (vector::length(&staging_area.code) <= index) {
+                vector::push_back(&mut staging_area.code, vector::empty());
+            };
+            vector::append(vector::borrow_mut(&mut staging_area.code, index), *vector::borrow(&code_chunks, i));
+            i = i + 1;
+        };
+
+        if (publish) {
+            let StagingArea { metadata_serialized, code } = move_from<StagingArea>(owner_address);
+            code::publish_package_txn(owner, metadata_serialized, code);
+        }
+    }
+
+    public entry fun cleanup(owner: &signer) acquires StagingArea {
+        let StagingArea { metadata_serialized: _, code: _ } = move_from<StagingArea>(signer::address_of(owner));
+    }
+
+    #[test(owner = @0xcafe)]
+    fun test_stage_and_cleanup(owner: signer) acquires StagingArea {
+        stage_code(&owner, b"ab", vector[1], vector[b"cd"], false);
+        stage_code(&owner, b"c", vector[0, 1], vector[b"x", b"e"], false);
+
+        let staging_area = borrow_global<StagingArea>(@0xcafe);
+        assert!(staging_area.metadata_serialized == b"abc", 0);
+        assert!(staging_area.code == vector[b"x", b"cde"], 1);
+
+        cleanup(&owner);
+        assert!(!exists<StagingArea>(@0xcafe), 2);
+    }
+
+    #[test(owner = @0xcafe)]
+    #[expected_failure(abort_code = 0x10001)]
+    fun test_code_mismatch(owner: signer) acquires StagingArea {
+        stage_code(&owner, vector::empty(), vector[0], vector::empty(), false);
+    }
+}
@@ -72,6 +72,15 @@ fn test_hello_blockchain() {
     run_tests_for_pkg("hello_blockchain", named_address);
 }
 
+#[test]
+fn test_large_packages() {
+    let named_address = BTreeMap::from([(
+        String::from("large_packages"),
+        AccountAddress::from_hex_literal("0x1").unwrap(),
+    )]);
+    run_tests_for_pkg("large_packages", named_address);
+}
+
 #[test]
 fn test_message_board() {
     let named_address = BTreeMap::from([(
@@ -9,7 +9,7 @@ use aptos_crypto::{
 use aptos_types::{account_address::AccountAddress, transaction::authenticator::AuthenticationKey};
 use rand::{
     rngs::{OsRng, StdRng},
-    Rng, SeedableRng,
+    Rng, RngCore, SeedableRng,
 };
 
 /// Ed25519 key generator.
@@ -30,6 +30,11 @@ impl KeyGen {
         Self::from_seed(seed)
     }
 
+    /// Fill `bytes` with random bytes, e.g. entropy for a mnemonic.
+    pub fn generate_bytes(&mut self, bytes: &mut [u8]) {
+        self.0.fill_bytes(bytes)
+    }
+
     /// Generate an Ed25519 private key.
     pub fn generate_ed25519_private_key(&mut self) -> Ed25519PrivateKey {
         Ed25519PrivateKey::generate(&mut self.0)
@@ -0,0 +1,240 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::{
+        list::account_or_profile,
+        resources::{matches_filter, Abis, DecodedValue, ResourceView},
+    },
+    common::types::{CliCommand, CliError, CliTypedResult, ProfileOptions, RestOptions},
+};
+use aptos_rest_client::{
+    aptos_api_types::{MoveStructTag, MoveType},
+    Client,
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Show how the resources of an account changed between two versions
+///
+/// Resources are compared by type, and are either added, removed, or changed.  For a changed
+/// resource, each field that differs is shown by its path, e.g. `coin.value`, with its values
+/// before and after.  With `--decode`, added and removed resources are decoded as for
+/// `aptos account resources --decode`.
+#[derive(Debug, Parser)]
+pub struct DiffAccount {
+    /// Address of the account to compare the resources of
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) account: Option<AccountAddress>,
+
+    /// Version to compare from
+    #[clap(long)]
+    pub(crate) from_version: u64,
+
+    /// Version to compare to
+    ///
+    /// Defaults to the latest version
+    #[clap(long)]
+    pub(crate) to_version: Option<u64>,
+
+    /// Only compare resources of this type, e.g. `0x1::coin::CoinStore`
+    ///
+    /// Without type arguments, resources of any instantiation of the struct are compared
+    #[clap(long)]
+    pub(crate) resource: Option<MoveStructTag>,
+
+    /// Decode added and removed resources with the ABIs of their modules
+    #[clap(long)]
+    pub(crate) decode: bool,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[derive(Serialize)]
+pub struct AccountDiff {
+    pub account: AccountAddress,
+    pub from_version: u64,
+    pub to_version: u64,
+    pub added: Vec<ResourceView>,
+    pub removed: Vec<ResourceView>,
+    pub changed: Vec<ResourceChange>,
+}
+
+#[derive(Serialize)]
+pub struct ResourceChange {
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/// A field that differs, with no value on the side it's missing from
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<serde_json::Value>,
+}
+
+#[async_trait]
+impl CliCommand<AccountDiff> for DiffAccount {
+    fn command_name(&self) -> &'static str {
+        "DiffAccount"
+    }
+
+    async fn execute(self) -> CliTypedResult<AccountDiff> {
+        let account = account_or_profile(self.account, &self.profile_options)?;
+        let client = self.rest_options.client(&self.profile_options)?;
+        let to_version = match self.to_version {
+            Some(version) => version,
+            None => {
+                client
+                    .get_ledger_information()
+                    .await
+                    .map_err(|err| CliError::ApiError(err.to_string()))?
+                    .into_inner()
+                    .version
+            }
+        };
+        let mut before = self
+            .resources_at(&client, account, self.from_version)
+            .await?;
+        let after = self.resources_at(&client, account, to_version).await?;
+
+        let mut added = vec![];
+        let mut changed = vec![];
+        for (typ, (tag, data)) in after {
+            match before.remove(&typ) {
+                None => added.push((tag, data)),
+                Some((_, old)) => {
+                    let mut fields = vec![];
+                    diff_values("", &old, &data, &mut fields);
+                    if !fields.is_empty() {
+                        changed.push(ResourceChange {
+                            resource_type: typ,
+                            fields,
+                        });
+                    }
+                }
+            }
+        }
+        let removed: Vec<_> = before.into_values().collect();
+
+        let mut abis = Abis::default();
+        if self.decode {
+            let types = added
+                .iter()
+                .chain(removed.iter())
+                .map(|(tag, _)| tag.clone())
+                .collect();
+            abis.load(&client, types).await?;
+        }
+        let view = |(tag, data): (MoveStructTag, serde_json::Value)| ResourceView {
+            resource_type: tag.to_string(),
+            data: if self.decode {
+                abis.decode(&MoveType::Struct(tag), &data)
+            } else {
+                DecodedValue::Value(data)
+            },
+        };
+        Ok(AccountDiff {
+            account,
+            from_version: self.from_version,
+            to_version,
+            added: added.into_iter().map(view).collect(),
+            removed: removed.into_iter().map(view).collect(),
+            changed,
+        })
+    }
+}
+
+impl DiffAccount {
+    /// The account's resources at the version, by type, that pass the filter
+    async fn resources_at(
+        &self,
+        client: &Client,
+        account: AccountAddress,
+        version: u64,
+    ) -> CliTypedResult<BTreeMap<String, (MoveStructTag, serde_json::Value)>> {
+        Ok(client
+            .get_account_resources_at_version(account, version)
+            .await?
+            .into_inner()
+            .into_iter()
+            .map(|resource| (MoveStructTag::from(resource.resource_type), resource.data))
+            .filter(|(tag, _)| match &self.resource {
+                Some(filter) => matches_filter(tag, filter),
+                None => true,
+            })
+            .map(|(tag, data)| (tag.to_string(), (tag, data)))
+            .collect())
+    }
+}
+
+/// Collects the fields that differ between two values, objects by key and arrays by index
+pub(crate) fn diff_values(
+    path: &str,
+    from: &serde_json::Value,
+    to: &serde_json::Value,
+    changes: &mut Vec<FieldChange>,
+) {
+    use serde_json::Value;
+    let field = |name: &str| {
+        if path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", path, name)
+        }
+    };
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            for (name, from_value) in from {
+                match to.get(name) {
+                    Some(to_value) => diff_values(&field(name), from_value, to_value, changes),
+                    None => changes.push(FieldChange {
+                        path: field(name),
+                        from: Some(from_value.clone()),
+                        to: None,
+                    }),
+                }
+            }
+            for (name, to_value) in to {
+                if !from.contains_key(name) {
+                    changes.push(FieldChange {
+                        path: field(name),
+                        from: None,
+                        to: Some(to_value.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(from), Value::Array(to)) => {
+            for index in 0..from.len().max(to.len()) {
+                let path = format!("{}[{}]", path, index);
+                match (from.get(index), to.get(index)) {
+                    (Some(from_value), Some(to_value)) => {
+                        diff_values(&path, from_value, to_value, changes)
+                    }
+                    (from_value, to_value) => changes.push(FieldChange {
+                        path,
+                        from: from_value.cloned(),
+                        to: to_value.cloned(),
+                    }),
+                }
+            }
+        }
+        (from, to) if from != to => changes.push(FieldChange {
+            path: path.to_string(),
+            from: Some(from.clone()),
+            to: Some(to.clone()),
+        }),
+        _ => {}
+    }
+}
@@ -50,6 +50,9 @@ impl CliCommand<String> for FundWithFaucet {
     async fn execute(self) -> CliTypedResult<String> {
         let hashes = fund_account(
             self.faucet_options.faucet_url(&self.profile_options)?,
+            self.faucet_options
+                .faucet_auth_token(&self.profile_options)?
+                .as_deref(),
             self.amount,
             self.account,
         )
@@ -0,0 +1,259 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::{
+        create::DEFAULT_FUNDED_COINS,
+        transfer_batch::{submit_transfers, Transfer},
+    },
+    common::{
+        types::{
+            account_address_from_public_key, CliCommand, CliError, CliTypedResult, FaucetOptions,
+            RngArgs, TransactionOptions,
+        },
+        utils::{fund_account_with_retries, write_to_user_only_file},
+    },
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    PrivateKey,
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use cached_packages::aptos_stdlib;
+use clap::Parser;
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// Create and fund many accounts, e.g. to set up a test
+///
+/// With `--count`, keys for new accounts are generated.  With `--accounts`, the given accounts
+/// are funded instead.  Accounts are funded from the faucet, or with `--from-profile`, by
+/// transfers from the profile's account, which also works on networks without a faucet.
+///
+/// The accounts, with the keys of new ones, are output as JSON.  With `--output-file`, they're
+/// also written to that file, readable only by the user, before any account is funded.
+#[derive(Debug, Parser)]
+pub struct FundMany {
+    /// Number of new accounts to create
+    #[clap(
+        long,
+        required_unless_present = "accounts",
+        conflicts_with = "accounts"
+    )]
+    pub(crate) count: Option<usize>,
+
+    /// Existing accounts to fund
+    #[clap(
+        long,
+        multiple_values = true,
+        parse(try_from_str = crate::common::types::load_account_arg)
+    )]
+    pub(crate) accounts: Vec<AccountAddress>,
+
+    /// Number of Octas to fund each account with, e.g. `100000000` or `1e8`
+    #[clap(long, default_value_t = DEFAULT_FUNDED_COINS, parse(try_from_str = parse_amount))]
+    pub(crate) amount: u64,
+
+    /// Fund the accounts by transfers from the profile's account, rather than from the faucet
+    #[clap(long)]
+    pub(crate) from_profile: bool,
+
+    /// Maximum number of accounts being funded at once
+    #[clap(long, visible_alias = "parallel", default_value_t = 8)]
+    pub(crate) max_concurrency: usize,
+
+    /// JSON file to write the accounts and keys to
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) rng_args: RngArgs,
+    #[clap(flatten)]
+    pub(crate) faucet_options: FaucetOptions,
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+}
+
+/// An account funded, with its keys if it was created
+#[derive(Debug, Serialize)]
+pub struct FundedAccount {
+    pub address: AccountAddress,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<Ed25519PrivateKey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<Ed25519PublicKey>,
+    pub amount: u64,
+}
+
+#[async_trait]
+impl CliCommand<Vec<FundedAccount>> for FundMany {
+    fn command_name(&self) -> &'static str {
+        "FundMany"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<FundedAccount>> {
+        if self.max_concurrency == 0 {
+            return Err(CliError::CommandArgumentError(
+                "--max-concurrency must be at least 1".to_string(),
+            ));
+        }
+        let accounts = match self.count {
+            Some(count) => {
+                let mut key_generator = self.rng_args.key_generator()?;
+                (0..count)
+                    .map(|_| {
+                        let private_key = key_generator.generate_ed25519_private_key();
+                        let public_key = private_key.public_key();
+                        FundedAccount {
+                            address: account_address_from_public_key(&public_key),
+                            private_key: Some(private_key),
+                            public_key: Some(public_key),
+                            amount: self.amount,
+                        }
+                    })
+                    .collect()
+            }
+            None => self
+                .accounts
+                .iter()
+                .map(|address| FundedAccount {
+                    address: *address,
+                    private_key: None,
+                    public_key: None,
+                    amount: self.amount,
+                })
+                .collect::<Vec<_>>(),
+        };
+
+        // Written first, so that the keys aren't lost if funding fails partway
+        if let Some(output_file) = &self.output_file {
+            let json = serde_json::to_vec_pretty(&accounts).map_err(|err| {
+                CliError::UnexpectedError(format!("Failed to serialize accounts: {}", err))
+            })?;
+            write_to_user_only_file(output_file, &output_file.display().to_string(), &json)?;
+        }
+
+        let addresses: Vec<_> = accounts.iter().map(|account| account.address).collect();
+        if self.from_profile {
+            let transfers = addresses
+                .iter()
+                .enumerate()
+                .map(|(index, address)| {
+                    Transfer::new(
+                        vec![index],
+                        address.to_hex_literal(),
+                        aptos_stdlib::aptos_account_transfer(*address, self.amount),
+                    )
+                })
+                .collect();
+            submit_transfers(
+                &self.txn_options,
+                transfers,
+                self.amount.saturating_mul(addresses.len() as u64),
+                self.max_concurrency,
+                |_, _| Ok(()),
+            )
+            .await?;
+        } else {
+            self.fund_from_faucet(&addresses).await?;
+        }
+        Ok(accounts)
+    }
+}
+
+impl FundMany {
+    /// Funds the accounts from the faucet, waiting for every funding transaction
+    async fn fund_from_faucet(&self, addresses: &[AccountAddress]) -> CliTypedResult<()> {
+        let profile_options = &self.txn_options.profile_options;
+        let faucet_url = self.faucet_options.faucet_url(profile_options)?;
+        let faucet_auth_token = self.faucet_options.faucet_auth_token(profile_options)?;
+        let client = self.txn_options.rest_options.client(profile_options)?;
+
+        let results: Vec<_> = stream::iter(addresses.iter().map(|address| {
+            let (faucet_url, faucet_auth_token, client) =
+                (faucet_url.clone(), faucet_auth_token.as_deref(), &client);
+            async move {
+                let hashes =
+                    fund_account_with_retries(faucet_url, faucet_auth_token, self.amount, *address)
+                        .await?;
+                let expiration = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_err(|e| CliError::UnexpectedError(e.to_string()))?
+                    .as_secs()
+                    + 30;
+                for hash in hashes {
+                    client
+                        .wait_for_transaction_by_hash(
+                            hash.into(),
+                            expiration,
+                            Some(Duration::from_secs(60)),
+                            None,
+                        )
+                        .await?;
+                }
+                Ok::<_, CliError>(())
+            }
+        }))
+        .buffered(self.max_concurrency)
+        .collect()
+        .await;
+
+        let failures: Vec<String> = addresses
+            .iter()
+            .zip(results)
+            .filter_map(|(address, result)| {
+                result
+                    .err()
+                    .map(|err| format!("{}: {}", address.to_hex_literal(), err))
+            })
+            .collect();
+        if !failures.is_empty() {
+            return Err(CliError::ApiError(format!(
+                "Funding {} of {} accounts failed:\n{}",
+                failures.len(),
+                addresses.len(),
+                failures.join("\n")
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Parses an amount, either an integer or in scientific notation like `1e8` or `2.5e6`
+pub(crate) fn parse_amount(str: &str) -> CliTypedResult<u64> {
+    let invalid = || {
+        CliError::CommandArgumentError(format!(
+            "Invalid amount {}, expected a whole number like 100000000 or 1e8",
+            str
+        ))
+    };
+    let (mantissa, exponent) = match str.split_once(|c| c == 'e' || c == 'E') {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<u32>().map_err(|_| invalid())?),
+        None => (str, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let fraction = fraction.trim_end_matches('0');
+    if whole.is_empty() && fraction.is_empty()
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    // The digits after the point are shifted in by the exponent, and must all be
+    let shift = exponent
+        .checked_sub(fraction.len() as u32)
+        .ok_or_else(invalid)?;
+    let digits = format!("{}{}", whole, fraction);
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|digits| 10u64.checked_pow(shift)?.checked_mul(digits))
+        .ok_or_else(invalid)
+}
@@ -3,11 +3,12 @@
 
 use crate::common::utils::prompt_yes;
 use crate::common::{
+    keychain,
     types::{
         CliCommand, CliConfig, CliError, CliTypedResult, ConfigSearchMode, EncodingOptions,
         EncodingType, ExtractPublicKey, ParsePrivateKey, ProfileConfig, ProfileOptions,
         PublicKeyInputOptions, RestOptions, RotationProofChallenge, TransactionOptions,
-        TransactionSummary,
+        TransactionSummary, DEFAULT_PROFILE,
     },
     utils::{prompt_yes_with_override, read_line},
 };
@@ -101,64 +102,13 @@ impl CliCommand<RotateSummary> for RotateKey {
             })?;
 
         let (current_private_key, sender_address) = self.txn_options.get_key_and_address()?;
-
-        // Get sequence number for account
-        let sequence_number = self.txn_options.sequence_number(sender_address).await?;
-        let auth_key = self.txn_options.auth_key(sender_address).await?;
-
-        let rotation_proof = RotationProofChallenge {
-            account_address: CORE_CODE_ADDRESS,
-            module_name: "account".to_string(),
-            struct_name: "RotationProofChallenge".to_string(),
-            sequence_number,
-            originator: sender_address,
-            current_auth_key: AccountAddress::from_bytes(&auth_key)
-                .map_err(|err| CliError::UnableToParse("auth_key", err.to_string()))?,
-            new_public_key: new_private_key.public_key().to_bytes().to_vec(),
-        };
-
-        let rotation_msg =
-            bcs::to_bytes(&rotation_proof).map_err(|err| CliError::BCS("rotation_proof", err))?;
-
-        // Signs the struct using both the current private key and the next private key
-        let rotation_proof_signed_by_current_private_key =
-            current_private_key.sign_arbitrary_message(&rotation_msg.clone());
-        let rotation_proof_signed_by_new_private_key =
-            new_private_key.sign_arbitrary_message(&rotation_msg);
-
-        let txn_summary = self
-            .txn_options
-            .submit_transaction(aptos_stdlib::account_rotate_authentication_key(
-                0,
-                // Existing public key
-                current_private_key.public_key().to_bytes().to_vec(),
-                0,
-                // New public key
-                new_private_key.public_key().to_bytes().to_vec(),
-                rotation_proof_signed_by_current_private_key
-                    .to_bytes()
-                    .to_vec(),
-                rotation_proof_signed_by_new_private_key.to_bytes().to_vec(),
-            ))
-            .await
-            .map(TransactionSummary::from)?;
-
-        let string = serde_json::to_string_pretty(&txn_summary)
-            .map_err(|err| CliError::UnableToParse("transaction summary", err.to_string()))?;
-
-        eprintln!("{}", string);
-
-        if let Some(txn_success) = txn_summary.success {
-            if !txn_success {
-                return Err(CliError::ApiError(
-                    "Transaction was not executed successfully".to_string(),
-                ));
-            }
-        } else {
-            return Err(CliError::UnexpectedError(
-                "Malformed transaction response".to_string(),
-            ));
-        }
+        let txn_summary = submit_rotation(
+            &self.txn_options,
+            &current_private_key,
+            sender_address,
+            &new_private_key,
+        )
+        .await?;
 
         let mut profile_name: String;
 
@@ -215,11 +165,20 @@ impl CliCommand<RotateSummary> for RotateKey {
         }
 
         let mut profile_config = ProfileConfig {
-            private_key: Some(new_private_key.clone()),
             public_key: Some(new_private_key.public_key()),
             account: Some(sender_address),
             ..self.txn_options.profile_options.profile()?
         };
+        // Keep the rotated key in the same kind of store as the old one
+        if profile_config.keychain_entry.is_some() {
+            profile_config.set_keychain_private_key(
+                new_private_key,
+                keychain::new_entry_name(&profile_name),
+            )?;
+        } else {
+            let encrypt = profile_config.encrypted_private_key.is_some();
+            profile_config.set_private_key(new_private_key, encrypt)?;
+        }
 
         if let Some(url) = self.txn_options.rest_options.url {
             profile_config.rest_url = Some(url.into());
@@ -229,12 +188,21 @@ impl CliCommand<RotateSummary> for RotateKey {
             config.profiles = Some(BTreeMap::new());
         }
 
-        config
+        let replaced = config
             .profiles
             .as_mut()
             .unwrap()
             .insert(profile_name.clone(), profile_config);
         config.save()?;
+        // Don't leave the replaced profile's key in the keychain once nothing uses it
+        if let Some(entry) = replaced.and_then(|profile| profile.keychain_entry) {
+            if let Err(err) = keychain::delete_unused_secret(&config, &entry) {
+                eprintln!(
+                    "Warning: unable to delete the old private key from the OS keychain: {}",
+                    err
+                );
+            }
+        }
 
         eprintln!("Profile {} is saved.", profile_name);
 
@@ -245,6 +213,205 @@ impl CliCommand<RotateSummary> for RotateKey {
     }
 }
 
+/// Submits the transaction rotating the sender's authentication key to `new_private_key`
+///
+/// Fails if the transaction isn't executed successfully
+pub(crate) async fn submit_rotation(
+    txn_options: &TransactionOptions,
+    current_private_key: &Ed25519PrivateKey,
+    sender_address: AccountAddress,
+    new_private_key: &Ed25519PrivateKey,
+) -> CliTypedResult<TransactionSummary> {
+    // Get sequence number for account
+    let sequence_number = txn_options.sequence_number(sender_address).await?;
+    let auth_key = txn_options.auth_key(sender_address).await?;
+
+    let rotation_proof = RotationProofChallenge {
+        account_address: CORE_CODE_ADDRESS,
+        module_name: "account".to_string(),
+        struct_name: "RotationProofChallenge".to_string(),
+        sequence_number,
+        originator: sender_address,
+        current_auth_key: AccountAddress::from_bytes(&auth_key)
+            .map_err(|err| CliError::UnableToParse("auth_key", err.to_string()))?,
+        new_public_key: new_private_key.public_key().to_bytes().to_vec(),
+    };
+
+    let rotation_msg =
+        bcs::to_bytes(&rotation_proof).map_err(|err| CliError::BCS("rotation_proof", err))?;
+
+    // Signs the struct using both the current private key and the next private key
+    let rotation_proof_signed_by_current_private_key =
+        current_private_key.sign_arbitrary_message(&rotation_msg.clone());
+    let rotation_proof_signed_by_new_private_key =
+        new_private_key.sign_arbitrary_message(&rotation_msg);
+
+    let txn_summary = txn_options
+        .submit_transaction(aptos_stdlib::account_rotate_authentication_key(
+            0,
+            // Existing public key
+            current_private_key.public_key().to_bytes().to_vec(),
+            0,
+            // New public key
+            new_private_key.public_key().to_bytes().to_vec(),
+            rotation_proof_signed_by_current_private_key
+                .to_bytes()
+                .to_vec(),
+            rotation_proof_signed_by_new_private_key.to_bytes().to_vec(),
+        ))
+        .await
+        .map(TransactionSummary::from)?;
+
+    let string = serde_json::to_string_pretty(&txn_summary)
+        .map_err(|err| CliError::UnableToParse("transaction summary", err.to_string()))?;
+
+    eprintln!("{}", string);
+
+    if let Some(txn_success) = txn_summary.success {
+        if !txn_success {
+            return Err(CliError::ApiError(
+                "Transaction was not executed successfully".to_string(),
+            ));
+        }
+    } else {
+        return Err(CliError::UnexpectedError(
+            "Malformed transaction response".to_string(),
+        ));
+    }
+    Ok(txn_summary)
+}
+
+/// Rotate an account's authentication key and update its profile in place
+///
+/// Unlike `rotate-key`, this requires the account to come from a profile in the current
+/// directory's config, and only rewrites that profile once the rotation is confirmed on
+/// chain: the account's authentication key must match the new key, and the originating
+/// address table used by `lookup-address` is checked to map the new key to the account.
+/// The new key is stored the same way as the old one (plain, encrypted, or in the OS
+/// keychain).
+#[derive(Debug, Parser)]
+pub struct RotateKeyAndUpdateProfile {
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+
+    /// File name that contains the new private key encoded in the type from `--encoding`
+    #[clap(long, group = "new_private_key", parse(from_os_str))]
+    pub(crate) new_private_key_file: Option<PathBuf>,
+
+    /// New private key encoded in the type from `--encoding`
+    #[clap(long, group = "new_private_key")]
+    pub(crate) new_private_key: Option<String>,
+}
+
+impl ParsePrivateKey for RotateKeyAndUpdateProfile {}
+
+#[async_trait]
+impl CliCommand<RotateSummary> for RotateKeyAndUpdateProfile {
+    fn command_name(&self) -> &'static str {
+        "RotateKeyAndUpdateProfile"
+    }
+
+    async fn execute(self) -> CliTypedResult<RotateSummary> {
+        let new_private_key = self
+            .parse_private_key(
+                self.txn_options.encoding_options.encoding,
+                self.new_private_key_file.clone(),
+                self.new_private_key.clone(),
+            )?
+            .ok_or_else(|| {
+                CliError::CommandArgumentError(
+                    "One of ['--new-private-key', '--new-private-key-file'] must be used"
+                        .to_string(),
+                )
+            })?;
+        let new_public_key = new_private_key.public_key();
+
+        // Check everything needed to update the profile before rotating, so a rotation
+        // never happens without the profile being updated
+        let profile_name = self
+            .txn_options
+            .profile_options
+            .profile_name()
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_string();
+        let mut config = CliConfig::load(ConfigSearchMode::CurrentDir)?;
+        let mut profile_config = config
+            .profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(&profile_name))
+            .cloned()
+            .ok_or_else(|| {
+                CliError::CommandArgumentError(format!(
+                    "Profile {} is not in the current directory's config",
+                    profile_name
+                ))
+            })?;
+        let (current_private_key, sender_address) = self.txn_options.get_key_and_address()?;
+        // The keychain entry is only overwritten once the chain has the new key, as the old
+        // key is still the account's until then
+        let keychain_entry = profile_config.keychain_entry.clone();
+        if keychain_entry.is_none() {
+            let encrypt = profile_config.encrypted_private_key.is_some();
+            profile_config.set_private_key(new_private_key.clone(), encrypt)?;
+        }
+        profile_config.public_key = Some(new_public_key.clone());
+        profile_config.account = Some(sender_address);
+
+        let txn_summary = submit_rotation(
+            &self.txn_options,
+            &current_private_key,
+            sender_address,
+            &new_private_key,
+        )
+        .await?;
+
+        // Verify the rotation on chain before touching the profile
+        let client = self
+            .txn_options
+            .rest_options
+            .client(&self.txn_options.profile_options)?;
+        let expected_auth_key = AuthenticationKey::ed25519(&new_public_key);
+        let auth_key = client
+            .get_account(sender_address)
+            .await?
+            .into_inner()
+            .authentication_key;
+        if auth_key != expected_auth_key {
+            return Err(CliError::UnexpectedError(format!(
+                "Account {} has authentication key {} on chain, not {} for the new key.  Profile {} was not updated",
+                sender_address, auth_key, expected_auth_key, profile_name
+            )));
+        }
+        match lookup_originating_address(&client, &new_public_key).await {
+            Ok(address) if address == sender_address => {}
+            Ok(address) => eprintln!(
+                "Warning: the originating address table maps the new key to {}, not {}",
+                address, sender_address
+            ),
+            Err(err) => eprintln!(
+                "Warning: unable to find the new key in the originating address table: {}",
+                err
+            ),
+        }
+
+        if let Some(entry) = keychain_entry {
+            profile_config.set_keychain_private_key(new_private_key, entry)?;
+        }
+        config
+            .profiles
+            .get_or_insert_with(BTreeMap::new)
+            .insert(profile_name.clone(), profile_config);
+        config.save()?;
+
+        let message = format!("Profile {} is updated with the new key.", profile_name);
+        eprintln!("{}", message);
+        Ok(RotateSummary {
+            transaction: txn_summary,
+            message: Some(message),
+        })
+    }
+}
+
 /// Lookup the account address through the on-chain lookup table
 ///
 /// If the account is rotated, it will provide the address accordingly.  If the account was not
@@ -284,42 +451,50 @@ impl CliCommand<AccountAddress> for LookupAddress {
 
     async fn execute(self) -> CliTypedResult<AccountAddress> {
         let rest_client = self.rest_client()?;
+        lookup_originating_address(&rest_client, &self.public_key()?).await
+    }
+}
 
-        let originating_resource: OriginatingResource = rest_client
-            .get_account_resource_bcs(CORE_CODE_ADDRESS, "0x1::account::OriginatingAddress")
-            .await?
-            .into_inner();
-
-        let table_handle = originating_resource.address_map.handle;
-
-        // The derived address that can be used to look up the original address
-        // TODO: This command needs to support multi-ed25519
-        let address_key = AuthenticationKey::ed25519(&self.public_key()?).derived_address();
-        match rest_client
-            .get_table_item_bcs(
-                table_handle,
-                "address",
-                "address",
-                address_key.to_hex_literal(),
-            )
-            .await
-        {
-            Ok(inner) => Ok(inner.into_inner()),
-            Err(RestError::Api(AptosErrorResponse {
-                error:
-                    AptosError {
-                        error_code: AptosErrorCode::TableItemNotFound,
-                        ..
-                    },
-                ..
-            })) => {
-                // If the table item wasn't found, let's at least check if the account exists
-                // It won't be in the table if it wasn't rotated, then return the derived account address
-                rest_client.get_account_bcs(address_key).await?;
-                Ok(address_key)
-            }
-            Err(err) => Err(err)?,
+/// Looks up the address of the account the public key belongs to, through the originating
+/// address table if the account was rotated, or by its derived address otherwise
+async fn lookup_originating_address(
+    rest_client: &Client,
+    public_key: &Ed25519PublicKey,
+) -> CliTypedResult<AccountAddress> {
+    let originating_resource: OriginatingResource = rest_client
+        .get_account_resource_bcs(CORE_CODE_ADDRESS, "0x1::account::OriginatingAddress")
+        .await?
+        .into_inner();
+
+    let table_handle = originating_resource.address_map.handle;
+
+    // The derived address that can be used to look up the original address
+    // TODO: This command needs to support multi-ed25519
+    let address_key = AuthenticationKey::ed25519(public_key).derived_address();
+    match rest_client
+        .get_table_item_bcs(
+            table_handle,
+            "address",
+            "address",
+            address_key.to_hex_literal(),
+        )
+        .await
+    {
+        Ok(inner) => Ok(inner.into_inner()),
+        Err(RestError::Api(AptosErrorResponse {
+            error:
+                AptosError {
+                    error_code: AptosErrorCode::TableItemNotFound,
+                    ..
+                },
+            ..
+        })) => {
+            // If the table item wasn't found, let's at least check if the account exists
+            // It won't be in the table if it wasn't rotated, then return the derived account address
+            rest_client.get_account_bcs(address_key).await?;
+            Ok(address_key)
         }
+        Err(err) => Err(err)?,
     }
 }
 
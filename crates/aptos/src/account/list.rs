@@ -71,21 +71,7 @@ impl CliCommand<Vec<serde_json::Value>> for ListAccount {
     }
 
     async fn execute(self) -> CliTypedResult<Vec<serde_json::Value>> {
-        let account = if let Some(account) = self.account {
-            account
-        } else if let Some(Some(account)) = CliConfig::load_profile(
-            self.profile_options.profile_name(),
-            ConfigSearchMode::CurrentDirAndParents,
-        )?
-        .map(|p| p.account)
-        {
-            account
-        } else {
-            return Err(CliError::CommandArgumentError(
-                "Please provide an account using --account or run aptos init".to_string(),
-            ));
-        };
-
+        let account = account_or_profile(self.account, &self.profile_options)?;
         let client = self.rest_options.client(&self.profile_options)?;
         let response = match self.query {
             ListQuery::Balance => vec![
@@ -122,3 +108,24 @@ impl CliCommand<Vec<serde_json::Value>> for ListAccount {
         Ok(response)
     }
 }
+
+/// The given account, or else the profile's
+pub(crate) fn account_or_profile(
+    account: Option<AccountAddress>,
+    profile_options: &ProfileOptions,
+) -> CliTypedResult<AccountAddress> {
+    if let Some(account) = account {
+        Ok(account)
+    } else if let Some(Some(account)) = CliConfig::load_profile(
+        profile_options.profile_name(),
+        ConfigSearchMode::CurrentDirAndParents,
+    )?
+    .map(|p| p.account)
+    {
+        Ok(account)
+    } else {
+        Err(CliError::CommandArgumentError(
+            "Please provide an account using --account or run aptos init".to_string(),
+        ))
+    }
+}
@@ -6,10 +6,16 @@ use clap::Subcommand;
 
 pub mod create;
 pub mod create_resource_account;
+pub mod diff;
 pub mod fund;
+pub mod fund_many;
 pub mod key_rotation;
 pub mod list;
+pub mod resource_account;
+pub mod resources;
 pub mod transfer;
+pub mod transfer_batch;
+pub mod watch;
 
 /// Tool for interacting with accounts
 ///
@@ -19,11 +25,20 @@ pub mod transfer;
 pub enum AccountTool {
     Create(create::CreateAccount),
     CreateResourceAccount(create_resource_account::CreateResourceAccount),
+    DeriveResourceAddress(resource_account::DeriveResourceAddress),
+    Diff(diff::DiffAccount),
+    FundMany(fund_many::FundMany),
+    FundResourceAccount(resource_account::FundResourceAccount),
     FundWithFaucet(fund::FundWithFaucet),
     List(list::ListAccount),
     LookupAddress(key_rotation::LookupAddress),
+    Resources(resources::ListResources),
     RotateKey(key_rotation::RotateKey),
+    RotateKeyAndUpdateProfile(key_rotation::RotateKeyAndUpdateProfile),
+    RotateResourceAccountKey(resource_account::RotateResourceAccountKey),
     Transfer(transfer::TransferCoins),
+    TransferBatch(transfer_batch::TransferBatch),
+    Watch(watch::WatchAccount),
 }
 
 impl AccountTool {
@@ -31,11 +46,20 @@ impl AccountTool {
         match self {
             AccountTool::Create(tool) => tool.execute_serialized().await,
             AccountTool::CreateResourceAccount(tool) => tool.execute_serialized().await,
+            AccountTool::DeriveResourceAddress(tool) => tool.execute_serialized().await,
+            AccountTool::Diff(tool) => tool.execute_serialized().await,
+            AccountTool::FundMany(tool) => tool.execute_serialized().await,
+            AccountTool::FundResourceAccount(tool) => tool.execute_serialized().await,
             AccountTool::FundWithFaucet(tool) => tool.execute_serialized().await,
             AccountTool::List(tool) => tool.execute_serialized().await,
             AccountTool::LookupAddress(tool) => tool.execute_serialized().await,
+            AccountTool::Resources(tool) => tool.execute_formatted().await,
             AccountTool::RotateKey(tool) => tool.execute_serialized().await,
+            AccountTool::RotateKeyAndUpdateProfile(tool) => tool.execute_serialized().await,
+            AccountTool::RotateResourceAccountKey(tool) => tool.execute_serialized().await,
             AccountTool::Transfer(tool) => tool.execute_serialized().await,
+            AccountTool::TransferBatch(tool) => tool.execute_serialized().await,
+            AccountTool::Watch(tool) => tool.execute_serialized().await,
         }
     }
 }
@@ -0,0 +1,310 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::{fund_many::parse_amount, key_rotation::submit_rotation, list::account_or_profile},
+    common::types::{
+        CliCommand, CliError, CliTypedResult, ParsePrivateKey, ProfileOptions, TransactionOptions,
+        TransactionSummary,
+    },
+};
+use aptos_crypto::{ed25519::Ed25519PublicKey, PrivateKey};
+use aptos_rest_client::Client;
+use aptos_types::{
+    account_address::{create_resource_address, AccountAddress},
+    transaction::authenticator::AuthenticationKey,
+    utility_coin::APTOS_COIN_TYPE,
+};
+use async_trait::async_trait;
+use cached_packages::aptos_stdlib;
+use clap::{ArgEnum, Parser};
+use serde::Serialize;
+use std::{
+    fmt::{Display, Formatter},
+    path::PathBuf,
+    str::FromStr,
+};
+
+const APTOS_COIN_STORE: &str = "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>";
+
+/// How a resource account's seed is turned into the bytes it's derived from
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub enum SeedEncoding {
+    /// BCS of the seed as a string, as `aptos account create-resource-account` does
+    Bcs,
+    /// The seed's UTF-8 bytes, as Move's `b"seed"`
+    Utf8,
+    /// The seed is hex, as Move's `x"5eed"`
+    Hex,
+}
+
+impl Display for SeedEncoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            SeedEncoding::Bcs => "bcs",
+            SeedEncoding::Utf8 => "utf8",
+            SeedEncoding::Hex => "hex",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for SeedEncoding {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bcs" => Ok(SeedEncoding::Bcs),
+            "utf8" => Ok(SeedEncoding::Utf8),
+            "hex" => Ok(SeedEncoding::Hex),
+            _ => Err("Invalid seed encoding. Valid values are bcs, utf8, hex"),
+        }
+    }
+}
+
+/// The seed of a resource account
+#[derive(Debug, Parser)]
+pub struct ResourceAccountSeed {
+    /// Seed the resource account was created with
+    #[clap(long)]
+    pub(crate) seed: String,
+
+    /// How the seed is turned into bytes: [bcs, utf8, hex]
+    ///
+    /// Accounts from `aptos account create-resource-account` use `bcs`.  Accounts created in
+    /// Move use the bytes given to `account::create_resource_account`, usually `utf8`.
+    #[clap(long, default_value_t = SeedEncoding::Bcs)]
+    pub(crate) seed_encoding: SeedEncoding,
+}
+
+impl ResourceAccountSeed {
+    pub(crate) fn seed_bytes(&self) -> CliTypedResult<Vec<u8>> {
+        match self.seed_encoding {
+            SeedEncoding::Bcs => Ok(bcs::to_bytes(&self.seed)?),
+            SeedEncoding::Utf8 => Ok(self.seed.as_bytes().to_vec()),
+            SeedEncoding::Hex => hex::decode(self.seed.trim_start_matches("0x"))
+                .map_err(|err| CliError::UnableToParse("--seed", err.to_string())),
+        }
+    }
+
+    /// Address of the resource account created by `origin` with this seed
+    pub(crate) fn address(&self, origin: AccountAddress) -> CliTypedResult<AccountAddress> {
+        Ok(create_resource_address(origin, &self.seed_bytes()?))
+    }
+}
+
+/// Derive the address of a resource account from its origin and seed
+///
+/// This computes the address offline, as `account::create_resource_address` does on chain, so
+/// it can be known before the account is created, e.g. for named addresses of a package.
+#[derive(Debug, Parser)]
+pub struct DeriveResourceAddress {
+    /// Address of the account that creates the resource account
+    ///
+    /// Defaults to the profile's account
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) address: Option<AccountAddress>,
+
+    #[clap(flatten)]
+    pub(crate) seed: ResourceAccountSeed,
+
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[async_trait]
+impl CliCommand<AccountAddress> for DeriveResourceAddress {
+    fn command_name(&self) -> &'static str {
+        "DeriveResourceAddress"
+    }
+
+    async fn execute(self) -> CliTypedResult<AccountAddress> {
+        let origin = account_or_profile(self.address, &self.profile_options)?;
+        self.seed.address(origin)
+    }
+}
+
+/// Transfer APT from the origin to one of its resource accounts
+///
+/// The resource account is derived from the sender and seed.  If it can't hold APT yet, it's
+/// registered for it first, in a transaction sent as the resource account.  That needs the
+/// resource account's authentication key to still be the sender's, as for accounts from
+/// `aptos account create-resource-account` without `--authentication-key`.
+#[derive(Debug, Parser)]
+pub struct FundResourceAccount {
+    #[clap(flatten)]
+    pub(crate) seed: ResourceAccountSeed,
+
+    /// Octas to transfer, e.g. 100000000 or 1e8
+    #[clap(long, parse(try_from_str = parse_amount))]
+    pub(crate) amount: u64,
+
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FundResourceAccountSummary {
+    pub resource_account: AccountAddress,
+    /// The registration for APT, if the account couldn't hold it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub register: Option<TransactionSummary>,
+    pub transfer: TransactionSummary,
+}
+
+#[async_trait]
+impl CliCommand<FundResourceAccountSummary> for FundResourceAccount {
+    fn command_name(&self) -> &'static str {
+        "FundResourceAccount"
+    }
+
+    async fn execute(self) -> CliTypedResult<FundResourceAccountSummary> {
+        let mut txn_options = self.txn_options;
+        let (public_key, origin) = txn_options.get_public_key_and_address()?;
+        let resource_account = self.seed.address(origin)?;
+        let client = txn_options
+            .rest_options
+            .client(&txn_options.profile_options)?;
+
+        let registered = client
+            .get_account_resource(resource_account, APTOS_COIN_STORE)
+            .await?
+            .into_inner()
+            .is_some();
+        let register = if registered {
+            None
+        } else {
+            check_signs_for(&client, resource_account, &public_key).await?;
+            let sender_account = txn_options.sender_account.replace(resource_account);
+            let summary = txn_options
+                .submit_transaction(aptos_stdlib::managed_coin_register(APTOS_COIN_TYPE.clone()))
+                .await
+                .map(TransactionSummary::from)?;
+            txn_options.sender_account = sender_account;
+            if summary.success != Some(true) {
+                return Err(CliError::ApiError(format!(
+                    "Failed to register {} for APT: {}",
+                    resource_account,
+                    summary.vm_status.unwrap_or_default()
+                )));
+            }
+            Some(summary)
+        };
+
+        let transfer = txn_options
+            .submit_transaction(aptos_stdlib::aptos_account_transfer(
+                resource_account,
+                self.amount,
+            ))
+            .await
+            .map(TransactionSummary::from)?;
+        Ok(FundResourceAccountSummary {
+            resource_account,
+            register,
+            transfer,
+        })
+    }
+}
+
+/// Rotate a resource account's authentication key away from its origin's key
+///
+/// Resource accounts from `aptos account create-resource-account` keep the origin's key, so
+/// the origin can sign for them.  This rotates the key of the resource account derived from
+/// the sender and seed to a new private key, signing with the sender's key.  Afterwards, use the
+/// new key with `--sender-account` set to the resource account.
+#[derive(Debug, Parser)]
+pub struct RotateResourceAccountKey {
+    #[clap(flatten)]
+    pub(crate) seed: ResourceAccountSeed,
+
+    /// File name that contains the new private key encoded in the type from `--encoding`
+    #[clap(long, group = "new_private_key", parse(from_os_str))]
+    pub(crate) new_private_key_file: Option<PathBuf>,
+
+    /// New private key encoded in the type from `--encoding`
+    #[clap(long, group = "new_private_key")]
+    pub(crate) new_private_key: Option<String>,
+
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+}
+
+impl ParsePrivateKey for RotateResourceAccountKey {}
+
+#[derive(Debug, Serialize)]
+pub struct RotateResourceAccountKeySummary {
+    pub resource_account: AccountAddress,
+    pub authentication_key: AuthenticationKey,
+    pub transaction: TransactionSummary,
+}
+
+#[async_trait]
+impl CliCommand<RotateResourceAccountKeySummary> for RotateResourceAccountKey {
+    fn command_name(&self) -> &'static str {
+        "RotateResourceAccountKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<RotateResourceAccountKeySummary> {
+        let new_private_key = self
+            .parse_private_key(
+                self.txn_options.encoding_options.encoding,
+                self.new_private_key_file.clone(),
+                self.new_private_key.clone(),
+            )?
+            .ok_or_else(|| {
+                CliError::CommandArgumentError(
+                    "One of ['--new-private-key', '--new-private-key-file'] must be used"
+                        .to_string(),
+                )
+            })?;
+
+        let mut txn_options = self.txn_options;
+        let (private_key, origin) = txn_options.get_key_and_address()?;
+        let resource_account = self.seed.address(origin)?;
+        let client = txn_options
+            .rest_options
+            .client(&txn_options.profile_options)?;
+        check_signs_for(&client, resource_account, &private_key.public_key()).await?;
+
+        txn_options.sender_account = Some(resource_account);
+        let transaction = submit_rotation(
+            &txn_options,
+            &private_key,
+            resource_account,
+            &new_private_key,
+        )
+        .await?;
+        Ok(RotateResourceAccountKeySummary {
+            resource_account,
+            authentication_key: AuthenticationKey::ed25519(&new_private_key.public_key()),
+            transaction,
+        })
+    }
+}
+
+/// Checks `public_key` can sign for the resource account, so its transactions aren't rejected
+async fn check_signs_for(
+    client: &Client,
+    resource_account: AccountAddress,
+    public_key: &Ed25519PublicKey,
+) -> CliTypedResult<()> {
+    let authentication_key = client
+        .get_account(resource_account)
+        .await
+        .map_err(|err| {
+            CliError::ApiError(format!(
+                "Unable to read resource account {}, was it created?  {}",
+                resource_account, err
+            ))
+        })?
+        .into_inner()
+        .authentication_key;
+    if authentication_key != AuthenticationKey::ed25519(public_key) {
+        return Err(CliError::CommandArgumentError(format!(
+            "Resource account {} has authentication key {}, which isn't the sender's key, so \
+             the sender can't sign for it",
+            resource_account, authentication_key
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,453 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::list::account_or_profile,
+    common::{
+        types::{CliCommand, CliError, CliResult, CliTypedResult, ProfileOptions, RestOptions},
+        utils::to_common_result,
+    },
+};
+use aptos_rest_client::{
+    aptos_api_types::{MoveModule, MoveStructTag, MoveType},
+    Client,
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::{ArgEnum, Parser};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter},
+    str::FromStr,
+    time::Instant,
+};
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourcesFormat {
+    Json,
+    Yaml,
+    Table,
+}
+
+impl Display for ResourcesFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ResourcesFormat::Json => "json",
+            ResourcesFormat::Yaml => "yaml",
+            ResourcesFormat::Table => "table",
+        })
+    }
+}
+
+impl FromStr for ResourcesFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ResourcesFormat::Json),
+            "yaml" => Ok(ResourcesFormat::Yaml),
+            "table" => Ok(ResourcesFormat::Table),
+            _ => Err("Invalid format. Valid values are json, yaml, table"),
+        }
+    }
+}
+
+/// Show the resources of an account
+///
+/// With `--decode`, each resource is read with the ABI of the modules declaring its types, so
+/// nested structs carry their type, and tables show their key and value types next to their
+/// handle.
+#[derive(Debug, Parser)]
+pub struct ListResources {
+    /// Address of the account to show the resources of
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) account: Option<AccountAddress>,
+
+    /// Only show resources of this type, e.g. `0x1::coin::CoinStore`
+    ///
+    /// Without type arguments, resources of any instantiation of the struct are shown
+    #[clap(long)]
+    pub(crate) resource: Option<MoveStructTag>,
+
+    /// Decode the resources with the ABIs of their modules
+    #[clap(long)]
+    pub(crate) decode: bool,
+
+    /// Output format: [json, yaml, table]
+    #[clap(long, default_value_t = ResourcesFormat::Json)]
+    pub(crate) format: ResourcesFormat,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[derive(Serialize)]
+pub struct ResourceView {
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub data: DecodedValue,
+}
+
+/// A resource's value, as decoded against its type
+pub enum DecodedValue {
+    /// A value as the API returns it, e.g. a number, or a string for a `u64` or `String`
+    Value(serde_json::Value),
+    Vector(Vec<DecodedValue>),
+    Struct {
+        typ: String,
+        fields: Vec<(String, DecodedValue)>,
+    },
+    Table {
+        handle: serde_json::Value,
+        key_type: String,
+        value_type: String,
+    },
+}
+
+impl Serialize for DecodedValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            DecodedValue::Value(value) => value.serialize(serializer),
+            DecodedValue::Vector(items) => items.serialize(serializer),
+            DecodedValue::Struct { typ, fields } => {
+                let mut map = serializer.serialize_map(Some(fields.len() + 1))?;
+                map.serialize_entry("@type", typ)?;
+                for (name, value) in fields {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            }
+            DecodedValue::Table {
+                handle,
+                key_type,
+                value_type,
+            } => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry("@table", handle)?;
+                map.serialize_entry("key_type", key_type)?;
+                map.serialize_entry("value_type", value_type)?;
+                map.end()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CliCommand<Vec<ResourceView>> for ListResources {
+    fn command_name(&self) -> &'static str {
+        "ListResources"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<ResourceView>> {
+        let account = account_or_profile(self.account, &self.profile_options)?;
+        let client = self.rest_options.client(&self.profile_options)?;
+        let resources: Vec<_> = client
+            .get_account_resources(account)
+            .await?
+            .into_inner()
+            .into_iter()
+            .map(|resource| (MoveStructTag::from(resource.resource_type), resource.data))
+            .filter(|(typ, _)| match &self.resource {
+                Some(filter) => matches_filter(typ, filter),
+                None => true,
+            })
+            .collect();
+
+        if !self.decode {
+            return Ok(resources
+                .into_iter()
+                .map(|(typ, data)| ResourceView {
+                    resource_type: typ.to_string(),
+                    data: DecodedValue::Value(data),
+                })
+                .collect());
+        }
+
+        let mut abis = Abis::default();
+        abis.load(
+            &client,
+            resources.iter().map(|(typ, _)| typ.clone()).collect(),
+        )
+        .await?;
+        Ok(resources
+            .into_iter()
+            .map(|(typ, data)| ResourceView {
+                resource_type: typ.to_string(),
+                data: abis.decode(&MoveType::Struct(typ), &data),
+            })
+            .collect())
+    }
+}
+
+impl ListResources {
+    /// Executes the command, printing YAML or a table instead of JSON if asked to
+    pub async fn execute_formatted(self) -> CliResult {
+        let format = self.format;
+        if format == ResourcesFormat::Json {
+            return self.execute_serialized().await;
+        }
+
+        let command_name = self.command_name();
+        let start_time = Instant::now();
+        let result = self.execute().await;
+        let output = match &result {
+            Ok(resources) if format == ResourcesFormat::Yaml => {
+                Some(serde_yaml::to_string(resources).map_err(|err| err.to_string())?)
+            }
+            Ok(resources) => Some(render_table(resources)),
+            Err(_) => None,
+        };
+        // Errors, and telemetry, are as for every other command
+        let json = to_common_result(command_name, start_time, result).await?;
+        Ok(output.unwrap_or(json))
+    }
+}
+
+/// Whether a resource type is the filter's, ignoring type arguments if the filter has none
+pub(crate) fn matches_filter(typ: &MoveStructTag, filter: &MoveStructTag) -> bool {
+    typ.address == filter.address
+        && typ.module == filter.module
+        && typ.name == filter.name
+        && (filter.generic_type_params.is_empty()
+            || typ.generic_type_params == filter.generic_type_params)
+}
+
+/// ABIs of the modules declaring the decoded types
+#[derive(Default)]
+pub(crate) struct Abis {
+    modules: BTreeMap<(AccountAddress, String), MoveModule>,
+}
+
+impl Abis {
+    /// Adds the ABI of a module, as of a local package, so it isn't fetched
+    pub(crate) fn insert(&mut self, module: MoveModule) {
+        self.modules
+            .insert((module.address.into(), module.name.to_string()), module);
+    }
+
+    /// Fetches the ABIs for the given types, and the types of all their fields
+    pub(crate) async fn load(
+        &mut self,
+        client: &Client,
+        types: Vec<MoveStructTag>,
+    ) -> CliTypedResult<()> {
+        let mut pending = types;
+        let mut seen = BTreeSet::new();
+        while let Some(tag) = pending.pop() {
+            if !seen.insert(tag.to_string()) {
+                continue;
+            }
+            let key = (AccountAddress::from(tag.address), tag.module.to_string());
+            if !self.modules.contains_key(&key) {
+                let abi = client
+                    .get_account_module(key.0, &key.1)
+                    .await?
+                    .into_inner()
+                    .try_parse_abi()
+                    .ok()
+                    .and_then(|module| module.abi)
+                    .ok_or_else(|| {
+                        CliError::UnableToParse("module", format!("{}::{}", key.0, key.1))
+                    })?;
+                self.modules.insert(key, abi);
+            }
+            for (_, typ) in self.fields(&tag).unwrap_or_default() {
+                collect_structs(&typ, &mut pending);
+            }
+        }
+        Ok(())
+    }
+
+    /// The fields of a struct, with its type arguments substituted in their types
+    pub(crate) fn fields(&self, tag: &MoveStructTag) -> Option<Vec<(String, MoveType)>> {
+        let module = self
+            .modules
+            .get(&(AccountAddress::from(tag.address), tag.module.to_string()))?;
+        let definition = module
+            .structs
+            .iter()
+            .find(|definition| definition.name == tag.name)?;
+        Some(
+            definition
+                .fields
+                .iter()
+                .map(|field| {
+                    (
+                        field.name.to_string(),
+                        substitute(&field.typ, &tag.generic_type_params),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    pub(crate) fn decode(&self, typ: &MoveType, value: &serde_json::Value) -> DecodedValue {
+        match (typ, value) {
+            (MoveType::Vector { items }, serde_json::Value::Array(values)) => DecodedValue::Vector(
+                values
+                    .iter()
+                    .map(|value| self.decode(items, value))
+                    .collect(),
+            ),
+            (MoveType::Struct(tag), serde_json::Value::Object(object)) => {
+                if is_table(tag) {
+                    if let Some(handle) = object.get("handle") {
+                        let type_arg = |index: usize| {
+                            tag.generic_type_params
+                                .get(index)
+                                .map(|typ| typ.to_string())
+                                .unwrap_or_default()
+                        };
+                        return DecodedValue::Table {
+                            handle: handle.clone(),
+                            key_type: type_arg(0),
+                            value_type: type_arg(1),
+                        };
+                    }
+                }
+                match self.fields(tag) {
+                    Some(fields) => DecodedValue::Struct {
+                        typ: tag.to_string(),
+                        fields: fields
+                            .into_iter()
+                            .map(|(name, typ)| {
+                                let value = object
+                                    .get(&name)
+                                    .map(|value| self.decode(&typ, value))
+                                    .unwrap_or(DecodedValue::Value(serde_json::Value::Null));
+                                (name, value)
+                            })
+                            .collect(),
+                    },
+                    None => DecodedValue::Value(value.clone()),
+                }
+            }
+            // Primitives, and structs the API shows as values, like `0x1::string::String`
+            _ => DecodedValue::Value(value.clone()),
+        }
+    }
+}
+
+fn is_table(tag: &MoveStructTag) -> bool {
+    AccountAddress::from(tag.address) == AccountAddress::ONE
+        && tag.module.as_str() == "table"
+        && tag.name.as_str() == "Table"
+}
+
+pub(crate) fn substitute(typ: &MoveType, type_args: &[MoveType]) -> MoveType {
+    match typ {
+        MoveType::GenericTypeParam { index } => type_args
+            .get(*index as usize)
+            .cloned()
+            .unwrap_or_else(|| typ.clone()),
+        MoveType::Vector { items } => MoveType::Vector {
+            items: Box::new(substitute(items, type_args)),
+        },
+        MoveType::Struct(tag) => MoveType::Struct(MoveStructTag {
+            generic_type_params: tag
+                .generic_type_params
+                .iter()
+                .map(|typ| substitute(typ, type_args))
+                .collect(),
+            ..tag.clone()
+        }),
+        _ => typ.clone(),
+    }
+}
+
+fn collect_structs(typ: &MoveType, structs: &mut Vec<MoveStructTag>) {
+    match typ {
+        MoveType::Vector { items } => collect_structs(items, structs),
+        MoveType::Struct(tag) => structs.push(tag.clone()),
+        _ => {}
+    }
+}
+
+/// The resources as an indented tree, a line per field
+fn render_table(resources: &[ResourceView]) -> String {
+    let mut lines = vec![];
+    for resource in resources {
+        lines.push(resource.resource_type.clone());
+        render_value(&resource.data, 1, &mut lines);
+    }
+    lines.join("\n")
+}
+
+fn render_value(value: &DecodedValue, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    match value {
+        DecodedValue::Value(serde_json::Value::Object(fields)) => {
+            for (name, field) in fields {
+                render_field(name, &DecodedValue::Value(field.clone()), depth, lines);
+            }
+        }
+        DecodedValue::Value(serde_json::Value::Array(items)) => {
+            for (index, item) in items.iter().enumerate() {
+                render_field(
+                    &format!("[{}]", index),
+                    &DecodedValue::Value(item.clone()),
+                    depth,
+                    lines,
+                );
+            }
+        }
+        DecodedValue::Vector(items) => {
+            for (index, item) in items.iter().enumerate() {
+                render_field(&format!("[{}]", index), item, depth, lines);
+            }
+        }
+        DecodedValue::Struct { fields, .. } => {
+            for (name, field) in fields {
+                render_field(name, field, depth, lines);
+            }
+        }
+        DecodedValue::Value(value) => lines.push(format!("{}{}", indent, scalar(value))),
+        DecodedValue::Table { .. } => {}
+    }
+}
+
+fn render_field(name: &str, value: &DecodedValue, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    match value {
+        DecodedValue::Value(serde_json::Value::Object(_)) => {
+            lines.push(format!("{}{}:", indent, name));
+            render_value(value, depth + 1, lines);
+        }
+        DecodedValue::Value(serde_json::Value::Array(items)) => {
+            lines.push(format!("{}{}: {} items", indent, name, items.len()));
+            render_value(value, depth + 1, lines);
+        }
+        DecodedValue::Value(value) => lines.push(format!("{}{}: {}", indent, name, scalar(value))),
+        DecodedValue::Vector(items) => {
+            lines.push(format!("{}{}: {} items", indent, name, items.len()));
+            render_value(value, depth + 1, lines);
+        }
+        DecodedValue::Struct { typ, .. } => {
+            lines.push(format!("{}{}: {}", indent, name, typ));
+            render_value(value, depth + 1, lines);
+        }
+        DecodedValue::Table {
+            handle,
+            key_type,
+            value_type,
+        } => lines.push(format!(
+            "{}{}: table {} ({} -> {})",
+            indent,
+            name,
+            scalar(handle),
+            key_type,
+            value_type
+        )),
+    }
+}
+
+/// A JSON scalar, with strings unquoted
+fn scalar(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
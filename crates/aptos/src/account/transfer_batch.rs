@@ -0,0 +1,654 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Paying out to many accounts from a CSV file
+//!
+//! Every row is validated before anything is simulated, and everything is simulated before
+//! anything is submitted.  Sequence numbers are handed out locally, so transactions are submitted
+//! in parallel, and are signed as they're submitted so that none expires while waiting its turn.
+//! A submission is retried when mempool is full, or with a new sequence number when another
+//! transaction of the account took its number.  Once one can't be submitted, the rest aren't,
+//! as they would wait behind its sequence number until they expire.  The lines paid out are
+//! recorded in a progress file, so a rerun after a failure transfers only the rest.
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, TransactionOptions, TransactionSummary},
+        utils::{
+            chain_id, get_sequence_number, prompt_yes_with_override, read_from_file, write_to_file,
+        },
+    },
+    governance::compile_in_temp_dir,
+    move_tool::{
+        compose::{compose_script, ComposeCall, ComposeSpec},
+        FrameworkPackageArgs,
+    },
+};
+use aptos_crypto::{ed25519::Ed25519Signature, HashValue, PrivateKey};
+use aptos_global_constants::adjust_gas_headroom;
+use aptos_rest_client::{
+    aptos_api_types::{AptosErrorCode, ExplainVMStatus},
+    error::{AptosErrorResponse, RestError},
+};
+use aptos_sdk::transaction_builder::TransactionFactory;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{Script, SignedTransaction, TransactionPayload},
+};
+use async_trait::async_trait;
+use cached_packages::aptos_stdlib;
+use clap::Parser;
+use futures::{stream, StreamExt};
+use move_core_types::{language_storage::TypeTag, parser::parse_type_tag};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+    time::Duration,
+};
+use tempfile::TempDir;
+
+/// Transactions a node's mempool takes from one account, its default `capacity_per_user`
+const MEMPOOL_TXNS_PER_ACCOUNT: usize = 100;
+/// Times a submission is retried, when mempool is full or its sequence number was taken
+const SUBMIT_RETRIES: u32 = 5;
+const SUBMIT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Transfer coins to many accounts, from a CSV file
+///
+/// Each row of the file is `address,amount[,coin type]`, where the amount is in the coin's
+/// smallest unit, e.g. Octas (10^-8 APT).  Without a coin type, APT is transferred and the
+/// recipient account is created if it doesn't exist.  A header row starting with `address`,
+/// blank lines, and lines starting with `#` are skipped.
+///
+/// Every transfer is simulated first, and the total amount and gas is confirmed before any is
+/// submitted.  By default each row is its own transaction, submitted `--max-concurrency` at a
+/// time.  With `--single-transaction`, all rows are transferred in one script transaction.
+///
+/// The lines transferred are recorded in a progress file, by default next to the CSV file.  If
+/// the command fails partway, running it again transfers only the lines not yet recorded.
+#[derive(Debug, Parser)]
+pub struct TransferBatch {
+    /// CSV file of the transfers
+    #[clap(long, parse(from_os_str))]
+    pub(crate) csv: PathBuf,
+
+    /// Transfer every row in one script transaction, rather than one transaction per row
+    #[clap(long)]
+    pub(crate) single_transaction: bool,
+
+    /// Maximum number of transactions being submitted at once
+    ///
+    /// At most 100, the transactions a node's mempool takes from one account
+    #[clap(long, visible_alias = "parallel", default_value_t = 8)]
+    pub(crate) max_concurrency: usize,
+
+    /// File recording the lines transferred
+    ///
+    /// Defaults to `<csv>.progress.json`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) progress_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) framework_package_args: FrameworkPackageArgs,
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+}
+
+/// A row of the CSV file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Payout {
+    /// Line of the row in the file, from 1
+    pub(crate) line: usize,
+    pub(crate) to: AccountAddress,
+    pub(crate) amount: u64,
+    /// Coin to transfer, APT if none
+    pub(crate) coin_type: Option<TypeTag>,
+}
+
+/// What's been transferred from a CSV file
+#[derive(Deserialize, Serialize)]
+struct Progress {
+    /// Hash of the CSV file, so that progress isn't applied to another one
+    csv_hash: String,
+    /// Hash of the transaction paying out each line
+    completed: BTreeMap<usize, String>,
+}
+
+/// A transaction to submit, with the rows of the input it pays out
+pub(crate) struct Transfer {
+    lines: Vec<usize>,
+    /// What's transferred, for reporting failures
+    description: String,
+    payload: TransactionPayload,
+    max_gas: u64,
+}
+
+impl Transfer {
+    pub(crate) fn new(lines: Vec<usize>, description: String, payload: TransactionPayload) -> Self {
+        Transfer {
+            lines,
+            description,
+            payload,
+            max_gas: 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransferBatchSummary {
+    /// Lines transferred by this run
+    pub transferred: usize,
+    /// Lines transferred by an earlier run
+    pub skipped: usize,
+    pub transactions: Vec<TransactionSummary>,
+}
+
+#[async_trait]
+impl CliCommand<TransferBatchSummary> for TransferBatch {
+    fn command_name(&self) -> &'static str {
+        "TransferBatch"
+    }
+
+    async fn execute(self) -> CliTypedResult<TransferBatchSummary> {
+        if self.max_concurrency == 0 {
+            return Err(CliError::CommandArgumentError(
+                "--max-concurrency must be at least 1".to_string(),
+            ));
+        }
+        let bytes = read_from_file(&self.csv)?;
+        let csv = String::from_utf8(bytes.clone()).map_err(|err| {
+            CliError::UnableToReadFile(self.csv.display().to_string(), err.to_string())
+        })?;
+        let payouts = parse_payouts(&csv)?;
+
+        let progress_file = self
+            .progress_file
+            .clone()
+            .unwrap_or_else(|| self.csv.with_extension("progress.json"));
+        let csv_hash = HashValue::sha3_256_of(&bytes).to_hex();
+        let mut progress = if progress_file.exists() {
+            let progress: Progress = serde_json::from_slice(&read_from_file(&progress_file)?)
+                .map_err(|err| CliError::UnableToParse("progress file", format!("{}", err)))?;
+            if progress.csv_hash != csv_hash {
+                return Err(CliError::CommandArgumentError(format!(
+                    "{} records progress for a different CSV file, remove it to start over",
+                    progress_file.display()
+                )));
+            }
+            progress
+        } else {
+            Progress {
+                csv_hash,
+                completed: BTreeMap::new(),
+            }
+        };
+
+        let pending: Vec<Payout> = payouts
+            .iter()
+            .filter(|payout| !progress.completed.contains_key(&payout.line))
+            .cloned()
+            .collect();
+        let skipped = payouts.len() - pending.len();
+        if pending.is_empty() {
+            eprintln!("All {} transfers are already done", payouts.len());
+            return Ok(TransferBatchSummary {
+                transferred: 0,
+                skipped,
+                transactions: vec![],
+            });
+        }
+        if skipped > 0 {
+            eprintln!(
+                "Resuming, {} of {} transfers are already done",
+                skipped,
+                payouts.len()
+            );
+        }
+
+        let transfers = if self.single_transaction {
+            vec![self.script_transfer(&pending)?]
+        } else {
+            pending
+                .iter()
+                .map(|payout| {
+                    Transfer::new(
+                        vec![payout.line],
+                        describe_lines(&[payout.line]),
+                        payout_payload(payout),
+                    )
+                })
+                .collect()
+        };
+
+        let octas = pending
+            .iter()
+            .filter(|payout| payout.coin_type.is_none())
+            .map(|payout| payout.amount)
+            .sum();
+        let mut transferred = 0;
+        let result = submit_transfers(
+            &self.txn_options,
+            transfers,
+            octas,
+            self.max_concurrency,
+            |lines, summary| {
+                transferred += lines.len();
+                for line in lines {
+                    progress
+                        .completed
+                        .insert(*line, summary.transaction_hash.to_string());
+                }
+                save_progress(&progress_file, &progress)
+            },
+        )
+        .await;
+        match result {
+            Ok(transactions) => Ok(TransferBatchSummary {
+                transferred,
+                skipped,
+                transactions,
+            }),
+            Err(err) => {
+                if transferred > 0 {
+                    eprintln!(
+                        "{} transfers were made and recorded in {}, run the command again to \
+                        retry the rest",
+                        transferred,
+                        progress_file.display()
+                    );
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+impl TransferBatch {
+    /// A script transaction making every transfer
+    fn script_transfer(&self, payouts: &[Payout]) -> CliTypedResult<Transfer> {
+        let calls = payouts
+            .iter()
+            .map(|payout| {
+                let args = vec![
+                    "signer".to_string(),
+                    format!("address:{}", payout.to.to_hex_literal()),
+                    format!("u64:{}", payout.amount),
+                ];
+                match &payout.coin_type {
+                    None => ComposeCall {
+                        function: "0x1::aptos_account::transfer".to_string(),
+                        type_args: vec![],
+                        args,
+                        result: None,
+                    },
+                    Some(coin_type) => ComposeCall {
+                        function: "0x1::coin::transfer".to_string(),
+                        type_args: vec![coin_type.to_string()],
+                        args,
+                        result: None,
+                    },
+                }
+            })
+            .collect();
+        let (source, args) = compose_script(&ComposeSpec { calls })?;
+
+        let temp_dir = TempDir::new().map_err(|err| {
+            CliError::UnexpectedError(format!("Failed to create temporary directory {}", err))
+        })?;
+        let script_path = temp_dir.path().join("transfer_batch.move");
+        write_to_file(&script_path, "Transfer script", source.as_bytes())?;
+        let (bytecode, _script_hash) = compile_in_temp_dir(
+            "TransferBatch",
+            &script_path,
+            &self.framework_package_args,
+            self.txn_options.prompt_options,
+        )?;
+        let lines: Vec<usize> = payouts.iter().map(|payout| payout.line).collect();
+        Ok(Transfer::new(
+            lines.clone(),
+            describe_lines(&lines),
+            TransactionPayload::Script(Script::new(bytecode, vec![], args)),
+        ))
+    }
+}
+
+/// Simulates the transfers, confirms their total cost, and submits them, calling `on_success`
+/// with the rows of each that succeeds
+///
+/// `octas` is the APT transferred, which the sender's balance must cover along with the gas.
+pub(crate) async fn submit_transfers(
+    txn_options: &TransactionOptions,
+    mut transfers: Vec<Transfer>,
+    octas: u64,
+    max_concurrency: usize,
+    mut on_success: impl FnMut(&[usize], &TransactionSummary) -> CliTypedResult<()>,
+) -> CliTypedResult<Vec<TransactionSummary>> {
+    let client = txn_options
+        .rest_options
+        .client(&txn_options.profile_options)?;
+    let (sender_key, sender) = txn_options.get_key_and_address()?;
+    let gas_unit_price = match txn_options.gas_options.gas_unit_price {
+        Some(gas_unit_price) => gas_unit_price,
+        None => txn_options.estimate_gas_price().await?,
+    };
+    let transaction_factory =
+        TransactionFactory::new(chain_id(&client).await?).with_gas_unit_price(gas_unit_price);
+
+    // Everything is simulated from the current sequence number, as the transfers don't
+    // depend on each other
+    let sequence_number = get_sequence_number(&client, sender).await?;
+    let simulations: Vec<_> = stream::iter(transfers.iter().map(|transfer| {
+        let unsigned_transaction = transaction_factory
+            .payload(transfer.payload.clone())
+            .sender(sender)
+            .sequence_number(sequence_number)
+            .build();
+        let signed_transaction = SignedTransaction::new(
+            unsigned_transaction,
+            sender_key.public_key(),
+            Ed25519Signature::try_from([0u8; 64].as_ref()).unwrap(),
+        );
+        let client = &client;
+        async move {
+            client
+                .simulate_bcs_with_gas_estimation(&signed_transaction, true, false)
+                .await
+                .map(|response| response.into_inner())
+        }
+    }))
+    .buffered(max_concurrency)
+    .collect()
+    .await;
+
+    let mut failures = vec![];
+    let (mut lower_cost_bound, mut upper_cost_bound) = (0u64, 0u64);
+    for (transfer, simulation) in transfers.iter_mut().zip(simulations) {
+        let simulated = simulation?;
+        let status = simulated.info.status();
+        if !status.is_success() {
+            failures.push(format!(
+                "{}: {}",
+                transfer.description,
+                client.explain_vm_status(status)
+            ));
+            continue;
+        }
+        transfer.max_gas = match txn_options.gas_options.max_gas {
+            Some(max_gas) => max_gas,
+            None => adjust_gas_headroom(
+                simulated.info.gas_used(),
+                simulated
+                    .transaction
+                    .as_signed_user_txn()
+                    .expect("Should be signed user transaction")
+                    .max_gas_amount(),
+            ),
+        };
+        lower_cost_bound += simulated.info.gas_used() * gas_unit_price;
+        upper_cost_bound += transfer.max_gas * gas_unit_price;
+    }
+    if !failures.is_empty() {
+        return Err(CliError::SimulationError(format!(
+            "{} of {} transactions would fail, nothing was submitted:\n{}",
+            failures.len(),
+            transfers.len(),
+            failures.join("\n")
+        )));
+    }
+
+    let balance = client
+        .get_account_balance(sender)
+        .await?
+        .into_inner()
+        .coin
+        .value
+        .0;
+    if balance < octas.saturating_add(upper_cost_bound) {
+        return Err(CliError::CommandArgumentError(format!(
+            "The balance of {} Octas can't cover transferring {} Octas and up to {} Octas \
+            of gas",
+            balance, octas, upper_cost_bound
+        )));
+    }
+    prompt_yes_with_override(
+        &format!(
+            "Do you want to make {} transfers, of {} Octas and any other coins, in {} \
+            transactions for a range of [{} - {}] Octas of gas at a gas unit price of {} \
+            Octas?",
+            transfers
+                .iter()
+                .map(|transfer| transfer.lines.len())
+                .sum::<usize>(),
+            octas,
+            transfers.len(),
+            lower_cost_bound,
+            upper_cost_bound,
+            gas_unit_price
+        ),
+        txn_options.prompt_options,
+    )?;
+
+    if max_concurrency > MEMPOOL_TXNS_PER_ACCOUNT {
+        eprintln!(
+            "Submitting {} transactions at a time, as many as mempool takes from an account",
+            MEMPOOL_TXNS_PER_ACCOUNT
+        );
+    }
+    let public_key = sender_key.public_key();
+    // Once a transaction fails to be submitted, its sequence number is a gap every later one
+    // would wait behind until it expires, so no more numbers are handed out
+    let next_sequence_number = Mutex::new(Some(get_sequence_number(&client, sender).await?));
+    let mut submissions = stream::iter(transfers.into_iter().map(|transfer| {
+        let (client, transaction_factory, sender_key, public_key, next_sequence_number) = (
+            &client,
+            &transaction_factory,
+            &sender_key,
+            public_key.clone(),
+            &next_sequence_number,
+        );
+        async move {
+            let result = async {
+                let mut sequence_number = match take_sequence_number(next_sequence_number) {
+                    Some(sequence_number) => sequence_number,
+                    None => return Ok(None),
+                };
+                let mut backoff = SUBMIT_INITIAL_BACKOFF;
+                let mut retries = 0;
+                loop {
+                    // Built and signed only once submitted, so the expiration time runs from then
+                    let signed_transaction = transaction_factory
+                        .payload(transfer.payload.clone())
+                        .sender(sender)
+                        .sequence_number(sequence_number)
+                        .max_gas_amount(transfer.max_gas)
+                        .build()
+                        .sign(sender_key, public_key.clone())
+                        .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+                        .into_inner();
+                    let err = match client.submit_and_wait(&signed_transaction).await {
+                        Ok(response) => return Ok(Some(response.into_inner())),
+                        Err(err) if retries < SUBMIT_RETRIES => err,
+                        Err(err) => return Err(CliError::ApiError(err.to_string())),
+                    };
+                    match submission_error_code(&err) {
+                        // Something else sent with the number, and this transaction was
+                        // rejected, so it's resent with the next number without paying twice
+                        Some(AptosErrorCode::SequenceNumberTooOld) => {
+                            let on_chain = get_sequence_number(client, sender).await?;
+                            if let Some(next) = next_sequence_number.lock().unwrap().as_mut() {
+                                *next = (*next).max(on_chain);
+                            }
+                            sequence_number = match take_sequence_number(next_sequence_number) {
+                                Some(sequence_number) => sequence_number,
+                                None => return Ok(None),
+                            };
+                        }
+                        Some(AptosErrorCode::MempoolIsFull) => {
+                            eprintln!(
+                                "Mempool is full, retrying {} in {} ms...",
+                                transfer.description,
+                                backoff.as_millis()
+                            );
+                            tokio::time::sleep(backoff).await;
+                            backoff *= 2;
+                        }
+                        _ => return Err(CliError::ApiError(err.to_string())),
+                    }
+                    retries += 1;
+                }
+            }
+            .await;
+            if result.is_err() {
+                *next_sequence_number.lock().unwrap() = None;
+            }
+            (transfer, result)
+        }
+    }))
+    .buffer_unordered(max_concurrency.min(MEMPOOL_TXNS_PER_ACCOUNT));
+
+    let mut transactions = vec![];
+    let mut succeeded = 0;
+    let mut not_submitted = 0;
+    while let Some((transfer, result)) = submissions.next().await {
+        match result {
+            Ok(Some(transaction)) if transaction.success() => {
+                let summary = TransactionSummary::from(&transaction);
+                succeeded += 1;
+                on_success(&transfer.lines, &summary)?;
+                transactions.push(summary);
+            }
+            Ok(Some(transaction)) => {
+                failures.push(format!(
+                    "{}: {}",
+                    transfer.description,
+                    transaction.vm_status()
+                ));
+                transactions.push(TransactionSummary::from(&transaction));
+            }
+            Ok(None) => not_submitted += 1,
+            Err(err) => failures.push(format!("{}: {}", transfer.description, err)),
+        }
+    }
+
+    if !failures.is_empty() {
+        let mut message = format!(
+            "{} of {} transactions failed:\n{}",
+            failures.len(),
+            succeeded + failures.len() + not_submitted,
+            failures.join("\n")
+        );
+        if not_submitted > 0 {
+            message.push_str(&format!(
+                "\n{} were not submitted, as one before them failed",
+                not_submitted
+            ));
+        }
+        return Err(CliError::UnexpectedError(message));
+    }
+    Ok(transactions)
+}
+
+/// Takes the next sequence number of the sender, if numbers are still handed out
+pub(crate) fn take_sequence_number(next_sequence_number: &Mutex<Option<u64>>) -> Option<u64> {
+    let mut next = next_sequence_number.lock().unwrap();
+    let sequence_number = (*next)?;
+    *next = Some(sequence_number + 1);
+    Some(sequence_number)
+}
+
+/// The API's error code for a rejected submission, if that's why it failed
+fn submission_error_code(err: &RestError) -> Option<AptosErrorCode> {
+    match err {
+        RestError::Api(AptosErrorResponse { error, .. }) => Some(error.error_code),
+        _ => None,
+    }
+}
+
+/// Parses the rows of a CSV file of transfers, reporting every invalid row
+pub(crate) fn parse_payouts(csv: &str) -> CliTypedResult<Vec<Payout>> {
+    let mut payouts = vec![];
+    let mut errors = vec![];
+    let mut seen_row = false;
+    for (index, row) in csv.lines().enumerate() {
+        let line = index + 1;
+        let row = row.trim();
+        if row.is_empty() || row.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+        if !seen_row {
+            seen_row = true;
+            if fields[0].eq_ignore_ascii_case("address") {
+                continue;
+            }
+        }
+        match parse_payout(line, &fields) {
+            Ok(payout) => payouts.push(payout),
+            Err(err) => errors.push(format!("line {}: {}", line, err)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(CliError::UnableToParse("CSV file", errors.join("\n")));
+    }
+    if payouts.is_empty() {
+        return Err(CliError::UnableToParse(
+            "CSV file",
+            "there are no transfers".to_string(),
+        ));
+    }
+    Ok(payouts)
+}
+
+fn parse_payout(line: usize, fields: &[&str]) -> Result<Payout, String> {
+    if fields.len() < 2 || fields.len() > 3 {
+        return Err(format!(
+            "expected `address,amount[,coin type]`, found {} fields",
+            fields.len()
+        ));
+    }
+    let to = AccountAddress::from_str(fields[0])
+        .map_err(|err| format!("invalid address {}: {}", fields[0], err))?;
+    let amount = match u64::from_str(fields[1]) {
+        Ok(0) => return Err("the amount is 0".to_string()),
+        Ok(amount) => amount,
+        Err(err) => return Err(format!("invalid amount {}: {}", fields[1], err)),
+    };
+    let coin_type = match fields.get(2) {
+        None | Some(&"") => None,
+        Some(coin_type) => match parse_type_tag(coin_type) {
+            Ok(coin_type @ TypeTag::Struct(_)) => Some(coin_type),
+            Ok(_) => return Err(format!("coin type {} is not a struct", coin_type)),
+            Err(err) => return Err(format!("invalid coin type {}: {}", coin_type, err)),
+        },
+    };
+    Ok(Payout {
+        line,
+        to,
+        amount,
+        coin_type,
+    })
+}
+
+fn payout_payload(payout: &Payout) -> TransactionPayload {
+    match &payout.coin_type {
+        None => aptos_stdlib::aptos_account_transfer(payout.to, payout.amount),
+        Some(coin_type) => aptos_stdlib::coin_transfer(coin_type.clone(), payout.to, payout.amount),
+    }
+}
+
+fn describe_lines(lines: &[usize]) -> String {
+    match lines {
+        [line] => format!("line {}", line),
+        lines => format!("{} lines", lines.len()),
+    }
+}
+
+fn save_progress(path: &Path, progress: &Progress) -> CliTypedResult<()> {
+    let bytes = serde_json::to_vec_pretty(progress)
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to save progress: {}", err)))?;
+    write_to_file(path, &path.display().to_string(), &bytes)
+}
@@ -0,0 +1,264 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::{list::account_or_profile, resources::Abis},
+    common::{
+        types::{CliCommand, CliTypedResult, ProfileOptions, RestOptions},
+        utils::get_sequence_number,
+    },
+    events::tail::event_handles,
+};
+use aptos_rest_client::{
+    aptos_api_types::{MoveType, TransactionPayload, UserTransaction},
+    Client, Transaction,
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+/// Most transactions sent by the account fetched in a request
+const PAGE_SIZE: u64 = 100;
+/// Most events fetched in a request
+const EVENT_PAGE_SIZE: u16 = 100;
+
+/// Print the transactions touching an account as they're committed, until interrupted
+///
+/// Transactions the account sends are followed by its sequence number, and others touching it,
+/// like transfers to it, by the events emitted to its event handles.  Each transaction is
+/// printed with its function and arguments, and its events decoded with the ABIs of their
+/// modules.
+#[derive(Debug, Parser)]
+pub struct WatchAccount {
+    /// Address of the account to watch
+    #[clap(parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) account: Option<AccountAddress>,
+
+    /// Print a JSON object per transaction, rather than text
+    #[clap(long)]
+    pub(crate) json: bool,
+
+    /// Milliseconds between polls
+    #[clap(long, default_value_t = 1000)]
+    pub(crate) poll_interval_ms: u64,
+
+    /// Stop after this many transactions
+    #[clap(long)]
+    pub(crate) max_transactions: Option<u64>,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[async_trait]
+impl CliCommand<u64> for WatchAccount {
+    fn command_name(&self) -> &'static str {
+        "WatchAccount"
+    }
+
+    async fn execute(self) -> CliTypedResult<u64> {
+        let account = account_or_profile(self.account, &self.profile_options)?;
+        let client = self.rest_options.client(&self.profile_options)?;
+        let mut abis = Abis::default();
+        let mut next_sequence_number = get_sequence_number(&client, account).await?;
+        // Sequence number of the next event of each handle
+        let mut handles: BTreeMap<String, u64> = BTreeMap::new();
+        // A transaction can be found both as sent and by its events, possibly in different polls
+        let mut printed_versions = BTreeSet::new();
+        let mut printed = 0;
+        let mut first_poll = true;
+
+        loop {
+            let mut found: BTreeMap<u64, Option<Transaction>> = BTreeMap::new();
+            loop {
+                let sent = client
+                    .get_account_transactions(account, Some(next_sequence_number), Some(PAGE_SIZE))
+                    .await?
+                    .into_inner();
+                let count = sent.len() as u64;
+                for transaction in sent {
+                    if let Some(version) = transaction.version() {
+                        found.insert(version, Some(transaction));
+                    }
+                }
+                next_sequence_number += count;
+                if count < PAGE_SIZE {
+                    break;
+                }
+            }
+
+            for (key, resource, field, counter) in
+                event_handles(&client, &mut abis, account, None).await?
+            {
+                // Handles found after the first poll are new, so all their events are
+                let next = handles
+                    .entry(key)
+                    .or_insert(if first_poll { counter } else { 0 });
+                while *next < counter {
+                    let events = client
+                        .get_account_events(
+                            account,
+                            &resource.to_string(),
+                            &field,
+                            Some(*next),
+                            Some(EVENT_PAGE_SIZE),
+                        )
+                        .await?
+                        .into_inner();
+                    let last = match events.last() {
+                        Some(last) => last.sequence_number.0,
+                        None => break,
+                    };
+                    for event in events {
+                        found.entry(event.version.0).or_insert(None);
+                    }
+                    *next = last + 1;
+                }
+            }
+            first_poll = false;
+
+            for (version, transaction) in found {
+                if !printed_versions.insert(version) {
+                    continue;
+                }
+                let transaction = match transaction {
+                    Some(transaction) => transaction,
+                    None => client
+                        .get_transaction_by_version(version)
+                        .await?
+                        .into_inner(),
+                };
+                if let Transaction::UserTransaction(user_transaction) = &transaction {
+                    load_event_types(&client, &mut abis, user_transaction).await?;
+                }
+                self.print(&abis, version, &transaction);
+                printed += 1;
+                if Some(printed) == self.max_transactions {
+                    return Ok(printed);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(self.poll_interval_ms)).await;
+        }
+    }
+}
+
+impl WatchAccount {
+    fn print(&self, abis: &Abis, version: u64, transaction: &Transaction) {
+        let user_transaction = match transaction {
+            Transaction::UserTransaction(user_transaction) => user_transaction,
+            // Others touch the account through the framework, e.g. genesis or staking rewards
+            transaction => {
+                if self.json {
+                    println!(
+                        "{}",
+                        json!({
+                            "version": version,
+                            "type": transaction.type_str(),
+                            "vm_status": transaction.vm_status(),
+                        })
+                    );
+                } else {
+                    println!(
+                        "{} {} {}",
+                        version,
+                        transaction.type_str(),
+                        transaction.vm_status()
+                    );
+                }
+                return;
+            }
+        };
+
+        let request = &user_transaction.request;
+        let events: Vec<_> = user_transaction
+            .events
+            .iter()
+            .map(|event| (event.typ.to_string(), abis.decode(&event.typ, &event.data)))
+            .collect();
+        if self.json {
+            println!(
+                "{}",
+                json!({
+                    "version": version,
+                    "hash": user_transaction.info.hash,
+                    "sender": request.sender,
+                    "sequence_number": request.sequence_number.0,
+                    "success": user_transaction.info.success,
+                    "vm_status": user_transaction.info.vm_status,
+                    "payload": request.payload,
+                    "events": events
+                        .iter()
+                        .map(|(typ, data)| json!({ "type": typ, "data": data }))
+                        .collect::<Vec<_>>(),
+                })
+            );
+        } else {
+            println!(
+                "{} {}#{} {} {}",
+                version,
+                request.sender,
+                request.sequence_number.0,
+                describe_payload(&request.payload),
+                user_transaction.info.vm_status
+            );
+            for (typ, data) in &events {
+                println!(
+                    "  {} {}",
+                    typ,
+                    serde_json::to_string(data).unwrap_or_default()
+                );
+            }
+        }
+    }
+}
+
+/// The function called, with its type arguments and arguments
+pub(crate) fn describe_payload(payload: &TransactionPayload) -> String {
+    let call = |function: String, type_arguments: &[MoveType], arguments: &[serde_json::Value]| {
+        let type_arguments = if type_arguments.is_empty() {
+            String::new()
+        } else {
+            let type_arguments: Vec<_> = type_arguments.iter().map(|typ| typ.to_string()).collect();
+            format!("<{}>", type_arguments.join(", "))
+        };
+        let arguments: Vec<_> = arguments.iter().map(|arg| arg.to_string()).collect();
+        format!("{}{}({})", function, type_arguments, arguments.join(", "))
+    };
+    match payload {
+        TransactionPayload::EntryFunctionPayload(payload) => call(
+            payload.function.to_string(),
+            &payload.type_arguments,
+            &payload.arguments,
+        ),
+        TransactionPayload::ScriptPayload(payload) => call(
+            "script".to_string(),
+            &payload.type_arguments,
+            &payload.arguments,
+        ),
+        TransactionPayload::ModuleBundlePayload(_) => "publish".to_string(),
+    }
+}
+
+/// Fetches the ABIs of the transaction's event types, to decode them with
+async fn load_event_types(
+    client: &Client,
+    abis: &mut Abis,
+    transaction: &UserTransaction,
+) -> CliTypedResult<()> {
+    let types = transaction
+        .events
+        .iter()
+        .filter_map(|event| match &event.typ {
+            MoveType::Struct(tag) => Some(tag.clone()),
+            _ => None,
+        })
+        .collect();
+    abis.load(client, types).await
+}
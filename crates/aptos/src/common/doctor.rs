@@ -0,0 +1,399 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::{
+    init::Network,
+    types::{
+        CliCommand, CliConfig, CliError, CliTypedResult, ConfigSearchMode, ProfileConfig,
+        ProfileOptions, RestOptions, DEFAULT_PROFILE,
+    },
+    utils::{cli_build_information, dir_default_to_current, read_from_file},
+};
+use aptos_build_info::BUILD_COMMIT_HASH;
+use aptos_rest_client::{
+    aptos_api_types::AptosErrorCode,
+    error::{AptosErrorResponse, RestError},
+    Client,
+};
+use async_trait::async_trait;
+use clap::Parser;
+use move_package::source_package::layout::SourcePackageLayout;
+use serde::Serialize;
+use std::{path::PathBuf, process::Command};
+
+/// Check the CLI environment and Move package for common problems
+///
+/// This checks the CLI version against the network, the Aptos framework revision in
+/// the Move.toml against the profile's network, that the profile's account exists and
+/// is funded, and that tools used by other commands are installed.  Every problem
+/// found comes with a suggested fix.
+#[derive(Debug, Parser)]
+pub struct DoctorTool {
+    /// Path to a move package (the folder with a Move.toml file)
+    ///
+    /// Defaults to the current directory.  Package checks are skipped if there is no Move.toml
+    #[clap(long, parse(from_os_str))]
+    pub(crate) package_dir: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// The result of a single doctor check
+#[derive(Debug, Serialize)]
+pub struct DoctorCheck {
+    pub check: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn ok(check: &'static str, message: String) -> Self {
+        DoctorCheck {
+            check,
+            status: CheckStatus::Ok,
+            message,
+            fix: None,
+        }
+    }
+
+    fn warning(check: &'static str, message: String, fix: String) -> Self {
+        DoctorCheck {
+            check,
+            status: CheckStatus::Warning,
+            message,
+            fix: Some(fix),
+        }
+    }
+
+    fn error(check: &'static str, message: String, fix: String) -> Self {
+        DoctorCheck {
+            check,
+            status: CheckStatus::Error,
+            message,
+            fix: Some(fix),
+        }
+    }
+}
+
+#[async_trait]
+impl CliCommand<Vec<DoctorCheck>> for DoctorTool {
+    fn command_name(&self) -> &'static str {
+        "Doctor"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<DoctorCheck>> {
+        let profile_name = self
+            .profile_options
+            .profile_name()
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_string();
+        let mut checks = Vec::new();
+
+        let profile = match CliConfig::load_profile(
+            self.profile_options.profile_name(),
+            ConfigSearchMode::CurrentDirAndParents,
+        ) {
+            Ok(Some(profile)) => Some(profile),
+            Ok(None)
+            | Err(CliError::ConfigNotFoundError(_))
+            | Err(CliError::CommandArgumentError(_)) => {
+                checks.push(DoctorCheck::error(
+                    "profile",
+                    format!("Profile {} was not found", profile_name),
+                    format!("Run `aptos init --profile {}`", profile_name),
+                ));
+                None
+            }
+            // `aptos init` would overwrite a config that fails to load, so it isn't suggested
+            Err(err) => {
+                checks.push(DoctorCheck::error(
+                    "profile",
+                    format!("Unable to load the config: {}", err),
+                    "Fix the config file in `.aptos` or `~/.aptos`".to_string(),
+                ));
+                None
+            }
+        };
+
+        // Only a reachable endpoint is used for the account checks
+        let client = match self.rest_options.client(&self.profile_options) {
+            Ok(client) => {
+                let check = check_cli_version(&client).await;
+                let reachable = check.status != CheckStatus::Error;
+                checks.push(check);
+                Some(client).filter(|_| reachable)
+            }
+            Err(_) => {
+                checks.push(DoctorCheck::error(
+                    "network",
+                    "No REST URL is configured".to_string(),
+                    "Pass `--url` or add a rest_url to the profile with `aptos init`".to_string(),
+                ));
+                None
+            }
+        };
+
+        if let Some(ref profile) = profile {
+            checks.extend(check_profile(&profile_name, profile, client.as_ref()).await);
+        }
+
+        let network = profile.as_ref().and_then(|profile| profile.network);
+        checks.extend(check_package(self.package_dir, network)?);
+        checks.extend(check_tools());
+
+        for check in checks.iter() {
+            let status = match check.status {
+                CheckStatus::Ok => "ok",
+                CheckStatus::Warning => "warning",
+                CheckStatus::Error => "error",
+            };
+            eprintln!("[{}] {}: {}", status, check.check, check.message);
+            if let Some(ref fix) = check.fix {
+                eprintln!("    fix: {}", fix);
+            }
+        }
+
+        Ok(checks)
+    }
+}
+
+/// Compares the commit the CLI was built from with the commit the node reports
+async fn check_cli_version(client: &Client) -> DoctorCheck {
+    const CHECK: &str = "cli_version";
+    let cli_hash = cli_build_information()
+        .remove(BUILD_COMMIT_HASH)
+        .unwrap_or_default();
+    match client.get_index().await {
+        Ok(response) => match response.into_inner().git_hash {
+            Some(node_hash) if node_hash == cli_hash => {
+                DoctorCheck::ok(CHECK, format!("CLI and node are both at {}", cli_hash))
+            }
+            Some(node_hash) => DoctorCheck::warning(
+                CHECK,
+                format!(
+                    "CLI was built from {}, but the node is running {}",
+                    cli_hash, node_hash
+                ),
+                "Install the CLI release matching the network's release".to_string(),
+            ),
+            None => DoctorCheck::ok(
+                CHECK,
+                "Node does not report its version, skipping".to_string(),
+            ),
+        },
+        Err(err) => DoctorCheck::error(
+            CHECK,
+            format!("Unable to reach the REST endpoint: {}", err),
+            "Check the rest_url in the profile or pass `--url`".to_string(),
+        ),
+    }
+}
+
+/// Checks that the profile can sign, and that its account exists and is funded
+async fn check_profile(
+    profile_name: &str,
+    profile: &ProfileConfig,
+    client: Option<&Client>,
+) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    if profile.has_private_key() {
+        checks.push(DoctorCheck::ok(
+            "profile_key",
+            format!("Profile {} has a private key", profile_name),
+        ));
+    } else {
+        checks.push(DoctorCheck::warning(
+            "profile_key",
+            format!(
+                "Profile {} has no private key, commands that sign transactions will fail",
+                profile_name
+            ),
+            format!("Run `aptos init --profile {}`", profile_name),
+        ));
+    }
+
+    let (account, client) = match (profile.account, client) {
+        (Some(account), Some(client)) => (account, client),
+        (None, _) => {
+            checks.push(DoctorCheck::error(
+                "account",
+                format!("Profile {} has no account", profile_name),
+                format!("Run `aptos init --profile {}`", profile_name),
+            ));
+            return checks;
+        }
+        (Some(account), None) => {
+            checks.push(DoctorCheck::warning(
+                "account_balance",
+                format!(
+                    "Account {} was not checked, as the REST endpoint couldn't be reached",
+                    account
+                ),
+                "Fix the REST endpoint, then run `aptos doctor` again".to_string(),
+            ));
+            return checks;
+        }
+    };
+
+    let fund_fix = if profile.faucet_url.is_some() {
+        format!(
+            "Run `aptos account fund-with-faucet --profile {} --account {}`",
+            profile_name, account
+        )
+    } else {
+        format!("Transfer APT to {} from a funded account", account)
+    };
+    match client.get_account_balance(account).await {
+        Ok(balance) => {
+            let balance = balance.into_inner().get();
+            if balance == 0 {
+                checks.push(DoctorCheck::warning(
+                    "account_balance",
+                    format!("Account {} has no APT to pay for gas", account),
+                    fund_fix,
+                ));
+            } else {
+                checks.push(DoctorCheck::ok(
+                    "account_balance",
+                    format!("Account {} has {} Octas", account, balance),
+                ));
+            }
+        }
+        Err(RestError::Api(AptosErrorResponse { error, .. }))
+            if matches!(
+                error.error_code,
+                AptosErrorCode::AccountNotFound | AptosErrorCode::ResourceNotFound
+            ) =>
+        {
+            checks.push(DoctorCheck::error(
+                "account_balance",
+                format!("Account {} was not found on chain", account),
+                fund_fix,
+            ))
+        }
+        Err(err) => checks.push(DoctorCheck::error(
+            "account_balance",
+            format!("Unable to look up account {}: {}", account, err),
+            "Check the rest_url in the profile or pass `--url`".to_string(),
+        )),
+    }
+    checks
+}
+
+/// Checks that Aptos framework git dependencies track the profile's network
+fn check_package(
+    package_dir: Option<PathBuf>,
+    network: Option<Network>,
+) -> CliTypedResult<Vec<DoctorCheck>> {
+    const CHECK: &str = "framework_rev";
+    let manifest_path =
+        dir_default_to_current(package_dir)?.join(SourcePackageLayout::Manifest.path());
+    if !manifest_path.exists() {
+        return Ok(vec![]);
+    }
+
+    let manifest = String::from_utf8(read_from_file(manifest_path.as_path())?)?;
+    let manifest: toml::Value = match toml::from_str(&manifest) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            return Ok(vec![DoctorCheck::error(
+                "manifest",
+                format!("Unable to parse {}: {}", manifest_path.display(), err),
+                "Fix the syntax error in the Move.toml".to_string(),
+            )])
+        }
+    };
+
+    let expected_rev = match network {
+        Some(Network::Mainnet) => "mainnet",
+        Some(Network::Testnet) => "testnet",
+        Some(Network::Devnet) => "devnet",
+        // Local and custom networks can run any revision
+        _ => return Ok(vec![]),
+    };
+
+    let mut checks = Vec::new();
+    let dependencies = manifest
+        .get("dependencies")
+        .and_then(|deps| deps.as_table())
+        .cloned()
+        .unwrap_or_default();
+    for (name, dependency) in dependencies {
+        let is_aptos_core = dependency
+            .get("git")
+            .and_then(|git| git.as_str())
+            .map_or(false, |git| git.contains("aptos-labs/aptos-core"));
+        if !is_aptos_core {
+            continue;
+        }
+
+        let rev = dependency
+            .get("rev")
+            .and_then(|rev| rev.as_str())
+            .unwrap_or_default();
+        if rev == expected_rev {
+            checks.push(DoctorCheck::ok(
+                CHECK,
+                format!("{} tracks the {} branch", name, expected_rev),
+            ));
+        } else {
+            checks.push(DoctorCheck::warning(
+                CHECK,
+                format!(
+                    "{} is at rev `{}`, which may not match the framework on {}",
+                    name, rev, expected_rev
+                ),
+                format!("Set `rev = \"{}\"` for {} in Move.toml", expected_rev, name),
+            ));
+        }
+    }
+    Ok(checks)
+}
+
+/// Checks for external tools used by other commands
+fn check_tools() -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    if Command::new("git").arg("--version").output().is_ok() {
+        checks.push(DoctorCheck::ok("git", "git is installed".to_string()));
+    } else {
+        checks.push(DoctorCheck::warning(
+            "git",
+            "git was not found, git dependencies in Move.toml can't be fetched".to_string(),
+            "Install git and make sure it is on the PATH".to_string(),
+        ));
+    }
+
+    for env_var in ["BOOGIE_EXE", "Z3_EXE"] {
+        let installed = std::env::var(env_var)
+            .map(|path| PathBuf::from(path).exists())
+            .unwrap_or(false);
+        if installed {
+            checks.push(DoctorCheck::ok(
+                "prover",
+                format!("{} points to an installed binary", env_var),
+            ));
+        } else {
+            checks.push(DoctorCheck::warning(
+                "prover",
+                format!(
+                    "{} is not set to an installed binary, `aptos move prove` will fail",
+                    env_var
+                ),
+                "Run `scripts/dev_setup.sh -b -p -y` from aptos-core".to_string(),
+            ));
+        }
+    }
+    checks
+}
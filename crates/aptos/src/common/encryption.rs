@@ -0,0 +1,169 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::types::{CliError, CliTypedResult};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    pbkdf2::{self, PBKDF2_HMAC_SHA256},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::{num::NonZeroU32, sync::Mutex};
+
+/// Environment variable holding the passphrase for encrypted private keys
+pub const KEY_PASSPHRASE_ENV: &str = "APTOS_KEY_PASSPHRASE";
+
+const KDF_PBKDF2_SHA256: &str = "pbkdf2-hmac-sha256";
+const CIPHER_AES_256_GCM: &str = "aes-256-gcm";
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+/// Passphrase entered during this run, so it's only prompted for once per command
+static CACHED_PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Data encrypted with a key derived from a passphrase
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptedData {
+    /// Key derivation function used on the passphrase
+    pub kdf: String,
+    /// Number of iterations of the key derivation function
+    pub iterations: u32,
+    /// Hex encoded salt for the key derivation function
+    pub salt: String,
+    /// Cipher used to encrypt the data
+    pub cipher: String,
+    /// Hex encoded nonce for the cipher
+    pub nonce: String,
+    /// Hex encoded ciphertext, including the authentication tag
+    pub ciphertext: String,
+}
+
+impl EncryptedData {
+    /// Encrypts `plaintext` with a key derived from `passphrase`
+    pub fn encrypt(plaintext: &[u8], passphrase: &str) -> CliTypedResult<Self> {
+        let rng = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill(&mut salt)
+            .and_then(|_| rng.fill(&mut nonce))
+            .map_err(|_| CliError::UnexpectedError("Failed to generate randomness".to_string()))?;
+
+        let key = derive_key(passphrase, &salt, PBKDF2_ITERATIONS)?;
+        let mut in_out = plaintext.to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut in_out,
+        )
+        .map_err(|_| CliError::UnexpectedError("Failed to encrypt data".to_string()))?;
+
+        Ok(EncryptedData {
+            kdf: KDF_PBKDF2_SHA256.to_string(),
+            iterations: PBKDF2_ITERATIONS,
+            salt: hex::encode(salt),
+            cipher: CIPHER_AES_256_GCM.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(in_out),
+        })
+    }
+
+    /// Decrypts the data with a key derived from `passphrase`
+    pub fn decrypt(&self, passphrase: &str) -> CliTypedResult<Vec<u8>> {
+        if self.kdf != KDF_PBKDF2_SHA256 || self.cipher != CIPHER_AES_256_GCM {
+            return Err(CliError::UnexpectedError(format!(
+                "Unsupported encryption {} with {}",
+                self.cipher, self.kdf
+            )));
+        }
+        // A file could otherwise make every unlock derive for arbitrarily long, or barely at all
+        if self.iterations != PBKDF2_ITERATIONS {
+            return Err(CliError::UnexpectedError(format!(
+                "Unsupported number of {} iterations {}, expected {}",
+                self.kdf, self.iterations, PBKDF2_ITERATIONS
+            )));
+        }
+        let salt = hex::decode(&self.salt)?;
+        let nonce = Nonce::try_assume_unique_for_key(&hex::decode(&self.nonce)?)
+            .map_err(|_| CliError::UnableToParse("nonce", "Invalid length".to_string()))?;
+        let mut in_out = hex::decode(&self.ciphertext)?;
+
+        let key = derive_key(passphrase, &salt, self.iterations)?;
+        let plaintext = key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| {
+                CliError::CommandArgumentError(
+                    "Failed to decrypt, the passphrase is incorrect".to_string(),
+                )
+            })?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> CliTypedResult<LessSafeKey> {
+    let iterations = NonZeroU32::new(iterations)
+        .ok_or_else(|| CliError::UnableToParse("iterations", "Must be non-zero".to_string()))?;
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::derive(
+        PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key)
+        .map_err(|_| CliError::UnexpectedError("Failed to build encryption key".to_string()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Retrieves the passphrase for encrypted private keys
+///
+/// Order of operations
+/// 1. The passphrase already entered during this run
+/// 2. `APTOS_KEY_PASSPHRASE`
+/// 3. Prompt, asking twice if `confirm` is set
+pub fn key_passphrase(confirm: bool) -> CliTypedResult<String> {
+    let mut cached = CACHED_PASSPHRASE.lock().unwrap();
+    if let Some(ref passphrase) = *cached {
+        return Ok(passphrase.clone());
+    }
+
+    let passphrase = read_passphrase("encrypted private key", KEY_PASSPHRASE_ENV, confirm)?;
+    *cached = Some(passphrase.clone());
+    Ok(passphrase)
+}
+
+/// Reads a passphrase from `env_var`, or prompts for it, asking twice if `confirm` is set
+pub fn read_passphrase(
+    purpose: &str,
+    env_var: &'static str,
+    confirm: bool,
+) -> CliTypedResult<String> {
+    let passphrase = if let Ok(passphrase) = std::env::var(env_var) {
+        passphrase
+    } else {
+        eprintln!("Enter the passphrase for the {}", purpose);
+        let passphrase = read_hidden()?;
+        if confirm {
+            eprintln!("Enter the passphrase again to confirm");
+            if read_hidden()? != passphrase {
+                return Err(CliError::CommandArgumentError(
+                    "Passphrases do not match".to_string(),
+                ));
+            }
+        }
+        passphrase
+    };
+
+    if passphrase.is_empty() {
+        return Err(CliError::CommandArgumentError(
+            "Passphrase must not be empty".to_string(),
+        ));
+    }
+    Ok(passphrase)
+}
+
+/// Reads a passphrase from the terminal without echoing it
+fn read_hidden() -> CliTypedResult<String> {
+    rpassword::read_password().map_err(|err| CliError::IO("Passphrase".to_string(), err))
+}
@@ -3,22 +3,34 @@
 
 use crate::common::types::{ConfigSearchMode, DEFAULT_PROFILE};
 use crate::common::{
+    keychain::{self, KeyStore},
+    mnemonic::{derive_ed25519_private_key, parse_mnemonic, APTOS_DERIVATION_PATH},
     types::{
         account_address_from_public_key, CliCommand, CliConfig, CliError, CliTypedResult,
         EncodingOptions, PrivateKeyInputOptions, ProfileConfig, ProfileOptions, PromptOptions,
         RngArgs,
     },
-    utils::{fund_account, prompt_yes_with_override, read_line},
+    utils::{
+        fund_account_with_retries, generate_vanity_ed25519_private_key, prompt_yes_with_override,
+        read_line,
+    },
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    PrivateKey, ValidCryptoMaterialStringExt,
 };
-use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, ValidCryptoMaterialStringExt};
-use aptos_rest_client::aptos_api_types::{AptosError, AptosErrorCode};
+use aptos_rest_client::aptos_api_types::{AptosError, AptosErrorCode, HashValue};
 use aptos_rest_client::error::{AptosErrorResponse, RestError};
+use aptos_rest_client::Client;
 use async_trait::async_trait;
 use clap::Parser;
+use move_core_types::account_address::AccountAddress;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 
 /// 1 APT (might not actually get that much, depending on the faucet)
 const NUM_DEFAULT_OCTAS: u64 = 100000000;
@@ -34,6 +46,17 @@ pub struct InitTool {
     #[clap(long)]
     pub network: Option<Network>,
 
+    /// Networks to create profiles for in one go e.g. `devnet,testnet,mainnet`
+    ///
+    /// Each profile is named after its network and shares one key, unless
+    /// `--separate-keys` is given.  Accounts are funded on networks with a faucet
+    #[clap(long, value_delimiter = ',', conflicts_with = "network")]
+    pub networks: Vec<Network>,
+
+    /// Generate a different key for each of `--networks`
+    #[clap(long, requires = "networks")]
+    pub separate_keys: bool,
+
     /// URL to a fullnode on the network
     #[clap(long)]
     pub rest_url: Option<Url>,
@@ -46,6 +69,57 @@ pub struct InitTool {
     #[clap(long)]
     pub skip_faucet: bool,
 
+    /// Bearer token for faucets that require authentication, saved to the profile
+    #[clap(long)]
+    pub faucet_auth_token: Option<String>,
+
+    /// Number of Octas to request from the faucet for a new account
+    ///
+    /// The faucet may limit the amount actually funded
+    #[clap(long, default_value_t = NUM_DEFAULT_OCTAS)]
+    pub faucet_amount: u64,
+
+    /// Store the private key encrypted with a passphrase
+    ///
+    /// The passphrase is read from `APTOS_KEY_PASSPHRASE`, or prompted for otherwise
+    #[clap(long, conflicts_with = "key-store")]
+    pub encrypt_private_key: bool,
+
+    /// Where to store the private key: [file, keychain]
+    ///
+    /// With `keychain`, the key goes into the OS keychain (macOS Keychain or the Secret
+    /// Service on Linux, Windows isn't supported) and the config only holds the name of the
+    /// keychain entry.
+    /// Defaults to where the profile's existing key is stored, or `file`
+    #[clap(long)]
+    pub key_store: Option<KeyStore>,
+
+    /// Generate a key whose account address starts with this hex prefix
+    ///
+    /// Keys are generated in parallel until one matches.  Each extra character makes the
+    /// search 16 times slower
+    #[clap(long)]
+    pub vanity_prefix: Option<String>,
+
+    /// Derive the key from a BIP-39 mnemonic, e.g. one exported from a wallet
+    #[clap(long, conflicts_with_all = &["vanity-prefix", "private-key", "private-key-file", "separate-keys"])]
+    pub mnemonic: Option<String>,
+
+    /// Derivation path for the key derived from `--mnemonic`
+    #[clap(long, default_value = APTOS_DERIVATION_PATH)]
+    pub derivation_path: String,
+
+    /// Create a read-only profile watching this account, without a private key
+    ///
+    /// Read-only profiles work for commands that only read the account, and fail with an
+    /// error for commands that sign transactions
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg), conflicts_with_all = &["networks", "vanity-prefix", "mnemonic", "private-key", "private-key-file"])]
+    pub watch_address: Option<AccountAddress>,
+
+    /// Public key of the watched account, needed to simulate transactions for it
+    #[clap(long, parse(try_from_str=Ed25519PublicKey::from_encoded_string), requires = "watch-address")]
+    pub watch_public_key: Option<Ed25519PublicKey>,
+
     #[clap(flatten)]
     pub rng_args: RngArgs,
     #[clap(flatten)]
@@ -65,6 +139,10 @@ impl CliCommand<()> for InitTool {
     }
 
     async fn execute(self) -> CliTypedResult<()> {
+        if !self.networks.is_empty() {
+            return self.init_networks().await;
+        }
+
         let mut config = if CliConfig::config_exists(ConfigSearchMode::CurrentDir) {
             CliConfig::load(ConfigSearchMode::CurrentDir)?
         } else {
@@ -103,27 +181,21 @@ impl CliCommand<()> for InitTool {
                 Network::from_str(input)?
             }
         };
+        self.set_network(network, &mut profile_config)?;
 
-        match network {
-            Network::Mainnet => {
-                profile_config.rest_url =
-                    Some("https://fullnode.mainnet.aptoslabs.com".to_string());
-                profile_config.faucet_url = None;
-            }
-            Network::Testnet => {
-                profile_config.rest_url =
-                    Some("https://fullnode.testnet.aptoslabs.com".to_string());
-                profile_config.faucet_url = None;
-            }
-            Network::Devnet => {
-                profile_config.rest_url = Some("https://fullnode.devnet.aptoslabs.com".to_string());
-                profile_config.faucet_url = Some("https://faucet.devnet.aptoslabs.com".to_string());
-            }
-            Network::Local => {
-                profile_config.rest_url = Some("http://localhost:8080".to_string());
-                profile_config.faucet_url = Some("http://localhost:8081".to_string());
-            }
-            Network::Custom => self.custom_network(&mut profile_config)?,
+        if let Some(address) = self.watch_address {
+            profile_config.private_key = None;
+            profile_config.encrypted_private_key = None;
+            profile_config.keychain_entry = None;
+            profile_config.public_key = self.watch_public_key.clone();
+            profile_config.account = Some(address);
+            config
+                .profiles
+                .get_or_insert_with(BTreeMap::new)
+                .insert(profile_name.to_string(), profile_config);
+            config.save()?;
+            eprintln!("\n---\nAptos CLI is now set up to watch account {} as read-only profile {}!  Commands that sign transactions will need a private key", address, profile_name);
+            return Ok(());
         }
 
         // Private key
@@ -133,12 +205,17 @@ impl CliCommand<()> for InitTool {
         {
             eprintln!("Using command line argument for private key");
             private_key
+        } else if let Some(private_key) = self.mnemonic_private_key()? {
+            eprintln!("Using key derived from the mnemonic");
+            private_key
+        } else if let Some(ref prefix) = self.vanity_prefix {
+            generate_vanity_ed25519_private_key(&self.rng_args, prefix)?
         } else {
-            eprintln!("Enter your private key as a hex literal (0x...) [Current: {} | No input: Generate new key (or keep one if present)]", profile_config.private_key.as_ref().map(|_| "Redacted").unwrap_or("None"));
+            eprintln!("Enter your private key as a hex literal (0x...) [Current: {} | No input: Generate new key (or keep one if present)]", if profile_config.has_private_key() { "Redacted" } else { "None" });
             let input = read_line("Private key")?;
             let input = input.trim();
             if input.is_empty() {
-                if let Some(private_key) = profile_config.private_key {
+                if let Some(private_key) = profile_config.load_private_key()? {
                     eprintln!("No key given, keeping existing key...");
                     private_key
                 } else {
@@ -152,77 +229,10 @@ impl CliCommand<()> for InitTool {
                     .map_err(|err| CliError::UnableToParse("Ed25519PrivateKey", err.to_string()))?
             }
         };
-        let public_key = private_key.public_key();
-        let address = account_address_from_public_key(&public_key);
-        profile_config.private_key = Some(private_key);
-        profile_config.public_key = Some(public_key);
-        profile_config.account = Some(address);
+        let address = self.store_private_key(&mut profile_config, private_key, profile_name)?;
 
         // Create account if it doesn't exist (and there's a faucet)
-        let client = aptos_rest_client::Client::new(
-            Url::parse(profile_config.rest_url.as_ref().unwrap())
-                .map_err(|err| CliError::UnableToParse("rest_url", err.to_string()))?,
-        );
-
-        // Check if account exists
-        let account_exists = match client.get_account(address).await {
-            Ok(_) => true,
-            Err(err) => {
-                if let RestError::Api(AptosErrorResponse {
-                    error:
-                        AptosError {
-                            error_code: AptosErrorCode::ResourceNotFound,
-                            ..
-                        },
-                    ..
-                })
-                | RestError::Api(AptosErrorResponse {
-                    error:
-                        AptosError {
-                            error_code: AptosErrorCode::AccountNotFound,
-                            ..
-                        },
-                    ..
-                }) = err
-                {
-                    false
-                } else {
-                    return Err(CliError::UnexpectedError(format!(
-                        "Failed to check if account exists: {:?}",
-                        err
-                    )));
-                }
-            }
-        };
-        if let Some(ref faucet_url) = profile_config.faucet_url {
-            if account_exists {
-                eprintln!("Account {} has been already found onchain", address);
-            } else {
-                eprintln!(
-                    "Account {} doesn't exist, creating it and funding it with {} Octas",
-                    address, NUM_DEFAULT_OCTAS
-                );
-                match fund_account(
-                    Url::parse(faucet_url)
-                        .map_err(|err| CliError::UnableToParse("rest_url", err.to_string()))?,
-                    NUM_DEFAULT_OCTAS,
-                    address,
-                )
-                .await
-                {
-                    Ok(_) => eprintln!("Account {} funded successfully", address),
-                    Err(err) => eprintln!("Account {} failed to be funded: {:?}", address, err),
-                };
-            }
-        } else if account_exists {
-            eprintln!("Account {} has been already found onchain", address);
-        } else if network == Network::Testnet {
-            eprintln!("Account {} does not exist, you will need to create and fund the account through a community faucet e.g. https://aptoslabs.com/testnet-faucet, or by transferring funds from another account", address);
-        } else if network == Network::Mainnet {
-            eprintln!("Account {} does not exist, you will need to create and fund the account through a faucet or by transferring funds from another account", address);
-        } else {
-            eprintln!("Account {} has been initialized locally, but you must have coins transferred to it to create the account onchain", address);
-        }
+        create_account(network, &profile_config, address, self.faucet_amount).await?;
 
         // Ensure the loaded config has profiles setup for a possible empty file
         if config.profiles.is_none() {
@@ -240,6 +250,154 @@ impl CliCommand<()> for InitTool {
 }
 
 impl InitTool {
+    /// Derives the private key from `--mnemonic`, if given
+    fn mnemonic_private_key(&self) -> CliTypedResult<Option<Ed25519PrivateKey>> {
+        self.mnemonic
+            .as_ref()
+            .map(|phrase| {
+                derive_ed25519_private_key(&parse_mnemonic(phrase)?, &self.derivation_path)
+            })
+            .transpose()
+    }
+
+    /// Creates one profile per network in `networks`, named after the network
+    async fn init_networks(&self) -> CliTypedResult<()> {
+        if self.networks.contains(&Network::Custom) {
+            return Err(CliError::CommandArgumentError(
+                "Custom networks need their own URLs, use `aptos init --network custom`"
+                    .to_string(),
+            ));
+        }
+        let cli_private_key = self
+            .private_key_options
+            .extract_private_key_cli(self.encoding_options.encoding)?;
+        if self.separate_keys && (cli_private_key.is_some() || self.vanity_prefix.is_some()) {
+            return Err(CliError::CommandArgumentError(
+                "--separate-keys can't be used with a given private key or vanity prefix"
+                    .to_string(),
+            ));
+        }
+
+        let mut config = if CliConfig::config_exists(ConfigSearchMode::CurrentDir) {
+            CliConfig::load(ConfigSearchMode::CurrentDir)?
+        } else {
+            CliConfig::default()
+        };
+        let existing: Vec<String> = self
+            .networks
+            .iter()
+            .map(|network| network.to_string())
+            .filter(|name| {
+                config
+                    .profiles
+                    .as_ref()
+                    .map_or(false, |profiles| profiles.contains_key(name))
+            })
+            .collect();
+        if !existing.is_empty() {
+            prompt_yes_with_override(
+                &format!(
+                    "Aptos already initialized for profiles {}, do you want to overwrite the existing configs?",
+                    existing.join(", ")
+                ),
+                self.prompt_options,
+            )?;
+        }
+
+        let mut keygen = self.rng_args.key_generator()?;
+        let shared_private_key = if self.separate_keys {
+            None
+        } else if let Some(private_key) = cli_private_key {
+            eprintln!("Using command line argument for private key");
+            Some(private_key)
+        } else if let Some(private_key) = self.mnemonic_private_key()? {
+            eprintln!("Using key derived from the mnemonic");
+            Some(private_key)
+        } else if let Some(ref prefix) = self.vanity_prefix {
+            Some(generate_vanity_ed25519_private_key(&self.rng_args, prefix)?)
+        } else {
+            Some(keygen.generate_ed25519_private_key())
+        };
+
+        let mut addresses = Vec::new();
+        for network in self.networks.iter().copied() {
+            let profile_name = network.to_string();
+            eprintln!("Configuring for profile {}", profile_name);
+            let mut profile_config = config.remove_profile(&profile_name).unwrap_or_default();
+            self.set_network(network, &mut profile_config)?;
+
+            let private_key = shared_private_key
+                .clone()
+                .unwrap_or_else(|| keygen.generate_ed25519_private_key());
+            let address =
+                self.store_private_key(&mut profile_config, private_key, &profile_name)?;
+            create_account(network, &profile_config, address, self.faucet_amount).await?;
+
+            config
+                .profiles
+                .get_or_insert_with(BTreeMap::new)
+                .insert(profile_name.clone(), profile_config);
+            addresses.push((profile_name, address));
+        }
+        config.save()?;
+
+        eprintln!("\n---\nAptos CLI is now set up for the following profiles!  Run `aptos --help` for more information about commands");
+        for (profile_name, address) in addresses {
+            eprintln!("{:<10} {}", profile_name, address);
+        }
+        Ok(())
+    }
+
+    /// Fills in the network and its default URLs
+    fn set_network(
+        &self,
+        network: Network,
+        profile_config: &mut ProfileConfig,
+    ) -> CliTypedResult<()> {
+        if let Some((rest_url, faucet_url)) = network.default_urls() {
+            profile_config.rest_url = Some(rest_url.to_string());
+            profile_config.faucet_url = faucet_url.map(|url| url.to_string());
+        } else {
+            self.custom_network(profile_config)?;
+        }
+        if let Some(ref token) = self.faucet_auth_token {
+            profile_config.faucet_auth_token = Some(token.clone());
+        }
+        profile_config.network = Some(network);
+        Ok(())
+    }
+
+    /// Saves the private key to the profile in the selected key store, returning its address
+    fn store_private_key(
+        &self,
+        profile_config: &mut ProfileConfig,
+        private_key: Ed25519PrivateKey,
+        profile_name: &str,
+    ) -> CliTypedResult<AccountAddress> {
+        let public_key = private_key.public_key();
+        let address = account_address_from_public_key(&public_key);
+        // Keep an existing key in the same kind of store
+        let key_store = self
+            .key_store
+            .unwrap_or(if profile_config.keychain_entry.is_some() {
+                KeyStore::Keychain
+            } else {
+                KeyStore::File
+            });
+        match key_store {
+            KeyStore::Keychain => profile_config
+                .set_keychain_private_key(private_key, keychain::new_entry_name(profile_name))?,
+            KeyStore::File => {
+                let encrypt =
+                    self.encrypt_private_key || profile_config.encrypted_private_key.is_some();
+                profile_config.set_private_key(private_key, encrypt)?
+            }
+        }
+        profile_config.public_key = Some(public_key);
+        profile_config.account = Some(address);
+        Ok(address)
+    }
+
     fn custom_network(&self, profile_config: &mut ProfileConfig) -> CliTypedResult<()> {
         // Rest Endpoint
         let rest_url = if let Some(ref rest_url) = self.rest_url {
@@ -311,6 +469,124 @@ impl InitTool {
     }
 }
 
+/// Creates the account on chain through the faucet if it doesn't exist and there is one
+async fn create_account(
+    network: Network,
+    profile_config: &ProfileConfig,
+    address: AccountAddress,
+    faucet_amount: u64,
+) -> CliTypedResult<()> {
+    let client = Client::new(
+        Url::parse(profile_config.rest_url.as_ref().unwrap())
+            .map_err(|err| CliError::UnableToParse("rest_url", err.to_string()))?,
+    );
+
+    // Check if account exists
+    let account_exists = match client.get_account(address).await {
+        Ok(_) => true,
+        Err(err) => {
+            if let RestError::Api(AptosErrorResponse {
+                error:
+                    AptosError {
+                        error_code: AptosErrorCode::ResourceNotFound,
+                        ..
+                    },
+                ..
+            })
+            | RestError::Api(AptosErrorResponse {
+                error:
+                    AptosError {
+                        error_code: AptosErrorCode::AccountNotFound,
+                        ..
+                    },
+                ..
+            }) = err
+            {
+                false
+            } else {
+                return Err(CliError::UnexpectedError(format!(
+                    "Failed to check if account exists: {:?}",
+                    err
+                )));
+            }
+        }
+    };
+    if let Some(ref faucet_url) = profile_config.faucet_url {
+        if account_exists {
+            eprintln!("Account {} has been already found onchain", address);
+        } else {
+            eprintln!(
+                "Account {} doesn't exist, creating it and funding it with {} Octas",
+                address, faucet_amount
+            );
+            match fund_account_with_retries(
+                Url::parse(faucet_url)
+                    .map_err(|err| CliError::UnableToParse("rest_url", err.to_string()))?,
+                profile_config.faucet_auth_token.as_deref(),
+                faucet_amount,
+                address,
+            )
+            .await
+            {
+                Ok(hashes) => {
+                    if let Err(err) = check_funded_balance(&client, address, hashes).await {
+                        eprintln!(
+                            "Account {} funding could not be confirmed: {:?}",
+                            address, err
+                        )
+                    }
+                }
+                Err(err) => eprintln!("Account {} failed to be funded: {:?}", address, err),
+            };
+        }
+    } else if account_exists {
+        eprintln!("Account {} has been already found onchain", address);
+    } else if network == Network::Testnet {
+        eprintln!("Account {} does not exist, you will need to create and fund the account through a community faucet e.g. https://aptoslabs.com/testnet-faucet, or by transferring funds from another account", address);
+    } else if network == Network::Mainnet {
+        eprintln!("Account {} does not exist, you will need to create and fund the account through a faucet or by transferring funds from another account", address);
+    } else {
+        eprintln!("Account {} has been initialized locally, but you must have coins transferred to it to create the account onchain", address);
+    }
+    Ok(())
+}
+
+/// Waits for the faucet transactions, and checks the account actually received funds
+async fn check_funded_balance(
+    client: &Client,
+    address: AccountAddress,
+    hashes: Vec<HashValue>,
+) -> CliTypedResult<()> {
+    let timeout = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+        .as_secs()
+        + 30;
+    for hash in hashes {
+        client
+            .wait_for_transaction_by_hash(hash.into(), timeout, Some(Duration::from_secs(60)), None)
+            .await?;
+    }
+
+    let balance = client
+        .get_account_balance(address)
+        .await?
+        .into_inner()
+        .get();
+    if balance == 0 {
+        eprintln!(
+            "Account {} was funded, but has no balance.  Try `aptos account fund-with-faucet --account {}`",
+            address, address
+        );
+    } else {
+        eprintln!(
+            "Account {} funded successfully, with a balance of {} Octas",
+            address, balance
+        );
+    }
+    Ok(())
+}
+
 /// A simplified list of all networks supported by the CLI
 ///
 /// Any command using this, will be simpler to setup as profiles
@@ -343,6 +619,34 @@ impl FromStr for Network {
     }
 }
 
+impl Network {
+    /// Default REST and faucet URLs for the network, `None` for custom networks
+    pub fn default_urls(&self) -> Option<(&'static str, Option<&'static str>)> {
+        match self {
+            Network::Mainnet => Some(("https://fullnode.mainnet.aptoslabs.com", None)),
+            Network::Testnet => Some(("https://fullnode.testnet.aptoslabs.com", None)),
+            Network::Devnet => Some((
+                "https://fullnode.devnet.aptoslabs.com",
+                Some("https://faucet.devnet.aptoslabs.com"),
+            )),
+            Network::Local => Some(("http://localhost:8080", Some("http://localhost:8081"))),
+            Network::Custom => None,
+        }
+    }
+}
+
+impl Display for Network {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Devnet => "devnet",
+            Network::Local => "local",
+            Network::Custom => "custom",
+        })
+    }
+}
+
 impl Default for Network {
     fn default() -> Self {
         Self::Devnet
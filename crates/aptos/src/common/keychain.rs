@@ -0,0 +1,232 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Storage of profile secrets in the OS keychain
+//!
+//! There is no keychain library in the dependency tree, so this shells out to the tools
+//! that ship with each OS: `security` on macOS and `secret-tool` for the Secret Service
+//! on Linux.  Windows has no such tool that can read secrets back, so the Windows
+//! Credential Manager isn't supported.
+
+use crate::common::types::{CliConfig, CliError, CliTypedResult};
+use clap::ArgEnum;
+use std::{
+    fmt::{Display, Formatter},
+    io::Write,
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+/// Keychain service all CLI secrets are stored under
+const KEYCHAIN_SERVICE: &str = "aptos-cli";
+
+/// Where a profile's private key is stored
+#[derive(ArgEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyStore {
+    /// In `.aptos/config.yaml`
+    File,
+    /// In the OS keychain, with only the entry name in `.aptos/config.yaml`
+    Keychain,
+}
+
+impl Display for KeyStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            KeyStore::File => "file",
+            KeyStore::Keychain => "keychain",
+        })
+    }
+}
+
+impl FromStr for KeyStore {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().trim() {
+            "file" => Ok(KeyStore::File),
+            "keychain" => Ok(KeyStore::Keychain),
+            _ => Err(CliError::CommandArgumentError(
+                "Invalid key store, must be one of [file, keychain]".to_string(),
+            )),
+        }
+    }
+}
+
+/// A new keychain entry name for a profile
+///
+/// Entries are shared by every config on the machine, so the name has a random suffix, to keep
+/// profiles of the same name in other workspaces or the global config from replacing it.
+pub fn new_entry_name(profile_name: &str) -> String {
+    format!(
+        "{}-{}",
+        profile_name,
+        hex::encode(rand::random::<[u8; 8]>())
+    )
+}
+
+/// Stores `secret` in the OS keychain under `entry`, replacing any existing secret
+pub fn store_secret(entry: &str, secret: &str) -> CliTypedResult<()> {
+    if cfg!(target_os = "macos") {
+        run_keychain_command(
+            Command::new("security").args([
+                "add-generic-password",
+                "-U",
+                "-s",
+                KEYCHAIN_SERVICE,
+                "-a",
+                entry,
+                // Last and without a value, so the secret is read from stdin rather than
+                // being visible to other users in the arguments
+                "-w",
+            ]),
+            // Once for the password, and once to retype it
+            Some(&format!("{}\n{}\n", secret, secret)),
+        )?;
+    } else if cfg!(target_os = "linux") {
+        run_keychain_command(
+            Command::new("secret-tool").args([
+                "store",
+                "--label",
+                &format!("Aptos CLI {}", entry),
+                "service",
+                KEYCHAIN_SERVICE,
+                "account",
+                entry,
+            ]),
+            Some(secret),
+        )?;
+    } else {
+        return Err(unsupported_os());
+    }
+    Ok(())
+}
+
+/// Loads the secret stored in the OS keychain under `entry`
+pub fn load_secret(entry: &str) -> CliTypedResult<String> {
+    let output = if cfg!(target_os = "macos") {
+        run_keychain_command(
+            Command::new("security").args([
+                "find-generic-password",
+                "-s",
+                KEYCHAIN_SERVICE,
+                "-a",
+                entry,
+                "-w",
+            ]),
+            None,
+        )?
+    } else if cfg!(target_os = "linux") {
+        run_keychain_command(
+            Command::new("secret-tool").args([
+                "lookup",
+                "service",
+                KEYCHAIN_SERVICE,
+                "account",
+                entry,
+            ]),
+            None,
+        )?
+    } else {
+        return Err(unsupported_os());
+    };
+
+    let secret = output.trim().to_string();
+    if secret.is_empty() {
+        Err(CliError::UnexpectedError(format!(
+            "No secret found in the OS keychain for {}",
+            entry
+        )))
+    } else {
+        Ok(secret)
+    }
+}
+
+/// Deletes the secret stored in the OS keychain under `entry`
+pub fn delete_secret(entry: &str) -> CliTypedResult<()> {
+    if cfg!(target_os = "macos") {
+        run_keychain_command(
+            Command::new("security").args([
+                "delete-generic-password",
+                "-s",
+                KEYCHAIN_SERVICE,
+                "-a",
+                entry,
+            ]),
+            None,
+        )?;
+    } else if cfg!(target_os = "linux") {
+        run_keychain_command(
+            Command::new("secret-tool").args([
+                "clear",
+                "service",
+                KEYCHAIN_SERVICE,
+                "account",
+                entry,
+            ]),
+            None,
+        )?;
+    } else {
+        return Err(unsupported_os());
+    }
+    Ok(())
+}
+
+/// Deletes the secret under `entry` once no profile in `config` uses it
+///
+/// A copy of a profile shares its entry, so the secret is kept while any copy is left.
+pub fn delete_unused_secret(config: &CliConfig, entry: &str) -> CliTypedResult<()> {
+    let in_use = config.profiles.as_ref().map_or(false, |profiles| {
+        profiles
+            .values()
+            .any(|profile| profile.keychain_entry.as_deref() == Some(entry))
+    });
+    if in_use {
+        Ok(())
+    } else {
+        delete_secret(entry)
+    }
+}
+
+fn run_keychain_command(command: &mut Command, stdin: Option<&str>) -> CliTypedResult<String> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            CliError::UnexpectedError(format!("Failed to run the OS keychain tool: {}", err))
+        })?;
+    if let Some(input) = stdin {
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(input.as_bytes())
+            .map_err(|err| CliError::IO("OS keychain".to_string(), err))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|err| CliError::IO("OS keychain".to_string(), err))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(CliError::UnexpectedError(format!(
+            "OS keychain tool failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+fn unsupported_os() -> CliError {
+    if cfg!(target_os = "windows") {
+        CliError::CommandArgumentError(
+            "The keychain key store doesn't support the Windows Credential Manager, use \
+             `--encrypt-private-key` to keep the key in the config encrypted instead"
+                .to_string(),
+        )
+    } else {
+        CliError::CommandArgumentError(
+            "The keychain key store is only supported on macOS and Linux".to_string(),
+        )
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! BIP-39 mnemonics for ed25519 keys
+//!
+//! Keys are derived from the mnemonic's seed with SLIP-0010, on the same derivation path
+//! that wallets use, so the same mnemonic gives the same account in the CLI and in a wallet.
+
+use crate::common::types::{CliError, CliTypedResult, RngArgs};
+use aptos_crypto::ed25519::Ed25519PrivateKey;
+use bip39::{Language, Mnemonic, Seed};
+use ring::hmac;
+use std::convert::TryFrom;
+
+/// Derivation path of the first account, as used by Aptos wallets
+pub const APTOS_DERIVATION_PATH: &str = "m/44'/637'/0'/0'/0'";
+
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+const ED25519_CURVE_KEY: &[u8] = b"ed25519 seed";
+
+/// Generates a new English mnemonic of `word_count` words, either 12 or 24
+pub fn generate_mnemonic(rng_args: &RngArgs, word_count: usize) -> CliTypedResult<Mnemonic> {
+    let entropy_len = match word_count {
+        12 => 16,
+        24 => 32,
+        _ => {
+            return Err(CliError::CommandArgumentError(
+                "Mnemonics must be 12 or 24 words".to_string(),
+            ))
+        }
+    };
+    let mut entropy = vec![0u8; entropy_len];
+    rng_args.key_generator()?.generate_bytes(&mut entropy);
+    Mnemonic::from_entropy(&entropy, Language::English)
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to create mnemonic: {}", err)))
+}
+
+/// Parses an English mnemonic phrase
+pub fn parse_mnemonic(phrase: &str) -> CliTypedResult<Mnemonic> {
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    Mnemonic::from_phrase(&phrase, Language::English)
+        .map_err(|err| CliError::UnableToParse("mnemonic", err.to_string()))
+}
+
+/// Derives the ed25519 private key at `derivation_path` from the mnemonic, without a
+/// BIP-39 passphrase
+pub fn derive_ed25519_private_key(
+    mnemonic: &Mnemonic,
+    derivation_path: &str,
+) -> CliTypedResult<Ed25519PrivateKey> {
+    let path = parse_derivation_path(derivation_path)?;
+    let seed = Seed::new(mnemonic, "");
+
+    let (mut key, mut chain_code) = split_hmac(ED25519_CURVE_KEY, seed.as_bytes());
+    for index in path {
+        let mut data = Vec::with_capacity(37);
+        data.push(0);
+        data.extend_from_slice(&key);
+        data.extend_from_slice(&index.to_be_bytes());
+        let (child_key, child_chain_code) = split_hmac(&chain_code, &data);
+        key = child_key;
+        chain_code = child_chain_code;
+    }
+
+    Ed25519PrivateKey::try_from(key.as_slice())
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to derive key: {}", err)))
+}
+
+/// Parses a derivation path like `m/44'/637'/0'/0'/0'`
+///
+/// SLIP-0010 only supports hardened derivation for ed25519, so every index must be hardened
+fn parse_derivation_path(derivation_path: &str) -> CliTypedResult<Vec<u32>> {
+    let invalid = |reason: &str| {
+        CliError::UnableToParse(
+            "derivation path",
+            format!("{}: {}", derivation_path, reason),
+        )
+    };
+    let mut segments = derivation_path.trim().split('/');
+    if segments.next() != Some("m") {
+        return Err(invalid("must start with m"));
+    }
+    segments
+        .map(|segment| {
+            let index = segment
+                .strip_suffix('\'')
+                .ok_or_else(|| invalid("every index must be hardened with '"))?
+                .parse::<u32>()
+                .map_err(|err| invalid(&err.to_string()))?;
+            if index >= HARDENED_OFFSET {
+                return Err(invalid("index is too large"));
+            }
+            Ok(index | HARDENED_OFFSET)
+        })
+        .collect()
+}
+
+/// Expands a derivation path with an index range like `m/44'/637'/{0..9}'/0'/0'` into each
+/// index in the range (inclusive) with its path
+///
+/// A path without a range is returned as is, with index 0
+pub fn expand_derivation_path(template: &str) -> CliTypedResult<Vec<(u32, String)>> {
+    let (prefix, rest) = match template.split_once('{') {
+        Some(split) => split,
+        None => return Ok(vec![(0, template.to_string())]),
+    };
+    let invalid = || {
+        CliError::UnableToParse(
+            "derivation path",
+            format!("{}: ranges must look like {{0..9}}", template),
+        )
+    };
+    let (range, suffix) = rest.split_once('}').ok_or_else(invalid)?;
+    let (start, end) = range.split_once("..").ok_or_else(invalid)?;
+    let start: u32 = start.trim().parse().map_err(|_| invalid())?;
+    let end: u32 = end.trim().parse().map_err(|_| invalid())?;
+    if start > end || suffix.contains('{') {
+        return Err(invalid());
+    }
+    Ok((start..=end)
+        .map(|index| (index, format!("{}{}{}", prefix, index, suffix)))
+        .collect())
+}
+
+/// HMAC-SHA512, split into the key and the chain code
+fn split_hmac(key: &[u8], data: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA512, key), data);
+    let (left, right) = tag.as_ref().split_at(32);
+    let mut child_key = [0u8; 32];
+    let mut chain_code = [0u8; 32];
+    child_key.copy_from_slice(left);
+    chain_code.copy_from_slice(right);
+    (child_key, chain_code)
+}
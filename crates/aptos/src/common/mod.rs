@@ -1,6 +1,11 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod doctor;
+pub mod encryption;
 pub mod init;
+pub mod key_import;
+pub mod keychain;
+pub mod mnemonic;
 pub mod types;
 pub mod utils;
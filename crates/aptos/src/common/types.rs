@@ -1,15 +1,19 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::common::encryption::{key_passphrase, EncryptedData};
 use crate::common::init::Network;
+use crate::common::key_import::load_ed25519_private_key_file;
+use crate::common::keychain;
 use crate::common::utils::prompt_yes_with_override;
+use crate::move_tool::lockfile::check_lockfile;
 use crate::{
     common::utils::{
         chain_id, check_if_file_exists, create_dir_if_not_exist, dir_default_to_current,
         get_auth_key, get_sequence_number, read_from_file, start_logger, to_common_result,
         to_common_success_result, write_to_file, write_to_file_with_opts, write_to_user_only_file,
     },
-    config::GlobalConfig,
+    config::{global_folder, GlobalConfig},
     genesis::git::from_yaml,
 };
 use aptos_crypto::ed25519::Ed25519Signature;
@@ -47,6 +51,8 @@ use thiserror::Error;
 
 const MAX_POSSIBLE_GAS_UNITS: u64 = 1_000_000;
 pub const DEFAULT_PROFILE: &str = "default";
+/// Environment variable overriding the profile's private key
+pub const PRIVATE_KEY_ENV: &str = "APTOS_PRIVATE_KEY";
 
 /// A common result to be returned to users
 pub type CliResult = Result<String, String>;
@@ -181,13 +187,19 @@ const LEGACY_CONFIG_FILE: &str = "config.yml";
 pub const CONFIG_FOLDER: &str = ".aptos";
 
 /// An individual profile
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ProfileConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub network: Option<Network>,
     /// Private key for commands.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub private_key: Option<Ed25519PrivateKey>,
+    /// Private key for commands, encrypted with the key passphrase
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encrypted_private_key: Option<EncryptedData>,
+    /// Name of the OS keychain entry holding the private key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keychain_entry: Option<String>,
     /// Public key for commands
     #[serde(skip_serializing_if = "Option::is_none")]
     pub public_key: Option<Ed25519PublicKey>,
@@ -200,6 +212,138 @@ pub struct ProfileConfig {
     /// URL for the Faucet endpoint (if applicable)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub faucet_url: Option<String>,
+    /// Bearer token for the Faucet endpoint (if required)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub faucet_auth_token: Option<String>,
+}
+
+impl ProfileConfig {
+    /// Whether the profile has a private key, encrypted or not
+    pub fn has_private_key(&self) -> bool {
+        self.private_key.is_some()
+            || self.encrypted_private_key.is_some()
+            || self.keychain_entry.is_some()
+    }
+
+    /// Loads the private key, decrypting it or fetching it from the OS keychain as needed
+    pub fn load_private_key(&self) -> CliTypedResult<Option<Ed25519PrivateKey>> {
+        if let Some(ref private_key) = self.private_key {
+            Ok(Some(private_key.clone()))
+        } else if let Some(ref encrypted) = self.encrypted_private_key {
+            let bytes = encrypted.decrypt(&key_passphrase(false)?)?;
+            let private_key = Ed25519PrivateKey::try_from(bytes.as_slice())
+                .map_err(|err| CliError::UnableToParse("encrypted_private_key", err.to_string()))?;
+            Ok(Some(private_key))
+        } else if let Some(ref entry) = self.keychain_entry {
+            let private_key =
+                Ed25519PrivateKey::from_encoded_string(&keychain::load_secret(entry)?)
+                    .map_err(|err| CliError::UnableToParse("keychain_entry", err.to_string()))?;
+            Ok(Some(private_key))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Sets the private key, encrypting it with the key passphrase if `encrypt` is set
+    pub fn set_private_key(
+        &mut self,
+        private_key: Ed25519PrivateKey,
+        encrypt: bool,
+    ) -> CliTypedResult<()> {
+        if encrypt {
+            self.encrypted_private_key = Some(EncryptedData::encrypt(
+                &private_key.to_bytes(),
+                &key_passphrase(true)?,
+            )?);
+            self.private_key = None;
+        } else {
+            self.private_key = Some(private_key);
+            self.encrypted_private_key = None;
+        }
+        self.keychain_entry = None;
+        Ok(())
+    }
+
+    /// Stores the private key in the OS keychain, keeping only the entry name in the profile
+    pub fn set_keychain_private_key(
+        &mut self,
+        private_key: Ed25519PrivateKey,
+        entry: String,
+    ) -> CliTypedResult<()> {
+        let encoded = private_key
+            .to_encoded_string()
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        keychain::store_secret(&entry, &encoded)?;
+        self.private_key = None;
+        self.encrypted_private_key = None;
+        self.keychain_entry = Some(entry);
+        Ok(())
+    }
+
+    /// The profile's account, derived from its key only if it has none
+    ///
+    /// The account is kept as is, since it no longer matches the key after a key rotation, and
+    /// watch profiles may have no key at all.
+    pub fn account_address(&self) -> CliTypedResult<Option<AccountAddress>> {
+        if self.account.is_some() {
+            return Ok(self.account);
+        }
+        let public_key = if let Some(ref public_key) = self.public_key {
+            Some(public_key.clone())
+        } else {
+            self.load_private_key()?
+                .map(|private_key| private_key.public_key())
+        };
+        Ok(public_key.map(|public_key| account_address_from_public_key(&public_key)))
+    }
+}
+
+/// Which config a profile value came from
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigOrigin {
+    /// The global config in `~/.aptos`
+    Global,
+    /// The workspace config in the current directory or one of its parents
+    Workspace,
+}
+
+/// Origin of each field set in a profile, keyed by field name
+pub type ProfileOrigins = BTreeMap<&'static str, ConfigOrigin>;
+
+/// Overlays the fields set in `profile` onto `merged`, recording `origin` for each of them
+fn merge_profile(
+    merged: &mut ProfileConfig,
+    origins: &mut ProfileOrigins,
+    profile: ProfileConfig,
+    origin: ConfigOrigin,
+) {
+    // The key is only taken as a whole, so layers can't mix e.g. a plain and encrypted key
+    if profile.has_private_key() {
+        merged.private_key = profile.private_key;
+        merged.encrypted_private_key = profile.encrypted_private_key;
+        merged.keychain_entry = profile.keychain_entry;
+        origins.insert("private_key", origin);
+    }
+
+    macro_rules! overlay {
+        ($($field:ident),*) => {
+            $(
+                if profile.$field.is_some() {
+                    merged.$field = profile.$field;
+                    origins.insert(stringify!($field), origin);
+                }
+            )*
+        };
+    }
+    overlay!(
+        network,
+        public_key,
+        account,
+        rest_url,
+        faucet_url,
+        faucet_auth_token
+    );
 }
 
 /// ProfileConfig but without the private parts
@@ -214,16 +358,20 @@ pub struct ProfileSummary {
     pub rest_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub faucet_url: Option<String>,
+    /// Which config each field came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origins: Option<ProfileOrigins>,
 }
 
 impl From<&ProfileConfig> for ProfileSummary {
     fn from(config: &ProfileConfig) -> Self {
         ProfileSummary {
-            has_private_key: config.private_key.is_some(),
+            has_private_key: config.has_private_key(),
             public_key: config.public_key.clone(),
             account: config.account,
             rest_url: config.rest_url.clone(),
             faucet_url: config.faucet_url.clone(),
+            origins: None,
         }
     }
 }
@@ -257,7 +405,13 @@ impl CliConfig {
     /// Loads the config from the current working directory or one of its parents.
     pub fn load(mode: ConfigSearchMode) -> CliTypedResult<Self> {
         let folder = Self::aptos_folder(mode)?;
+        Self::load_from_folder(&folder)?.ok_or_else(|| {
+            CliError::ConfigNotFoundError(format!("{}", folder.join(CONFIG_FILE).display()))
+        })
+    }
 
+    /// Loads the config in `folder`, if there is one
+    fn load_from_folder(folder: &Path) -> CliTypedResult<Option<Self>> {
         let config_file = folder.join(CONFIG_FILE);
         let old_config_file = folder.join(LEGACY_CONFIG_FILE);
         if config_file.exists() {
@@ -265,24 +419,75 @@ impl CliConfig {
                 &String::from_utf8(read_from_file(config_file.as_path())?)
                     .map_err(CliError::from)?,
             )
+            .map(Some)
         } else if old_config_file.exists() {
             from_yaml(
                 &String::from_utf8(read_from_file(old_config_file.as_path())?)
                     .map_err(CliError::from)?,
             )
+            .map(Some)
         } else {
-            Err(CliError::ConfigNotFoundError(format!(
+            Ok(None)
+        }
+    }
+
+    /// Loads the workspace config layered over the global config in `~/.aptos`
+    ///
+    /// Profiles with the same name are merged field by field, with workspace values taking
+    /// precedence, so a workspace can e.g. override only the REST URL of a global profile.
+    /// The private key is taken as a whole from one layer.  Also returns where each profile
+    /// field came from.
+    pub fn load_layered(
+        mode: ConfigSearchMode,
+    ) -> CliTypedResult<(Self, BTreeMap<String, ProfileOrigins>)> {
+        let folder = Self::aptos_folder(mode)?;
+        let global_folder = global_folder()?;
+        let workspace = Self::load_from_folder(&folder)?;
+        // In global mode, or from the home directory, there is only one layer
+        let global = if global_folder != folder {
+            Self::load_from_folder(&global_folder)?
+        } else {
+            None
+        };
+        if workspace.is_none() && global.is_none() {
+            return Err(CliError::ConfigNotFoundError(format!(
                 "{}",
-                config_file.display()
-            )))
+                folder.join(CONFIG_FILE).display()
+            )));
+        }
+
+        let mut profiles = BTreeMap::new();
+        let mut origins = BTreeMap::new();
+        let layers = [
+            (ConfigOrigin::Global, global),
+            (ConfigOrigin::Workspace, workspace),
+        ];
+        for (origin, config) in layers {
+            for (name, profile) in config
+                .and_then(|config| config.profiles)
+                .unwrap_or_default()
+            {
+                let merged = profiles
+                    .entry(name.clone())
+                    .or_insert_with(ProfileConfig::default);
+                let profile_origins = origins.entry(name).or_insert_with(ProfileOrigins::new);
+                merge_profile(merged, profile_origins, profile, origin);
+            }
         }
+
+        Ok((
+            CliConfig {
+                profiles: Some(profiles),
+            },
+            origins,
+        ))
     }
 
     pub fn load_profile(
         profile: Option<&str>,
         mode: ConfigSearchMode,
     ) -> CliTypedResult<Option<ProfileConfig>> {
-        let mut config = Self::load(mode)?;
+        let (mut config, _) = Self::load_layered(mode)?;
 
         // If no profile was given, use `default`
         if let Some(profile) = profile {
@@ -314,12 +519,16 @@ impl CliConfig {
         // Create if it doesn't exist
         create_dir_if_not_exist(aptos_folder.as_path())?;
 
-        // Save over previous config file
+        // Save over previous config file, through a temporary file so that a failed write
+        // can't leave a partially written config behind
         let config_file = aptos_folder.join(CONFIG_FILE);
+        let temp_config_file = aptos_folder.join(format!("{}.tmp", CONFIG_FILE));
         let config_bytes = serde_yaml::to_string(&self).map_err(|err| {
             CliError::UnexpectedError(format!("Failed to serialize config {}", err))
         })?;
-        write_to_user_only_file(&config_file, CONFIG_FILE, config_bytes.as_bytes())?;
+        write_to_user_only_file(&temp_config_file, CONFIG_FILE, config_bytes.as_bytes())?;
+        std::fs::rename(&temp_config_file, &config_file)
+            .map_err(|err| CliError::IO(CONFIG_FILE.to_string(), err))?;
 
         // As a cleanup, delete the old if it exists
         let legacy_config_file = aptos_folder.join(LEGACY_CONFIG_FILE);
@@ -380,7 +589,11 @@ pub struct ProfileOptions {
     /// the REST URL, the Faucet URL, and the private key arguments.
     ///
     /// Defaults to "default"
-    #[clap(long)]
+    ///
+    /// Settings are taken from command line flags first, then from the `APTOS_PROFILE`,
+    /// `APTOS_REST_URL`, `APTOS_FAUCET_URL`, and `APTOS_PRIVATE_KEY` environment variables,
+    /// and only then from the profile
+    #[clap(long, env = "APTOS_PROFILE")]
     pub profile: Option<String>,
 }
 
@@ -520,6 +733,11 @@ impl RngArgs {
         }
     }
 
+    /// Whether keys are generated from a seed, and so are reproducible
+    pub fn is_seeded(&self) -> bool {
+        self.random_seed.is_some()
+    }
+
     /// Returns a key generator with the seed if given
     pub fn key_generator(&self) -> CliTypedResult<KeyGen> {
         if let Some(ref seed) = self.random_seed {
@@ -658,9 +876,11 @@ pub trait ParsePrivateKey {
         private_key: Option<String>,
     ) -> CliTypedResult<Option<Ed25519PrivateKey>> {
         if let Some(ref file) = private_key_file {
-            Ok(Some(
-                encoding.load_key("--private-key-file", file.as_path())?,
-            ))
+            Ok(Some(load_ed25519_private_key_file(
+                encoding,
+                "--private-key-file",
+                file.as_path(),
+            )?))
         } else if let Some(ref key) = private_key {
             let key = key.as_bytes().to_vec();
             Ok(Some(encoding.decode_key("--private-key", key)?))
@@ -674,7 +894,8 @@ pub trait ParsePrivateKey {
 pub struct PrivateKeyInputOptions {
     /// Signing Ed25519 private key file path
     ///
-    /// Encoded with type from `--encoding`
+    /// Encoded with type from `--encoding`, or a PKCS#8 PEM, OpenSSH, or passphrase
+    /// encrypted key file, detected from the contents
     /// Mutually exclusive with `--private-key`
     #[clap(long, group = "private_key_input", parse(from_os_str))]
     private_key_file: Option<PathBuf>,
@@ -682,6 +903,7 @@ pub struct PrivateKeyInputOptions {
     ///
     /// Encoded with type from `--encoding`
     /// Mutually exclusive with `--private-key-file`
+    /// Falls back to `APTOS_PRIVATE_KEY` if neither is given
     #[clap(long, group = "private_key_input")]
     private_key: Option<String>,
 }
@@ -737,13 +959,14 @@ impl PrivateKeyInputOptions {
                 let address = account_address_from_public_key(&key.public_key());
                 Ok((key, address))
             }
-        } else if let Some((Some(key), maybe_config_address)) = CliConfig::load_profile(
+        } else if let Some(profile_config) = CliConfig::load_profile(
             profile.profile_name(),
             ConfigSearchMode::CurrentDirAndParents,
-        )?
-        .map(|p| (p.private_key, p.account))
-        {
-            match (maybe_address, maybe_config_address) {
+        )? {
+            let key = profile_config
+                .load_private_key()?
+                .ok_or_else(|| read_only_profile_error(profile))?;
+            match (maybe_address, profile_config.account) {
                 (Some(address), _) => Ok((key, address)),
                 (_, Some(address)) => Ok((key, address)),
                 (None, None) => {
@@ -766,13 +989,13 @@ impl PrivateKeyInputOptions {
     ) -> CliTypedResult<Ed25519PrivateKey> {
         if let Some(key) = self.extract_private_key_cli(encoding)? {
             Ok(key)
-        } else if let Some(Some(private_key)) = CliConfig::load_profile(
+        } else if let Some(profile_config) = CliConfig::load_profile(
             profile.profile_name(),
             ConfigSearchMode::CurrentDirAndParents,
-        )?
-        .map(|p| p.private_key)
-        {
-            Ok(private_key)
+        )? {
+            profile_config
+                .load_private_key()?
+                .ok_or_else(|| read_only_profile_error(profile))
         } else {
             Err(CliError::CommandArgumentError(
                 "One of ['--private-key', '--private-key-file'] must be used".to_string(),
@@ -780,16 +1003,20 @@ impl PrivateKeyInputOptions {
         }
     }
 
-    /// Extract private key from CLI args
+    /// Extract private key from CLI args, falling back to `APTOS_PRIVATE_KEY`
+    ///
+    /// The environment variable isn't a clap `env`, since it would then conflict with
+    /// `--private-key-file`
     pub fn extract_private_key_cli(
         &self,
         encoding: EncodingType,
     ) -> CliTypedResult<Option<Ed25519PrivateKey>> {
-        self.parse_private_key(
-            encoding,
-            self.private_key_file.clone(),
-            self.private_key.clone(),
-        )
+        let private_key = if self.private_key.is_none() && self.private_key_file.is_none() {
+            std::env::var(PRIVATE_KEY_ENV).ok()
+        } else {
+            self.private_key.clone()
+        };
+        self.parse_private_key(encoding, self.private_key_file.clone(), private_key)
     }
 }
 
@@ -812,6 +1039,14 @@ pub trait ExtractPublicKey {
     ) -> CliTypedResult<Ed25519PublicKey>;
 }
 
+/// Error for signing with a profile that has no private key
+fn read_only_profile_error(profile: &ProfileOptions) -> CliError {
+    CliError::CommandArgumentError(format!(
+        "Profile {} is read-only and has no private key to sign with, use ['--private-key', '--private-key-file'] or another profile",
+        profile.profile_name().unwrap_or(DEFAULT_PROFILE)
+    ))
+}
+
 pub fn account_address_from_public_key(public_key: &Ed25519PublicKey) -> AccountAddress {
     let auth_key = AuthenticationKey::ed25519(public_key);
     AccountAddress::new(*auth_key.derived_address())
@@ -853,7 +1088,7 @@ pub struct RestOptions {
     /// URL to a fullnode on the network
     ///
     /// Defaults to the URL in the `default` profile
-    #[clap(long)]
+    #[clap(long, env = "APTOS_REST_URL")]
     pub(crate) url: Option<reqwest::Url>,
 
     /// Connection timeout in seconds, used for the REST endpoint of the fullnode
@@ -912,6 +1147,17 @@ pub struct MovePackageDir {
     /// Note: This will fail if there are duplicates in the Move.toml file remove those first.
     #[clap(long, parse(try_from_str = crate::common::utils::parse_map), default_value = "")]
     pub(crate) named_addresses: BTreeMap<String, AccountAddressWrapper>,
+    /// Fail unless the git dependencies match the commits in Move.lock
+    ///
+    /// Create Move.lock with `aptos move lock`
+    #[clap(long)]
+    pub(crate) locked: bool,
+    /// Build for an environment defined in Move.toml
+    ///
+    /// The addresses and dependencies in `[env.<name>]`, e.g. `[env.testnet.addresses]`, replace
+    /// those of the same names
+    #[clap(long)]
+    pub(crate) env: Option<String>,
 }
 
 impl MovePackageDir {
@@ -920,11 +1166,18 @@ impl MovePackageDir {
             package_dir: Some(package_dir),
             output_dir: None,
             named_addresses: Default::default(),
+            locked: false,
+            env: None,
         }
     }
 
+    /// Retrieves the package path, checking Move.lock first with `--locked`
     pub fn get_package_path(&self) -> CliTypedResult<PathBuf> {
-        dir_default_to_current(self.package_dir.clone())
+        let package_dir = dir_default_to_current(self.package_dir.clone())?;
+        if self.locked {
+            check_lockfile(&package_dir)?;
+        }
+        Ok(package_dir)
     }
 
     /// Retrieve the NamedAddresses, resolving all the account addresses accordingly
@@ -942,6 +1195,9 @@ impl MovePackageDir {
     }
 }
 
+/// Prefix of an address given by the name of a profile, e.g. `profile:default`
+pub const PROFILE_PREFIX: &str = "profile:";
+
 /// A wrapper around `AccountAddress` to be more flexible from strings than AccountAddress
 #[derive(Clone, Copy, Debug)]
 pub struct AccountAddressWrapper {
@@ -959,19 +1215,32 @@ impl FromStr for AccountAddressWrapper {
 }
 
 /// Loads an account arg and allows for naming based on profiles
+///
+/// A profile can be named plainly, or as `profile:<name>` to tell it apart from an address
 pub fn load_account_arg(str: &str) -> Result<AccountAddress, CliError> {
-    if str.starts_with("0x") {
+    if let Some(profile) = str.strip_prefix(PROFILE_PREFIX) {
+        CliConfig::load_profile(Some(profile), ConfigSearchMode::CurrentDirAndParents)?
+            .map(|p| p.account_address())
+            .transpose()?
+            .flatten()
+            .ok_or_else(|| {
+                CliError::CommandArgumentError(format!(
+                    "Profile {} doesn't exist or has no account",
+                    profile
+                ))
+            })
+    } else if str.starts_with("0x") {
         AccountAddress::from_hex_literal(str).map_err(|err| {
             CliError::CommandArgumentError(format!("Failed to parse AccountAddress {}", err))
         })
     } else if let Ok(account_address) = AccountAddress::from_str(str) {
         Ok(account_address)
-    } else if let Some(Some(private_key)) =
+    } else if let Some(Some(account_address)) =
         CliConfig::load_profile(Some(str), ConfigSearchMode::CurrentDirAndParents)?
-            .map(|p| p.private_key)
+            .map(|p| p.account_address())
+            .transpose()?
     {
-        let public_key = private_key.public_key();
-        Ok(account_address_from_public_key(&public_key))
+        Ok(account_address)
     } else {
         Err(CliError::CommandArgumentError(
             "'--account' or '--profile' after using aptos init must be provided".to_string(),
@@ -1007,12 +1276,12 @@ pub fn load_manifest_account_arg(str: &str) -> Result<Option<AccountAddress>, Cl
             })
     } else if let Ok(account_address) = AccountAddress::from_str(str) {
         Ok(Some(account_address))
-    } else if let Some(Some(private_key)) =
+    } else if let Some(Some(account_address)) =
         CliConfig::load_profile(Some(str), ConfigSearchMode::CurrentDirAndParents)?
-            .map(|p| p.private_key)
+            .map(|p| p.account_address())
+            .transpose()?
     {
-        let public_key = private_key.public_key();
-        Ok(Some(account_address_from_public_key(&public_key)))
+        Ok(Some(account_address))
     } else {
         Err(CliError::CommandArgumentError(
             "Invalid Move manifest account address".to_string(),
@@ -1172,13 +1441,35 @@ pub struct ChangeSummary {
 #[derive(Debug, Default, Parser)]
 pub struct FaucetOptions {
     /// URL for the faucet endpoint e.g. https://faucet.devnet.aptoslabs.com
-    #[clap(long)]
+    #[clap(long, env = "APTOS_FAUCET_URL")]
     faucet_url: Option<reqwest::Url>,
+
+    /// Bearer token for faucets that require authentication
+    ///
+    /// Defaults to the token in the profile
+    #[clap(long, env = "APTOS_FAUCET_AUTH_TOKEN")]
+    faucet_auth_token: Option<String>,
 }
 
 impl FaucetOptions {
     pub fn new(faucet_url: Option<reqwest::Url>) -> Self {
-        FaucetOptions { faucet_url }
+        FaucetOptions {
+            faucet_url,
+            faucet_auth_token: None,
+        }
+    }
+
+    /// Retrieve the faucet auth token from the command line or the profile
+    pub fn faucet_auth_token(&self, profile: &ProfileOptions) -> CliTypedResult<Option<String>> {
+        if let Some(ref token) = self.faucet_auth_token {
+            Ok(Some(token.clone()))
+        } else {
+            Ok(CliConfig::load_profile(
+                profile.profile_name(),
+                ConfigSearchMode::CurrentDirAndParents,
+            )?
+            .and_then(|profile| profile.faucet_auth_token))
+        }
     }
 
     pub fn faucet_url(&self, profile: &ProfileOptions) -> CliTypedResult<reqwest::Url> {
@@ -1267,6 +1558,44 @@ impl TransactionOptions {
         )
     }
 
+    /// Retrieves the public key and the associated address
+    ///
+    /// Unlike `get_key_and_address`, this works with read-only profiles that have a public key,
+    /// which is all that's needed for simulation
+    pub fn get_public_key_and_address(&self) -> CliTypedResult<(Ed25519PublicKey, AccountAddress)> {
+        if self
+            .private_key_options
+            .extract_private_key_cli(self.encoding_options.encoding)?
+            .is_none()
+        {
+            if let Some(profile) = CliConfig::load_profile(
+                self.profile_options.profile_name(),
+                ConfigSearchMode::CurrentDirAndParents,
+            )? {
+                if !profile.has_private_key() {
+                    let profile_name = self
+                        .profile_options
+                        .profile_name()
+                        .unwrap_or(DEFAULT_PROFILE);
+                    let public_key = profile.public_key.ok_or_else(|| {
+                        CliError::CommandArgumentError(format!(
+                            "Profile {} is read-only and has no public key, which is needed to simulate transactions",
+                            profile_name
+                        ))
+                    })?;
+                    let address = self
+                        .sender_account
+                        .or(profile.account)
+                        .unwrap_or_else(|| account_address_from_public_key(&public_key));
+                    return Ok((public_key, address));
+                }
+            }
+        }
+
+        let (private_key, address) = self.get_key_and_address()?;
+        Ok((private_key.public_key(), address))
+    }
+
     pub fn sender_address(&self) -> CliTypedResult<AccountAddress> {
         Ok(self.get_key_and_address()?.1)
     }
@@ -1390,10 +1719,26 @@ impl TransactionOptions {
         amount_transfer: Option<u64>,
     ) -> CliTypedResult<UserTransaction> {
         let client = self.rest_client()?;
-        let (sender_key, sender_address) = self.get_key_and_address()?;
+        let signed_transaction = self
+            .simulation_transaction(&client, payload, gas_price, amount_transfer)
+            .await?;
+        let txns = client.simulate(&signed_transaction).await?.into_inner();
+        Ok(txns.first().unwrap().clone())
+    }
+
+    /// Builds the transaction to simulate, with an empty signature and the most gas the sender
+    /// can pay for
+    pub(crate) async fn simulation_transaction(
+        &self,
+        client: &Client,
+        payload: TransactionPayload,
+        gas_price: Option<u64>,
+        amount_transfer: Option<u64>,
+    ) -> CliTypedResult<SignedTransaction> {
+        let (sender_public_key, sender_address) = self.get_public_key_and_address()?;
 
         // Get sequence number for account
-        let sequence_number = get_sequence_number(&client, sender_address).await?;
+        let sequence_number = get_sequence_number(client, sender_address).await?;
 
         // Estimate gas price if necessary
         let gas_price = if let Some(gas_price) = gas_price {
@@ -1427,7 +1772,7 @@ impl TransactionOptions {
             )
         };
 
-        let transaction_factory = TransactionFactory::new(chain_id(&client).await?)
+        let transaction_factory = TransactionFactory::new(chain_id(client).await?)
             .with_gas_unit_price(gas_price)
             .with_max_gas_amount(max_possible_gas);
 
@@ -1437,13 +1782,11 @@ impl TransactionOptions {
             .sequence_number(sequence_number)
             .build();
 
-        let signed_transaction = SignedTransaction::new(
+        Ok(SignedTransaction::new(
             unsigned_transaction,
-            sender_key.public_key(),
+            sender_public_key,
             Ed25519Signature::try_from([0u8; 64].as_ref()).unwrap(),
-        );
-        let txns = client.simulate(&signed_transaction).await?.into_inner();
-        Ok(txns.first().unwrap().clone())
+        ))
     }
 
     pub async fn estimate_gas_price(&self) -> CliTypedResult<u64> {
@@ -2,10 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    common::types::{CliError, CliTypedResult, PromptOptions},
+    common::types::{
+        account_address_from_public_key, CliError, CliTypedResult, PromptOptions, RngArgs,
+    },
     CliResult,
 };
 use aptos_build_info::build_information;
+use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, ValidCryptoMaterial};
 use aptos_logger::{debug, Level};
 use aptos_rest_client::aptos_api_types::HashValue;
 use aptos_rest_client::{Account, Client};
@@ -13,7 +16,7 @@ use aptos_telemetry::service::telemetry_is_disabled;
 use aptos_types::{chain_id::ChainId, transaction::authenticator::AuthenticationKey};
 use itertools::Itertools;
 use move_core_types::account_address::AccountAddress;
-use reqwest::Url;
+use reqwest::{StatusCode, Url};
 use serde::Serialize;
 #[cfg(unix)]
 use std::os::unix::fs::OpenOptionsExt;
@@ -24,6 +27,10 @@ use std::{
     io::Write,
     path::{Path, PathBuf},
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc,
+    },
     time::{Duration, Instant},
 };
 
@@ -321,15 +328,74 @@ pub fn read_line(input_name: &'static str) -> CliTypedResult<String> {
 /// Fund account (and possibly create it) from a faucet
 pub async fn fund_account(
     faucet_url: Url,
+    faucet_auth_token: Option<&str>,
     num_octas: u64,
     address: AccountAddress,
 ) -> CliTypedResult<Vec<HashValue>> {
-    let response = reqwest::Client::new()
+    request_faucet_funds(faucet_url, faucet_auth_token, num_octas, address)
+        .await?
+        .map_err(|status| CliError::ApiError(format!("Faucet issue: {}", status)))
+}
+
+/// Number of times to retry a faucet request that was rate limited or hit a server error
+const FAUCET_RETRIES: u32 = 5;
+/// Delay before the first faucet retry, doubled for every retry after
+const FAUCET_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Fund account (and possibly create it) from a faucet, retrying with exponential backoff
+///
+/// Only rate limits (429) and server errors (5xx) are retried, since those are what a
+/// busy or just reset faucet returns
+pub async fn fund_account_with_retries(
+    faucet_url: Url,
+    faucet_auth_token: Option<&str>,
+    num_octas: u64,
+    address: AccountAddress,
+) -> CliTypedResult<Vec<HashValue>> {
+    let mut backoff = FAUCET_INITIAL_BACKOFF;
+    let mut retries = 0;
+    loop {
+        match request_faucet_funds(faucet_url.clone(), faucet_auth_token, num_octas, address)
+            .await?
+        {
+            Ok(hashes) => return Ok(hashes),
+            Err(status)
+                if retries < FAUCET_RETRIES
+                    && (status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) =>
+            {
+                eprintln!(
+                    "Faucet returned {}, retrying in {} seconds...",
+                    status,
+                    backoff.as_secs()
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                retries += 1;
+            }
+            Err(status) => {
+                return Err(CliError::ApiError(format!("Faucet issue: {}", status)));
+            }
+        }
+    }
+}
+
+/// Sends a funding request to the faucet, returning the status code if it wasn't successful
+async fn request_faucet_funds(
+    faucet_url: Url,
+    faucet_auth_token: Option<&str>,
+    num_octas: u64,
+    address: AccountAddress,
+) -> CliTypedResult<Result<Vec<HashValue>, StatusCode>> {
+    let mut request = reqwest::Client::new()
         .post(format!(
             "{}mint?amount={}&auth_key={}",
             faucet_url, num_octas, address
         ))
-        .body("{}")
+        .body("{}");
+    if let Some(token) = faucet_auth_token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
         .send()
         .await
         .map_err(|err| CliError::ApiError(err.to_string()))?;
@@ -338,13 +404,110 @@ pub async fn fund_account(
             .json()
             .await
             .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
-        Ok(hashes)
+        Ok(Ok(hashes))
     } else {
-        Err(CliError::ApiError(format!(
-            "Faucet issue: {}",
-            response.status()
-        )))
+        Ok(Err(response.status()))
+    }
+}
+
+/// Prefix length after which grinding a vanity address takes a very long time
+const VANITY_PREFIX_WARNING_LENGTH: usize = 7;
+
+/// Generates Ed25519 keys until the derived account address starts with `prefix`
+///
+/// Keys are generated in parallel, except with a seed, where the search is single threaded so
+/// it's reproducible.
+pub fn generate_vanity_ed25519_private_key(
+    rng_args: &RngArgs,
+    prefix: &str,
+) -> CliTypedResult<Ed25519PrivateKey> {
+    let prefix = prefix.strip_prefix("0x").unwrap_or(prefix).to_lowercase();
+    if prefix.is_empty()
+        || prefix.len() > AccountAddress::LENGTH * 2
+        || !prefix.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(CliError::CommandArgumentError(format!(
+            "Vanity prefix must be 1 to {} hex characters",
+            AccountAddress::LENGTH * 2
+        )));
     }
+
+    let expected_attempts = 16f64.powi(prefix.len() as i32);
+    eprintln!(
+        "Searching for an address starting with 0x{}, expecting about {} attempts",
+        prefix, expected_attempts
+    );
+    if prefix.len() >= VANITY_PREFIX_WARNING_LENGTH {
+        eprintln!(
+            "Warning: each extra character makes this 16 times slower, a {} character prefix may take hours or more",
+            prefix.len()
+        );
+    }
+
+    // Which thread finds a key first depends on scheduling, so a seeded search runs on one
+    // thread, to always find the same key for the same seed
+    let num_threads = if rng_args.is_seeded() {
+        1
+    } else {
+        std::thread::available_parallelism()
+            .map(|num| num.get())
+            .unwrap_or(1)
+    };
+    let mut keygen = rng_args.key_generator()?;
+    let seeds: Vec<[u8; 32]> = (0..num_threads)
+        .map(|_| {
+            let mut seed = [0u8; 32];
+            seed.copy_from_slice(&keygen.generate_ed25519_private_key().to_bytes());
+            seed
+        })
+        .collect();
+
+    let found = AtomicBool::new(false);
+    let attempts = AtomicU64::new(0);
+    let start = Instant::now();
+    let (sender, receiver) = mpsc::channel();
+    let private_key = std::thread::scope(|scope| {
+        for seed in seeds {
+            let (found, attempts, prefix, sender) = (&found, &attempts, &prefix, sender.clone());
+            scope.spawn(move || {
+                let mut keygen = aptos_keygen::KeyGen::from_seed(seed);
+                while !found.load(Ordering::Relaxed) {
+                    let private_key = keygen.generate_ed25519_private_key();
+                    let address = account_address_from_public_key(&private_key.public_key());
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    if hex::encode(address).starts_with(prefix.as_str()) {
+                        found.store(true, Ordering::Relaxed);
+                        let _ = sender.send(private_key);
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        loop {
+            match receiver.recv_timeout(Duration::from_secs(5)) {
+                Ok(private_key) => break private_key,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    let attempts = attempts.load(Ordering::Relaxed);
+                    eprintln!(
+                        "Tried {} keys ({:.0} keys/s)",
+                        attempts,
+                        attempts as f64 / start.elapsed().as_secs_f64()
+                    );
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    unreachable!("Search threads only stop once a key is found")
+                }
+            }
+        }
+    });
+
+    eprintln!(
+        "Found a matching address after {} attempts in {:.1}s",
+        attempts.load(Ordering::Relaxed),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(private_key)
 }
 
 pub fn start_logger() {
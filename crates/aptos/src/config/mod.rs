@@ -1,12 +1,14 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::common::encryption::{read_passphrase, EncryptedData};
 use crate::common::types::{
-    CliCommand, CliConfig, CliError, CliResult, CliTypedResult, ConfigSearchMode, ProfileSummary,
-    CONFIG_FOLDER,
+    CliCommand, CliConfig, CliError, CliResult, CliTypedResult, ConfigSearchMode, ProfileConfig,
+    ProfileOptions, ProfileSummary, PromptOptions, SaveFile, CONFIG_FOLDER, DEFAULT_PROFILE,
 };
 use crate::common::utils::{
-    create_dir_if_not_exist, current_dir, read_from_file, write_to_user_only_file,
+    create_dir_if_not_exist, current_dir, prompt_yes_with_override, read_from_file,
+    write_to_user_only_file,
 };
 use crate::genesis::git::{from_yaml, to_yaml};
 use crate::Tool;
@@ -22,6 +24,8 @@ use std::fmt::Formatter;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+pub mod profile;
+
 /// Tool for interacting with configuration of the Aptos CLI tool
 ///
 /// This tool handles the global configuration of the CLI tool for
@@ -32,7 +36,12 @@ pub enum ConfigTool {
     GenerateShellCompletions(GenerateShellCompletions),
     SetGlobalConfig(SetGlobalConfig),
     ShowGlobalConfig(ShowGlobalConfig),
+    #[clap(alias = "show")]
     ShowProfiles(ShowProfiles),
+    ExportProfile(ExportProfile),
+    ImportProfile(ImportProfile),
+    #[clap(subcommand)]
+    Profile(profile::ProfileTool),
 }
 
 impl ConfigTool {
@@ -43,6 +52,9 @@ impl ConfigTool {
             ConfigTool::SetGlobalConfig(tool) => tool.execute_serialized().await,
             ConfigTool::ShowGlobalConfig(tool) => tool.execute_serialized().await,
             ConfigTool::ShowProfiles(tool) => tool.execute_serialized().await,
+            ConfigTool::ExportProfile(tool) => tool.execute_serialized_success().await,
+            ConfigTool::ImportProfile(tool) => tool.execute_serialized().await,
+            ConfigTool::Profile(tool) => tool.execute().await,
         }
     }
 }
@@ -88,6 +100,10 @@ pub struct SetGlobalConfig {
     /// `Global` will put the `.aptos/` folder in your home directory
     #[clap(long)]
     config_type: Option<ConfigType>,
+
+    /// URL of the source verification service `aptos move verify-source` submits to
+    #[clap(long)]
+    verification_url: Option<reqwest::Url>,
 }
 
 #[async_trait]
@@ -104,6 +120,9 @@ impl CliCommand<GlobalConfig> for SetGlobalConfig {
         if let Some(config_type) = self.config_type {
             config.config_type = Some(config_type);
         }
+        if let Some(verification_url) = self.verification_url {
+            config.verification_url = Some(verification_url.to_string());
+        }
 
         config.save()?;
         config.display()
@@ -113,7 +132,8 @@ impl CliCommand<GlobalConfig> for SetGlobalConfig {
 /// Shows the current profiles available
 ///
 /// This will only show public information and will not show
-/// private information
+/// private information.  Profiles from the global config in `~/.aptos` are
+/// merged with the workspace config, the same way commands see them
 #[derive(Parser, Debug)]
 pub struct ShowProfiles {
     /// Which profile to show
@@ -121,6 +141,10 @@ pub struct ShowProfiles {
     /// If provided, show only this profile
     #[clap(long)]
     profile: Option<String>,
+
+    /// Show whether each value came from the global or the workspace config
+    #[clap(long)]
+    origin: bool,
 }
 
 #[async_trait]
@@ -131,7 +155,7 @@ impl CliCommand<BTreeMap<String, ProfileSummary>> for ShowProfiles {
 
     async fn execute(self) -> CliTypedResult<BTreeMap<String, ProfileSummary>> {
         // Load the profile config
-        let config = CliConfig::load(ConfigSearchMode::CurrentDir)?;
+        let (config, mut origins) = CliConfig::load_layered(ConfigSearchMode::CurrentDir)?;
         Ok(config
             .profiles
             .unwrap_or_default()
@@ -143,11 +167,163 @@ impl CliCommand<BTreeMap<String, ProfileSummary>> for ShowProfiles {
                     true
                 }
             })
-            .map(|(key, profile)| (key, ProfileSummary::from(&profile)))
+            .map(|(key, profile)| {
+                let mut summary = ProfileSummary::from(&profile);
+                if self.origin {
+                    summary.origins = origins.remove(&key);
+                }
+                (key, summary)
+            })
             .collect())
     }
 }
 
+/// Environment variable holding the passphrase for profile bundles
+const PROFILE_BUNDLE_PASSPHRASE_ENV: &str = "APTOS_PROFILE_BUNDLE_PASSPHRASE";
+const PROFILE_BUNDLE_VERSION: u32 = 1;
+
+/// An exported profile, encrypted with a passphrase
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileBundle {
+    /// Format version of the bundle
+    pub version: u32,
+    /// YAML encoded profile
+    pub profile: EncryptedData,
+}
+
+/// Export a profile to an encrypted bundle
+///
+/// The bundle holds the private key, network, and REST and faucet URLs, encrypted with a
+/// passphrase read from `APTOS_PROFILE_BUNDLE_PASSPHRASE` or prompted for.  Use it with
+/// `aptos config import-profile` to move a profile to another machine.
+#[derive(Parser, Debug)]
+pub struct ExportProfile {
+    /// Leave the private key and faucet auth token out of the bundle, for sharing the profile
+    /// read-only
+    #[clap(long)]
+    read_only: bool,
+
+    #[clap(flatten)]
+    profile_options: ProfileOptions,
+    #[clap(flatten)]
+    save_file: SaveFile,
+}
+
+#[async_trait]
+impl CliCommand<()> for ExportProfile {
+    fn command_name(&self) -> &'static str {
+        "ExportProfile"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        self.save_file.check_file()?;
+        let profile = exported_profile(self.profile_options.profile()?, self.read_only)?;
+
+        let passphrase = read_passphrase("profile bundle", PROFILE_BUNDLE_PASSPHRASE_ENV, true)?;
+        let bundle = ProfileBundle {
+            version: PROFILE_BUNDLE_VERSION,
+            profile: EncryptedData::encrypt(to_yaml(&profile)?.as_bytes(), &passphrase)?,
+        };
+        self.save_file
+            .save_to_file_confidential("Profile bundle", to_yaml(&bundle)?.as_bytes())
+    }
+}
+
+/// The profile as it's exported, without any secrets if it's read-only
+pub(crate) fn exported_profile(
+    profile: ProfileConfig,
+    read_only: bool,
+) -> CliTypedResult<ProfileConfig> {
+    // Keys are always exported in plain text inside the encrypted bundle, since
+    // encryption and keychain entries are specific to this machine
+    let (private_key, faucet_auth_token) = if read_only {
+        (None, None)
+    } else {
+        (
+            profile.load_private_key()?,
+            profile.faucet_auth_token.clone(),
+        )
+    };
+    Ok(ProfileConfig {
+        private_key,
+        encrypted_private_key: None,
+        keychain_entry: None,
+        faucet_auth_token,
+        ..profile
+    })
+}
+
+/// Import a profile from a bundle created by `aptos config export-profile`
+///
+/// The profile is saved to the config in the current directory
+#[derive(Parser, Debug)]
+pub struct ImportProfile {
+    /// Bundle file to import
+    #[clap(long, parse(from_os_str))]
+    bundle_file: PathBuf,
+
+    /// Name of the profile to save the bundle as
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// Store the imported private key encrypted with a passphrase
+    #[clap(long)]
+    encrypt_private_key: bool,
+
+    #[clap(flatten)]
+    prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<ProfileSummary> for ImportProfile {
+    fn command_name(&self) -> &'static str {
+        "ImportProfile"
+    }
+
+    async fn execute(self) -> CliTypedResult<ProfileSummary> {
+        let bundle: ProfileBundle = from_yaml(&String::from_utf8(read_from_file(
+            self.bundle_file.as_path(),
+        )?)?)?;
+        if bundle.version != PROFILE_BUNDLE_VERSION {
+            return Err(CliError::CommandArgumentError(format!(
+                "Unsupported profile bundle version {}, expected {}",
+                bundle.version, PROFILE_BUNDLE_VERSION
+            )));
+        }
+
+        let passphrase = read_passphrase("profile bundle", PROFILE_BUNDLE_PASSPHRASE_ENV, false)?;
+        let mut profile: ProfileConfig =
+            from_yaml(&String::from_utf8(bundle.profile.decrypt(&passphrase)?)?)?;
+        if let Some(private_key) = profile.private_key.take() {
+            profile.set_private_key(private_key, self.encrypt_private_key)?;
+        }
+
+        let mut config = if CliConfig::config_exists(ConfigSearchMode::CurrentDir) {
+            CliConfig::load(ConfigSearchMode::CurrentDir)?
+        } else {
+            CliConfig::default()
+        };
+        if config.remove_profile(&self.profile).is_some() {
+            prompt_yes_with_override(
+                &format!(
+                    "Profile {} already exists, do you want to overwrite it?",
+                    self.profile
+                ),
+                self.prompt_options,
+            )?;
+        }
+
+        let summary = ProfileSummary::from(&profile);
+        config
+            .profiles
+            .get_or_insert_with(BTreeMap::new)
+            .insert(self.profile.clone(), profile);
+        config.save()?;
+        eprintln!("Profile {} is saved.", self.profile);
+        Ok(summary)
+    }
+}
+
 /// Shows the properties in the global config
 #[derive(Parser, Debug)]
 pub struct ShowGlobalConfig {}
@@ -174,6 +350,9 @@ pub struct GlobalConfig {
     /// Whether to be using Global or Workspace mode
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config_type: Option<ConfigType>,
+    /// Source verification service of `aptos move verify-source`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification_url: Option<String>,
 }
 
 impl GlobalConfig {
@@ -216,7 +395,7 @@ impl GlobalConfig {
     }
 }
 
-fn global_folder() -> CliTypedResult<PathBuf> {
+pub fn global_folder() -> CliTypedResult<PathBuf> {
     if let Some(dir) = dirs::home_dir() {
         Ok(dir.join(CONFIG_FOLDER))
     } else {
@@ -0,0 +1,320 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::{
+    init::Network,
+    keychain,
+    types::{
+        CliCommand, CliConfig, CliError, CliResult, CliTypedResult, ConfigSearchMode,
+        ProfileConfig, ProfileOrigins, PromptOptions, DEFAULT_PROFILE,
+    },
+    utils::prompt_yes_with_override,
+};
+use aptos_rest_client::Client;
+use async_trait::async_trait;
+use clap::{Parser, Subcommand};
+use move_core_types::account_address::AccountAddress;
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Tool for managing the profiles in the current directory's config
+///
+/// Profiles are created with `aptos init`, this handles everything after that
+#[derive(Debug, Subcommand)]
+pub enum ProfileTool {
+    List(ListProfiles),
+    Rename(RenameProfile),
+    Copy(CopyProfile),
+    Delete(DeleteProfile),
+    SetDefault(SetDefaultProfile),
+}
+
+impl ProfileTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            ProfileTool::List(tool) => tool.execute_serialized().await,
+            ProfileTool::Rename(tool) => tool.execute_serialized_success().await,
+            ProfileTool::Copy(tool) => tool.execute_serialized_success().await,
+            ProfileTool::Delete(tool) => tool.execute_serialized_success().await,
+            ProfileTool::SetDefault(tool) => tool.execute_serialized_success().await,
+        }
+    }
+}
+
+/// Summary of a profile, with its on-chain balance
+#[derive(Debug, Serialize)]
+pub struct ProfileListing {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<AccountAddress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<Network>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest_url: Option<String>,
+    pub has_private_key: bool,
+    /// Balance in Octas, if the account could be looked up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<u64>,
+    /// Which config each field came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origins: Option<ProfileOrigins>,
+}
+
+/// List profiles with their account, network, and balance
+///
+/// Profiles from the global config in `~/.aptos` are merged with the workspace config, as in
+/// `aptos config show-profiles`
+#[derive(Debug, Parser)]
+pub struct ListProfiles {
+    /// Skip looking up balances on chain
+    #[clap(long)]
+    pub(crate) skip_balance: bool,
+
+    /// Show whether each value came from the global or the workspace config
+    #[clap(long)]
+    pub(crate) origin: bool,
+}
+
+#[async_trait]
+impl CliCommand<BTreeMap<String, ProfileListing>> for ListProfiles {
+    fn command_name(&self) -> &'static str {
+        "ListProfiles"
+    }
+
+    async fn execute(self) -> CliTypedResult<BTreeMap<String, ProfileListing>> {
+        let (config, mut origins) = CliConfig::load_layered(ConfigSearchMode::CurrentDir)?;
+        let mut listings = BTreeMap::new();
+        for (name, profile) in config.profiles.unwrap_or_default() {
+            let balance = if self.skip_balance {
+                None
+            } else {
+                lookup_balance(&profile).await
+            };
+            let origins = if self.origin {
+                origins.remove(&name)
+            } else {
+                None
+            };
+            listings.insert(
+                name,
+                ProfileListing {
+                    account: profile.account,
+                    network: profile.network,
+                    has_private_key: profile.has_private_key(),
+                    rest_url: profile.rest_url,
+                    balance,
+                    origins,
+                },
+            );
+        }
+        Ok(listings)
+    }
+}
+
+/// Looks up the profile account's balance, `None` if it can't be found
+async fn lookup_balance(profile: &ProfileConfig) -> Option<u64> {
+    let rest_url = Url::parse(profile.rest_url.as_ref()?).ok()?;
+    let balance = Client::new(rest_url)
+        .get_account_balance(profile.account?)
+        .await
+        .ok()?;
+    Some(balance.into_inner().get())
+}
+
+/// Rename a profile
+#[derive(Debug, Parser)]
+pub struct RenameProfile {
+    /// Profile to rename
+    #[clap(long)]
+    pub(crate) profile: String,
+    /// New name for the profile
+    #[clap(long)]
+    pub(crate) to: String,
+
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<()> for RenameProfile {
+    fn command_name(&self) -> &'static str {
+        "RenameProfile"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        let mut config = CliConfig::load(ConfigSearchMode::CurrentDir)?;
+        let profile = take_profile(&mut config, &self.profile)?;
+        insert_profile(&mut config, &self.to, profile, self.prompt_options)?;
+        config.save()?;
+        eprintln!("Profile {} renamed to {}", self.profile, self.to);
+        Ok(())
+    }
+}
+
+/// Copy a profile, optionally pointing the copy at another network
+///
+/// The copy keeps the same key and account.  With `--to-network`, the REST and faucet
+/// URLs are replaced with the network's defaults
+#[derive(Debug, Parser)]
+pub struct CopyProfile {
+    /// Profile to copy
+    #[clap(long)]
+    pub(crate) profile: String,
+    /// Name of the new profile
+    ///
+    /// Defaults to the name of `--to-network`
+    #[clap(long, required_unless_present = "to-network")]
+    pub(crate) to: Option<String>,
+    /// Network for the new profile, one of [devnet, testnet, mainnet, local]
+    #[clap(long)]
+    pub(crate) to_network: Option<Network>,
+
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<()> for CopyProfile {
+    fn command_name(&self) -> &'static str {
+        "CopyProfile"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        let mut config = CliConfig::load(ConfigSearchMode::CurrentDir)?;
+        let mut profile = config
+            .profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(&self.profile))
+            .cloned()
+            .ok_or_else(|| profile_not_found(&self.profile))?;
+
+        if let Some(network) = self.to_network {
+            let (rest_url, faucet_url) = network.default_urls().ok_or_else(|| {
+                CliError::CommandArgumentError(
+                    "Custom networks need their own URLs, use `aptos init --network custom`"
+                        .to_string(),
+                )
+            })?;
+            profile.network = Some(network);
+            profile.rest_url = Some(rest_url.to_string());
+            profile.faucet_url = faucet_url.map(|url| url.to_string());
+        }
+
+        let to = self
+            .to
+            .or_else(|| self.to_network.map(|network| network.to_string()))
+            .expect("clap requires one of --to and --to-network");
+        insert_profile(&mut config, &to, profile, self.prompt_options)?;
+        config.save()?;
+        eprintln!("Profile {} copied to {}", self.profile, to);
+        Ok(())
+    }
+}
+
+/// Delete a profile
+#[derive(Debug, Parser)]
+pub struct DeleteProfile {
+    /// Profile to delete
+    #[clap(long)]
+    pub(crate) profile: String,
+
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<()> for DeleteProfile {
+    fn command_name(&self) -> &'static str {
+        "DeleteProfile"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        let mut config = CliConfig::load(ConfigSearchMode::CurrentDir)?;
+        let profile = take_profile(&mut config, &self.profile)?;
+        if profile.has_private_key() {
+            prompt_yes_with_override(
+                &format!(
+                    "Profile {} has a private key, which will be lost unless it's backed up.  Do you want to delete it?",
+                    self.profile
+                ),
+                self.prompt_options,
+            )?;
+        }
+        config.save()?;
+        if let Some(ref entry) = profile.keychain_entry {
+            if let Err(err) = keychain::delete_unused_secret(&config, entry) {
+                eprintln!(
+                    "Warning: unable to delete the private key from the OS keychain: {}",
+                    err
+                );
+            }
+        }
+        eprintln!("Profile {} deleted", self.profile);
+        Ok(())
+    }
+}
+
+/// Make a profile the default profile
+///
+/// The profile is copied to `default`, which is used when no `--profile` is given
+#[derive(Debug, Parser)]
+pub struct SetDefaultProfile {
+    /// Profile to use by default
+    #[clap(long)]
+    pub(crate) profile: String,
+
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<()> for SetDefaultProfile {
+    fn command_name(&self) -> &'static str {
+        "SetDefaultProfile"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        let mut config = CliConfig::load(ConfigSearchMode::CurrentDir)?;
+        let profile = config
+            .profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(&self.profile))
+            .cloned()
+            .ok_or_else(|| profile_not_found(&self.profile))?;
+        insert_profile(&mut config, DEFAULT_PROFILE, profile, self.prompt_options)?;
+        config.save()?;
+        eprintln!("Profile {} is now the default profile", self.profile);
+        Ok(())
+    }
+}
+
+fn profile_not_found(profile: &str) -> CliError {
+    CliError::CommandArgumentError(format!("Profile {} not found", profile))
+}
+
+fn take_profile(config: &mut CliConfig, profile: &str) -> CliTypedResult<ProfileConfig> {
+    config
+        .remove_profile(profile)
+        .ok_or_else(|| profile_not_found(profile))
+}
+
+/// Inserts a profile, asking before overwriting an existing one
+fn insert_profile(
+    config: &mut CliConfig,
+    name: &str,
+    profile: ProfileConfig,
+    prompt_options: PromptOptions,
+) -> CliTypedResult<()> {
+    let profiles = config.profiles.get_or_insert_with(BTreeMap::new);
+    if profiles.contains_key(name) {
+        prompt_yes_with_override(
+            &format!(
+                "Profile {} already exists, do you want to overwrite it?",
+                name
+            ),
+            prompt_options,
+        )?;
+    }
+    profiles.insert(name.to_string(), profile);
+    Ok(())
+}
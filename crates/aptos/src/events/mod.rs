@@ -0,0 +1,21 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::types::{CliCommand, CliResult};
+use clap::Subcommand;
+
+pub mod tail;
+
+/// Tool for following the events of accounts
+#[derive(Subcommand)]
+pub enum EventsTool {
+    Tail(tail::TailEvents),
+}
+
+impl EventsTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            EventsTool::Tail(tool) => tool.execute_serialized().await,
+        }
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::{
+        list::account_or_profile,
+        resources::{matches_filter, Abis},
+    },
+    common::types::{CliCommand, CliTypedResult, ProfileOptions, RestOptions},
+};
+use aptos_rest_client::{
+    aptos_api_types::{MoveStructTag, MoveType, VersionedEvent},
+    Client,
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use serde_json::json;
+use std::{collections::BTreeMap, time::Duration};
+
+/// Most events fetched in a request
+const PAGE_SIZE: u16 = 100;
+
+/// Print an account's events as they're emitted, until interrupted
+///
+/// The account's event handles are found in its resources, and polled for new events.  Handles
+/// in resources created while tailing are picked up too.  Events are decoded with the ABIs of
+/// their modules, and printed a line each.
+#[derive(Parser)]
+pub struct TailEvents {
+    /// Address of the account to follow the events of
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) account: Option<AccountAddress>,
+
+    /// Only follow events of this type, e.g. `0x1::coin::DepositEvent`
+    ///
+    /// Without type arguments, events of any instantiation of the struct are followed
+    #[clap(long)]
+    pub(crate) event_type: Option<MoveStructTag>,
+
+    /// Print the events already emitted, rather than only new ones
+    #[clap(long)]
+    pub(crate) from_start: bool,
+
+    /// Print a JSON object per event, rather than a line of text
+    #[clap(long)]
+    pub(crate) json: bool,
+
+    /// Milliseconds between polls
+    #[clap(long, default_value_t = 1000)]
+    pub(crate) poll_interval_ms: u64,
+
+    /// Stop after this many events
+    #[clap(long)]
+    pub(crate) max_events: Option<u64>,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+/// An event handle, a field of one of the account's resources
+struct EventStream {
+    resource: MoveStructTag,
+    field: String,
+    /// Sequence number of the next event to print
+    next: u64,
+}
+
+#[async_trait]
+impl CliCommand<u64> for TailEvents {
+    fn command_name(&self) -> &'static str {
+        "TailEvents"
+    }
+
+    async fn execute(self) -> CliTypedResult<u64> {
+        let account = account_or_profile(self.account, &self.profile_options)?;
+        let client = self.rest_options.client(&self.profile_options)?;
+        let mut abis = Abis::default();
+        let mut streams: BTreeMap<String, EventStream> = BTreeMap::new();
+        let mut printed = 0;
+        let mut first_poll = true;
+
+        loop {
+            for (key, resource, field, counter) in
+                event_handles(&client, &mut abis, account, self.event_type.as_ref()).await?
+            {
+                // Handles found after the first poll are new, so all their events are
+                let next = if first_poll && !self.from_start {
+                    counter
+                } else {
+                    0
+                };
+                let stream = streams.entry(key).or_insert(EventStream {
+                    resource,
+                    field,
+                    next,
+                });
+                while stream.next < counter {
+                    let events = client
+                        .get_account_events(
+                            account,
+                            &stream.resource.to_string(),
+                            &stream.field,
+                            Some(stream.next),
+                            Some(PAGE_SIZE),
+                        )
+                        .await?
+                        .into_inner();
+                    let last = match events.last() {
+                        Some(last) => last.sequence_number.0,
+                        None => break,
+                    };
+                    load_event_types(&client, &mut abis, &events).await?;
+                    for event in &events {
+                        self.print(&abis, stream, event);
+                        printed += 1;
+                        if Some(printed) == self.max_events {
+                            return Ok(printed);
+                        }
+                    }
+                    stream.next = last + 1;
+                }
+            }
+            first_poll = false;
+            tokio::time::sleep(Duration::from_millis(self.poll_interval_ms)).await;
+        }
+    }
+}
+
+impl TailEvents {
+    fn print(&self, abis: &Abis, stream: &EventStream, event: &VersionedEvent) {
+        let data = abis.decode(&event.typ, &event.data);
+        if self.json {
+            println!(
+                "{}",
+                json!({
+                    "version": event.version.0,
+                    "handle": format!("{}.{}", stream.resource, stream.field),
+                    "sequence_number": event.sequence_number.0,
+                    "type": event.typ.to_string(),
+                    "data": data,
+                })
+            );
+        } else {
+            println!(
+                "{} {}.{}#{} {} {}",
+                event.version.0,
+                stream.resource.name,
+                stream.field,
+                event.sequence_number.0,
+                event.typ,
+                serde_json::to_string(&data).unwrap_or_default()
+            );
+        }
+    }
+}
+
+/// The account's event handles, of the given type if any, with their number of events
+pub(crate) async fn event_handles(
+    client: &Client,
+    abis: &mut Abis,
+    account: AccountAddress,
+    event_type: Option<&MoveStructTag>,
+) -> CliTypedResult<Vec<(String, MoveStructTag, String, u64)>> {
+    let resources: Vec<_> = client
+        .get_account_resources(account)
+        .await?
+        .into_inner()
+        .into_iter()
+        .map(|resource| (MoveStructTag::from(resource.resource_type), resource.data))
+        .collect();
+    abis.load(
+        client,
+        resources.iter().map(|(typ, _)| typ.clone()).collect(),
+    )
+    .await?;
+
+    let mut handles = vec![];
+    for (resource, data) in resources {
+        for (field, typ) in abis.fields(&resource).unwrap_or_default() {
+            let handle_type = match event_handle_type(&typ) {
+                Some(handle_type) => handle_type,
+                None => continue,
+            };
+            if let (Some(filter), MoveType::Struct(tag)) = (event_type, handle_type) {
+                if !matches_filter(tag, filter) {
+                    continue;
+                }
+            }
+            let counter = data[&field]["counter"]
+                .as_str()
+                .and_then(|counter| counter.parse().ok())
+                .unwrap_or_default();
+            handles.push((
+                format!("{}.{}", resource, field),
+                resource.clone(),
+                field,
+                counter,
+            ));
+        }
+    }
+    Ok(handles)
+}
+
+/// The event type of a `0x1::event::EventHandle<T>`
+fn event_handle_type(typ: &MoveType) -> Option<&MoveType> {
+    match typ {
+        MoveType::Struct(tag)
+            if AccountAddress::from(tag.address) == AccountAddress::ONE
+                && tag.module.as_str() == "event"
+                && tag.name.as_str() == "EventHandle" =>
+        {
+            tag.generic_type_params.first()
+        }
+        _ => None,
+    }
+}
+
+/// Fetches the ABIs of the events' types, to decode them with
+async fn load_event_types(
+    client: &Client,
+    abis: &mut Abis,
+    events: &[VersionedEvent],
+) -> CliTypedResult<()> {
+    let types = events
+        .iter()
+        .filter_map(|event| match &event.typ {
+            MoveType::Struct(tag) => Some(tag.clone()),
+            _ => None,
+        })
+        .collect();
+    abis.load(client, types).await
+}
@@ -6,6 +6,7 @@
 pub mod account;
 pub mod common;
 pub mod config;
+pub mod events;
 pub mod genesis;
 pub mod governance;
 pub mod move_tool;
@@ -14,6 +15,8 @@ pub mod op;
 pub mod stake;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod test;
+pub mod transaction;
+pub mod util;
 
 use crate::common::types::{CliCommand, CliResult, CliTypedResult};
 use crate::common::utils::cli_build_information;
@@ -29,6 +32,9 @@ pub enum Tool {
     Account(account::AccountTool),
     #[clap(subcommand)]
     Config(config::ConfigTool),
+    Doctor(common::doctor::DoctorTool),
+    #[clap(subcommand)]
+    Events(events::EventsTool),
     #[clap(subcommand)]
     Genesis(genesis::GenesisTool),
     #[clap(subcommand)]
@@ -43,6 +49,10 @@ pub enum Tool {
     Node(node::NodeTool),
     #[clap(subcommand)]
     Stake(stake::StakeTool),
+    #[clap(subcommand)]
+    Transaction(transaction::TransactionTool),
+    #[clap(subcommand)]
+    Util(util::UtilTool),
 }
 
 impl Tool {
@@ -51,6 +61,8 @@ impl Tool {
         match self {
             Account(tool) => tool.execute().await,
             Config(tool) => tool.execute().await,
+            Doctor(tool) => tool.execute_serialized().await,
+            Events(tool) => tool.execute().await,
             Genesis(tool) => tool.execute().await,
             Governance(tool) => tool.execute().await,
             Info(tool) => tool.execute_serialized().await,
@@ -60,6 +72,8 @@ impl Tool {
             Move(tool) => tool.execute().await,
             Node(tool) => tool.execute().await,
             Stake(tool) => tool.execute().await,
+            Transaction(tool) => tool.execute().await,
+            Util(tool) => tool.execute().await,
         }
     }
 }
@@ -0,0 +1,279 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The ABI of a package, the model the bindings generators work from
+//!
+//! Types and functions use the REST API's representation, so the JSON matches what a node
+//! returns for the published modules.
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, MovePackageDir},
+        utils::write_to_file,
+    },
+    move_tool::IncludedArtifacts,
+};
+use aptos_rest_client::aptos_api_types::{
+    Address, Bytecode, IdentifierWrapper, MoveFunction, MoveFunctionVisibility, MoveStruct,
+    MoveType,
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use framework::{BuildOptions, BuiltPackage};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, path::PathBuf};
+
+/// Version of the ABI JSON, bumped on incompatible changes to it
+pub const ABI_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PackageAbi {
+    pub schema_version: u32,
+    pub package: String,
+    pub modules: Vec<ModuleAbi>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModuleAbi {
+    pub address: Address,
+    pub name: IdentifierWrapper,
+    /// Functions that can be called by a transaction
+    pub entry_functions: Vec<MoveFunction>,
+    /// Public functions that aren't entry functions, which only other modules can call
+    pub public_functions: Vec<MoveFunction>,
+    pub structs: Vec<MoveStruct>,
+    /// Events emitted through the `EventHandle` fields of this module's structs
+    pub events: Vec<EventAbi>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EventAbi {
+    /// The struct holding the event handle
+    #[serde(rename = "struct")]
+    pub struct_: IdentifierWrapper,
+    /// The event handle field
+    pub field: IdentifierWrapper,
+    /// Type of the events
+    #[serde(rename = "type")]
+    pub typ: MoveType,
+}
+
+impl PackageAbi {
+    pub fn new(package: &BuiltPackage) -> Self {
+        let modules = package
+            .modules()
+            .map(|module| {
+                let module_id = module.self_id();
+                let mut entry_functions = vec![];
+                let mut public_functions = vec![];
+                for definition in &module.function_defs {
+                    let function = module.new_move_function(definition);
+                    if function.is_entry {
+                        entry_functions.push(function);
+                    } else if function.visibility == MoveFunctionVisibility::Public {
+                        public_functions.push(function);
+                    }
+                }
+                let structs: Vec<_> = module
+                    .struct_defs
+                    .iter()
+                    .map(|definition| module.new_move_struct(definition))
+                    .collect();
+                let events = structs
+                    .iter()
+                    .flat_map(|move_struct| {
+                        move_struct.fields.iter().filter_map(|field| {
+                            event_type(&field.typ).map(|typ| EventAbi {
+                                struct_: move_struct.name.clone(),
+                                field: field.name.clone(),
+                                typ,
+                            })
+                        })
+                    })
+                    .collect();
+                ModuleAbi {
+                    address: (*module_id.address()).into(),
+                    name: module_id.name().into(),
+                    entry_functions,
+                    public_functions,
+                    structs,
+                    events,
+                }
+            })
+            .collect();
+        Self {
+            schema_version: ABI_SCHEMA_VERSION,
+            package: package.name().to_string(),
+            modules,
+        }
+    }
+
+    /// Lists the ABI as Move-like declarations
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for module in &self.modules {
+            let _ = writeln!(out, "module {}::{} {{", module.address, module.name);
+            for function in module
+                .entry_functions
+                .iter()
+                .chain(module.public_functions.iter())
+            {
+                let _ = writeln!(out, "    {}", function_signature(function));
+            }
+            for move_struct in &module.structs {
+                let _ = writeln!(out, "    {}", struct_declaration(move_struct));
+            }
+            for event in &module.events {
+                let _ = writeln!(
+                    out,
+                    "    event {} // {}.{}",
+                    event.typ, event.struct_, event.field
+                );
+            }
+            out.push_str("}\n");
+        }
+        out
+    }
+}
+
+/// The type of events an `0x1::event::EventHandle` field emits
+pub fn event_type(typ: &MoveType) -> Option<MoveType> {
+    match typ {
+        MoveType::Struct(tag)
+            if tag.address == Address::from(AccountAddress::ONE)
+                && tag.module.as_str() == "event"
+                && tag.name.as_str() == "EventHandle" =>
+        {
+            tag.generic_type_params.first().cloned()
+        }
+        _ => None,
+    }
+}
+
+fn type_params(count: usize) -> String {
+    if count == 0 {
+        String::new()
+    } else {
+        format!(
+            "<{}>",
+            (0..count)
+                .map(|index| format!("T{}", index))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+fn function_signature(function: &MoveFunction) -> String {
+    let visibility = match function.visibility {
+        MoveFunctionVisibility::Public => "public ",
+        MoveFunctionVisibility::Friend => "public(friend) ",
+        MoveFunctionVisibility::Private => "",
+    };
+    let params: Vec<_> = function.params.iter().map(|typ| typ.to_string()).collect();
+    let returns = match function.return_.len() {
+        0 => String::new(),
+        1 => format!(": {}", function.return_[0]),
+        _ => format!(
+            ": ({})",
+            function
+                .return_
+                .iter()
+                .map(|typ| typ.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    format!(
+        "{}{}fun {}{}({}){}",
+        visibility,
+        if function.is_entry { "entry " } else { "" },
+        function.name,
+        type_params(function.generic_type_params.len()),
+        params.join(", "),
+        returns
+    )
+}
+
+fn struct_declaration(move_struct: &MoveStruct) -> String {
+    let abilities = if move_struct.abilities.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " has {}",
+            move_struct
+                .abilities
+                .iter()
+                .map(|ability| ability.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    let fields: Vec<_> = move_struct
+        .fields
+        .iter()
+        .map(|field| format!("{}: {}", field.name, field.typ))
+        .collect();
+    format!(
+        "struct {}{}{} {{ {} }}",
+        move_struct.name,
+        type_params(move_struct.generic_type_params.len()),
+        abilities,
+        fields.join(", ")
+    )
+}
+
+/// Builds a package and returns its ABI
+pub(crate) fn build_abi(move_options: &MovePackageDir) -> CliTypedResult<PackageAbi> {
+    let build_options = BuildOptions {
+        install_dir: move_options.output_dir.clone(),
+        ..IncludedArtifacts::None.build_options(move_options.named_addresses())
+    };
+    let pack = BuiltPackage::build(move_options.get_package_path()?, build_options)
+        .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
+    Ok(PackageAbi::new(&pack))
+}
+
+/// Show the ABI of a package: its entry and public functions, structs, and events
+///
+/// With `--json`, the ABI is written as JSON for codegen tools, in the same representation of
+/// types as the REST API.  The JSON has a `schema_version`, which changes if the format changes
+/// incompatibly
+#[derive(Parser)]
+pub struct ExportPackageAbi {
+    /// Output the ABI as JSON
+    #[clap(long)]
+    pub(crate) json: bool,
+
+    /// File to write the ABI to, instead of printing it
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<()> for ExportPackageAbi {
+    fn command_name(&self) -> &'static str {
+        "ExportPackageAbi"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        let abi = build_abi(&self.move_options)?;
+        let output = if self.json {
+            serde_json::to_string_pretty(&abi)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+        } else {
+            abi.to_text()
+        };
+        match self.output_file {
+            Some(ref output_file) => write_to_file(output_file, "ABI", output.as_bytes()),
+            None => {
+                println!("{}", output);
+                Ok(())
+            }
+        }
+    }
+}
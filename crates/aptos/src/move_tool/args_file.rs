@@ -0,0 +1,85 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Type arguments and arguments of `aptos move run` and `run-script` read from a JSON file
+//!
+//! ```json
+//! {
+//!   "type_args": ["0x1::aptos_coin::AptosCoin"],
+//!   "args": [
+//!     {"type": "address", "value": "0xcafe"},
+//!     {"type": "u64", "value": 100}
+//!   ]
+//! }
+//! ```
+//!
+//! The arguments' types are those of `--args`.  For `run`, `json_args` can be given instead of
+//! `args`, as for `--json-args`.
+
+use crate::{
+    common::{
+        types::{CliError, CliTypedResult},
+        utils::read_from_file,
+    },
+    move_tool::ArgWithType,
+};
+use aptos_rest_client::aptos_api_types::MoveType;
+use serde::Deserialize;
+use std::{path::Path, str::FromStr};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ArgsFile {
+    #[serde(default)]
+    pub(crate) type_args: Vec<String>,
+    #[serde(default)]
+    pub(crate) args: Vec<FileArg>,
+    /// Arguments typed by the function's ABI
+    pub(crate) json_args: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FileArg {
+    #[serde(rename = "type")]
+    pub(crate) typ: String,
+    /// The value, as a string, number or bool
+    pub(crate) value: serde_json::Value,
+}
+
+impl ArgsFile {
+    pub(crate) fn load(path: &Path) -> CliTypedResult<ArgsFile> {
+        let args_file: ArgsFile = serde_json::from_slice(&read_from_file(path)?)
+            .map_err(|err| CliError::UnableToParse("--args-file", err.to_string()))?;
+        if args_file.json_args.is_some() && !args_file.args.is_empty() {
+            return Err(CliError::UnableToParse(
+                "--args-file",
+                "only one of args and json_args can be given".to_string(),
+            ));
+        }
+        Ok(args_file)
+    }
+
+    pub(crate) fn type_args(&self) -> CliTypedResult<Vec<MoveType>> {
+        let mut type_args = vec![];
+        for type_arg in &self.type_args {
+            type_args.push(
+                MoveType::from_str(type_arg)
+                    .map_err(|err| CliError::UnableToParse("type_args", err.to_string()))?,
+            );
+        }
+        Ok(type_args)
+    }
+
+    pub(crate) fn args(&self) -> CliTypedResult<Vec<ArgWithType>> {
+        let mut args = vec![];
+        for arg in &self.args {
+            let value = match &arg.value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            args.push(ArgWithType::from_str(&format!("{}:{}", arg.typ, value))?);
+        }
+        Ok(args)
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Build attestations, for checking that a package rebuilds to the same bytecode
+//!
+//! `aptos move compile --reproducible` records what the bytecode depends on: the CLI, which has
+//! the compiler built in, the build options, the digest of the sources, and the locked commits
+//! of the git dependencies.  Next to those, it records the digests of the bytecode of every
+//! module and of every dependency, which a rebuild with the same inputs must reproduce.
+
+use crate::{
+    common::{
+        types::{CliError, CliTypedResult, MovePackageDir},
+        utils::{cli_build_information, read_from_file, write_to_file},
+    },
+    move_tool::{lockfile::MoveLock, resolved_package::resolve_package},
+    CliCommand,
+};
+use aptos_build_info::{BUILD_COMMIT_HASH, BUILD_PKG_VERSION};
+use aptos_crypto::HashValue;
+use async_trait::async_trait;
+use clap::Parser;
+use framework::{BuildOptions, BuiltPackage};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use tempfile::TempDir;
+
+pub const ATTESTATION_FILE: &str = "build-info.json";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BuildAttestation {
+    pub package: String,
+    /// Version of the CLI, and so of the compiler
+    pub cli_version: String,
+    pub cli_commit: String,
+    /// The environment of Move.toml the package was built for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    pub options: BuildOptions,
+    pub source_digest: String,
+    pub dependencies: BTreeMap<String, AttestedDependency>,
+    /// Digest of the bytecode of each module
+    pub modules: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AttestedDependency {
+    /// Digest of the bytecode of the dependency's modules
+    pub digest: String,
+    /// The locked commit of a git dependency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+impl BuildAttestation {
+    pub(crate) fn new(
+        package: &BuiltPackage,
+        package_dir: &Path,
+        env: Option<String>,
+        options: BuildOptions,
+    ) -> CliTypedResult<Self> {
+        let build_information = cli_build_information();
+        let info = |key: &str| build_information.get(key).cloned().unwrap_or_default();
+
+        let mut modules = BTreeMap::new();
+        for module in package.modules() {
+            let mut bytes = vec![];
+            module
+                .serialize(&mut bytes)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            modules.insert(module.self_id().name().to_string(), digest(&bytes));
+        }
+
+        let mut dependency_code: BTreeMap<String, Vec<Vec<u8>>> = BTreeMap::new();
+        for (name, bytes) in package.extract_dependency_code() {
+            dependency_code.entry(name).or_default().push(bytes);
+        }
+        let lock = MoveLock::load(package_dir)?.unwrap_or_default();
+        let dependencies = dependency_code
+            .into_iter()
+            .map(|(name, mut code)| {
+                code.sort();
+                let dependency = AttestedDependency {
+                    digest: digest(&code.concat()),
+                    commit: lock
+                        .dependencies
+                        .get(&name)
+                        .map(|locked| locked.commit.clone()),
+                };
+                (name, dependency)
+            })
+            .collect();
+
+        Ok(BuildAttestation {
+            package: package.name().to_string(),
+            cli_version: info(BUILD_PKG_VERSION),
+            cli_commit: info(BUILD_COMMIT_HASH),
+            env,
+            // Where the build goes doesn't change it
+            options: BuildOptions {
+                install_dir: None,
+                ..options
+            },
+            source_digest: package.extract_metadata()?.source_digest,
+            dependencies,
+            modules,
+        })
+    }
+
+    pub fn load(path: &Path) -> CliTypedResult<Self> {
+        serde_json::from_slice(&read_from_file(path)?)
+            .map_err(|err| CliError::UnableToParse(ATTESTATION_FILE, err.to_string()))
+    }
+
+    pub fn save(&self, path: &Path) -> CliTypedResult<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        write_to_file(path, ATTESTATION_FILE, json.as_bytes())
+    }
+
+    /// How a rebuild differs from the attested build
+    fn mismatches(&self, rebuilt: &BuildAttestation) -> Vec<String> {
+        let mut mismatches = vec![];
+        if self.source_digest != rebuilt.source_digest {
+            mismatches.push(format!(
+                "Source digest: {}, attested {}",
+                rebuilt.source_digest, self.source_digest
+            ));
+        }
+        compare(
+            "Module",
+            &self.modules,
+            &rebuilt.modules,
+            |digest| digest.clone(),
+            &mut mismatches,
+        );
+        compare(
+            "Dependency",
+            &self.dependencies,
+            &rebuilt.dependencies,
+            |dependency| match &dependency.commit {
+                Some(commit) => format!("{} at commit {}", dependency.digest, commit),
+                None => dependency.digest.clone(),
+            },
+            &mut mismatches,
+        );
+        mismatches
+    }
+}
+
+fn compare<T: PartialEq>(
+    kind: &str,
+    attested: &BTreeMap<String, T>,
+    rebuilt: &BTreeMap<String, T>,
+    describe: impl Fn(&T) -> String,
+    mismatches: &mut Vec<String>,
+) {
+    for (name, value) in attested {
+        match rebuilt.get(name) {
+            Some(rebuilt) if rebuilt == value => {}
+            Some(rebuilt) => mismatches.push(format!(
+                "{} {}: {}, attested {}",
+                kind,
+                name,
+                describe(rebuilt),
+                describe(value)
+            )),
+            None => mismatches.push(format!("{} {}: missing from the rebuild", kind, name)),
+        }
+    }
+    for name in rebuilt.keys().filter(|name| !attested.contains_key(*name)) {
+        mismatches.push(format!("{} {}: not in the attestation", kind, name));
+    }
+}
+
+fn digest(bytes: &[u8]) -> String {
+    HashValue::sha3_256_of(bytes).to_hex()
+}
+
+/// Rebuilds a package as recorded in its build attestation, and checks the bytecode is identical
+///
+/// The attestation is written by `aptos move compile --reproducible`.  The package is built with
+/// the attested options, in a temporary directory.
+#[derive(Parser)]
+pub struct VerifyAttestation {
+    /// The build attestation
+    ///
+    /// Defaults to `<package_dir>/build-info.json`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) attestation_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<&'static str> for VerifyAttestation {
+    fn command_name(&self) -> &'static str {
+        "VerifyAttestation"
+    }
+
+    async fn execute(self) -> CliTypedResult<&'static str> {
+        let package_dir = self.move_options.get_package_path()?;
+        let attestation = BuildAttestation::load(
+            &self
+                .attestation_file
+                .unwrap_or_else(|| package_dir.join(ATTESTATION_FILE)),
+        )?;
+        let build_information = cli_build_information();
+        if build_information.get(BUILD_PKG_VERSION) != Some(&attestation.cli_version)
+            || build_information.get(BUILD_COMMIT_HASH) != Some(&attestation.cli_commit)
+        {
+            eprintln!(
+                "Warning: the attestation was made with CLI {} ({}), whose compiler may differ",
+                attestation.cli_version, attestation.cli_commit
+            );
+        }
+
+        let resolved = resolve_package(&package_dir, attestation.env.as_deref())?;
+        let build_dir = TempDir::new().map_err(|err| {
+            CliError::UnexpectedError(format!("Failed to create temporary directory {}", err))
+        })?;
+        let options = BuildOptions {
+            install_dir: Some(build_dir.path().to_path_buf()),
+            ..attestation.options.clone()
+        };
+        let package = BuiltPackage::build(resolved.path(), options.clone())
+            .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
+        let rebuilt =
+            BuildAttestation::new(&package, &package_dir, attestation.env.clone(), options)?;
+
+        let mismatches = attestation.mismatches(&rebuilt);
+        if mismatches.is_empty() {
+            Ok("Successfully reproduced the attested build")
+        } else {
+            Err(CliError::UnexpectedError(format!(
+                "Package {} doesn't rebuild to the attested bytecode:\n{}",
+                attestation.package,
+                mismatches.join("\n")
+            )))
+        }
+    }
+}
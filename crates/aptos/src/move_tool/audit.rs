@@ -0,0 +1,416 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Heuristic security checks of a package's Move code
+//!
+//! The checks look at the bytecode of the package as it's published, and report where in the
+//! sources they found something with the source maps.  They're heuristics: a finding is worth a
+//! look, not necessarily a bug, and not finding anything proves nothing.
+
+use crate::common::{
+    types::{CliCommand, CliError, CliTypedResult, MovePackageDir},
+    utils::read_from_file,
+};
+use async_trait::async_trait;
+use clap::{ArgEnum, Parser};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{
+        Bytecode, FunctionDefinitionIndex, SignatureToken, StructDefinitionIndex, Visibility,
+    },
+    CompiledModule,
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
+use move_package::BuildConfig;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+#[derive(ArgEnum, Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        })
+    }
+}
+
+impl FromStr for Severity {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            _ => Err("Invalid severity. Valid values are low, medium, high"),
+        }
+    }
+}
+
+/// Check a package for common security issues
+///
+/// The checks are heuristics on the compiled package:
+/// - arithmetic: `<<`, which silently drops the bits shifted out, and division before
+///   multiplication, which loses precision
+/// - signer: entry functions changing global storage without a signer, or ignoring theirs
+/// - capability: capabilities with `store`, which can be transferred anywhere, and public
+///   functions handing capabilities out to callers without a signer
+/// - mutable getter: public functions returning `&mut` into global storage
+///
+/// Findings are reported with their location in the sources.  Fails if there are findings of
+/// the `--fail-on` severity or higher
+#[derive(Parser)]
+pub struct AuditPackage {
+    /// Only report findings of this severity or higher: [low, medium, high]
+    #[clap(long, default_value_t = Severity::Low)]
+    pub(crate) min_severity: Severity,
+
+    /// Fail if there are findings of this severity or higher: [low, medium, high]
+    #[clap(long, default_value_t = Severity::High)]
+    pub(crate) fail_on: Severity,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[derive(Serialize)]
+pub struct AuditFinding {
+    pub check: &'static str,
+    pub severity: Severity,
+    /// The function or struct, e.g. `coin::mint`
+    pub item: String,
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+#[async_trait]
+impl CliCommand<Vec<AuditFinding>> for AuditPackage {
+    fn command_name(&self) -> &'static str {
+        "AuditPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<AuditFinding>> {
+        let package = BuildConfig {
+            additional_named_addresses: self.move_options.named_addresses(),
+            install_dir: self.move_options.output_dir.clone(),
+            ..Default::default()
+        }
+        .compile_package_no_exit(
+            &self.move_options.get_package_path()?,
+            &mut std::io::stderr(),
+        )
+        .map_err(|err| CliError::MoveCompilationError(format!("{:#}", err)))?;
+
+        let mut findings = vec![];
+        let mut sources = Sources::default();
+        for unit in package.root_modules() {
+            if let CompiledUnit::Module(NamedCompiledModule {
+                module, source_map, ..
+            }) = &unit.unit
+            {
+                let mut audit = ModuleAudit {
+                    module,
+                    source_map,
+                    file: PathBuf::from(&unit.source_path),
+                    sources: &mut sources,
+                    findings: vec![],
+                };
+                audit.run()?;
+                findings.extend(audit.findings);
+            }
+        }
+        findings.retain(|finding| finding.severity >= self.min_severity);
+        findings.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then_with(|| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)))
+        });
+
+        for finding in &findings {
+            eprintln!(
+                "{}:{}:{}: {} [{}, {}] {}",
+                finding.file.display(),
+                finding.line,
+                finding.column,
+                finding.severity,
+                finding.check,
+                finding.item,
+                finding.message
+            );
+        }
+        let failing = findings
+            .iter()
+            .filter(|finding| finding.severity >= self.fail_on)
+            .count();
+        if failing > 0 {
+            return Err(CliError::UnexpectedError(format!(
+                "{} findings of severity {} or higher",
+                failing, self.fail_on
+            )));
+        }
+        Ok(findings)
+    }
+}
+
+/// Source files, to turn byte offsets into lines and columns
+#[derive(Default)]
+pub(crate) struct Sources {
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl Sources {
+    pub(crate) fn line_and_column(
+        &mut self,
+        file: &Path,
+        offset: u32,
+    ) -> CliTypedResult<(usize, usize)> {
+        if !self.files.contains_key(file) {
+            let text = String::from_utf8(read_from_file(file)?).map_err(|err| {
+                CliError::UnableToReadFile(file.display().to_string(), err.to_string())
+            })?;
+            self.files.insert(file.to_path_buf(), text);
+        }
+        let text = &self.files[file];
+        let before = &text[..(offset as usize).min(text.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+        Ok((line, column))
+    }
+}
+
+struct ModuleAudit<'a> {
+    module: &'a CompiledModule,
+    source_map: &'a SourceMap,
+    file: PathBuf,
+    sources: &'a mut Sources,
+    findings: Vec<AuditFinding>,
+}
+
+impl<'a> ModuleAudit<'a> {
+    fn run(&mut self) -> CliTypedResult<()> {
+        let module = self.module;
+        let source_map = self.source_map;
+        for (index, definition) in module.struct_defs().iter().enumerate() {
+            let handle = module.struct_handle_at(definition.struct_handle);
+            let name = module.identifier_at(handle.name).to_string();
+            if is_capability(&name) && handle.abilities.has_store() {
+                let offset = source_map
+                    .get_struct_source_map(StructDefinitionIndex(index as u16))
+                    .map(|map| map.definition_location.start())
+                    .unwrap_or_default();
+                self.report(
+                    "capability",
+                    Severity::Medium,
+                    name,
+                    offset,
+                    "capability has `store`, so whoever holds it can transfer it anywhere",
+                )?;
+            }
+        }
+
+        for (index, definition) in module.function_defs().iter().enumerate() {
+            let index = FunctionDefinitionIndex(index as u16);
+            let handle = module.function_handle_at(definition.function);
+            let name = module.identifier_at(handle.name).to_string();
+            let params = &module.signature_at(handle.parameters).0;
+            let returns = &module.signature_at(handle.return_).0;
+            let code = match &definition.code {
+                Some(code) => &code.code,
+                None => continue,
+            };
+            let function_map = source_map.get_function_source_map(index).ok();
+            let definition_offset = function_map
+                .map(|map| map.definition_location.start())
+                .unwrap_or_default();
+            let code_offset = |offset: usize| {
+                function_map
+                    .and_then(|map| map.get_code_location(offset as u16))
+                    .map_or(definition_offset, |loc| loc.start())
+            };
+
+            let signers: Vec<_> = params
+                .iter()
+                .enumerate()
+                .filter(|(_, param)| is_signer(param))
+                .map(|(index, _)| index as u8)
+                .collect();
+            let writes_global = code.iter().any(|instruction| {
+                matches!(
+                    instruction,
+                    Bytecode::MutBorrowGlobal(_)
+                        | Bytecode::MutBorrowGlobalGeneric(_)
+                        | Bytecode::MoveFrom(_)
+                        | Bytecode::MoveFromGeneric(_)
+                )
+            });
+            let public = definition.visibility == Visibility::Public;
+
+            if definition.is_entry && writes_global && signers.is_empty() {
+                self.report(
+                    "signer",
+                    Severity::High,
+                    name.clone(),
+                    definition_offset,
+                    "entry function changes global storage, but anyone can call it without a \
+                    signer",
+                )?;
+            }
+            if definition.is_entry || public {
+                for signer in &signers {
+                    let used = code.iter().any(|instruction| {
+                        matches!(
+                            instruction,
+                            Bytecode::CopyLoc(local)
+                                | Bytecode::MoveLoc(local)
+                                | Bytecode::ImmBorrowLoc(local)
+                                | Bytecode::MutBorrowLoc(local)
+                                if local == signer
+                        )
+                    });
+                    if !used {
+                        self.report(
+                            "signer",
+                            Severity::Medium,
+                            name.clone(),
+                            definition_offset,
+                            "takes a signer but never checks or uses it",
+                        )?;
+                    }
+                }
+            }
+            if public
+                && signers.is_empty()
+                && returns.iter().any(|typ| self.is_capability_type(typ))
+            {
+                self.report(
+                    "capability",
+                    Severity::High,
+                    name.clone(),
+                    definition_offset,
+                    "public function hands out a capability to any caller, without a signer",
+                )?;
+            }
+            if public
+                && returns
+                    .iter()
+                    .any(|typ| matches!(typ, SignatureToken::MutableReference(_)))
+                && code.iter().any(|instruction| {
+                    matches!(
+                        instruction,
+                        Bytecode::MutBorrowGlobal(_) | Bytecode::MutBorrowGlobalGeneric(_)
+                    )
+                })
+            {
+                self.report(
+                    "mutable getter",
+                    Severity::High,
+                    name.clone(),
+                    definition_offset,
+                    "public function returns `&mut` into global storage, which any module can \
+                    then change",
+                )?;
+            }
+
+            for (offset, instruction) in code.iter().enumerate() {
+                match instruction {
+                    Bytecode::Shl => self.report(
+                        "arithmetic",
+                        Severity::Low,
+                        name.clone(),
+                        code_offset(offset),
+                        "`<<` drops the bits shifted out without aborting",
+                    )?,
+                    Bytecode::Div if multiplies_quotient(&code[offset + 1..]) => self.report(
+                        "arithmetic",
+                        Severity::Low,
+                        name.clone(),
+                        code_offset(offset),
+                        "division before multiplication loses precision",
+                    )?,
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_capability_type(&self, typ: &SignatureToken) -> bool {
+        match typ {
+            SignatureToken::Struct(handle) | SignatureToken::StructInstantiation(handle, _) => {
+                is_capability(
+                    self.module
+                        .identifier_at(self.module.struct_handle_at(*handle).name)
+                        .as_str(),
+                )
+            }
+            _ => false,
+        }
+    }
+
+    fn report(
+        &mut self,
+        check: &'static str,
+        severity: Severity,
+        item: String,
+        offset: u32,
+        message: &str,
+    ) -> CliTypedResult<()> {
+        let (line, column) = self.sources.line_and_column(&self.file, offset)?;
+        self.findings.push(AuditFinding {
+            check,
+            severity,
+            item: format!("{}::{}", self.module.self_id().name(), item),
+            file: self.file.clone(),
+            line,
+            column,
+            message: message.to_string(),
+        });
+        Ok(())
+    }
+}
+
+fn is_capability(name: &str) -> bool {
+    name.ends_with("Capability") || name.ends_with("Cap")
+}
+
+fn is_signer(typ: &SignatureToken) -> bool {
+    match typ {
+        SignatureToken::Signer => true,
+        SignatureToken::Reference(inner) => is_signer(inner),
+        _ => false,
+    }
+}
+
+/// Whether the quotient on top of the stack is multiplied next, as in `a / b * c`
+fn multiplies_quotient(rest: &[Bytecode]) -> bool {
+    match rest {
+        [Bytecode::Mul, ..] => true,
+        [operand, Bytecode::Mul, ..] => matches!(
+            operand,
+            Bytecode::CopyLoc(_)
+                | Bytecode::MoveLoc(_)
+                | Bytecode::LdU8(_)
+                | Bytecode::LdU64(_)
+                | Bytecode::LdU128(_)
+                | Bytecode::LdConst(_)
+        ),
+        _ => false,
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Gas benchmarks of entry functions, checked against a baseline
+//!
+//! Benchmarks are listed in a YAML file of the package, each an entry function with its
+//! arguments.  They run in a local executor, like `aptos move fuzz`, and the gas they use is
+//! compared with a baseline file that's checked in, so CI fails when gas goes up.
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, MovePackageDir},
+        utils::{read_from_file, write_to_file},
+    },
+    move_tool::{
+        local_executor::{entry_function_transaction, package_executor},
+        ArgWithType, IncludedArtifacts, MemberId,
+    },
+};
+use aptos_rest_client::aptos_api_types::MoveType;
+use aptos_types::transaction::{EntryFunction, ExecutionStatus, TransactionStatus};
+use async_trait::async_trait;
+use clap::Parser;
+use framework::{BuildOptions, BuiltPackage};
+use move_core_types::{identifier::Identifier, language_storage::TypeTag};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+const BENCHMARKS_FILE: &str = "bench.yaml";
+const BASELINE_FILE: &str = "bench_baseline.json";
+
+/// Benchmark the gas and time of entry functions against a baseline
+///
+/// Benchmarks are read from `<package_dir>/bench.yaml`, a list of:
+///
+/// ```yaml
+/// - name: set_short_message
+///   function: message::set_message
+///   args: ["string:hello"]
+///   type_args: []
+/// ```
+///
+/// Functions are `<module>::<function>` of the package, or `<address>::<module>::<function>`,
+/// and arguments are as for `aptos move run`.  Signers are the sender, a funded account in a
+/// local executor with the package published.  Benchmarks using more gas than the baseline
+/// fail the command, while faster or cheaper ones are reported with their deltas
+#[derive(Parser)]
+pub struct BenchPackage {
+    /// Only run benchmarks whose names contain this
+    #[clap(long, short)]
+    pub(crate) filter: Option<String>,
+
+    /// File of the benchmarks, defaults to `<package_dir>/bench.yaml`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) benchmarks: Option<PathBuf>,
+
+    /// Baseline file, defaults to `<package_dir>/bench_baseline.json`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) baseline: Option<PathBuf>,
+
+    /// Write the results as the new baseline, instead of comparing with it
+    #[clap(long)]
+    pub(crate) update_baseline: bool,
+
+    /// Percentage of gas over the baseline that's allowed
+    #[clap(long, default_value_t = 0.0)]
+    pub(crate) tolerance: f64,
+
+    /// Times each benchmark runs, for its execution time
+    #[clap(long, default_value_t = 10)]
+    pub(crate) iterations: u32,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+/// A benchmark from the benchmarks file
+#[derive(Deserialize)]
+struct Benchmark {
+    name: String,
+    function: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    type_args: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchResult {
+    pub name: String,
+    pub function: String,
+    pub gas_used: u64,
+    /// Mean execution time in microseconds
+    pub time_us: u128,
+    pub baseline_gas_used: Option<u64>,
+    /// Change in gas from the baseline, in percent
+    pub gas_delta: Option<f64>,
+}
+
+#[async_trait]
+impl CliCommand<Vec<BenchResult>> for BenchPackage {
+    fn command_name(&self) -> &'static str {
+        "BenchPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<BenchResult>> {
+        let package_path = self.move_options.get_package_path()?;
+        let benchmarks_path = self
+            .benchmarks
+            .clone()
+            .unwrap_or_else(|| package_path.join(BENCHMARKS_FILE));
+        let baseline_path = self
+            .baseline
+            .clone()
+            .unwrap_or_else(|| package_path.join(BASELINE_FILE));
+        let benchmarks: Vec<Benchmark> = serde_yaml::from_slice(&read_from_file(&benchmarks_path)?)
+            .map_err(|err| CliError::UnableToParse(BENCHMARKS_FILE, err.to_string()))?;
+        let baseline: BTreeMap<String, u64> = if baseline_path.exists() && !self.update_baseline {
+            serde_json::from_slice(&read_from_file(&baseline_path)?)
+                .map_err(|err| CliError::UnableToParse(BASELINE_FILE, err.to_string()))?
+        } else {
+            BTreeMap::new()
+        };
+
+        let build_options = BuildOptions {
+            install_dir: self.move_options.output_dir.clone(),
+            ..IncludedArtifacts::None.build_options(self.move_options.named_addresses())
+        };
+        let pack = BuiltPackage::build(package_path, build_options)
+            .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
+        let (executor, sender) = package_executor(&pack)?;
+
+        let mut results = vec![];
+        for benchmark in benchmarks.iter().filter(|benchmark| match self.filter {
+            Some(ref filter) => benchmark.name.contains(filter.as_str()),
+            None => true,
+        }) {
+            let function = resolve_function(&pack, &benchmark.function)?;
+            let args = benchmark
+                .args
+                .iter()
+                .map(|arg| ArgWithType::from_str(arg).map(|arg| arg.arg))
+                .collect::<CliTypedResult<Vec<_>>>()?;
+            let type_args = benchmark
+                .type_args
+                .iter()
+                .map(|typ| {
+                    MoveType::from_str(typ)
+                        .map_err(|err| CliError::UnableToParse("type_args", err.to_string()))
+                        .and_then(|typ| {
+                            TypeTag::try_from(typ).map_err(|err| {
+                                CliError::UnableToParse("type_args", err.to_string())
+                            })
+                        })
+                })
+                .collect::<CliTypedResult<Vec<_>>>()?;
+            let txn = entry_function_transaction(
+                &sender,
+                EntryFunction::new(function.module_id, function.member_id, type_args, args),
+            );
+
+            // The transaction isn't applied, so every iteration runs from the same state
+            let mut gas_used = 0;
+            let mut time = Duration::ZERO;
+            for _ in 0..self.iterations.max(1) {
+                let start = Instant::now();
+                let output = executor.execute(txn.clone())?;
+                time += start.elapsed();
+                if output.status() != &TransactionStatus::Keep(ExecutionStatus::Success) {
+                    return Err(CliError::UnexpectedError(format!(
+                        "Benchmark {} failed: {:?}",
+                        benchmark.name,
+                        output.status()
+                    )));
+                }
+                gas_used = output.gas_used();
+            }
+
+            let baseline_gas_used = baseline.get(&benchmark.name).copied();
+            results.push(BenchResult {
+                name: benchmark.name.clone(),
+                function: benchmark.function.clone(),
+                gas_used,
+                time_us: time.as_micros() / self.iterations.max(1) as u128,
+                baseline_gas_used,
+                gas_delta: baseline_gas_used.map(|baseline| {
+                    (gas_used as f64 - baseline as f64) * 100.0 / (baseline as f64).max(1.0)
+                }),
+            });
+        }
+
+        write_summary(&results);
+        if self.update_baseline {
+            let baseline: BTreeMap<_, _> = results
+                .iter()
+                .map(|result| (result.name.clone(), result.gas_used))
+                .collect();
+            let json = serde_json::to_string_pretty(&baseline)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            write_to_file(&baseline_path, BASELINE_FILE, json.as_bytes())?;
+            return Ok(results);
+        }
+
+        let regressions: Vec<_> = results
+            .iter()
+            .filter(|result| {
+                result
+                    .gas_delta
+                    .map_or(false, |delta| delta > self.tolerance)
+            })
+            .map(|result| format!("{} (+{:.2}%)", result.name, result.gas_delta.unwrap()))
+            .collect();
+        if regressions.is_empty() {
+            Ok(results)
+        } else {
+            Err(CliError::UnexpectedError(format!(
+                "Gas regressions over {}%: {}",
+                self.tolerance,
+                regressions.join(", ")
+            )))
+        }
+    }
+}
+
+/// Resolves `<module>::<function>` within the package, or a fully qualified function
+fn resolve_function(pack: &BuiltPackage, function: &str) -> CliTypedResult<MemberId> {
+    if function.matches("::").count() == 2 {
+        return MemberId::from_str(function);
+    }
+    let (module_name, function_name) = function.split_once("::").ok_or_else(|| {
+        CliError::CommandArgumentError(format!(
+            "Function {} must be <module>::<function> or <address>::<module>::<function>",
+            function
+        ))
+    })?;
+    let module = pack
+        .modules()
+        .find(|module| module.self_id().name().as_str() == module_name)
+        .ok_or_else(|| {
+            CliError::CommandArgumentError(format!("No module {} in the package", module_name))
+        })?;
+    Ok(MemberId {
+        module_id: module.self_id(),
+        member_id: Identifier::new(function_name)
+            .map_err(|err| CliError::UnableToParse("function", err.to_string()))?,
+    })
+}
+
+/// Prints a table of the results, with the gas deltas from the baseline
+fn write_summary(results: &[BenchResult]) {
+    let width = results
+        .iter()
+        .map(|result| result.name.len())
+        .max()
+        .unwrap_or_default()
+        .max("benchmark".len());
+    eprintln!(
+        "{:width$}  {:>10}  {:>10}  {:>10}",
+        "benchmark",
+        "gas",
+        "delta",
+        "time (us)",
+        width = width
+    );
+    for result in results {
+        let delta = match result.gas_delta {
+            Some(delta) => format!("{:+.2}%", delta),
+            None => "new".to_string(),
+        };
+        eprintln!(
+            "{:width$}  {:>10}  {:>10}  {:>10}",
+            result.name,
+            result.gas_used,
+            delta,
+            result.time_us,
+            width = width
+        );
+    }
+}
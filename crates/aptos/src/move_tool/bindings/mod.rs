@@ -0,0 +1,153 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Client bindings generated from a package's ABI
+//!
+//! Each generator turns the [`PackageAbi`] into source files, which are either written to the
+//! output directory or, with `--check`, compared with what's there so CI catches bindings that
+//! weren't regenerated after a change to the package.
+
+mod python;
+mod rust;
+mod typescript;
+
+pub use python::GeneratePython;
+pub use rust::GenerateRust;
+pub use typescript::GenerateTypescript;
+
+use crate::{
+    common::{
+        types::{CliError, CliTypedResult, MovePackageDir},
+        utils::{create_dir_if_not_exist, write_to_file},
+    },
+    move_tool::abi::{build_abi, PackageAbi},
+};
+use clap::Parser;
+use std::{collections::BTreeMap, path::PathBuf};
+
+#[derive(Parser)]
+pub struct BindingsOptions {
+    /// Directory to write the bindings to
+    ///
+    /// Defaults to `<package_dir>/bindings/<language>`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) out: Option<PathBuf>,
+
+    /// Check the bindings in the output directory are up to date, instead of writing them
+    ///
+    /// Fails with the files that need regenerating
+    #[clap(long)]
+    pub(crate) check: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+impl BindingsOptions {
+    /// Builds the package, and generates its bindings with `generate`
+    ///
+    /// The files are relative to the output directory, the paths of all of them are returned
+    pub(crate) fn generate(
+        &self,
+        language: &str,
+        generate: impl FnOnce(&PackageAbi) -> BTreeMap<PathBuf, String>,
+    ) -> CliTypedResult<Vec<PathBuf>> {
+        let out = match self.out {
+            Some(ref out) => out.clone(),
+            None => self
+                .move_options
+                .get_package_path()?
+                .join("bindings")
+                .join(language),
+        };
+        let files = generate(&build_abi(&self.move_options)?);
+
+        if self.check {
+            let outdated: Vec<_> = files
+                .iter()
+                .filter(|(path, contents)| {
+                    std::fs::read_to_string(out.join(path)).ok().as_ref() != Some(*contents)
+                })
+                .map(|(path, _)| out.join(path).display().to_string())
+                .collect();
+            if !outdated.is_empty() {
+                return Err(CliError::UnexpectedError(format!(
+                    "Bindings are out of date, regenerate them without --check: {}",
+                    outdated.join(", ")
+                )));
+            }
+        } else {
+            for (path, contents) in &files {
+                let path = out.join(path);
+                if let Some(parent) = path.parent() {
+                    create_dir_if_not_exist(parent)?;
+                }
+                write_to_file(&path, "bindings", contents.as_bytes())?;
+            }
+        }
+        Ok(files.into_keys().map(|path| out.join(path)).collect())
+    }
+}
+
+/// Splits an identifier into lowercase words, on underscores and case changes
+fn words(name: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// `transfer_coins` or `TransferCoins` as `TransferCoins`
+fn pascal_case(name: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// `transfer_coins` or `TransferCoins` as `transferCoins`
+fn camel_case(name: &str) -> String {
+    let pascal = pascal_case(name);
+    let mut chars = pascal.chars();
+    chars
+        .next()
+        .map(|first| first.to_lowercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// `TransferCoins` as `transfer_coins`
+fn snake_case(name: &str) -> String {
+    words(name).join("_")
+}
+
+/// The header of every generated file, behind the language's line comment
+fn header(comment: &str, language: &str, abi: &PackageAbi) -> String {
+    format!(
+        "{0} Generated by `aptos move generate-{1}` from the {2} package, do not edit.\n\
+         {0} Regenerate after changing the package, `--check` verifies it's up to date.\n",
+        comment, language, abi.package
+    )
+}
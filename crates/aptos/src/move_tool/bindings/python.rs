@@ -0,0 +1,358 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Python bindings, a package with a file per module holding a dataclass per struct and a
+//! payload builder per entry function
+//!
+//! Dataclasses are read from the JSON the REST API returns, and payloads are built with the
+//! Python SDK's `EntryFunction` and BCS `Serializer`.
+
+use super::{header, snake_case, BindingsOptions};
+use crate::{
+    common::types::{CliCommand, CliTypedResult},
+    move_tool::abi::{ModuleAbi, PackageAbi},
+};
+use aptos_rest_client::aptos_api_types::{
+    Address, MoveFunction, MoveStruct, MoveStructTag, MoveType,
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::PathBuf,
+};
+
+/// Keywords, which get a trailing underscore
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Generate Python bindings for a package
+///
+/// Writes a Python package named after the Move package, with a file per module.  Each struct
+/// gets a dataclass with `from_json`, to read resources and events from the REST API, and each
+/// entry function a function building its `TransactionPayload` with the Python SDK
+#[derive(Parser)]
+pub struct GeneratePython {
+    #[clap(flatten)]
+    pub(crate) options: BindingsOptions,
+}
+
+#[async_trait]
+impl CliCommand<Vec<PathBuf>> for GeneratePython {
+    fn command_name(&self) -> &'static str {
+        "GeneratePython"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<PathBuf>> {
+        self.options.generate("python", generate)
+    }
+}
+
+fn generate(abi: &PackageAbi) -> BTreeMap<PathBuf, String> {
+    let package_dir = PathBuf::from(snake_case(&abi.package));
+    let header = header("#", "python", abi);
+    let mut files = BTreeMap::new();
+
+    let mut init = header.clone();
+    let _ = writeln!(init, "\nPACKAGE_NAME = \"{}\"\n", abi.package);
+    for module in &abi.modules {
+        let name = identifier(module.name.as_str());
+        let _ = writeln!(init, "from . import {}", name);
+        files.insert(
+            package_dir.join(format!("{}.py", name)),
+            generate_module(abi, module, &header),
+        );
+    }
+    files.insert(package_dir.join("__init__.py"), init);
+    files
+}
+
+fn generate_module(abi: &PackageAbi, module: &ModuleAbi, header: &str) -> String {
+    let mut imports = BTreeSet::new();
+    let mut body = String::new();
+    let _ = writeln!(
+        body,
+        "MODULE_ADDRESS = \"{}\"\nMODULE_NAME = \"{}\"",
+        module.address, module.name
+    );
+
+    for move_struct in &module.structs {
+        body.push_str("\n\n");
+        body.push_str(&generate_struct(abi, module, move_struct, &mut imports));
+    }
+
+    if !module.events.is_empty() {
+        body.push_str("\n\n# Event handles of the module's structs, and their event types\n");
+        body.push_str("EVENT_HANDLES = [\n");
+        for event in &module.events {
+            let _ = writeln!(
+                body,
+                "    {{\"struct\": \"{}\", \"field\": \"{}\", \"type\": \"{}\"}},",
+                event.struct_, event.field, event.typ
+            );
+        }
+        body.push_str("]\n");
+    }
+
+    for function in &module.entry_functions {
+        body.push_str("\n\n");
+        body.push_str(&entry_function(module, function));
+    }
+
+    let mut out = header.to_string();
+    out.push_str(
+        "\nfrom __future__ import annotations\n\n\
+         from dataclasses import dataclass\n\
+         from typing import Any, List, Optional\n\n\
+         from aptos_sdk.account_address import AccountAddress\n\
+         from aptos_sdk.bcs import Serializer\n\
+         from aptos_sdk.transactions import EntryFunction, TransactionArgument, TransactionPayload\n\
+         from aptos_sdk.type_tag import TypeTag\n",
+    );
+    for other in imports {
+        let _ = writeln!(out, "\nfrom . import {}", other);
+    }
+    out.push('\n');
+    out.push_str(&body);
+    out
+}
+
+fn generate_struct(
+    abi: &PackageAbi,
+    module: &ModuleAbi,
+    move_struct: &MoveStruct,
+    imports: &mut BTreeSet<String>,
+) -> String {
+    let mut fields = String::new();
+    let mut conversions = vec![];
+    for field in &move_struct.fields {
+        let name = identifier(field.name.as_str());
+        let _ = writeln!(
+            fields,
+            "    {}: {}",
+            name,
+            value_type(abi, module, &field.typ, imports)
+        );
+        conversions.push(format!(
+            "            {}={},\n",
+            name,
+            from_json(
+                abi,
+                module,
+                &field.typ,
+                &format!("data[\"{}\"]", field.name),
+                0
+            )
+        ));
+    }
+    if move_struct.fields.is_empty() {
+        fields.push_str("    pass\n");
+    }
+    format!(
+        "@dataclass\n\
+         class {0}:\n    \
+           \"\"\"`{1}::{2}::{0}`\"\"\"\n\n\
+         {3}\n    \
+           @classmethod\n    \
+           def from_json(cls, data: dict) -> {0}:\n        \
+             return cls(\n{4}        )\n",
+        move_struct.name,
+        module.address,
+        module.name,
+        fields,
+        conversions.concat()
+    )
+}
+
+fn is_framework_struct(tag: &MoveStructTag, module: &str, name: &str) -> bool {
+    tag.address == Address::from(AccountAddress::ONE)
+        && tag.module.as_str() == module
+        && tag.name.as_str() == name
+}
+
+/// Finds the module of the package a struct is in
+fn owner<'a>(abi: &'a PackageAbi, tag: &MoveStructTag) -> Option<&'a ModuleAbi> {
+    abi.modules
+        .iter()
+        .find(|other| other.address == tag.address && other.name == tag.module)
+}
+
+/// The Python type of a field
+fn value_type(
+    abi: &PackageAbi,
+    module: &ModuleAbi,
+    typ: &MoveType,
+    imports: &mut BTreeSet<String>,
+) -> String {
+    match typ {
+        MoveType::Bool => "bool".to_string(),
+        MoveType::U8 | MoveType::U64 | MoveType::U128 => "int".to_string(),
+        // Addresses and byte vectors are hex encoded
+        MoveType::Address | MoveType::Signer => "str".to_string(),
+        MoveType::Vector { items } if **items == MoveType::U8 => "str".to_string(),
+        MoveType::Vector { items } => {
+            format!("List[{}]", value_type(abi, module, items, imports))
+        }
+        MoveType::Reference { to, .. } => value_type(abi, module, to, imports),
+        MoveType::Struct(tag) if is_framework_struct(tag, "string", "String") => "str".to_string(),
+        MoveType::Struct(tag) if is_framework_struct(tag, "option", "Option") => {
+            match tag.generic_type_params.first() {
+                Some(inner) => format!("Optional[{}]", value_type(abi, module, inner, imports)),
+                None => "Any".to_string(),
+            }
+        }
+        MoveType::Struct(tag) => match owner(abi, tag) {
+            Some(owner) if owner.name == module.name => tag.name.to_string(),
+            Some(owner) => {
+                imports.insert(identifier(owner.name.as_str()));
+                format!("{}.{}", identifier(owner.name.as_str()), tag.name)
+            }
+            // Structs from other packages are left as their JSON
+            None => "dict".to_string(),
+        },
+        MoveType::GenericTypeParam { .. } | MoveType::Unparsable(_) => "Any".to_string(),
+    }
+}
+
+/// Converts a value from its REST API JSON
+fn from_json(
+    abi: &PackageAbi,
+    module: &ModuleAbi,
+    typ: &MoveType,
+    value: &str,
+    depth: usize,
+) -> String {
+    let item = format!("item{}", depth);
+    match typ {
+        // 64 and 128 bit integers are strings in JSON
+        MoveType::U64 | MoveType::U128 => format!("int({})", value),
+        MoveType::Vector { items } if **items != MoveType::U8 => format!(
+            "[{} for {} in {}]",
+            from_json(abi, module, items, &item, depth + 1),
+            item,
+            value
+        ),
+        MoveType::Reference { to, .. } => from_json(abi, module, to, value, depth),
+        MoveType::Struct(tag) if is_framework_struct(tag, "option", "Option") => {
+            match tag.generic_type_params.first() {
+                Some(inner) => format!(
+                    "next(({} for {} in {}[\"vec\"]), None)",
+                    from_json(abi, module, inner, &item, depth + 1),
+                    item,
+                    value
+                ),
+                None => value.to_string(),
+            }
+        }
+        MoveType::Struct(tag) if !is_framework_struct(tag, "string", "String") => {
+            match owner(abi, tag) {
+                Some(owner) if owner.name == module.name => {
+                    format!("{}.from_json({})", tag.name, value)
+                }
+                Some(owner) => format!(
+                    "{}.{}.from_json({})",
+                    identifier(owner.name.as_str()),
+                    tag.name,
+                    value
+                ),
+                None => value.to_string(),
+            }
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// The Python type of an entry function argument, and the SDK encoder for it
+fn argument(typ: &MoveType) -> (String, String) {
+    match typ {
+        MoveType::Bool => ("bool".to_string(), "Serializer.bool".to_string()),
+        MoveType::U8 => ("int".to_string(), "Serializer.u8".to_string()),
+        MoveType::U64 => ("int".to_string(), "Serializer.u64".to_string()),
+        MoveType::U128 => ("int".to_string(), "Serializer.u128".to_string()),
+        MoveType::Vector { items } if **items == MoveType::U8 => {
+            ("bytes".to_string(), "Serializer.to_bytes".to_string())
+        }
+        MoveType::Vector { items } => {
+            let (item_type, item_encoder) = argument(items);
+            (
+                format!("List[{}]", item_type),
+                format!("Serializer.sequence_serializer({})", item_encoder),
+            )
+        }
+        MoveType::Struct(tag) if is_framework_struct(tag, "string", "String") => {
+            ("str".to_string(), "Serializer.str".to_string())
+        }
+        _ => (
+            "AccountAddress".to_string(),
+            "Serializer.struct".to_string(),
+        ),
+    }
+}
+
+fn entry_function(module: &ModuleAbi, function: &MoveFunction) -> String {
+    let mut params = vec![];
+    let mut arguments = vec![];
+    let type_count = function.generic_type_params.len();
+    if type_count > 0 {
+        params.push("type_arguments: List[TypeTag]".to_string());
+    }
+    // Signers are the transaction's senders, they aren't passed as arguments
+    for (index, typ) in function
+        .params
+        .iter()
+        .filter(|typ| !typ.is_signer())
+        .enumerate()
+    {
+        let (python_type, encoder) = argument(typ);
+        params.push(format!("arg{}: {}", index, python_type));
+        arguments.push(format!(
+            "            TransactionArgument(arg{}, {}),\n",
+            index, encoder
+        ));
+    }
+
+    format!(
+        "def {0}({1}) -> TransactionPayload:\n    \
+           \"\"\"Payload of `{2}::{3}::{4}({5})`\"\"\"\n    \
+           return TransactionPayload(\n        \
+             EntryFunction.natural(\n            \
+               \"{2}::{3}\",\n            \
+               \"{4}\",\n            \
+               {6},\n            \
+               [\n{7}            ],\n        \
+             )\n    \
+           )\n",
+        identifier(function.name.as_str()),
+        params.join(", "),
+        module.address,
+        module.name,
+        function.name,
+        function
+            .params
+            .iter()
+            .map(|typ| typ.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        if type_count > 0 {
+            "type_arguments"
+        } else {
+            "[]"
+        },
+        arguments.concat()
+    )
+}
+
+/// Appends an underscore to keywords
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rust bindings, a module per Move module with its structs laid out for BCS and a function per
+//! entry function building its payload
+//!
+//! The generated code depends on `aptos-types`, `move-core-types`, `bcs`, and `serde`.
+
+use super::{header, snake_case, BindingsOptions};
+use crate::{
+    common::types::{CliCommand, CliTypedResult},
+    move_tool::abi::{ModuleAbi, PackageAbi},
+};
+use aptos_rest_client::aptos_api_types::{
+    Address, MoveFunction, MoveStruct, MoveStructTag, MoveType,
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::PathBuf,
+};
+
+/// Keywords, which need to be raw identifiers
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type", "unsafe",
+    "use", "where", "while", "yield",
+];
+
+/// Generate Rust bindings for a package
+///
+/// Writes `<package>.rs`, with a module per Move module.  Structs derive serde, so resources
+/// and events can be decoded from BCS, and each entry function gets a function building its
+/// `TransactionPayload`.  Structs with fields from other packages are skipped, other than the
+/// framework's `String`, `Option`, and `EventHandle`
+#[derive(Parser)]
+pub struct GenerateRust {
+    #[clap(flatten)]
+    pub(crate) options: BindingsOptions,
+}
+
+#[async_trait]
+impl CliCommand<Vec<PathBuf>> for GenerateRust {
+    fn command_name(&self) -> &'static str {
+        "GenerateRust"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<PathBuf>> {
+        self.options.generate("rust", generate)
+    }
+}
+
+fn generate(abi: &PackageAbi) -> BTreeMap<PathBuf, String> {
+    let mut out = header("//", "rust", abi);
+    out.push_str("\n#![allow(dead_code, non_camel_case_types, unused_imports)]\n");
+    for module in &abi.modules {
+        out.push('\n');
+        out.push_str(&generate_module(abi, module));
+    }
+    let mut files = BTreeMap::new();
+    files.insert(
+        PathBuf::from(format!("{}.rs", snake_case(&abi.package))),
+        out,
+    );
+    files
+}
+
+fn generate_module(abi: &PackageAbi, module: &ModuleAbi) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "pub mod {} {{\n    \
+           use aptos_types::{{\n        \
+             account_address::AccountAddress,\n        \
+             event::EventHandle,\n        \
+             transaction::{{EntryFunction, TransactionPayload}},\n    \
+           }};\n    \
+           use move_core_types::{{ident_str, language_storage::{{ModuleId, TypeTag}}}};\n    \
+           use serde::{{Deserialize, Serialize}};\n\n    \
+           pub fn module_id() -> ModuleId {{\n        \
+             ModuleId::new(\n            \
+               AccountAddress::from_hex_literal(\"{}\").unwrap(),\n            \
+               ident_str!(\"{}\").to_owned(),\n        \
+             )\n    \
+           }}\n",
+        identifier(module.name.as_str()),
+        module.address,
+        module.name
+    );
+
+    for move_struct in &module.structs {
+        out.push('\n');
+        out.push_str(&generate_struct(abi, module, move_struct));
+    }
+    for function in &module.entry_functions {
+        out.push('\n');
+        out.push_str(&entry_function(function));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn generate_struct(abi: &PackageAbi, module: &ModuleAbi, move_struct: &MoveStruct) -> String {
+    let mut fields = vec![];
+    for field in &move_struct.fields {
+        match field_type(abi, module, &field.typ) {
+            Some(typ) => fields.push(format!(
+                "        pub {}: {},\n",
+                identifier(field.name.as_str()),
+                typ
+            )),
+            None => {
+                return format!(
+                    "    // Skipped {}, field {} has a type from another package\n",
+                    move_struct.name, field.name
+                )
+            }
+        }
+    }
+    let params: Vec<_> = used_type_params(abi, move_struct)
+        .into_iter()
+        .map(|index| format!("T{}", index))
+        .collect();
+    let generics = if params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", params.join(", "))
+    };
+    format!(
+        "    /// `{}::{}::{}`\n    \
+         #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]\n    \
+         pub struct {}{} {{\n{}    }}\n",
+        module.address,
+        module.name,
+        move_struct.name,
+        move_struct.name,
+        generics,
+        fields.concat()
+    )
+}
+
+/// The type parameters a struct's fields use, the others have nothing to hold in Rust
+fn used_type_params(abi: &PackageAbi, move_struct: &MoveStruct) -> BTreeSet<u16> {
+    fn collect(abi: &PackageAbi, typ: &MoveType, used: &mut BTreeSet<u16>) {
+        match typ {
+            MoveType::GenericTypeParam { index } => {
+                used.insert(*index);
+            }
+            MoveType::Vector { items } => collect(abi, items, used),
+            MoveType::Reference { to, .. } => collect(abi, to, used),
+            MoveType::Struct(tag) => match find_struct(abi, tag) {
+                // Only the parameters the struct itself uses are passed to it
+                Some((_, move_struct)) => {
+                    for index in used_type_params(abi, move_struct) {
+                        if let Some(param) = tag.generic_type_params.get(index as usize) {
+                            collect(abi, param, used);
+                        }
+                    }
+                }
+                // Event handles only hold the type of their events
+                None if is_framework_struct(tag, "event", "EventHandle") => {}
+                None => {
+                    for param in &tag.generic_type_params {
+                        collect(abi, param, used);
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+    let mut used = BTreeSet::new();
+    for field in &move_struct.fields {
+        collect(abi, &field.typ, &mut used);
+    }
+    used
+}
+
+/// Finds a struct of the package, and the module it's in
+fn find_struct<'a>(
+    abi: &'a PackageAbi,
+    tag: &MoveStructTag,
+) -> Option<(&'a ModuleAbi, &'a MoveStruct)> {
+    let owner = abi
+        .modules
+        .iter()
+        .find(|other| other.address == tag.address && other.name == tag.module)?;
+    let move_struct = owner
+        .structs
+        .iter()
+        .find(|move_struct| move_struct.name == tag.name)?;
+    Some((owner, move_struct))
+}
+
+fn is_framework_struct(tag: &MoveStructTag, module: &str, name: &str) -> bool {
+    tag.address == Address::from(AccountAddress::ONE)
+        && tag.module.as_str() == module
+        && tag.name.as_str() == name
+}
+
+/// The Rust type of a field, if it can be decoded
+fn field_type(abi: &PackageAbi, module: &ModuleAbi, typ: &MoveType) -> Option<String> {
+    Some(match typ {
+        MoveType::Bool => "bool".to_string(),
+        MoveType::U8 => "u8".to_string(),
+        MoveType::U64 => "u64".to_string(),
+        MoveType::U128 => "u128".to_string(),
+        MoveType::Address | MoveType::Signer => "AccountAddress".to_string(),
+        MoveType::Vector { items } => format!("Vec<{}>", field_type(abi, module, items)?),
+        MoveType::GenericTypeParam { index } => format!("T{}", index),
+        MoveType::Reference { to, .. } => field_type(abi, module, to)?,
+        MoveType::Struct(tag) if is_framework_struct(tag, "string", "String") => {
+            "String".to_string()
+        }
+        // An option is a vector of at most one element, which BCS encodes like Rust's option
+        MoveType::Struct(tag) if is_framework_struct(tag, "option", "Option") => format!(
+            "Option<{}>",
+            field_type(abi, module, tag.generic_type_params.first()?)?
+        ),
+        MoveType::Struct(tag) if is_framework_struct(tag, "event", "EventHandle") => {
+            "EventHandle".to_string()
+        }
+        MoveType::Struct(tag) => {
+            let (owner, move_struct) = find_struct(abi, tag)?;
+            // Structs that can't be decoded aren't generated
+            for field in &move_struct.fields {
+                field_type(abi, owner, &field.typ)?;
+            }
+            let path = if owner.name == module.name {
+                tag.name.to_string()
+            } else {
+                format!("super::{}::{}", identifier(owner.name.as_str()), tag.name)
+            };
+            let params = used_type_params(abi, move_struct)
+                .into_iter()
+                .map(|index| field_type(abi, module, tag.generic_type_params.get(index as usize)?))
+                .collect::<Option<Vec<_>>>()?;
+            if params.is_empty() {
+                path
+            } else {
+                format!("{}<{}>", path, params.join(", "))
+            }
+        }
+        MoveType::Unparsable(_) => return None,
+    })
+}
+
+/// The Rust type an entry function argument is taken as
+fn argument_type(typ: &MoveType) -> String {
+    match typ {
+        MoveType::Bool => "bool".to_string(),
+        MoveType::U8 => "u8".to_string(),
+        MoveType::U64 => "u64".to_string(),
+        MoveType::U128 => "u128".to_string(),
+        MoveType::Vector { items } => format!("Vec<{}>", argument_type(items)),
+        MoveType::Struct(tag) if is_framework_struct(tag, "string", "String") => {
+            "String".to_string()
+        }
+        _ => "AccountAddress".to_string(),
+    }
+}
+
+fn entry_function(function: &MoveFunction) -> String {
+    let mut params = vec![];
+    let mut arguments = vec![];
+    let type_count = function.generic_type_params.len();
+    if type_count > 0 {
+        params.push(format!("type_args: [TypeTag; {}]", type_count));
+    }
+    // Signers are the transaction's senders, they aren't passed as arguments
+    for (index, typ) in function
+        .params
+        .iter()
+        .filter(|typ| !typ.is_signer())
+        .enumerate()
+    {
+        params.push(format!("arg{}: {}", index, argument_type(typ)));
+        arguments.push(format!(
+            "            bcs::to_bytes(&arg{}).unwrap(),\n",
+            index
+        ));
+    }
+
+    format!(
+        "    /// Payload of `{0}({1})`\n    \
+         pub fn {2}({3}) -> TransactionPayload {{\n        \
+           TransactionPayload::EntryFunction(EntryFunction::new(\n            \
+             module_id(),\n            \
+             ident_str!(\"{0}\").to_owned(),\n            \
+             {4},\n            \
+             vec![\n{5}            ],\n        \
+           ))\n    \
+         }}\n",
+        function.name,
+        function
+            .params
+            .iter()
+            .map(|typ| typ.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        identifier(function.name.as_str()),
+        params.join(", "),
+        if type_count > 0 {
+            "type_args.to_vec()"
+        } else {
+            "vec![]"
+        },
+        arguments.concat()
+    )
+}
+
+/// Escapes keywords as raw identifiers
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! TypeScript bindings, a file per module with its structs as interfaces and a payload builder
+//! per entry function
+//!
+//! Interfaces follow the JSON the REST API returns for resources and events, and payloads the
+//! JSON it accepts, so they work with the TypeScript SDK's `EntryFunctionPayload`.
+
+use super::{camel_case, header, BindingsOptions};
+use crate::{
+    common::types::{CliCommand, CliTypedResult},
+    move_tool::abi::{ModuleAbi, PackageAbi},
+};
+use aptos_rest_client::aptos_api_types::{Address, MoveFunction, MoveStructTag, MoveType};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::PathBuf,
+};
+
+/// Words that can't name a TypeScript function or parameter
+const RESERVED: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+];
+
+/// Generate TypeScript bindings for a package
+///
+/// Writes a file per module, with an interface per struct and a function per entry function
+/// building its transaction payload, plus an `index.ts` exporting them all
+#[derive(Parser)]
+pub struct GenerateTypescript {
+    #[clap(flatten)]
+    pub(crate) options: BindingsOptions,
+}
+
+#[async_trait]
+impl CliCommand<Vec<PathBuf>> for GenerateTypescript {
+    fn command_name(&self) -> &'static str {
+        "GenerateTypescript"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<PathBuf>> {
+        self.options.generate("ts", generate)
+    }
+}
+
+fn generate(abi: &PackageAbi) -> BTreeMap<PathBuf, String> {
+    let mut files = BTreeMap::new();
+    let header = header("//", "ts", abi);
+
+    let mut index = header.clone();
+    index.push_str("\nexport * from \"./types\";\n");
+    for module in &abi.modules {
+        let _ = writeln!(index, "export * as {0} from \"./{0}\";", module.name);
+        files.insert(
+            PathBuf::from(format!("{}.ts", module.name)),
+            generate_module(abi, module, &header),
+        );
+    }
+    files.insert(PathBuf::from("index.ts"), index);
+
+    files.insert(
+        PathBuf::from("types.ts"),
+        format!(
+            "{}\n\
+             export const PACKAGE_NAME = \"{}\";\n\n\
+             /** The JSON of an entry function payload, as the REST API accepts it */\n\
+             export interface EntryFunctionPayload {{\n  \
+               type: \"entry_function_payload\";\n  \
+               function: string;\n  \
+               type_arguments: string[];\n  \
+               arguments: any[];\n\
+             }}\n",
+            header, abi.package
+        ),
+    );
+    files
+}
+
+fn generate_module(abi: &PackageAbi, module: &ModuleAbi, header: &str) -> String {
+    let mut imports = BTreeSet::new();
+    let mut body = String::new();
+    let _ = writeln!(
+        body,
+        "export const MODULE_ADDRESS = \"{}\";\nexport const MODULE_NAME = \"{}\";",
+        module.address, module.name
+    );
+
+    for move_struct in &module.structs {
+        let generics = type_params(move_struct.generic_type_params.len(), " = unknown");
+        let _ = writeln!(
+            body,
+            "\n/** `{}::{}::{}` */\nexport interface {}{} {{",
+            module.address, module.name, move_struct.name, move_struct.name, generics
+        );
+        for field in &move_struct.fields {
+            let _ = writeln!(
+                body,
+                "  {}: {};",
+                field.name,
+                value_type(abi, module, &field.typ, &mut imports)
+            );
+        }
+        body.push_str("}\n");
+    }
+
+    if !module.events.is_empty() {
+        body.push_str("\n/** Event handles of the module's structs, and their event types */\n");
+        body.push_str("export const EVENT_HANDLES = [\n");
+        for event in &module.events {
+            let _ = writeln!(
+                body,
+                "  {{ struct: \"{}\", field: \"{}\", type: \"{}\" }},",
+                event.struct_, event.field, event.typ
+            );
+        }
+        body.push_str("] as const;\n");
+    }
+
+    for function in &module.entry_functions {
+        body.push('\n');
+        body.push_str(&entry_function(module, function));
+    }
+
+    let mut out = header.to_string();
+    out.push('\n');
+    if !module.entry_functions.is_empty() {
+        out.push_str("import type { EntryFunctionPayload } from \"./types\";\n");
+    }
+    for other in imports {
+        let _ = writeln!(out, "import type * as {0} from \"./{0}\";", other);
+    }
+    out.push('\n');
+    out.push_str(&body);
+    out
+}
+
+fn entry_function(module: &ModuleAbi, function: &MoveFunction) -> String {
+    let mut params = vec![];
+    let mut arguments = vec![];
+    let type_count = function.generic_type_params.len();
+    if type_count > 0 {
+        params.push(format!(
+            "typeArguments: [{}]",
+            vec!["string"; type_count].join(", ")
+        ));
+    }
+    // Signers are the transaction's senders, they aren't passed as arguments
+    for (index, typ) in function
+        .params
+        .iter()
+        .filter(|typ| !typ.is_signer())
+        .enumerate()
+    {
+        let name = format!("arg{}", index);
+        params.push(format!("{}: {}", name, argument_type(typ)));
+        arguments.push(argument_value(typ, &name));
+    }
+
+    let mut name = camel_case(function.name.as_str());
+    if RESERVED.contains(&name.as_str()) {
+        name.push('_');
+    }
+    format!(
+        "/** `{0}::{1}::{2}({3})` */\n\
+         export function {4}({5}): EntryFunctionPayload {{\n  \
+           return {{\n    \
+             type: \"entry_function_payload\",\n    \
+             function: \"{0}::{1}::{2}\",\n    \
+             type_arguments: {6},\n    \
+             arguments: [{7}],\n  \
+           }};\n\
+         }}\n",
+        module.address,
+        module.name,
+        function.name,
+        function
+            .params
+            .iter()
+            .map(|typ| typ.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        name,
+        params.join(", "),
+        if type_count > 0 {
+            "typeArguments"
+        } else {
+            "[]"
+        },
+        arguments.join(", ")
+    )
+}
+
+fn type_params(count: usize, default: &str) -> String {
+    if count == 0 {
+        return String::new();
+    }
+    let params: Vec<_> = (0..count)
+        .map(|index| format!("T{}{}", index, default))
+        .collect();
+    format!("<{}>", params.join(", "))
+}
+
+fn is_struct(tag: &MoveStructTag, module: &str, name: &str) -> bool {
+    tag.address == Address::from(AccountAddress::ONE)
+        && tag.module.as_str() == module
+        && tag.name.as_str() == name
+}
+
+/// The TypeScript type of a value as the REST API returns it
+fn value_type(
+    abi: &PackageAbi,
+    module: &ModuleAbi,
+    typ: &MoveType,
+    imports: &mut BTreeSet<String>,
+) -> String {
+    match typ {
+        MoveType::Bool => "boolean".to_string(),
+        MoveType::U8 => "number".to_string(),
+        MoveType::U64 | MoveType::U128 | MoveType::Address | MoveType::Signer => {
+            "string".to_string()
+        }
+        MoveType::Vector { items } if **items == MoveType::U8 => "string".to_string(),
+        MoveType::Vector { items } => format!("{}[]", value_type(abi, module, items, imports)),
+        MoveType::GenericTypeParam { index } => format!("T{}", index),
+        MoveType::Reference { to, .. } => value_type(abi, module, to, imports),
+        MoveType::Struct(tag) if is_struct(tag, "string", "String") => "string".to_string(),
+        MoveType::Struct(tag) if is_struct(tag, "option", "Option") => {
+            let inner = tag
+                .generic_type_params
+                .first()
+                .map(|inner| value_type(abi, module, inner, imports))
+                .unwrap_or_else(|| "unknown".to_string());
+            format!("{{ vec: [] | [{}] }}", inner)
+        }
+        MoveType::Struct(tag) => {
+            let owner = abi
+                .modules
+                .iter()
+                .find(|other| other.address == tag.address && other.name == tag.module);
+            let name = match owner {
+                Some(owner) if owner.name == module.name => tag.name.to_string(),
+                Some(owner) => {
+                    imports.insert(owner.name.to_string());
+                    format!("{}.{}", owner.name, tag.name)
+                }
+                // Structs from other packages aren't generated
+                None => return "any".to_string(),
+            };
+            if tag.generic_type_params.is_empty() {
+                name
+            } else {
+                let params: Vec<_> = tag
+                    .generic_type_params
+                    .iter()
+                    .map(|param| value_type(abi, module, param, imports))
+                    .collect();
+                format!("{}<{}>", name, params.join(", "))
+            }
+        }
+        MoveType::Unparsable(_) => "unknown".to_string(),
+    }
+}
+
+/// The TypeScript type an entry function argument is taken as
+fn argument_type(typ: &MoveType) -> String {
+    match typ {
+        MoveType::Bool => "boolean".to_string(),
+        MoveType::U8 => "number".to_string(),
+        MoveType::U64 | MoveType::U128 => "bigint | number | string".to_string(),
+        // Byte vectors are passed hex encoded
+        MoveType::Vector { items } if **items == MoveType::U8 => "string".to_string(),
+        MoveType::Vector { items } => format!("({})[]", argument_type(items)),
+        _ => "string".to_string(),
+    }
+}
+
+/// Converts an entry function argument to the JSON the REST API accepts
+fn argument_value(typ: &MoveType, value: &str) -> String {
+    match typ {
+        MoveType::U64 | MoveType::U128 => format!("String({})", value),
+        MoveType::Vector { items } if matches!(**items, MoveType::U64 | MoveType::U128) => {
+            format!("{}.map((item) => String(item))", value)
+        }
+        MoveType::Vector { items } if matches!(**items, MoveType::Vector { .. }) => {
+            format!("{}.map((item) => {})", value, argument_value(items, "item"))
+        }
+        _ => value.to_string(),
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sizes of a package's modules, and how close publishing it is to the limits
+
+use crate::{
+    common::types::{CliCommand, CliError, CliTypedResult, MovePackageDir},
+    move_tool::{IncludedArtifactsArgs, MAX_PUBLISH_PACKAGE_SIZE},
+};
+use async_trait::async_trait;
+use clap::Parser;
+use framework::BuiltPackage;
+use move_binary_format::access::ModuleAccess;
+use serde::Serialize;
+
+/// Largest transaction the chain accepts, `max_transaction_size_in_bytes` of the gas schedule
+const MAX_TRANSACTION_SIZE: usize = 64 * 1024;
+
+/// Report the bytecode size of each module of a package, and its distance from publish limits
+///
+/// For each module: its bytecode size, functions, constants and their size, and the modules it
+/// depends on.  For the package: the size of the publish transaction's payload, with the
+/// artifacts `--included-artifacts` adds, against the CLI's and the chain's limits
+#[derive(Parser)]
+pub struct BuildReport {
+    /// Fail if the publish payload is larger than this many bytes
+    #[clap(long)]
+    pub(crate) fail_over: Option<usize>,
+
+    #[clap(flatten)]
+    pub(crate) included_artifacts_args: IncludedArtifactsArgs,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[derive(Serialize)]
+pub struct ModuleReport {
+    pub name: String,
+    pub bytecode_size: usize,
+    pub functions: usize,
+    pub structs: usize,
+    pub constants: usize,
+    /// Bytes of the constant pool's values
+    pub constants_size: usize,
+    /// Modules this one uses, other than itself
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PackageReport {
+    pub package: String,
+    pub modules: Vec<ModuleReport>,
+    pub bytecode_size: usize,
+    /// Size of the package metadata, with its sources and source maps if included
+    pub metadata_size: usize,
+    /// Size of the `code::publish_package_txn` payload
+    pub payload_size: usize,
+    /// Bytes left under the CLI's publish limit, negative when over it
+    pub publish_headroom: i64,
+    /// Bytes left under the chain's transaction size limit, negative when over it
+    pub transaction_headroom: i64,
+}
+
+#[async_trait]
+impl CliCommand<PackageReport> for BuildReport {
+    fn command_name(&self) -> &'static str {
+        "BuildReport"
+    }
+
+    async fn execute(self) -> CliTypedResult<PackageReport> {
+        let options = self
+            .included_artifacts_args
+            .included_artifacts
+            .build_options(self.move_options.named_addresses());
+        let pack = BuiltPackage::build(self.move_options.get_package_path()?, options)
+            .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
+
+        let code = pack.extract_code();
+        let modules: Vec<_> = pack
+            .modules()
+            .zip(&code)
+            .map(|(module, bytes)| {
+                let self_id = module.self_id();
+                ModuleReport {
+                    name: self_id.name().to_string(),
+                    bytecode_size: bytes.len(),
+                    functions: module.function_defs.len(),
+                    structs: module.struct_defs.len(),
+                    constants: module.constant_pool.len(),
+                    constants_size: module
+                        .constant_pool
+                        .iter()
+                        .map(|constant| constant.data.len())
+                        .sum(),
+                    dependencies: module
+                        .immediate_dependencies()
+                        .into_iter()
+                        .filter(|dependency| *dependency != self_id)
+                        .map(|dependency| dependency.short_str_lossless())
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let metadata = bcs::to_bytes(&pack.extract_metadata()?).expect("PackageMetadata has BCS");
+        let metadata_size = metadata.len();
+        let payload = cached_packages::aptos_stdlib::code_publish_package_txn(metadata, code);
+        let payload_size = bcs::serialized_size(&payload)?;
+
+        let report = PackageReport {
+            package: pack.name().to_string(),
+            bytecode_size: modules.iter().map(|module| module.bytecode_size).sum(),
+            modules,
+            metadata_size,
+            payload_size,
+            publish_headroom: MAX_PUBLISH_PACKAGE_SIZE as i64 - payload_size as i64,
+            transaction_headroom: MAX_TRANSACTION_SIZE as i64 - payload_size as i64,
+        };
+        write_summary(&report);
+
+        match self.fail_over {
+            Some(limit) if payload_size > limit => Err(CliError::UnexpectedError(format!(
+                "The publish payload is {} bytes, over the limit of {} bytes",
+                payload_size, limit
+            ))),
+            _ => Ok(report),
+        }
+    }
+}
+
+/// Prints a table of the modules, and the package's totals
+fn write_summary(report: &PackageReport) {
+    let width = report
+        .modules
+        .iter()
+        .map(|module| module.name.len())
+        .max()
+        .unwrap_or_default()
+        .max("module".len());
+    eprintln!(
+        "{:width$}  {:>9}  {:>9}  {:>9}  {:>9}  {:>12}",
+        "module",
+        "bytes",
+        "functions",
+        "structs",
+        "constants",
+        "dependencies",
+        width = width
+    );
+    for module in &report.modules {
+        eprintln!(
+            "{:width$}  {:>9}  {:>9}  {:>9}  {:>9}  {:>12}",
+            module.name,
+            module.bytecode_size,
+            module.functions,
+            module.structs,
+            module.constants,
+            module.dependencies.len(),
+            width = width
+        );
+    }
+    eprintln!(
+        "\nBytecode {} bytes, metadata {} bytes, publish payload {} bytes",
+        report.bytecode_size, report.metadata_size, report.payload_size
+    );
+    eprintln!(
+        "{} bytes under the publish limit of {}, {} under the transaction limit of {}",
+        report.publish_headroom,
+        MAX_PUBLISH_PACKAGE_SIZE,
+        report.transaction_headroom,
+        MAX_TRANSACTION_SIZE
+    );
+}
@@ -0,0 +1,410 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reading back compiled modules, from chain or from a build
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, ProfileOptions, RestOptions},
+        utils::{create_dir_if_not_exist, dir_default_to_current, read_from_file, write_to_file},
+    },
+    move_tool::CachedPackageRegistry,
+};
+use aptos_rest_client::Client;
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use framework::{unzip_metadata, unzip_metadata_str};
+use move_binary_format::{
+    binary_views::BinaryIndexedView, file_format::FunctionDefinitionIndex, CompiledModule,
+};
+use move_bytecode_source_map::{mapping::SourceMapping, source_map::SourceMap};
+use move_command_line_common::files::SOURCE_MAP_EXTENSION;
+use move_disassembler::disassembler::{Disassembler, DisassemblerOptions};
+use move_ir_types::location::Spanned;
+use move_package::compilation::package_layout::CompiledPackageLayout;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// Extension of disassembled modules, which are written next to the sources
+pub const DISASSEMBLY_EXTENSION: &str = "mvasm";
+
+/// Recover Move packages from the modules published at an account
+///
+/// Modules are grouped into the packages they were published with, each written as
+/// `<output-dir>/<package>/` with its Move.toml.  Modules published with their source get it
+/// in `sources/`, the others can't be turned back into Move source and are disassembled into
+/// `disassembly/` instead
+#[derive(Parser)]
+pub struct DecompileModules {
+    /// Address of the account the modules are published at
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) account: AccountAddress,
+
+    /// Only recover this module
+    #[clap(long)]
+    pub(crate) module: Option<String>,
+
+    /// Directory to write the packages to. Defaults to the current directory.
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_dir: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveredModule {
+    pub package: String,
+    pub path: PathBuf,
+    /// Whether the original source was recovered, rather than a disassembly
+    pub source: bool,
+}
+
+#[async_trait]
+impl CliCommand<BTreeMap<String, RecoveredModule>> for DecompileModules {
+    fn command_name(&self) -> &'static str {
+        "DecompileModules"
+    }
+
+    async fn execute(self) -> CliTypedResult<BTreeMap<String, RecoveredModule>> {
+        let url = self.rest_options.url(&self.profile_options)?;
+        let output_dir = dir_default_to_current(self.output_dir)?;
+        let modules = fetch_modules(&Client::new(url.clone()), self.account).await?;
+        // Modules can be published without the package registry, they're kept on their own
+        let registry = CachedPackageRegistry::create(url, self.account).await.ok();
+
+        let mut recovered = BTreeMap::new();
+        for (name, module) in modules {
+            if self.module.as_ref().map_or(false, |only| *only != name) {
+                continue;
+            }
+
+            let mut manifest = None;
+            let mut source = None;
+            let mut package_name = format!("{}_modules", self.account.short_str_lossless());
+            if let Some(ref registry) = registry {
+                for candidate in registry.package_names() {
+                    let package = registry
+                        .get_package(candidate)
+                        .await
+                        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                    if let Ok(metadata) = package.module(&name) {
+                        package_name = package.name().to_string();
+                        manifest = package.manifest().ok();
+                        if !metadata.zipped_source().is_empty() {
+                            source = unzip_metadata_str(metadata.zipped_source()).ok();
+                        }
+                        break;
+                    }
+                }
+            }
+
+            let package_dir = output_dir.join(&package_name);
+            let manifest = manifest.unwrap_or_else(|| {
+                format!(
+                    "[package]\nname = \"{}\"\nversion = \"0.0.0\"\n",
+                    package_name
+                )
+            });
+            create_dir_if_not_exist(&package_dir)?;
+            write_to_file(
+                &package_dir.join("Move.toml"),
+                "Move.toml",
+                manifest.as_bytes(),
+            )?;
+
+            let (path, is_source, contents) = match source {
+                Some(source) => (
+                    package_dir.join("sources").join(format!("{}.move", name)),
+                    true,
+                    source,
+                ),
+                None => (
+                    package_dir
+                        .join("disassembly")
+                        .join(format!("{}.{}", name, DISASSEMBLY_EXTENSION)),
+                    false,
+                    disassemble(&module)?,
+                ),
+            };
+            if let Some(parent) = path.parent() {
+                create_dir_if_not_exist(parent)?;
+            }
+            write_to_file(&path, "recovered module", contents.as_bytes())?;
+            recovered.insert(
+                name,
+                RecoveredModule {
+                    package: package_name,
+                    path,
+                    source: is_source,
+                },
+            );
+        }
+
+        if let Some(module) = self.module {
+            if recovered.is_empty() {
+                return Err(CliError::CommandArgumentError(format!(
+                    "No module {} at {}",
+                    module, self.account
+                )));
+            }
+        }
+        Ok(recovered)
+    }
+}
+
+/// Fetches and deserializes all the modules published at an account, by name
+pub(crate) async fn fetch_modules(
+    client: &Client,
+    account: AccountAddress,
+) -> CliTypedResult<BTreeMap<String, CompiledModule>> {
+    let modules = client
+        .get_account_modules(account)
+        .await
+        .map_err(|err| CliError::ApiError(err.to_string()))?
+        .into_inner();
+    let mut by_name = BTreeMap::new();
+    for module in modules {
+        let module = CompiledModule::deserialize(module.bytecode.inner())
+            .map_err(|err| CliError::UnableToParse("module bytecode", err.to_string()))?;
+        by_name.insert(module.self_id().name().to_string(), module);
+    }
+    Ok(by_name)
+}
+
+/// Disassembles a module into Move IR
+pub(crate) fn disassemble(module: &CompiledModule) -> CliTypedResult<String> {
+    let source_mapping = SourceMapping::new_from_view(
+        BinaryIndexedView::Module(module),
+        Spanned::unsafe_no_loc(()).loc,
+    )
+    .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+    let mut options = DisassemblerOptions::new();
+    options.print_code = true;
+    Disassembler::new(source_mapping, options)
+        .disassemble()
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))
+}
+
+/// Disassemble a module, from a build or from chain
+///
+/// With `--with-source`, each instruction is listed under the source line it was compiled
+/// from.  A local module's source map and source are looked up in its package's build
+/// directory, unless passed explicitly.  An on-chain module needs to have been published with
+/// them, e.g. with `--included-artifacts all`
+#[derive(Parser)]
+pub struct DisassembleModule {
+    /// Compiled module to disassemble, e.g. `build/<package>/bytecode_modules/<module>.mv`
+    #[clap(long, parse(from_os_str), required_unless_present = "account")]
+    pub(crate) bytecode_path: Option<PathBuf>,
+
+    /// Address of the account the module is published at
+    #[clap(
+        long,
+        parse(try_from_str=crate::common::types::load_account_arg),
+        conflicts_with = "bytecode-path",
+        requires = "module"
+    )]
+    pub(crate) account: Option<AccountAddress>,
+
+    /// Name of the on-chain module
+    #[clap(long, requires = "account")]
+    pub(crate) module: Option<String>,
+
+    /// Interleave the source lines with the instructions
+    #[clap(long)]
+    pub(crate) with_source: bool,
+
+    /// Source map of a local module, defaults to the one in its build directory
+    #[clap(long, parse(from_os_str), requires = "bytecode-path")]
+    pub(crate) source_map_path: Option<PathBuf>,
+
+    /// Source of a local module, defaults to the one in its build directory
+    #[clap(long, parse(from_os_str), requires = "bytecode-path")]
+    pub(crate) source_path: Option<PathBuf>,
+
+    /// File to write the disassembly to, instead of printing it
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+impl DisassembleModule {
+    /// Loads the module, and with `--with-source` its source map and source
+    async fn load(&self) -> CliTypedResult<(CompiledModule, Option<(SourceMap, String)>)> {
+        if let Some(ref bytecode_path) = self.bytecode_path {
+            let module = CompiledModule::deserialize(&read_from_file(bytecode_path)?)
+                .map_err(|err| CliError::UnableToParse("module bytecode", err.to_string()))?;
+            if !self.with_source {
+                return Ok((module, None));
+            }
+            let source_map_path = self.source_map_path.clone().unwrap_or_else(|| {
+                build_artifact(
+                    bytecode_path,
+                    CompiledPackageLayout::SourceMaps,
+                    SOURCE_MAP_EXTENSION,
+                )
+            });
+            let source_path = self.source_path.clone().unwrap_or_else(|| {
+                build_artifact(bytecode_path, CompiledPackageLayout::Sources, "move")
+            });
+            for path in [&source_map_path, &source_path] {
+                if !path.exists() {
+                    return Err(CliError::CommandArgumentError(format!(
+                        "{} doesn't exist, pass `--source-map-path` and `--source-path` for \
+                        modules outside a package build",
+                        path.display()
+                    )));
+                }
+            }
+            let source_map = bcs::from_bytes(&read_from_file(&source_map_path)?)
+                .map_err(|err| CliError::UnableToParse("source map", err.to_string()))?;
+            let source = String::from_utf8(read_from_file(&source_path)?)
+                .map_err(|err| CliError::UnableToParse("source", err.to_string()))?;
+            return Ok((module, Some((source_map, source))));
+        }
+
+        // Both are required without `--bytecode-path`
+        let account = self.account.unwrap();
+        let name = self.module.clone().unwrap();
+        let url = self.rest_options.url(&self.profile_options)?;
+        let module = fetch_modules(&Client::new(url.clone()), account)
+            .await?
+            .remove(&name)
+            .ok_or_else(|| {
+                CliError::CommandArgumentError(format!("No module {} at {}", name, account))
+            })?;
+        if !self.with_source {
+            return Ok((module, None));
+        }
+
+        let missing = || {
+            CliError::CommandArgumentError(format!(
+                "Module {} wasn't published with its source and source map",
+                name
+            ))
+        };
+        let registry = CachedPackageRegistry::create(url, account).await?;
+        let metadata = registry.get_module(&name).await.map_err(|_| missing())?;
+        if metadata.zipped_source().is_empty() || metadata.zipped_source_map_raw().is_empty() {
+            return Err(missing());
+        }
+        let source_map = unzip_metadata(metadata.zipped_source_map_raw())
+            .map_err(|err| CliError::UnableToParse("source map", err.to_string()))
+            .and_then(|bytes| {
+                bcs::from_bytes(&bytes)
+                    .map_err(|err| CliError::UnableToParse("source map", err.to_string()))
+            })?;
+        let source = unzip_metadata_str(metadata.zipped_source())
+            .map_err(|err| CliError::UnableToParse("source", err.to_string()))?;
+        Ok((module, Some((source_map, source))))
+    }
+}
+
+#[async_trait]
+impl CliCommand<()> for DisassembleModule {
+    fn command_name(&self) -> &'static str {
+        "DisassembleModule"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        let disassembly = match self.load().await? {
+            (module, Some((source_map, source))) => {
+                disassemble_with_source(&module, &source_map, &source)?
+            }
+            (module, None) => disassemble(&module)?,
+        };
+        match self.output_file {
+            Some(ref output_file) => {
+                write_to_file(output_file, "disassembly", disassembly.as_bytes())
+            }
+            None => {
+                println!("{}", disassembly);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Finds another artifact of a module in its package's build directory
+///
+/// `build/<package>/bytecode_modules/<module>.mv` has its source map in
+/// `build/<package>/source_maps/<module>.mvsm`, and likewise for dependencies
+fn build_artifact(bytecode_path: &Path, layout: CompiledPackageLayout, extension: &str) -> PathBuf {
+    let modules_dir = bytecode_path.ancestors().find(|dir| {
+        dir.file_name() == Some(CompiledPackageLayout::CompiledModules.path().as_os_str())
+    });
+    let mut path = match modules_dir.and_then(|dir| Some((dir.parent()?, dir))) {
+        Some((package_dir, modules_dir)) => package_dir.join(layout.path()).join(
+            bytecode_path
+                .strip_prefix(modules_dir)
+                .unwrap_or(bytecode_path),
+        ),
+        None => bytecode_path.to_path_buf(),
+    };
+    path.set_extension(extension);
+    path
+}
+
+/// Lists each function's instructions under the source lines they were compiled from
+fn disassemble_with_source(
+    module: &CompiledModule,
+    source_map: &SourceMap,
+    source: &str,
+) -> CliTypedResult<String> {
+    // Byte offset each line starts at
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+        .collect();
+    let lines: Vec<&str> = source.lines().collect();
+    let line_of = |offset: u32| match line_starts.binary_search(&(offset as usize)) {
+        Ok(line) => line,
+        Err(next_line) => next_line - 1,
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "// Module {}", module.self_id());
+    for (index, definition) in module.function_defs.iter().enumerate() {
+        let handle = module.function_handle_at(definition.function);
+        let _ = writeln!(out, "\nfun {}", module.identifier_at(handle.name));
+        let code = match definition.code {
+            Some(ref code) => code,
+            None => {
+                let _ = writeln!(out, "    native");
+                continue;
+            }
+        };
+        let function_source_map = source_map
+            .get_function_source_map(FunctionDefinitionIndex(index as u16))
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+
+        let mut last_line = None;
+        for (offset, instruction) in code.code.iter().enumerate() {
+            let location = function_source_map.get_code_location(offset as u16);
+            if let Some(location) = location {
+                let (first, last) = (line_of(location.start()), line_of(location.end()));
+                if last_line != Some((first, last)) {
+                    for line in first..=last.min(first + 2) {
+                        if let Some(text) = lines.get(line) {
+                            let _ = writeln!(out, "    // {:>4}: {}", line + 1, text.trim());
+                        }
+                    }
+                    last_line = Some((first, last));
+                }
+            }
+            let _ = writeln!(out, "    {:>4}: {:?}", offset, instruction);
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,473 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of a package's Move.toml that would otherwise only fail, or be silently ignored, when
+//! building or publishing
+
+use crate::{
+    common::{
+        types::{load_account_arg, CliCommand, CliError, CliTypedResult, MovePackageDir},
+        utils::write_to_file,
+    },
+    move_tool::{
+        dependencies::{dependency_dir, git_ls_remote, is_commit_hash, load_manifest, lookup_ref},
+        resolved_package::{ENV, ENV_SECTIONS},
+    },
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use framework::natives::code::UpgradePolicy;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use toml_edit::{Document, Item};
+
+const SECTIONS: &[&str] = &[
+    "package",
+    "addresses",
+    "dev-addresses",
+    "dependencies",
+    "dev-dependencies",
+    "build",
+    ENV,
+];
+const PACKAGE_KEYS: &[&str] = &["name", "version", "authors", "license", "upgrade_policy"];
+const DEPENDENCY_KEYS: &[&str] = &[
+    "local",
+    "git",
+    "rev",
+    "subdir",
+    "addr_subst",
+    "digest",
+    "aptos",
+    "address",
+];
+const DEPENDENCY_SECTIONS: [&str; 2] = ["dependencies", "dev-dependencies"];
+
+/// Check a package's Move.toml for problems
+///
+/// The checks are:
+/// - keys: sections and keys the package system doesn't know, which are ignored
+/// - version: a package version that isn't `<major>.<minor>.<patch>`, or an unknown
+///   `upgrade_policy`
+/// - git: git dependencies whose repository can't be reached, or whose rev isn't a branch, tag,
+///   or commit of it
+/// - placeholder: named addresses left as `_`, which publishing needs given with
+///   `--named-addresses`, and profile addresses whose profile doesn't exist
+/// - address conflict: a named address given different addresses by the package and its
+///   dependencies.  Git dependencies are checked once downloaded by a build
+///
+/// The issues are printed, and written as JSON to `--output` for CI.  Fails if there are
+/// errors, or with `--strict` warnings.
+#[derive(Parser)]
+pub struct CheckManifest {
+    /// Don't query git remotes, skipping the git checks
+    #[clap(long)]
+    pub(crate) offline: bool,
+
+    /// Fail on warnings as well as errors
+    #[clap(long)]
+    pub(crate) strict: bool,
+
+    /// File to write the issues to, as JSON
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueLevel {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestIssue {
+    pub level: IssueLevel,
+    pub check: &'static str,
+    /// Where in Move.toml, e.g. `dependencies.AptosFramework.rev`
+    pub key: String,
+    pub message: String,
+}
+
+#[async_trait]
+impl CliCommand<Vec<ManifestIssue>> for CheckManifest {
+    fn command_name(&self) -> &'static str {
+        "CheckManifest"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<ManifestIssue>> {
+        let package_dir = self.move_options.get_package_path()?;
+        let (_, manifest) = load_manifest(&package_dir)?;
+
+        let mut issues = check_keys(&manifest);
+        issues.extend(check_version(&manifest));
+        if !self.offline {
+            issues.extend(check_git_dependencies(&manifest));
+        }
+        issues.extend(check_placeholders(
+            &manifest,
+            &self.move_options.named_addresses(),
+        ));
+        issues.extend(check_address_conflicts(&package_dir, &manifest));
+
+        for issue in &issues {
+            let level = match issue.level {
+                IssueLevel::Warning => "warning",
+                IssueLevel::Error => "error",
+            };
+            eprintln!(
+                "{}: {} [{}] {}",
+                level, issue.key, issue.check, issue.message
+            );
+        }
+        if let Some(ref output) = self.output {
+            let json = serde_json::to_string_pretty(&issues)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            write_to_file(output, "manifest issues", json.as_bytes())?;
+        }
+
+        let threshold = if self.strict {
+            IssueLevel::Warning
+        } else {
+            IssueLevel::Error
+        };
+        let failing = issues
+            .iter()
+            .filter(|issue| issue.level >= threshold)
+            .count();
+        if failing > 0 {
+            return Err(CliError::UnexpectedError(format!(
+                "Move.toml has {} issues",
+                failing
+            )));
+        }
+        Ok(issues)
+    }
+}
+
+fn issue(level: IssueLevel, check: &'static str, key: String, message: String) -> ManifestIssue {
+    ManifestIssue {
+        level,
+        check,
+        key,
+        message,
+    }
+}
+
+/// The dependency tables of the manifest and its environments, with their keys
+fn dependency_tables(manifest: &Document) -> Vec<(String, &Item)> {
+    let mut tables: Vec<_> = DEPENDENCY_SECTIONS
+        .iter()
+        .filter_map(|section| Some((section.to_string(), manifest.get(section)?)))
+        .collect();
+    if let Some(envs) = manifest.get(ENV).and_then(|envs| envs.as_table_like()) {
+        for (env, overlay) in envs.iter() {
+            for section in DEPENDENCY_SECTIONS {
+                if let Some(table) = overlay.get(section) {
+                    tables.push((format!("{}.{}.{}", ENV, env, section), table));
+                }
+            }
+        }
+    }
+    tables
+}
+
+pub(crate) fn check_keys(manifest: &Document) -> Vec<ManifestIssue> {
+    let unknown = |key: String, known: &[&str]| {
+        issue(
+            IssueLevel::Warning,
+            "keys",
+            key,
+            format!("unknown key, ignored; expected one of {}", known.join(", ")),
+        )
+    };
+    let mut issues = vec![];
+    for (section, _) in manifest.iter() {
+        if !SECTIONS.contains(&section) {
+            issues.push(unknown(section.to_string(), SECTIONS));
+        }
+    }
+    if let Some(package) = manifest.get("package").and_then(|p| p.as_table_like()) {
+        for (key, _) in package.iter() {
+            if !PACKAGE_KEYS.contains(&key) {
+                issues.push(unknown(format!("package.{}", key), PACKAGE_KEYS));
+            }
+        }
+    }
+    if let Some(envs) = manifest.get(ENV).and_then(|envs| envs.as_table_like()) {
+        for (env, overlay) in envs.iter() {
+            for (section, _) in overlay.as_table_like().into_iter().flat_map(|t| t.iter()) {
+                if !ENV_SECTIONS.contains(&section) {
+                    issues.push(unknown(
+                        format!("{}.{}.{}", ENV, env, section),
+                        &ENV_SECTIONS,
+                    ));
+                }
+            }
+        }
+    }
+    for (section, table) in dependency_tables(manifest) {
+        for (name, dependency) in table.as_table_like().into_iter().flat_map(|t| t.iter()) {
+            let keys = match dependency.as_table_like() {
+                Some(keys) => keys,
+                None => {
+                    issues.push(issue(
+                        IssueLevel::Error,
+                        "keys",
+                        format!("{}.{}", section, name),
+                        "dependency must be a table".to_string(),
+                    ));
+                    continue;
+                }
+            };
+            for (key, _) in keys.iter() {
+                if !DEPENDENCY_KEYS.contains(&key) {
+                    issues.push(unknown(
+                        format!("{}.{}.{}", section, name, key),
+                        DEPENDENCY_KEYS,
+                    ));
+                }
+            }
+            if !keys.contains_key("local") && !keys.contains_key("git") {
+                issues.push(issue(
+                    IssueLevel::Error,
+                    "keys",
+                    format!("{}.{}", section, name),
+                    "dependency has neither `local` nor `git`".to_string(),
+                ));
+            } else if keys.contains_key("git") && !keys.contains_key("rev") {
+                issues.push(issue(
+                    IssueLevel::Error,
+                    "keys",
+                    format!("{}.{}", section, name),
+                    "git dependency has no `rev`".to_string(),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+pub(crate) fn check_version(manifest: &Document) -> Vec<ManifestIssue> {
+    let mut issues = vec![];
+    let package = manifest.get("package");
+    match package.and_then(|package| package.get("version")) {
+        None => issues.push(issue(
+            IssueLevel::Error,
+            "version",
+            "package.version".to_string(),
+            "missing".to_string(),
+        )),
+        Some(version) => {
+            let valid = version.as_str().map_or(false, |version| {
+                let parts: Vec<_> = version.split('.').collect();
+                parts.len() == 3 && parts.iter().all(|part| u64::from_str(part).is_ok())
+            });
+            if !valid {
+                issues.push(issue(
+                    IssueLevel::Error,
+                    "version",
+                    "package.version".to_string(),
+                    format!(
+                        "{} isn't of the form <major>.<minor>.<patch>",
+                        version.to_string().trim()
+                    ),
+                ));
+            }
+        }
+    }
+    if let Some(policy) = package.and_then(|package| package.get("upgrade_policy")) {
+        if policy
+            .as_str()
+            .map_or(true, |policy| UpgradePolicy::from_str(policy).is_err())
+        {
+            issues.push(issue(
+                IssueLevel::Error,
+                "version",
+                "package.upgrade_policy".to_string(),
+                format!(
+                    "{} isn't one of arbitrary, compatible, immutable",
+                    policy.to_string().trim()
+                ),
+            ));
+        }
+    }
+    issues
+}
+
+fn check_git_dependencies(manifest: &Document) -> Vec<ManifestIssue> {
+    let mut issues = vec![];
+    // Each remote is queried once, whichever dependencies use it
+    let mut remotes = BTreeMap::new();
+    for (section, table) in dependency_tables(manifest) {
+        for (name, dependency) in table.as_table_like().into_iter().flat_map(|t| t.iter()) {
+            let (git, rev) = match (
+                dependency.get("git").and_then(|git| git.as_str()),
+                dependency.get("rev").and_then(|rev| rev.as_str()),
+            ) {
+                (Some(git), Some(rev)) => (git, rev),
+                _ => continue,
+            };
+            let key = format!("{}.{}", section, name);
+            let refs = remotes
+                .entry(git.to_string())
+                .or_insert_with(|| git_ls_remote(git).map_err(|err| err.to_string()));
+            match &*refs {
+                Err(err) => issues.push(issue(
+                    IssueLevel::Error,
+                    "git",
+                    format!("{}.git", key),
+                    format!("can't reach {}: {}", git, err),
+                )),
+                // A commit may be anywhere in the history, the remote only lists the tips
+                Ok(_) if is_commit_hash(rev) => {}
+                Ok(refs) if lookup_ref(refs, rev).is_none() => issues.push(issue(
+                    IssueLevel::Error,
+                    "git",
+                    format!("{}.rev", key),
+                    format!("{} has no branch or tag {}", git, rev),
+                )),
+                Ok(_) => {}
+            }
+        }
+    }
+    issues
+}
+
+pub(crate) fn check_placeholders(
+    manifest: &Document,
+    named_addresses: &BTreeMap<String, AccountAddress>,
+) -> Vec<ManifestIssue> {
+    let mut issues = vec![];
+    let addresses = match manifest
+        .get("addresses")
+        .and_then(|addresses| addresses.as_table_like())
+    {
+        Some(addresses) => addresses,
+        None => return issues,
+    };
+    for (name, value) in addresses.iter() {
+        let key = format!("addresses.{}", name);
+        match value.as_str() {
+            Some("_") if !named_addresses.contains_key(name) => issues.push(issue(
+                IssueLevel::Warning,
+                "placeholder",
+                key,
+                format!(
+                    "placeholder, publishing needs it given with --named-addresses {}=<address>",
+                    name
+                ),
+            )),
+            Some("_") => {}
+            Some(value) => {
+                if let Err(err) = load_account_arg(value) {
+                    issues.push(issue(
+                        IssueLevel::Error,
+                        "placeholder",
+                        key,
+                        err.to_string(),
+                    ));
+                }
+            }
+            None => issues.push(issue(
+                IssueLevel::Error,
+                "placeholder",
+                key,
+                "address must be a string".to_string(),
+            )),
+        }
+    }
+    issues
+}
+
+fn check_address_conflicts(package_dir: &Path, manifest: &Document) -> Vec<ManifestIssue> {
+    let mut issues = vec![];
+    // Name -> address -> packages assigning it
+    let mut assigned: BTreeMap<String, BTreeMap<AccountAddress, BTreeSet<String>>> =
+        BTreeMap::new();
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![(package_dir.to_path_buf(), manifest.clone())];
+    while let Some((dir, manifest)) = pending.pop() {
+        let package = manifest
+            .get("package")
+            .and_then(|package| package.get("name"))
+            .and_then(|name| name.as_str())
+            .unwrap_or("<unnamed>")
+            .to_string();
+        if !visited.insert(package.clone()) {
+            continue;
+        }
+        for (name, value) in manifest
+            .get("addresses")
+            .and_then(|addresses| addresses.as_table_like())
+            .into_iter()
+            .flat_map(|addresses| addresses.iter())
+        {
+            // Placeholders take any address, and bad addresses are reported on their own
+            if let Some(address) = value
+                .as_str()
+                .filter(|value| *value != "_")
+                .and_then(|value| load_account_arg(value).ok())
+            {
+                assigned
+                    .entry(name.to_string())
+                    .or_default()
+                    .entry(address)
+                    .or_default()
+                    .insert(package.clone());
+            }
+        }
+
+        let dependencies = manifest
+            .get("dependencies")
+            .and_then(|dependencies| dependencies.as_table_like());
+        for (name, dependency) in dependencies.into_iter().flat_map(|t| t.iter()) {
+            match dependency_dir(&dir, dependency).and_then(|dir| {
+                let (_, manifest) = load_manifest(&dir).ok()?;
+                Some((dir, manifest))
+            }) {
+                Some(found) => pending.push(found),
+                None if dir == package_dir => issues.push(issue(
+                    IssueLevel::Warning,
+                    "address conflict",
+                    format!("dependencies.{}", name),
+                    "not found locally, build the package to download it and check its \
+                    addresses"
+                        .to_string(),
+                )),
+                None => {}
+            }
+        }
+    }
+
+    for (name, addresses) in assigned {
+        if addresses.len() > 1 {
+            issues.push(issue(
+                IssueLevel::Error,
+                "address conflict",
+                format!("addresses.{}", name),
+                format!(
+                    "assigned different addresses: {}",
+                    addresses
+                        .iter()
+                        .map(|(address, packages)| format!(
+                            "{} by {}",
+                            address.to_hex_literal(),
+                            packages.iter().cloned().collect::<Vec<_>>().join(", ")
+                        ))
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                ),
+            ));
+        }
+    }
+    issues
+}
@@ -0,0 +1,221 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Publishing packages over the transaction size limit, through the `large_packages` module
+//!
+//! The module, in `aptos-move/move-examples/large_packages`, stages a package under the sender
+//! across `stage_code` transactions, and the last of them publishes it.  Chunking is
+//! deterministic, so what's already staged tells how many chunks went through, and a failed
+//! publish resumes from there.
+
+use crate::common::types::{CliError, CliTypedResult, TransactionOptions, TransactionSummary};
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{EntryFunction, TransactionPayload},
+};
+use move_core_types::{ident_str, language_storage::ModuleId};
+
+/// Bytes of metadata and code per transaction, leaving headroom under the publish limit
+const CHUNK_SIZE: usize = 55_000;
+
+/// A `stage_code` transaction's arguments
+#[derive(Clone, Default)]
+struct Chunk {
+    metadata: Vec<u8>,
+    code: Vec<(u16, Vec<u8>)>,
+}
+
+/// Staged metadata and code, as after some chunks
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Staged {
+    metadata: Vec<u8>,
+    code: Vec<Vec<u8>>,
+}
+
+impl Staged {
+    fn apply(&mut self, chunk: &Chunk) {
+        self.metadata.extend_from_slice(&chunk.metadata);
+        for (index, bytes) in &chunk.code {
+            let index = *index as usize;
+            if self.code.len() <= index {
+                self.code.resize(index + 1, vec![]);
+            }
+            self.code[index].extend_from_slice(bytes);
+        }
+    }
+}
+
+/// Publishes a package in chunks, resuming from any chunks staged before
+pub(crate) async fn publish_chunked(
+    txn_options: &TransactionOptions,
+    large_packages: AccountAddress,
+    metadata: Vec<u8>,
+    code: Vec<Vec<u8>>,
+) -> CliTypedResult<TransactionSummary> {
+    let chunks = chunk_package(metadata, code);
+    let sender = txn_options.sender_address()?;
+
+    let start = match staged(txn_options, large_packages, sender).await? {
+        None => 0,
+        Some(staged) => match resume_point(&chunks, &staged) {
+            Some(start) => {
+                eprintln!(
+                    "Resuming from chunk {} of {}, the ones before are staged",
+                    start + 1,
+                    chunks.len()
+                );
+                start
+            }
+            None => {
+                eprintln!("Removing a staged package that isn't this one");
+                cleanup(txn_options, large_packages).await?;
+                0
+            }
+        },
+    };
+
+    for (index, chunk) in chunks.iter().enumerate().skip(start) {
+        let publish = index + 1 == chunks.len();
+        eprintln!("Submitting chunk {} of {}", index + 1, chunks.len());
+        let result = txn_options
+            .submit_transaction(stage_code(large_packages, chunk, publish))
+            .await;
+        match result {
+            Ok(transaction) if publish => return Ok(TransactionSummary::from(&transaction)),
+            Ok(_) => {}
+            // What's staged can't be published, so it's removed rather than left for a rerun
+            Err(err) if publish => {
+                cleanup(txn_options, large_packages).await?;
+                return Err(err);
+            }
+            Err(err) => {
+                return Err(CliError::UnexpectedError(format!(
+                    "Chunk {} of {} failed, run the command again to resume: {}",
+                    index + 1,
+                    chunks.len(),
+                    err
+                )))
+            }
+        }
+    }
+    unreachable!("there's always a publishing chunk")
+}
+
+/// Splits the metadata and modules into chunks of at most `CHUNK_SIZE` bytes
+fn chunk_package(metadata: Vec<u8>, code: Vec<Vec<u8>>) -> Vec<Chunk> {
+    let mut chunks = vec![];
+    let mut chunk = Chunk::default();
+    let mut size = 0;
+    for piece in metadata.chunks(CHUNK_SIZE) {
+        if size + piece.len() > CHUNK_SIZE {
+            chunks.push(std::mem::take(&mut chunk));
+            size = 0;
+        }
+        chunk.metadata.extend_from_slice(piece);
+        size += piece.len();
+    }
+    for (index, module) in code.iter().enumerate() {
+        let mut rest = module.as_slice();
+        loop {
+            if size == CHUNK_SIZE {
+                chunks.push(std::mem::take(&mut chunk));
+                size = 0;
+            }
+            let (piece, remaining) = rest.split_at(rest.len().min(CHUNK_SIZE - size));
+            chunk.code.push((index as u16, piece.to_vec()));
+            size += piece.len();
+            rest = remaining;
+            if rest.is_empty() {
+                break;
+            }
+        }
+    }
+    chunks.push(chunk);
+    chunks
+}
+
+/// The number of chunks that leave exactly what's staged, if any do
+fn resume_point(chunks: &[Chunk], staged: &Staged) -> Option<usize> {
+    let mut expected = Staged::default();
+    for (index, chunk) in chunks.iter().enumerate() {
+        expected.apply(chunk);
+        // The publishing chunk is never staged, as it removes the staging area
+        if &expected == staged && index + 1 < chunks.len() {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+/// What's staged under the sender, if anything
+async fn staged(
+    txn_options: &TransactionOptions,
+    large_packages: AccountAddress,
+    sender: AccountAddress,
+) -> CliTypedResult<Option<Staged>> {
+    let client = txn_options
+        .rest_options
+        .client(&txn_options.profile_options)?;
+    let resource = client
+        .get_account_resource(
+            sender,
+            &format!(
+                "{}::large_packages::StagingArea",
+                large_packages.to_hex_literal()
+            ),
+        )
+        .await?
+        .into_inner();
+    let data = match resource {
+        Some(resource) => resource.data,
+        None => return Ok(None),
+    };
+
+    let bytes = |value: &serde_json::Value| {
+        value
+            .as_str()
+            .and_then(|hex| hex::decode(hex.trim_start_matches("0x")).ok())
+            .ok_or_else(|| CliError::UnexpectedError(format!("Unexpected staging area {}", data)))
+    };
+    let metadata = bytes(&data["metadata_serialized"])?;
+    let code = data["code"]
+        .as_array()
+        .map(|code| code.iter().map(bytes).collect::<CliTypedResult<Vec<_>>>())
+        .unwrap_or_else(|| {
+            Err(CliError::UnexpectedError(format!(
+                "Unexpected staging area {}",
+                data
+            )))
+        })?;
+    Ok(Some(Staged { metadata, code }))
+}
+
+async fn cleanup(
+    txn_options: &TransactionOptions,
+    large_packages: AccountAddress,
+) -> CliTypedResult<()> {
+    txn_options
+        .submit_transaction(TransactionPayload::EntryFunction(EntryFunction::new(
+            ModuleId::new(large_packages, ident_str!("large_packages").to_owned()),
+            ident_str!("cleanup").to_owned(),
+            vec![],
+            vec![],
+        )))
+        .await
+        .map(|_| ())
+}
+
+fn stage_code(large_packages: AccountAddress, chunk: &Chunk, publish: bool) -> TransactionPayload {
+    let (indices, code): (Vec<_>, Vec<_>) = chunk.code.iter().cloned().unzip();
+    TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(large_packages, ident_str!("large_packages").to_owned()),
+        ident_str!("stage_code").to_owned(),
+        vec![],
+        vec![
+            bcs::to_bytes(&chunk.metadata).expect("bytes have BCS"),
+            bcs::to_bytes(&indices).expect("indices have BCS"),
+            bcs::to_bytes(&code).expect("code has BCS"),
+            bcs::to_bytes(&publish).expect("bool has BCS"),
+        ],
+    ))
+}
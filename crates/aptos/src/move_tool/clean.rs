@@ -0,0 +1,286 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Removing build artifacts and downloaded dependencies
+//!
+//! Under `build/`, each package compiled from the directory has a directory of its name, whose
+//! `bytecode_modules`, `source_maps` and `sources` each keep the dependencies' files apart in a
+//! `dependencies` directory.  Git dependencies are downloaded to the shared cache in `~/.move`,
+//! a directory per repository and revision.
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, MovePackageDir, PromptOptions},
+        utils::prompt_yes_with_override,
+    },
+    move_tool::dependencies::{git_cache_dir, load_manifest},
+};
+use async_trait::async_trait;
+use clap::Parser;
+use move_command_line_common::env::MOVE_HOME;
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const DEPENDENCIES_DIR: &str = "dependencies";
+
+/// Cleans derived artifacts of a package.
+///
+/// Without a selection, the package's `build` directory is removed, and removing the shared
+/// download cache in `~/.move` is offered.  What's removed is printed with its size first.
+#[derive(Parser)]
+pub struct CleanPackage {
+    /// Remove the compiled dependencies, and the downloads of the git dependencies in `~/.move`
+    #[clap(long)]
+    pub(crate) deps: bool,
+
+    /// Remove the package's own compiled artifacts, keeping the compiled dependencies
+    #[clap(long)]
+    pub(crate) artifacts: bool,
+
+    /// Remove the whole `build` directory and the whole `~/.move` cache
+    #[clap(long, conflicts_with_all = &["deps", "artifacts"])]
+    pub(crate) all: bool,
+
+    /// Only clean this package: one compiled under `build` with `--artifacts`, or a dependency
+    /// with `--deps`
+    #[clap(long)]
+    pub(crate) package: Option<String>,
+
+    /// Print what would be removed, without removing it
+    #[clap(long)]
+    pub(crate) dry_run: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[derive(Serialize)]
+pub struct CleanSummary {
+    /// What was removed, or would be with `--dry-run`
+    pub paths: Vec<CleanedPath>,
+    pub bytes: u64,
+    pub dry_run: bool,
+}
+
+#[derive(Serialize)]
+pub struct CleanedPath {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+#[async_trait]
+impl CliCommand<CleanSummary> for CleanPackage {
+    fn command_name(&self) -> &'static str {
+        "CleanPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<CleanSummary> {
+        let package_dir = self.move_options.get_package_path()?;
+        let build_dir = package_dir.join("build");
+        let move_home = PathBuf::from(&*MOVE_HOME);
+
+        // Paths under `build`, and in the shared cache
+        let (local, shared) = if self.all || !(self.deps || self.artifacts) {
+            (vec![build_dir], vec![move_home])
+        } else {
+            let mut local = vec![];
+            let mut shared = vec![];
+            for compiled in subdirs(&build_dir)? {
+                let name = compiled
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default();
+                if self.artifacts
+                    && self
+                        .package
+                        .as_ref()
+                        .map_or(true, |package| name == package.as_str())
+                {
+                    local.extend(own_artifacts(&compiled)?);
+                }
+                if self.deps {
+                    local.extend(compiled_dependencies(&compiled, self.package.as_deref())?);
+                }
+            }
+            if self.deps {
+                shared.extend(self.git_dependencies(&package_dir)?);
+            }
+            (local, shared)
+        };
+
+        let mut summary = CleanSummary {
+            paths: vec![],
+            bytes: 0,
+            dry_run: self.dry_run,
+        };
+        for path in local.into_iter().filter(|path| path.exists()) {
+            let bytes = size(&path)?;
+            eprintln!("{} ({})", path.display(), human_size(bytes));
+            if !self.dry_run {
+                remove(&path)?;
+            }
+            summary.bytes += bytes;
+            summary.paths.push(CleanedPath { path, bytes });
+        }
+
+        let shared: Vec<_> = shared.into_iter().filter(|path| path.exists()).collect();
+        if shared.is_empty() {
+            return Ok(summary);
+        }
+        let mut sizes = vec![];
+        for path in &shared {
+            let bytes = size(path)?;
+            eprintln!("{} ({})", path.display(), human_size(bytes));
+            sizes.push(bytes);
+        }
+        // Other packages use the shared cache too, so it's only removed when confirmed
+        if self.dry_run
+            || prompt_yes_with_override(
+                &format!(
+                    "Do you also want to delete the local package download cache at `{}`?",
+                    move_home.display()
+                ),
+                self.prompt_options,
+            )
+            .is_ok()
+        {
+            for (path, bytes) in shared.into_iter().zip(sizes) {
+                if !self.dry_run {
+                    remove(&path)?;
+                }
+                summary.bytes += bytes;
+                summary.paths.push(CleanedPath { path, bytes });
+            }
+        }
+        Ok(summary)
+    }
+}
+
+impl CleanPackage {
+    /// Download directories of the git dependencies in Move.toml
+    fn git_dependencies(&self, package_dir: &Path) -> CliTypedResult<Vec<PathBuf>> {
+        let (_, manifest) = load_manifest(package_dir)?;
+        let mut dirs = vec![];
+        for section in ["dependencies", "dev-dependencies"] {
+            let dependencies = match manifest
+                .as_table()
+                .get(section)
+                .and_then(|deps| deps.as_table_like())
+            {
+                Some(dependencies) => dependencies,
+                None => continue,
+            };
+            for (name, dependency) in dependencies.iter() {
+                if self
+                    .package
+                    .as_ref()
+                    .map_or(false, |package| package.as_str() != name)
+                {
+                    continue;
+                }
+                let git = dependency.get("git").and_then(|git| git.as_str());
+                let rev = dependency.get("rev").and_then(|rev| rev.as_str());
+                if let (Some(git), Some(rev)) = (git, rev) {
+                    dirs.push(git_cache_dir(git, rev));
+                }
+            }
+        }
+        Ok(dirs)
+    }
+}
+
+/// Everything in a compiled package's directory but its dependencies
+fn own_artifacts(compiled: &Path) -> CliTypedResult<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in entries(compiled)? {
+        if entry.join(DEPENDENCIES_DIR).is_dir() {
+            paths.extend(
+                entries(&entry)?
+                    .into_iter()
+                    .filter(|path| !path.ends_with(DEPENDENCIES_DIR)),
+            );
+        } else {
+            paths.push(entry);
+        }
+    }
+    Ok(paths)
+}
+
+/// The `dependencies` directories of a compiled package, or only one dependency's part of them
+fn compiled_dependencies(compiled: &Path, package: Option<&str>) -> CliTypedResult<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in subdirs(compiled)? {
+        let dependencies = entry.join(DEPENDENCIES_DIR);
+        paths.push(match package {
+            Some(package) => dependencies.join(package),
+            None => dependencies,
+        });
+    }
+    Ok(paths)
+}
+
+fn entries(dir: &Path) -> CliTypedResult<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir).map_err(|err| CliError::IO(dir.display().to_string(), err))? {
+        paths.push(
+            entry
+                .map_err(|err| CliError::IO(dir.display().to_string(), err))?
+                .path(),
+        );
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn subdirs(dir: &Path) -> CliTypedResult<Vec<PathBuf>> {
+    Ok(entries(dir)?
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .collect())
+}
+
+/// Bytes in the files under a path
+fn size(path: &Path) -> CliTypedResult<u64> {
+    let metadata =
+        fs::symlink_metadata(path).map_err(|err| CliError::IO(path.display().to_string(), err))?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in entries(path)? {
+        total += size(&entry)?;
+    }
+    Ok(total)
+}
+
+fn remove(path: &Path) -> CliTypedResult<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+    .map_err(|err| CliError::IO(format!("Removing {}", path.display()), err))
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
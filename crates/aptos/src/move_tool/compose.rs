@@ -0,0 +1,223 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Composing a script from a sequence of function calls
+//!
+//! The calls are written out as a Move script, whose literal arguments become script
+//! parameters, and which is compiled against the Aptos framework like a governance script.
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, TransactionOptions, TransactionSummary},
+        utils::{read_from_file, write_to_file},
+    },
+    governance::compile_in_temp_dir,
+    move_tool::{ArgWithType, FrameworkPackageArgs, FunctionArgType, MemberId},
+};
+use aptos_rest_client::aptos_api_types::{MoveType, Transaction};
+use aptos_types::transaction::{Script, TransactionArgument, TransactionPayload};
+use async_trait::async_trait;
+use clap::Parser;
+use move_core_types::identifier::Identifier;
+use serde::Deserialize;
+use std::{collections::BTreeSet, path::PathBuf, str::FromStr};
+use tempfile::TempDir;
+
+/// Compose a script from a sequence of function calls, and simulate or submit it
+///
+/// The calls are described in a YAML or JSON file:
+///
+/// calls:
+///   - function: 0x1::coin::withdraw
+///     type_args: [0x1::aptos_coin::AptosCoin]
+///     args: [signer, u64:100]
+///     result: coins
+///   - function: 0x1::coin::deposit
+///     type_args: [0x1::aptos_coin::AptosCoin]
+///     args: [address:0xcafe, $coins]
+///
+/// Arguments are `signer` for the sender, `$<name>` for the result of an earlier call, or
+/// `<type>:<value>` as for `aptos move run`, of types [address, bool, hex, string, u8, u64,
+/// u128].  Calls can be to entry or public functions, and results are piped between them by
+/// binding them with `result`.  The script is simulated, and submitted with `--submit`.
+#[derive(Parser)]
+pub struct ComposeScript {
+    /// YAML or JSON file of the calls
+    #[clap(long, parse(from_os_str))]
+    pub(crate) spec: PathBuf,
+
+    /// Submit the script after simulating it
+    #[clap(long)]
+    pub(crate) submit: bool,
+
+    /// Write the generated Move script to this file
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_script: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) framework_package_args: FrameworkPackageArgs,
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ComposeSpec {
+    pub(crate) calls: Vec<ComposeCall>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ComposeCall {
+    pub(crate) function: String,
+    #[serde(default)]
+    pub(crate) type_args: Vec<String>,
+    #[serde(default)]
+    pub(crate) args: Vec<String>,
+    /// Name to bind the call's result to
+    pub(crate) result: Option<String>,
+}
+
+#[async_trait]
+impl CliCommand<TransactionSummary> for ComposeScript {
+    fn command_name(&self) -> &'static str {
+        "ComposeScript"
+    }
+
+    async fn execute(self) -> CliTypedResult<TransactionSummary> {
+        let spec: ComposeSpec = serde_yaml::from_slice(&read_from_file(&self.spec)?)?;
+        let (source, args) = compose_script(&spec)?;
+        if let Some(output_script) = &self.output_script {
+            write_to_file(output_script, "Composed script", source.as_bytes())?;
+        }
+
+        let temp_dir = TempDir::new().map_err(|err| {
+            CliError::UnexpectedError(format!("Failed to create temporary directory {}", err))
+        })?;
+        let script_path = temp_dir.path().join("compose.move");
+        write_to_file(&script_path, "Composed script", source.as_bytes())?;
+        let (bytecode, _script_hash) = compile_in_temp_dir(
+            "Compose",
+            &script_path,
+            &self.framework_package_args,
+            self.txn_options.prompt_options,
+        )?;
+        let payload = TransactionPayload::Script(Script::new(bytecode, vec![], args));
+
+        let simulated = self
+            .txn_options
+            .simulate_transaction(
+                payload.clone(),
+                self.txn_options.gas_options.gas_unit_price,
+                None,
+            )
+            .await?;
+        eprintln!(
+            "Simulated {} calls: {}, {} gas used",
+            spec.calls.len(),
+            simulated.info.vm_status,
+            simulated.info.gas_used.0
+        );
+        if !simulated.info.success {
+            return Err(CliError::SimulationError(simulated.info.vm_status));
+        }
+        if !self.submit {
+            return Ok(TransactionSummary::from(Transaction::UserTransaction(
+                Box::new(simulated),
+            )));
+        }
+
+        self.txn_options
+            .submit_transaction(payload)
+            .await
+            .map(TransactionSummary::from)
+    }
+}
+
+/// The Move source of a script making the calls, and the values of its parameters
+pub(crate) fn compose_script(
+    spec: &ComposeSpec,
+) -> CliTypedResult<(String, Vec<TransactionArgument>)> {
+    let mut params = vec!["sender: &signer".to_string()];
+    let mut values = vec![];
+    let mut results = BTreeSet::new();
+    let mut lines = vec![];
+
+    for (index, call) in spec.calls.iter().enumerate() {
+        let function = MemberId::from_str(&call.function)?;
+        let mut type_args = vec![];
+        for type_arg in &call.type_args {
+            let type_arg = MoveType::from_str(type_arg)
+                .map_err(|err| CliError::UnableToParse("type_args", err.to_string()))?;
+            type_args.push(type_arg.to_string());
+        }
+
+        let mut args = vec![];
+        for arg in &call.args {
+            if arg == "signer" {
+                args.push("sender".to_string());
+            } else if let Some(name) = arg.strip_prefix('$') {
+                if !results.contains(name) {
+                    return Err(CliError::CommandArgumentError(format!(
+                        "Call {} uses ${}, which no earlier call returns",
+                        index + 1,
+                        name
+                    )));
+                }
+                args.push(name.to_string());
+            } else {
+                let value = ArgWithType::from_str(arg)?;
+                let param = format!("arg_{}", values.len());
+                let (typ, use_as) = match value._ty {
+                    FunctionArgType::Address => ("address", param.clone()),
+                    FunctionArgType::Bool => ("bool", param.clone()),
+                    FunctionArgType::Hex => ("vector<u8>", param.clone()),
+                    FunctionArgType::String => {
+                        ("vector<u8>", format!("0x1::string::utf8({})", param))
+                    }
+                    FunctionArgType::U8 => ("u8", param.clone()),
+                    FunctionArgType::U64 => ("u64", param.clone()),
+                    FunctionArgType::U128 => ("u128", param.clone()),
+                    FunctionArgType::HexArray | FunctionArgType::Raw => {
+                        return Err(CliError::CommandArgumentError(format!(
+                            "Call {} has argument {}, whose type scripts can't take",
+                            index + 1,
+                            arg
+                        )))
+                    }
+                };
+                params.push(format!("{}: {}", param, typ));
+                values.push(value.try_into()?);
+                args.push(use_as);
+            }
+        }
+
+        let mut line = format!(
+            "{}::{}::{}",
+            function.module_id.address().to_hex_literal(),
+            function.module_id.name(),
+            function.member_id
+        );
+        if !type_args.is_empty() {
+            line = format!("{}<{}>", line, type_args.join(", "));
+        }
+        line = format!("{}({});", line, args.join(", "));
+        if let Some(result) = &call.result {
+            if !Identifier::is_valid(result) || result == "sender" || result.starts_with("arg_") {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Call {} binds its result to {}, which isn't a usable name",
+                    index + 1,
+                    result
+                )));
+            }
+            results.insert(result.as_str());
+            line = format!("let {} = {}", result, line);
+        }
+        lines.push(format!("        {}", line));
+    }
+
+    let source = format!(
+        "script {{\n    fun main({}) {{\n{}\n    }}\n}}\n",
+        params.join(", "),
+        lines.join("\n")
+    );
+    Ok((source, values))
+}
@@ -0,0 +1,354 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Coverage reports for `aptos move test --coverage`
+//!
+//! The tests write a coverage map of the executed instructions to the package, which is
+//! mapped back onto the sources here.  A line counts as covered if it has code and none of it
+//! was skipped, so declarations and closing braces count as covered too.
+
+use crate::common::types::{CliCommand, CliError, CliTypedResult, MovePackageDir};
+use crate::common::utils::{create_dir_if_not_exist, write_to_file};
+use async_trait::async_trait;
+use clap::{ArgEnum, Parser};
+use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
+use move_coverage::{
+    coverage_map::CoverageMap,
+    source_coverage::{SourceCoverageBuilder, StringSegment},
+    summary::summarize_inst_cov,
+};
+use move_package::BuildConfig;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Where `aptos move test --coverage` saves the coverage map, in the package directory
+pub const COVERAGE_MAP_NAME: &str = ".coverage_map.mvcov";
+
+#[derive(ArgEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CoverageFormat {
+    /// Instructions covered per module, in the terminal
+    Summary,
+    /// A page per module with the uncovered code highlighted
+    Html,
+    /// An lcov tracefile, for uploading from CI
+    Lcov,
+}
+
+impl Display for CoverageFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CoverageFormat::Summary => "summary",
+            CoverageFormat::Html => "html",
+            CoverageFormat::Lcov => "lcov",
+        })
+    }
+}
+
+impl FromStr for CoverageFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "summary" => Ok(CoverageFormat::Summary),
+            "html" => Ok(CoverageFormat::Html),
+            "lcov" => Ok(CoverageFormat::Lcov),
+            _ => Err("Invalid format. Valid values are summary, html, lcov"),
+        }
+    }
+}
+
+/// Report the coverage of the last `aptos move test --coverage`
+///
+/// The summary is printed, while the HTML report and lcov tracefile are written to
+/// `--coverage-dir`
+#[derive(Parser)]
+pub struct CoveragePackage {
+    /// Report format: [summary, html, lcov]
+    #[clap(long, default_value_t = CoverageFormat::Summary)]
+    pub(crate) format: CoverageFormat,
+
+    /// Include every function in the summary
+    #[clap(long)]
+    pub(crate) functions: bool,
+
+    /// Directory for the HTML report or `lcov.info`
+    ///
+    /// Defaults to `<package_dir>/coverage`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) coverage_dir: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<String> for CoveragePackage {
+    fn command_name(&self) -> &'static str {
+        "CoveragePackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        let package_dir = self.move_options.get_package_path()?;
+        let coverage_map_path = package_dir.join(COVERAGE_MAP_NAME);
+        if !coverage_map_path.exists() {
+            return Err(CliError::CommandArgumentError(format!(
+                "No coverage map found at {}, run `aptos move test --coverage` first",
+                coverage_map_path.display()
+            )));
+        }
+        let coverage_map = CoverageMap::from_binary_file(&coverage_map_path)
+            .map_err(|err| CliError::UnableToParse("coverage map", err.to_string()))?;
+
+        let config = BuildConfig {
+            additional_named_addresses: self.move_options.named_addresses(),
+            install_dir: self.move_options.output_dir.clone(),
+            ..Default::default()
+        };
+        let package = config
+            .compile_package_no_exit(&package_dir, &mut std::io::stderr())
+            .map_err(|err| CliError::MoveCompilationError(format!("{:#}", err)))?;
+
+        if self.format == CoverageFormat::Summary {
+            let mut summary = Vec::new();
+            let (mut total, mut covered) = (0, 0);
+            for unit in package.root_modules() {
+                if let CompiledUnit::Module(NamedCompiledModule { module, .. }) = &unit.unit {
+                    let (module_total, module_covered) = summarize_inst_cov(module, &coverage_map)
+                        .summarize_human(&mut summary, self.functions)
+                        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                    total += module_total;
+                    covered += module_covered;
+                }
+            }
+            eprint!("{}", String::from_utf8_lossy(&summary));
+            return Ok(format!(
+                "{:.2}% of instructions covered",
+                percent(covered as usize, total as usize)
+            ));
+        }
+
+        // Several modules can share a source file, so merge their lines per file
+        let mut files: BTreeMap<PathBuf, FileCoverage> = BTreeMap::new();
+        for unit in package.root_modules() {
+            if let CompiledUnit::Module(NamedCompiledModule {
+                module, source_map, ..
+            }) = &unit.unit
+            {
+                let source_coverage = SourceCoverageBuilder::new(module, &coverage_map, source_map)
+                    .compute_source_coverage(&unit.source_path);
+                let lines: Vec<_> = source_coverage
+                    .annotated_lines
+                    .iter()
+                    .map(|segments| LineCoverage::new(segments))
+                    .collect();
+                let file = files
+                    .entry(unit.source_path.clone())
+                    .or_insert_with(|| FileCoverage {
+                        modules: Vec::new(),
+                        lines: lines.clone(),
+                    });
+                file.modules.push(module.self_id().name().to_string());
+                file.merge(lines);
+            }
+        }
+
+        let coverage_dir = self
+            .coverage_dir
+            .unwrap_or_else(|| package_dir.join("coverage"));
+        create_dir_if_not_exist(&coverage_dir)?;
+        let output = match self.format {
+            CoverageFormat::Html => write_html(&coverage_dir, &package_dir, &files)?,
+            _ => {
+                let path = coverage_dir.join("lcov.info");
+                write_to_file(&path, "lcov.info", lcov(&files).as_bytes())?;
+                path
+            }
+        };
+
+        let (total, covered) = files.values().fold((0, 0), |(total, covered), file| {
+            let (file_total, file_covered) = file.counts();
+            (total + file_total, covered + file_covered)
+        });
+        Ok(format!(
+            "{:.2}% of lines covered, report written to {}",
+            percent(covered, total),
+            output.display()
+        ))
+    }
+}
+
+/// A source line, split into its covered and uncovered parts
+#[derive(Clone)]
+struct LineCoverage {
+    segments: Vec<(String, bool)>,
+}
+
+impl LineCoverage {
+    fn new(segments: &[StringSegment]) -> Self {
+        Self {
+            segments: segments
+                .iter()
+                .map(|segment| match segment {
+                    StringSegment::Covered(text) => (text.clone(), true),
+                    StringSegment::Uncovered(text) => (text.clone(), false),
+                })
+                .collect(),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect()
+    }
+
+    /// Whether the line counts towards coverage, it's blank, a comment or a brace otherwise
+    fn has_code(&self) -> bool {
+        let text = self.text();
+        let text = text.trim();
+        !text.starts_with("//") && !text.trim_matches(|c| "{}();".contains(c)).is_empty()
+    }
+
+    fn is_covered(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|(text, covered)| *covered || text.trim().is_empty())
+    }
+}
+
+struct FileCoverage {
+    modules: Vec<String>,
+    lines: Vec<LineCoverage>,
+}
+
+impl FileCoverage {
+    /// Keeps a line's code uncovered where any module left it uncovered
+    fn merge(&mut self, lines: Vec<LineCoverage>) {
+        for (line, other) in self.lines.iter_mut().zip(lines) {
+            if !other.is_covered() {
+                *line = other;
+            }
+        }
+    }
+
+    /// Lines with code, and how many of those are covered
+    fn counts(&self) -> (usize, usize) {
+        let code = self.lines.iter().filter(|line| line.has_code());
+        let total = code.clone().count();
+        (total, code.filter(|line| line.is_covered()).count())
+    }
+}
+
+fn percent(covered: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        covered as f64 * 100.0 / total as f64
+    }
+}
+
+fn lcov(files: &BTreeMap<PathBuf, FileCoverage>) -> String {
+    let mut out = String::new();
+    for (path, file) in files {
+        let (total, covered) = file.counts();
+        let _ = writeln!(out, "TN:\nSF:{}", path.display());
+        for (number, line) in file.lines.iter().enumerate() {
+            if line.has_code() {
+                let _ = writeln!(out, "DA:{},{}", number + 1, line.is_covered() as u8);
+            }
+        }
+        let _ = writeln!(out, "LF:{}\nLH:{}\nend_of_record", total, covered);
+    }
+    out
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; } \
+table { border-collapse: collapse; } td, th { padding: 2px 12px; text-align: left; } \
+pre { line-height: 1.3; } .uncovered { background: #fcc; } .line-number { color: #999; }";
+
+/// Writes `index.html`, and a page per source file, returning the index
+fn write_html(
+    coverage_dir: &Path,
+    package_dir: &Path,
+    files: &BTreeMap<PathBuf, FileCoverage>,
+) -> CliTypedResult<PathBuf> {
+    let mut index = String::new();
+    let _ = write!(
+        index,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Coverage</title>\
+         <style>{}</style></head><body>\n<h1>Coverage</h1>\n<table>\n\
+         <tr><th>Modules</th><th>File</th><th>Lines</th><th>Covered</th></tr>\n",
+        HTML_STYLE
+    );
+
+    for (path, file) in files {
+        let relative_path = path.strip_prefix(package_dir).unwrap_or(path);
+        let page = format!("{}.html", file.modules.join("-"));
+        let (total, covered) = file.counts();
+        let _ = writeln!(
+            index,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}/{}</td><td>{:.2}%</td></tr>",
+            page,
+            escape_html(&file.modules.join(", ")),
+            escape_html(&relative_path.display().to_string()),
+            covered,
+            total,
+            percent(covered, total)
+        );
+
+        let mut source = String::new();
+        let _ = write!(
+            source,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title>\
+             <style>{1}</style></head><body>\n<p><a href=\"index.html\">Coverage</a></p>\n\
+             <h1>{0}</h1>\n<p>{2}/{3} lines covered ({4:.2}%)</p>\n<pre>",
+            escape_html(&relative_path.display().to_string()),
+            HTML_STYLE,
+            covered,
+            total,
+            percent(covered, total)
+        );
+        for (number, line) in file.lines.iter().enumerate() {
+            let _ = write!(
+                source,
+                "<span class=\"line-number\">{:5}</span>  ",
+                number + 1
+            );
+            for (text, covered) in &line.segments {
+                if *covered || text.trim().is_empty() {
+                    source.push_str(&escape_html(text));
+                } else {
+                    let _ = write!(
+                        source,
+                        "<span class=\"uncovered\">{}</span>",
+                        escape_html(text)
+                    );
+                }
+            }
+            source.push('\n');
+        }
+        source.push_str("</pre>\n</body></html>\n");
+        write_to_file(
+            &coverage_dir.join(&page),
+            "coverage page",
+            source.as_bytes(),
+        )?;
+    }
+
+    index.push_str("</table>\n</body></html>\n");
+    let index_path = coverage_dir.join("index.html");
+    write_to_file(&index_path, "coverage index", index.as_bytes())?;
+    Ok(index_path)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,720 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Editing the dependencies of a package's Move.toml
+//!
+//! Edits go through `toml_edit`, so the rest of the manifest keeps its formatting and comments
+
+use crate::{
+    common::{
+        init::Network,
+        types::{CliError, CliTypedResult, MovePackageDir, ProfileOptions, PromptOptions},
+        utils::{create_dir_if_not_exist, prompt_yes_with_override, read_from_file, write_to_file},
+    },
+    move_tool::IncludedArtifacts,
+    CliCommand,
+};
+use async_trait::async_trait;
+use clap::Parser;
+use framework::BuiltPackage;
+use move_command_line_common::env::MOVE_HOME;
+use move_package::source_package::layout::SourcePackageLayout;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    process::Command,
+};
+use toml_edit::{Document, InlineTable, Item, Table, Value};
+
+const DEPENDENCIES: &str = "dependencies";
+pub(crate) const APTOS_GIT_PATH: &str = "https://github.com/aptos-labs/aptos-core.git";
+const DEFAULT_BRANCH: &str = "main";
+
+/// Packages in the aptos-core repository, and their directories in it
+const KNOWN_PACKAGES: &[(&str, &str)] = &[
+    ("AptosFramework", "aptos-move/framework/aptos-framework"),
+    ("AptosStdlib", "aptos-move/framework/aptos-stdlib"),
+    ("MoveStdlib", "aptos-move/framework/move-stdlib"),
+    ("AptosToken", "aptos-move/framework/aptos-token"),
+];
+
+/// Add a dependency to a package's Move.toml
+///
+/// The dependency is either one of the Aptos framework packages by name [AptosFramework,
+/// AptosStdlib, MoveStdlib, AptosToken], a git repository, or a local directory.  Framework
+/// packages default to the revision the package already uses for the framework.
+///
+/// The package is then built to check the dependency resolves, and Move.toml is restored if
+/// it doesn't.
+///
+/// Example: `aptos move add AptosToken`
+/// Example: `aptos move add https://github.com/org/repo.git --name Repo --rev main --subdir move`
+#[derive(Parser)]
+pub struct AddDependency {
+    /// Name of a framework package, or git URL of the dependency
+    ///
+    /// Not needed with `--local`
+    #[clap(required_unless_present = "local")]
+    pub(crate) dependency: Option<String>,
+
+    /// Name of the dependency's package
+    ///
+    /// Required for git dependencies, for local ones defaults to the name in its Move.toml
+    #[clap(long)]
+    pub(crate) name: Option<String>,
+
+    /// Git revision or branch
+    #[clap(long)]
+    pub(crate) rev: Option<String>,
+
+    /// Directory of the package in the git repository
+    #[clap(long)]
+    pub(crate) subdir: Option<String>,
+
+    /// Local directory of the dependency, relative to the package
+    #[clap(long, conflicts_with_all = &["dependency", "rev", "subdir"])]
+    pub(crate) local: Option<String>,
+
+    /// Skip building the package to check the dependency
+    #[clap(long)]
+    pub(crate) skip_build: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<String> for AddDependency {
+    fn command_name(&self) -> &'static str {
+        "AddDependency"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        let package_dir = self.move_options.get_package_path()?;
+        let (original, mut manifest) = load_manifest(&package_dir)?;
+
+        let mut dependency = InlineTable::new();
+        let name = if let Some(ref local) = self.local {
+            dependency.insert("local", Value::from(local.as_str()));
+            match self.name {
+                Some(ref name) => name.clone(),
+                None => local_package_name(&package_dir.join(local))?,
+            }
+        } else {
+            let requested = self.dependency.as_deref().expect("Required by clap");
+            if let Some((name, subdir)) = known_package(requested) {
+                dependency = framework_dependency(&manifest, subdir, self.rev.clone());
+                name.to_string()
+            } else if requested.contains("://") || requested.starts_with("git@") {
+                let name = self.name.clone().ok_or_else(|| {
+                    CliError::CommandArgumentError(
+                        "--name is required for git dependencies".to_string(),
+                    )
+                })?;
+                dependency.insert("git", Value::from(requested));
+                dependency.insert(
+                    "rev",
+                    Value::from(self.rev.as_deref().unwrap_or(DEFAULT_BRANCH)),
+                );
+                if let Some(ref subdir) = self.subdir {
+                    dependency.insert("subdir", Value::from(subdir.as_str()));
+                }
+                name
+            } else {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Unknown package {}, must be one of [{}], a git URL, or --local",
+                    requested,
+                    KNOWN_PACKAGES
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+        };
+
+        let dependencies = dependencies_table(&mut manifest)?;
+        if dependencies.contains_key(&name) {
+            prompt_yes_with_override(
+                &format!(
+                    "Dependency {} already exists, do you want to replace it?",
+                    name
+                ),
+                self.prompt_options,
+            )?;
+        }
+        dependencies.insert(&name, Item::Value(Value::InlineTable(dependency)));
+        save_manifest(&package_dir, &manifest.to_string())?;
+
+        if !self.skip_build {
+            let build_options =
+                IncludedArtifacts::None.build_options(self.move_options.named_addresses());
+            if let Err(err) = BuiltPackage::build(package_dir.clone(), build_options) {
+                save_manifest(&package_dir, &original)?;
+                return Err(CliError::MoveCompilationError(format!(
+                    "Failed to build with dependency {}, Move.toml was restored: {:#}",
+                    name, err
+                )));
+            }
+        }
+        Ok(format!("Added dependency {}", name))
+    }
+}
+
+/// Remove a dependency from a package's Move.toml
+///
+/// Reports the source files that still use named addresses declared by the removed package,
+/// which won't compile until they're changed.  With `--prune-addresses`, the removed
+/// package's named addresses that no source file uses are removed from `[addresses]`.
+///
+/// The removed package's addresses are read from its Move.toml, so git dependencies have to
+/// have been downloaded by a previous build.
+#[derive(Parser)]
+pub struct RemoveDependency {
+    /// Name of the dependency to remove
+    pub(crate) name: String,
+
+    /// Remove the removed package's named addresses from `[addresses]` if they're unused
+    #[clap(long)]
+    pub(crate) prune_addresses: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+/// Summary of a removed dependency
+#[derive(Debug, Serialize)]
+pub struct RemoveDependencySummary {
+    pub removed: String,
+    /// Source files, with the removed package's named addresses they still use
+    pub still_referenced: BTreeMap<String, BTreeSet<String>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pruned_addresses: Vec<String>,
+}
+
+#[async_trait]
+impl CliCommand<RemoveDependencySummary> for RemoveDependency {
+    fn command_name(&self) -> &'static str {
+        "RemoveDependency"
+    }
+
+    async fn execute(self) -> CliTypedResult<RemoveDependencySummary> {
+        let package_dir = self.move_options.get_package_path()?;
+        let (_, mut manifest) = load_manifest(&package_dir)?;
+        let dependencies = dependencies_table(&mut manifest)?;
+        let removed = dependencies.remove(&self.name).ok_or_else(|| {
+            CliError::CommandArgumentError(format!("No dependency named {}", self.name))
+        })?;
+
+        // Addresses still declared by the remaining dependencies are still available
+        let mut removed_addresses = match dependency_addresses(&package_dir, &removed) {
+            Some(addresses) => addresses,
+            None => {
+                eprintln!(
+                    "Unable to read the Move.toml of {}, so its named addresses can't be checked.  Build the package first to download it",
+                    self.name
+                );
+                BTreeSet::new()
+            }
+        };
+        for (_, dependency) in dependencies.iter() {
+            for address in dependency_addresses(&package_dir, dependency).unwrap_or_default() {
+                removed_addresses.remove(&address);
+            }
+        }
+
+        let still_referenced = find_address_references(&package_dir, &removed_addresses)?;
+        let mut pruned_addresses = Vec::new();
+        if self.prune_addresses {
+            if let Some(addresses) = manifest
+                .get_mut("addresses")
+                .and_then(|addresses| addresses.as_table_mut())
+            {
+                for address in &removed_addresses {
+                    let used = still_referenced
+                        .values()
+                        .any(|referenced| referenced.contains(address));
+                    if !used && addresses.remove(address).is_some() {
+                        pruned_addresses.push(address.clone());
+                    }
+                }
+            }
+        }
+        save_manifest(&package_dir, &manifest.to_string())?;
+
+        Ok(RemoveDependencySummary {
+            removed: self.name,
+            still_referenced,
+            pruned_addresses,
+        })
+    }
+}
+
+/// Named addresses declared in a dependency's Move.toml, if it can be found locally
+fn dependency_addresses(package_dir: &Path, dependency: &Item) -> Option<BTreeSet<String>> {
+    let (_, manifest) = load_manifest(&dependency_dir(package_dir, dependency)?).ok()?;
+    Some(
+        manifest
+            .get("addresses")
+            .and_then(|addresses| addresses.as_table_like())
+            .map(|addresses| addresses.iter().map(|(name, _)| name.to_string()).collect())
+            .unwrap_or_default(),
+    )
+}
+
+/// Directory of a dependency, for git dependencies where the package system downloads it to
+pub(crate) fn dependency_dir(package_dir: &Path, dependency: &Item) -> Option<PathBuf> {
+    if let Some(local) = dependency.get("local").and_then(|local| local.as_str()) {
+        return Some(package_dir.join(local));
+    }
+    let git = dependency.get("git")?.as_str()?;
+    let rev = dependency.get("rev")?.as_str()?;
+    let dir = git_cache_dir(git, rev);
+    Some(
+        match dependency.get("subdir").and_then(|subdir| subdir.as_str()) {
+            Some(subdir) => dir.join(subdir),
+            None => dir,
+        },
+    )
+}
+
+/// Directory the package system downloads a git dependency to
+pub(crate) fn git_cache_dir(git: &str, rev: &str) -> PathBuf {
+    PathBuf::from(&*MOVE_HOME).join(format!(
+        "{}_{}",
+        regex::Regex::new(r"/|:|\.|@")
+            .unwrap()
+            .replace_all(git, "_"),
+        rev.replace('/', "__")
+    ))
+}
+
+/// Finds the package's Move files using any of `addresses`, e.g. `name::module` or `@name`
+fn find_address_references(
+    package_dir: &Path,
+    addresses: &BTreeSet<String>,
+) -> CliTypedResult<BTreeMap<String, BTreeSet<String>>> {
+    let mut references = BTreeMap::new();
+    if addresses.is_empty() {
+        return Ok(references);
+    }
+    let patterns: Vec<(&String, regex::Regex)> = addresses
+        .iter()
+        .map(|address| {
+            let escaped = regex::escape(address);
+            let pattern = format!(r"\b{}\s*::|@{}\b", escaped, escaped);
+            (address, regex::Regex::new(&pattern).unwrap())
+        })
+        .collect();
+
+    for layout in [
+        SourcePackageLayout::Sources,
+        SourcePackageLayout::Scripts,
+        SourcePackageLayout::Tests,
+    ] {
+        let dir = package_dir.join(layout.path());
+        for entry in walkdir::WalkDir::new(dir).into_iter().flatten() {
+            let path = entry.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "move")
+            {
+                continue;
+            }
+            let source = String::from_utf8(read_from_file(path)?)
+                .map_err(|err| CliError::UnableToParse("Move source", err.to_string()))?;
+            let used: BTreeSet<String> = patterns
+                .iter()
+                .filter(|(_, pattern)| pattern.is_match(&source))
+                .map(|(address, _)| (*address).clone())
+                .collect();
+            if !used.is_empty() {
+                let relative = path.strip_prefix(package_dir).unwrap_or(path);
+                references.insert(relative.display().to_string(), used);
+            }
+        }
+    }
+    Ok(references)
+}
+
+/// Check git dependencies for newer commits
+///
+/// Each git dependency's remote is queried with `git ls-remote`.  A dependency is outdated if:
+/// - its rev is a commit, and the remote's branch has moved past it
+/// - its rev is a branch, and the downloaded copy in the package cache is behind the branch
+/// - it's an aptos-core dependency, and its rev isn't the release branch of `--network`
+///
+/// With `--update`, commit revs are moved to the latest commit, aptos-core revs to the
+/// network's release branch, and stale downloads are removed from the cache so the next
+/// build fetches them again.
+#[derive(Parser)]
+pub struct OutdatedDependencies {
+    /// Network whose release branch aptos-core dependencies should track [devnet, testnet, mainnet]
+    ///
+    /// Defaults to the network of the profile
+    #[clap(long)]
+    pub(crate) network: Option<Network>,
+
+    /// Branch to compare commit revs of other dependencies with
+    ///
+    /// Defaults to the remote's default branch
+    #[clap(long)]
+    pub(crate) branch: Option<String>,
+
+    /// Update outdated dependencies
+    #[clap(long)]
+    pub(crate) update: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+/// Status of a git dependency
+#[derive(Debug, Serialize)]
+pub struct DependencyStatus {
+    pub git: String,
+    pub rev: String,
+    /// Ref the dependency is compared with
+    pub target: String,
+    /// Latest commit of the target
+    pub latest_commit: String,
+    /// Commit of the downloaded copy in the package cache, if there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_commit: Option<String>,
+    pub outdated: bool,
+    /// Rev the dependency was updated to with `--update`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_rev: Option<String>,
+}
+
+#[async_trait]
+impl CliCommand<BTreeMap<String, DependencyStatus>> for OutdatedDependencies {
+    fn command_name(&self) -> &'static str {
+        "OutdatedDependencies"
+    }
+
+    async fn execute(self) -> CliTypedResult<BTreeMap<String, DependencyStatus>> {
+        let package_dir = self.move_options.get_package_path()?;
+        let (_, mut manifest) = load_manifest(&package_dir)?;
+        let release_branch = match self.network.or_else(|| {
+            self.profile_options
+                .profile()
+                .ok()
+                .and_then(|profile| profile.network)
+        }) {
+            Some(network @ (Network::Devnet | Network::Testnet | Network::Mainnet)) => {
+                Some(network.to_string())
+            }
+            _ => None,
+        };
+
+        let dependencies = dependencies_table(&mut manifest)?;
+        let names: Vec<String> = dependencies
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        let mut statuses = BTreeMap::new();
+        for name in names {
+            let dependency = &mut dependencies[&name];
+            let (git, rev) = match (
+                dependency.get("git").and_then(|git| git.as_str()),
+                dependency.get("rev").and_then(|rev| rev.as_str()),
+            ) {
+                (Some(git), Some(rev)) => (git.to_string(), rev.to_string()),
+                _ => continue,
+            };
+            let remote_refs = git_ls_remote(&git)?;
+            let is_commit = is_commit_hash(&rev);
+
+            let target = if git == APTOS_GIT_PATH && release_branch.is_some() {
+                release_branch.clone().unwrap()
+            } else if !is_commit {
+                rev.clone()
+            } else if let Some(ref branch) = self.branch {
+                branch.clone()
+            } else {
+                "HEAD".to_string()
+            };
+            let latest_commit = lookup_ref(&remote_refs, &target).ok_or_else(|| {
+                CliError::UnexpectedError(format!("{} has no ref {}", git, target))
+            })?;
+            let cached_commit = git_head(&git_cache_dir(&git, &rev));
+
+            let (outdated, new_rev) = if is_commit {
+                let outdated = !latest_commit.starts_with(&rev);
+                let new_rev = if target == rev {
+                    None
+                } else {
+                    Some(latest_commit.clone())
+                };
+                (outdated, new_rev)
+            } else if target != rev {
+                (true, Some(target.clone()))
+            } else {
+                let stale_cache = cached_commit
+                    .as_ref()
+                    .map_or(false, |cached| cached != &latest_commit);
+                (stale_cache, None)
+            };
+
+            let mut updated_rev = None;
+            if self.update && outdated {
+                if let Some(new_rev) = new_rev {
+                    dependency["rev"] = toml_edit::value(new_rev.as_str());
+                    updated_rev = Some(new_rev);
+                } else {
+                    // Floating branch with a stale download, fetch it again on the next build
+                    std::fs::remove_dir_all(git_cache_dir(&git, &rev))
+                        .map_err(|err| CliError::IO("package cache".to_string(), err))?;
+                    updated_rev = Some(rev.clone());
+                }
+            }
+
+            statuses.insert(
+                name,
+                DependencyStatus {
+                    git,
+                    rev,
+                    target,
+                    latest_commit,
+                    cached_commit,
+                    outdated,
+                    updated_rev,
+                },
+            );
+        }
+
+        if self.update {
+            save_manifest(&package_dir, &manifest.to_string())?;
+        }
+        Ok(statuses)
+    }
+}
+
+/// Lists the refs of a git remote, from ref name to commit
+pub(crate) fn git_ls_remote(git: &str) -> CliTypedResult<BTreeMap<String, String>> {
+    let output = run_git(None, &["ls-remote", git])?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let (commit, name) = line.split_once('\t')?;
+            Some((name.to_string(), commit.to_string()))
+        })
+        .collect())
+}
+
+/// Finds the commit of a branch, tag, or `HEAD` in the output of `git ls-remote`
+pub(crate) fn lookup_ref(refs: &BTreeMap<String, String>, name: &str) -> Option<String> {
+    [
+        name.to_string(),
+        format!("refs/heads/{}", name),
+        // Annotated tags point to the tag object, the peeled ref points to the commit
+        format!("refs/tags/{}^{{}}", name),
+        format!("refs/tags/{}", name),
+    ]
+    .iter()
+    .find_map(|candidate| refs.get(candidate).cloned())
+}
+
+/// Commit checked out in a local git repository, if it is one
+pub(crate) fn git_head(dir: &Path) -> Option<String> {
+    run_git(Some(dir), &["rev-parse", "HEAD"])
+        .ok()
+        .map(|commit| commit.trim().to_string())
+}
+
+pub(crate) fn is_commit_hash(rev: &str) -> bool {
+    rev.len() >= 7 && rev.len() <= 40 && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn run_git(dir: Option<&Path>, args: &[&str]) -> CliTypedResult<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let output = command
+        .args(args)
+        .output()
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to run git: {}", err)))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(CliError::UnexpectedError(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+/// Looks up a framework package by name, ignoring case
+pub(crate) fn known_package(name: &str) -> Option<(&'static str, &'static str)> {
+    KNOWN_PACKAGES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// Adds a framework package to the manifest's dependencies, unless a dependency already
+/// brings it in
+///
+/// Returns whether the dependency was added
+pub(crate) fn ensure_framework_dependency(package_dir: &Path, name: &str) -> CliTypedResult<bool> {
+    let (name, subdir) = known_package(name)
+        .ok_or_else(|| CliError::UnexpectedError(format!("{} is not a framework package", name)))?;
+    let (_, mut manifest) = load_manifest(package_dir)?;
+    let dependencies = dependencies_table(&mut manifest)?;
+    if KNOWN_PACKAGES
+        .iter()
+        .any(|(known, _)| dependencies.contains_key(known) && includes_package(known, name))
+    {
+        return Ok(false);
+    }
+    let dependency = framework_dependency(&manifest, subdir, None);
+    dependencies_table(&mut manifest)?.insert(name, Item::Value(Value::InlineTable(dependency)));
+    save_manifest(package_dir, &manifest.to_string())?;
+    Ok(true)
+}
+
+/// Whether a framework package is, or depends on, another
+fn includes_package(package: &str, other: &str) -> bool {
+    package == other
+        || match package {
+            "AptosToken" => includes_package("AptosFramework", other),
+            "AptosFramework" => includes_package("AptosStdlib", other),
+            "AptosStdlib" => other == "MoveStdlib",
+            _ => false,
+        }
+}
+
+/// A dependency on a framework package, at the revision the manifest already uses by default
+pub(crate) fn framework_dependency(
+    manifest: &Document,
+    subdir: &str,
+    rev: Option<String>,
+) -> InlineTable {
+    let rev = rev
+        .or_else(|| framework_rev(manifest))
+        .unwrap_or_else(|| DEFAULT_BRANCH.to_string());
+    let mut dependency = InlineTable::new();
+    dependency.insert("git", Value::from(APTOS_GIT_PATH));
+    dependency.insert("rev", Value::from(rev));
+    dependency.insert("subdir", Value::from(subdir));
+    dependency
+}
+
+/// Revision of the framework packages the manifest already depends on
+fn framework_rev(manifest: &Document) -> Option<String> {
+    let dependencies = manifest.get(DEPENDENCIES)?;
+    KNOWN_PACKAGES.iter().find_map(|(name, _)| {
+        let dependency = dependencies.get(*name)?;
+        if dependency.get("git")?.as_str()? != APTOS_GIT_PATH {
+            return None;
+        }
+        dependency.get("rev")?.as_str().map(|rev| rev.to_string())
+    })
+}
+
+/// Reads the package name from a local package's Move.toml
+fn local_package_name(dir: &Path) -> CliTypedResult<String> {
+    let (_, manifest) = load_manifest(dir)?;
+    manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .map(|name| name.to_string())
+        .ok_or_else(|| {
+            CliError::UnableToParse(
+                "Move.toml",
+                format!("{} has no package name", dir.display()),
+            )
+        })
+}
+
+fn manifest_path(package_dir: &Path) -> PathBuf {
+    package_dir.join(SourcePackageLayout::Manifest.path())
+}
+
+/// Loads Move.toml, returning its original text as well
+pub(crate) fn load_manifest(package_dir: &Path) -> CliTypedResult<(String, Document)> {
+    let text = String::from_utf8(read_from_file(&manifest_path(package_dir))?)
+        .map_err(|err| CliError::UnableToParse("Move.toml", err.to_string()))?;
+    let manifest = text
+        .parse::<Document>()
+        .map_err(|err| CliError::UnableToParse("Move.toml", err.to_string()))?;
+    Ok((text, manifest))
+}
+
+pub(crate) fn save_manifest(package_dir: &Path, text: &str) -> CliTypedResult<()> {
+    write_to_file(
+        &manifest_path(package_dir),
+        SourcePackageLayout::Manifest.location_str(),
+        text.as_bytes(),
+    )
+}
+
+/// Copies the package without its build output, with local dependencies made absolute
+pub(crate) fn copy_package(from: &Path, to: &Path) -> CliTypedResult<()> {
+    for entry in walkdir::WalkDir::new(from)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            !(entry.depth() == 1 && name == "build") && !name.starts_with('.')
+        })
+        .flatten()
+    {
+        let relative = entry
+            .path()
+            .strip_prefix(from)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            create_dir_if_not_exist(&target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)
+                .map_err(|err| CliError::IO(target.display().to_string(), err))?;
+        }
+    }
+
+    let (_, mut manifest) = load_manifest(from)?;
+    make_local_dependencies_absolute(&mut manifest, from);
+    save_manifest(to, &manifest.to_string())
+}
+
+/// Makes the paths of local dependencies absolute, for a manifest moved out of the package
+pub(crate) fn make_local_dependencies_absolute(manifest: &mut Document, package_dir: &Path) {
+    for table in ["dependencies", "dev-dependencies"] {
+        let dependencies = match manifest
+            .get_mut(table)
+            .and_then(|table| table.as_table_like_mut())
+        {
+            Some(dependencies) => dependencies,
+            None => continue,
+        };
+        for (_, dependency) in dependencies.iter_mut() {
+            let local = match dependency.get("local").and_then(|local| local.as_str()) {
+                Some(local) => package_dir.join(local),
+                None => continue,
+            };
+            dependency["local"] = toml_edit::value(local.display().to_string());
+        }
+    }
+}
+
+/// The `[dependencies]` table, created if it doesn't exist
+fn dependencies_table(manifest: &mut Document) -> CliTypedResult<&mut Table> {
+    manifest
+        .as_table_mut()
+        .entry(DEPENDENCIES)
+        .or_insert(Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| {
+            CliError::UnableToParse("Move.toml", "[dependencies] is not a table".to_string())
+        })
+}
@@ -0,0 +1,430 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Simulating a transaction, and describing its changes against the current chain state
+
+use crate::{
+    account::resources::Abis,
+    common::{
+        types::{CliError, CliTypedResult, TransactionOptions, TransactionSummary},
+        utils::read_from_file,
+    },
+    move_tool::audit::Sources,
+};
+use aptos_rest_client::{
+    aptos_api_types::{
+        MoveModule, MoveModuleId, MoveStructTag, MoveType, Transaction, WriteSetChange,
+    },
+    Client,
+};
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{AbortLocation, ExecutionStatus, TransactionPayload},
+};
+use framework::BuiltPackage;
+use move_binary_format::{
+    access::ModuleAccess, file_format::FunctionDefinitionIndex, CompiledModule,
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
+use move_core_types::language_storage::ModuleId;
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Simulates a transaction and prints what it would change, without submitting it
+///
+/// Modules are new, changed, or unchanged from what's published, resources are diffed field by
+/// field, and table items are counted.  Events and new resources are decoded with the ABIs of
+/// their modules.  A failure in one of the package's modules is placed in its source.
+pub(crate) async fn dry_run(
+    txn_options: &TransactionOptions,
+    payload: TransactionPayload,
+    sources: &PackageSources,
+) -> CliTypedResult<TransactionSummary> {
+    let client = txn_options
+        .rest_options
+        .client(&txn_options.profile_options)?;
+    let signed_transaction = txn_options
+        .simulation_transaction(
+            &client,
+            payload,
+            txn_options.gas_options.gas_unit_price,
+            None,
+        )
+        .await?;
+    let transaction = client
+        .simulate(&signed_transaction)
+        .await?
+        .into_inner()
+        .into_iter()
+        .next()
+        .ok_or_else(|| CliError::UnexpectedError("The simulation had no output".to_string()))?;
+
+    let mut abis = Abis::default();
+    for module in sources.modules.values() {
+        abis.insert(MoveModule::from(module.module.clone()));
+    }
+    let types = transaction
+        .events
+        .iter()
+        .filter_map(|event| match &event.typ {
+            MoveType::Struct(tag) => Some(tag.clone()),
+            _ => None,
+        })
+        .chain(
+            transaction
+                .info
+                .changes
+                .iter()
+                .filter_map(|change| match change {
+                    WriteSetChange::WriteResource(write) => Some(write.data.typ.clone()),
+                    _ => None,
+                }),
+        );
+    // Decoding is best effort, values of types without an ABI are shown as the API returns them
+    for tag in types {
+        let _ = abis.load(&client, vec![tag]).await;
+    }
+
+    let mut state = ChainState::default();
+    let mut modules = vec![];
+    let mut resources = vec![];
+    let (mut table_writes, mut table_deletes) = (0, 0);
+    for change in &transaction.info.changes {
+        match change {
+            WriteSetChange::WriteModule(write) => {
+                let bytecode = write.data.bytecode.inner();
+                let module = CompiledModule::deserialize(bytecode)
+                    .map_err(|err| CliError::UnableToParse("module", err.to_string()))?;
+                let id = MoveModuleId::from(module.self_id());
+                let line = match state.module(&client, &id).await? {
+                    None => format!("  + {} ({} bytes)", id, bytecode.len()),
+                    Some(old) if old.as_slice() == bytecode => format!("    {} unchanged", id),
+                    Some(old) => format!("  ~ {} ({} -> {} bytes)", id, old.len(), bytecode.len()),
+                };
+                modules.push(line);
+            }
+            WriteSetChange::DeleteModule(delete) => {
+                modules.push(format!("  - {}", delete.module));
+            }
+            WriteSetChange::WriteResource(write) => {
+                let new = serde_json::to_value(&write.data.data)
+                    .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                let address = AccountAddress::from(write.address);
+                match state.resource(&client, address, &write.data.typ).await? {
+                    None => {
+                        resources.push(format!("  + {} at {}", write.data.typ, write.address));
+                        let decoded = abis.decode(&MoveType::Struct(write.data.typ.clone()), &new);
+                        let decoded = serde_json::to_value(&decoded)
+                            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                        resources.extend(
+                            flatten(&decoded)
+                                .into_iter()
+                                .filter(|(path, _)| !path.ends_with("@type"))
+                                .map(|(path, value)| format!("      {}: {}", path, value)),
+                        );
+                    }
+                    Some(old) => {
+                        let diff = diff_json(&old, &new);
+                        if diff.is_empty() {
+                            continue;
+                        }
+                        resources.push(format!("  ~ {} at {}", write.data.typ, write.address));
+                        resources.extend(diff.into_iter().map(|line| format!("      {}", line)));
+                    }
+                }
+            }
+            WriteSetChange::DeleteResource(delete) => {
+                resources.push(format!("  - {} at {}", delete.resource, delete.address));
+            }
+            WriteSetChange::WriteTableItem(_) => table_writes += 1,
+            WriteSetChange::DeleteTableItem(_) => table_deletes += 1,
+        }
+    }
+
+    eprintln!("Modules:");
+    for line in &modules {
+        eprintln!("{}", line);
+    }
+    eprintln!("Resources:");
+    for line in &resources {
+        eprintln!("{}", line);
+    }
+    eprintln!(
+        "Table items: {} written, {} deleted",
+        table_writes, table_deletes
+    );
+    eprintln!("Events:");
+    for event in &transaction.events {
+        eprintln!(
+            "  {} {}",
+            event.typ,
+            serde_json::to_string(&abis.decode(&event.typ, &event.data)).unwrap_or_default()
+        );
+    }
+    eprintln!(
+        "Gas used: {} at {} Octas per unit, status: {}",
+        transaction.info.gas_used.0,
+        transaction.request.gas_unit_price.0,
+        transaction.info.vm_status
+    );
+    // The JSON output only explains the status, so it's simulated again for where it failed
+    if !transaction.info.success && !sources.modules.is_empty() {
+        let status = client
+            .simulate_bcs(&signed_transaction)
+            .await?
+            .into_inner()
+            .info
+            .status()
+            .clone();
+        for line in sources.locate(&status)? {
+            eprintln!("  {}", line);
+        }
+    }
+    eprintln!("Dry run, nothing was submitted");
+
+    Ok(TransactionSummary::from(Transaction::UserTransaction(
+        Box::new(transaction),
+    )))
+}
+
+/// The modules of a locally built package, with their source maps and sources
+#[derive(Default)]
+pub(crate) struct PackageSources {
+    modules: BTreeMap<ModuleId, LocalModule>,
+}
+
+struct LocalModule {
+    module: CompiledModule,
+    source_map: SourceMap,
+    file: PathBuf,
+}
+
+impl PackageSources {
+    pub(crate) fn new(package: &BuiltPackage) -> Self {
+        let modules = package
+            .package()
+            .root_modules()
+            .filter_map(|unit| match &unit.unit {
+                CompiledUnit::Module(NamedCompiledModule {
+                    module, source_map, ..
+                }) => Some((
+                    module.self_id(),
+                    LocalModule {
+                        module: module.clone(),
+                        source_map: source_map.clone(),
+                        file: PathBuf::from(&unit.source_path),
+                    },
+                )),
+                CompiledUnit::Script(_) => None,
+            })
+            .collect();
+        PackageSources { modules }
+    }
+
+    /// Where a failure in one of the modules is in their source, as `file:line:column` lines
+    fn locate(&self, status: &ExecutionStatus) -> CliTypedResult<Vec<String>> {
+        match status {
+            ExecutionStatus::ExecutionFailure {
+                location: AbortLocation::Module(id),
+                function,
+                code_offset,
+            } => {
+                let local = match self.modules.get(id) {
+                    Some(local) => local,
+                    None => return Ok(vec![]),
+                };
+                let index = FunctionDefinitionIndex(*function);
+                let name = local
+                    .module
+                    .function_defs()
+                    .get(*function as usize)
+                    .map(|definition| {
+                        let handle = local.module.function_handle_at(definition.function);
+                        local.module.identifier_at(handle.name).to_string()
+                    })
+                    .unwrap_or_else(|| format!("<#{} function>", function));
+                let offset = match local.source_map.get_function_source_map(index) {
+                    Ok(map) => map
+                        .get_code_location(*code_offset)
+                        .unwrap_or(map.definition_location)
+                        .start(),
+                    Err(_) => return Ok(vec![]),
+                };
+                let (line, column) = Sources::default().line_and_column(&local.file, offset)?;
+                Ok(vec![format!(
+                    "{}:{}:{}: failed in {}::{}",
+                    local.file.display(),
+                    line,
+                    column,
+                    id,
+                    name
+                )])
+            }
+            ExecutionStatus::MoveAbort {
+                location: AbortLocation::Module(id),
+                code,
+                ..
+            } => {
+                let local = match self.modules.get(id) {
+                    Some(local) => local,
+                    None => return Ok(vec![]),
+                };
+                // Aborts have no code offset, so these are where the error's constant is raised
+                let source = String::from_utf8(read_from_file(&local.file)?).map_err(|err| {
+                    CliError::UnableToReadFile(local.file.display().to_string(), err.to_string())
+                })?;
+                Ok(abort_sites(&source, *code)
+                    .into_iter()
+                    .map(|(line, text)| {
+                        format!(
+                            "{}:{}: may abort here: {}",
+                            local.file.display(),
+                            line,
+                            text
+                        )
+                    })
+                    .collect())
+            }
+            _ => Ok(vec![]),
+        }
+    }
+}
+
+/// Lines using a constant whose value is the abort code, or its reason as `std::error` codes
+/// keep it in their lowest 16 bits
+pub(crate) fn abort_sites(source: &str, code: u64) -> Vec<(usize, String)> {
+    let names: Vec<&str> = source
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.trim().strip_prefix("const ")?.split_once('=')?;
+            let name = name.split(':').next()?.trim();
+            let value = value.trim().trim_end_matches(';').trim();
+            let value = match value.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+                None => value.parse::<u64>().ok()?,
+            };
+            (value == code || value == code & 0xffff).then_some(name)
+        })
+        .collect();
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().starts_with("const "))
+        .filter(|(_, line)| names.iter().any(|name| contains_word(line, name)))
+        .map(|(index, line)| (index + 1, line.trim().to_string()))
+        .collect()
+}
+
+/// Whether the name appears in the line, not as part of a longer identifier
+fn contains_word(line: &str, name: &str) -> bool {
+    let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    line.match_indices(name).any(|(index, _)| {
+        !line[..index].ends_with(is_identifier)
+            && !line[index + name.len()..].starts_with(is_identifier)
+    })
+}
+
+/// Published modules and resources, fetched an account at a time
+#[derive(Default)]
+struct ChainState {
+    modules: BTreeMap<AccountAddress, BTreeMap<MoveModuleId, Vec<u8>>>,
+    resources: BTreeMap<AccountAddress, Vec<(MoveStructTag, serde_json::Value)>>,
+}
+
+impl ChainState {
+    async fn module(
+        &mut self,
+        client: &Client,
+        id: &MoveModuleId,
+    ) -> CliTypedResult<Option<Vec<u8>>> {
+        let address = AccountAddress::from(id.address);
+        if !self.modules.contains_key(&address) {
+            // Accounts that don't exist yet have no modules
+            let modules = client
+                .get_account_modules_bcs(address)
+                .await
+                .map(|response| response.into_inner())
+                .unwrap_or_default();
+            self.modules.insert(address, modules);
+        }
+        Ok(self.modules[&address].get(id).cloned())
+    }
+
+    async fn resource(
+        &mut self,
+        client: &Client,
+        address: AccountAddress,
+        typ: &MoveStructTag,
+    ) -> CliTypedResult<Option<serde_json::Value>> {
+        if !self.resources.contains_key(&address) {
+            let resources = client
+                .get_account_resources(address)
+                .await
+                .map(|response| response.into_inner())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|resource| (MoveStructTag::from(resource.resource_type), resource.data))
+                .collect();
+            self.resources.insert(address, resources);
+        }
+        Ok(self.resources[&address]
+            .iter()
+            .find(|(other, _)| other == typ)
+            .map(|(_, data)| data.clone()))
+    }
+}
+
+/// Lines for the values that differ, by their path in the JSON
+fn diff_json(old: &serde_json::Value, new: &serde_json::Value) -> Vec<String> {
+    let (old, new) = (flatten(old), flatten(new));
+    let mut lines = vec![];
+    for (path, old_value) in &old {
+        match new.get(path) {
+            Some(new_value) if new_value == old_value => {}
+            Some(new_value) => lines.push(format!("{}: {} -> {}", path, old_value, new_value)),
+            None => lines.push(format!("- {}: {}", path, old_value)),
+        }
+    }
+    for (path, new_value) in &new {
+        if !old.contains_key(path) {
+            lines.push(format!("+ {}: {}", path, new_value));
+        }
+    }
+    lines
+}
+
+/// The scalar values of a JSON value, by paths like `packages[0].name`
+fn flatten(value: &serde_json::Value) -> BTreeMap<String, String> {
+    fn walk(value: &serde_json::Value, path: String, out: &mut BTreeMap<String, String>) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                for (name, field) in fields {
+                    let path = if path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{}.{}", path, name)
+                    };
+                    walk(field, path, out);
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    walk(item, format!("{}[{}]", path, index), out);
+                }
+            }
+            scalar => {
+                // Long values, like source code, are summarized by their length
+                let text = scalar.to_string();
+                let text = if text.len() > 80 {
+                    format!("<{} bytes>", text.len())
+                } else {
+                    text
+                };
+                out.insert(path, text);
+            }
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(value, String::new(), &mut out);
+    out
+}
@@ -0,0 +1,588 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fuzzing of entry functions in a local executor
+//!
+//! Arguments are generated from the function's signature, biased towards edge cases, and each
+//! call runs as a transaction against a genesis with the package's modules added.  Calls that
+//! fail are shrunk to the smallest arguments failing the same way, and written out as
+//! reproducers.
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, MovePackageDir},
+        utils::{create_dir_if_not_exist, write_to_file},
+    },
+    move_tool::{
+        abi::PackageAbi,
+        local_executor::{entry_function_transaction, package_executor, LocalExecutor},
+        IncludedArtifacts,
+    },
+};
+use aptos_rest_client::aptos_api_types::{Address, MoveFunction, MoveType};
+use aptos_sdk::types::LocalAccount;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{EntryFunction, ExecutionStatus, TransactionStatus},
+};
+use async_trait::async_trait;
+use clap::Parser;
+use framework::{BuildOptions, BuiltPackage};
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{ModuleId, TypeTag},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Serialize, Serializer};
+use std::{collections::BTreeSet, convert::TryFrom, fmt::Write, path::PathBuf};
+
+/// Most calls a failure is shrunk with
+const MAX_SHRINK_RUNS: usize = 2000;
+
+/// Fuzz an entry function of a package with random arguments
+///
+/// The package's modules are added to a local executor with the current framework, and the
+/// function is called with arguments generated from its signature: integers around their
+/// bounds, addresses like the sender and the framework's, valid UTF-8 strings, and vectors up
+/// to `--max-length`.  Signers are the sender.  Calls failing other than by an abort, e.g.
+/// with an arithmetic error or by running out of gas, are failures, as are aborts with codes
+/// not in `--expected-aborts` if it's given.  Each distinct failure is shrunk to minimal
+/// arguments and written to `--output-dir` as a reproducer
+#[derive(Parser)]
+pub struct FuzzFunction {
+    /// Function to fuzz, as `<module>::<function>` of the package
+    pub(crate) function: String,
+
+    /// TypeTag arguments separated by spaces, for generic functions
+    ///
+    /// Example: `u8 u64 u128 bool address vector signer`
+    #[clap(long, multiple_values = true)]
+    pub(crate) type_args: Vec<MoveType>,
+
+    /// Number of calls to make
+    #[clap(long, default_value_t = 1000)]
+    pub(crate) runs: usize,
+
+    /// Seed of the random arguments, to repeat a run
+    #[clap(long)]
+    pub(crate) seed: Option<u64>,
+
+    /// Abort codes the function is expected to abort with, other aborts are failures
+    ///
+    /// Without it, any abort is expected
+    #[clap(long, multiple_values = true)]
+    pub(crate) expected_aborts: Vec<u64>,
+
+    /// Longest vector or string to generate
+    #[clap(long, default_value_t = 16)]
+    pub(crate) max_length: usize,
+
+    /// Directory to write reproducers of failures to
+    ///
+    /// Defaults to `<package_dir>/fuzz`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_dir: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[derive(Serialize)]
+pub struct FuzzSummary {
+    pub seed: u64,
+    pub runs: usize,
+    pub failed_runs: usize,
+    /// A reproducer for each distinct failure
+    pub reproducers: Vec<PathBuf>,
+}
+
+#[async_trait]
+impl CliCommand<FuzzSummary> for FuzzFunction {
+    fn command_name(&self) -> &'static str {
+        "FuzzFunction"
+    }
+
+    async fn execute(self) -> CliTypedResult<FuzzSummary> {
+        let (module_name, function_name) = self.function.split_once("::").ok_or_else(|| {
+            CliError::CommandArgumentError(
+                "Function must be of the form <module>::<function>".to_string(),
+            )
+        })?;
+        let package_path = self.move_options.get_package_path()?;
+        let build_options = BuildOptions {
+            install_dir: self.move_options.output_dir.clone(),
+            ..IncludedArtifacts::None.build_options(self.move_options.named_addresses())
+        };
+        let pack = BuiltPackage::build(package_path.clone(), build_options)
+            .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
+
+        let abi = PackageAbi::new(&pack);
+        let (module, function) = abi
+            .modules
+            .iter()
+            .filter(|module| module.name.as_str() == module_name)
+            .find_map(|module| {
+                module
+                    .entry_functions
+                    .iter()
+                    .find(|function| function.name.as_str() == function_name)
+                    .map(|function| (module, function))
+            })
+            .ok_or_else(|| {
+                CliError::CommandArgumentError(format!(
+                    "No entry function {} in package {}",
+                    self.function, abi.package
+                ))
+            })?;
+        if function.generic_type_params.len() != self.type_args.len() {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} takes {} type arguments",
+                self.function,
+                function.generic_type_params.len()
+            )));
+        }
+        let type_args = self
+            .type_args
+            .iter()
+            .cloned()
+            .map(|typ| {
+                TypeTag::try_from(typ)
+                    .map_err(|err| CliError::UnableToParse("--type-args", err.to_string()))
+            })
+            .collect::<CliTypedResult<Vec<_>>>()?;
+        let params = argument_types(function)?;
+
+        let (executor, sender) = package_executor(&pack)?;
+        let target = Target {
+            executor,
+            sender,
+            module_id: ModuleId::new(
+                AccountAddress::from(module.address),
+                Identifier::new(module_name)
+                    .map_err(|err| CliError::UnableToParse("function", err.to_string()))?,
+            ),
+            function: Identifier::new(function_name)
+                .map_err(|err| CliError::UnableToParse("function", err.to_string()))?,
+            type_args,
+            expected_aborts: self.expected_aborts.clone(),
+        };
+
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let generator = Generator {
+            sender: target.sender.address(),
+            max_length: self.max_length,
+        };
+        let output_dir = match self.output_dir {
+            Some(ref output_dir) => output_dir.clone(),
+            None => package_path.join("fuzz"),
+        };
+
+        let mut failures = BTreeSet::new();
+        let mut failed_runs = 0;
+        let mut reproducers = vec![];
+        for _ in 0..self.runs {
+            let args: Vec<_> = params
+                .iter()
+                .map(|typ| generator.generate(typ, &mut rng))
+                .collect();
+            let failure = match target.failure(&args) {
+                Some(failure) => failure,
+                None => continue,
+            };
+            failed_runs += 1;
+            if !failures.insert(failure.clone()) {
+                continue;
+            }
+
+            let args = target.shrink(args, &failure);
+            create_dir_if_not_exist(&output_dir)?;
+            let path = output_dir.join(format!(
+                "{}-{}-{}.json",
+                module_name,
+                function_name,
+                failures.len()
+            ));
+            let reproducer = target.reproducer(seed, &params, &args, &failure);
+            let json = serde_json::to_string_pretty(&reproducer)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            write_to_file(&path, "fuzz reproducer", json.as_bytes())?;
+            eprintln!("Failure {}, reproducer in {}", failure, path.display());
+            reproducers.push(path);
+        }
+
+        Ok(FuzzSummary {
+            seed,
+            runs: self.runs,
+            failed_runs,
+            reproducers,
+        })
+    }
+}
+
+/// The types of the arguments of an entry function, without its signers
+fn argument_types(function: &MoveFunction) -> CliTypedResult<Vec<MoveType>> {
+    fn check(typ: &MoveType) -> bool {
+        match typ {
+            MoveType::Bool | MoveType::U8 | MoveType::U64 | MoveType::U128 | MoveType::Address => {
+                true
+            }
+            MoveType::Vector { items } => check(items),
+            MoveType::Struct(_) => is_string(typ),
+            _ => false,
+        }
+    }
+
+    let params: Vec<_> = function
+        .params
+        .iter()
+        .filter(|typ| !typ.is_signer())
+        .cloned()
+        .collect();
+    if function.params.len() - params.len() > 1 {
+        return Err(CliError::CommandArgumentError(format!(
+            "{} takes more than one signer, which isn't supported",
+            function.name
+        )));
+    }
+    match params.iter().find(|typ| !check(typ)) {
+        Some(typ) => Err(CliError::CommandArgumentError(format!(
+            "Arguments of type {} can't be generated",
+            typ
+        ))),
+        None => Ok(params),
+    }
+}
+
+pub(crate) fn is_string(typ: &MoveType) -> bool {
+    match typ {
+        MoveType::Struct(tag) => {
+            tag.address == Address::from(AccountAddress::ONE)
+                && tag.module.as_str() == "string"
+                && tag.name.as_str() == "String"
+        }
+        _ => false,
+    }
+}
+
+/// A generated argument, serialized as BCS
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Value {
+    Bool(bool),
+    U8(u8),
+    U64(u64),
+    U128(u128),
+    Address(AccountAddress),
+    String(String),
+    Vector(Vec<Value>),
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Bool(value) => value.serialize(serializer),
+            Value::U8(value) => value.serialize(serializer),
+            Value::U64(value) => value.serialize(serializer),
+            Value::U128(value) => value.serialize(serializer),
+            Value::Address(value) => value.serialize(serializer),
+            Value::String(value) => value.serialize(serializer),
+            Value::Vector(items) => items.serialize(serializer),
+        }
+    }
+}
+
+impl Value {
+    /// The value as an argument of `aptos move run`, if it can be one
+    fn cli_arg(&self) -> Option<String> {
+        Some(match self {
+            Value::Bool(value) => format!("bool:{}", value),
+            Value::U8(value) => format!("u8:{}", value),
+            Value::U64(value) => format!("u64:{}", value),
+            Value::U128(value) => format!("u128:{}", value),
+            Value::Address(value) => format!("address:{}", value.to_hex_literal()),
+            Value::String(value) if !value.contains(char::is_whitespace) => {
+                format!("string:{}", value)
+            }
+            Value::Vector(items) => {
+                let mut bytes = String::new();
+                for item in items {
+                    match item {
+                        Value::U8(byte) => {
+                            let _ = write!(bytes, "{:02x}", byte);
+                        }
+                        _ => return None,
+                    }
+                }
+                format!("hex:{}", bytes)
+            }
+            Value::String(_) => return None,
+        })
+    }
+
+    /// The value as a Move expression
+    pub(crate) fn move_literal(&self) -> String {
+        match self {
+            Value::Bool(value) => value.to_string(),
+            Value::U8(value) => format!("{}u8", value),
+            Value::U64(value) => format!("{}u64", value),
+            Value::U128(value) => format!("{}u128", value),
+            Value::Address(value) => format!("@{}", value.to_hex_literal()),
+            Value::String(value) => format!("std::string::utf8(x\"{}\")", hex::encode(value)),
+            // Empty vectors are left to `vector[]`, which takes its type from the parameter
+            Value::Vector(items)
+                if !items.is_empty() && items.iter().all(|item| matches!(item, Value::U8(_))) =>
+            {
+                let mut bytes = String::new();
+                for item in items {
+                    if let Value::U8(byte) = item {
+                        let _ = write!(bytes, "{:02x}", byte);
+                    }
+                }
+                format!("x\"{}\"", bytes)
+            }
+            Value::Vector(items) => format!(
+                "vector[{}]",
+                items
+                    .iter()
+                    .map(Value::move_literal)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Simpler values to try in place of this one, simplest first
+    fn shrink(&self) -> Vec<Value> {
+        fn smaller(value: u128) -> Vec<u128> {
+            let mut candidates = vec![0, value / 2, value.saturating_sub(1)];
+            candidates.retain(|candidate| *candidate < value);
+            candidates.dedup();
+            candidates
+        }
+        match self {
+            Value::Bool(true) => vec![Value::Bool(false)],
+            Value::Bool(false) => vec![],
+            Value::U8(value) => smaller(*value as u128)
+                .into_iter()
+                .map(|value| Value::U8(value as u8))
+                .collect(),
+            Value::U64(value) => smaller(*value as u128)
+                .into_iter()
+                .map(|value| Value::U64(value as u64))
+                .collect(),
+            Value::U128(value) => smaller(*value).into_iter().map(Value::U128).collect(),
+            Value::Address(address) if *address == AccountAddress::ZERO => vec![],
+            Value::Address(_) => vec![Value::Address(AccountAddress::ZERO)],
+            Value::String(value) => {
+                let chars: Vec<_> = value.chars().collect();
+                let mut candidates = vec![];
+                if !chars.is_empty() {
+                    candidates.push(Value::String(String::new()));
+                    candidates.push(Value::String(chars[..chars.len() / 2].iter().collect()));
+                    candidates.push(Value::String(chars[1..].iter().collect()));
+                }
+                candidates
+            }
+            Value::Vector(items) => {
+                let mut candidates = vec![];
+                if !items.is_empty() {
+                    candidates.push(Value::Vector(vec![]));
+                    candidates.push(Value::Vector(items[..items.len() / 2].to_vec()));
+                    for index in 0..items.len() {
+                        let mut fewer = items.clone();
+                        fewer.remove(index);
+                        candidates.push(Value::Vector(fewer));
+                    }
+                }
+                for (index, item) in items.iter().enumerate() {
+                    for simpler in item.shrink() {
+                        let mut items = items.clone();
+                        items[index] = simpler;
+                        candidates.push(Value::Vector(items));
+                    }
+                }
+                candidates
+            }
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Bool(value) => serde_json::json!(value),
+            Value::U8(value) => serde_json::json!(value),
+            // 64 and 128 bit integers are strings, like in the REST API
+            Value::U64(value) => serde_json::json!(value.to_string()),
+            Value::U128(value) => serde_json::json!(value.to_string()),
+            Value::Address(value) => serde_json::json!(value.to_hex_literal()),
+            Value::String(value) => serde_json::json!(value),
+            Value::Vector(items) => {
+                serde_json::Value::Array(items.iter().map(Value::to_json).collect())
+            }
+        }
+    }
+}
+
+pub(crate) struct Generator {
+    pub(crate) sender: AccountAddress,
+    pub(crate) max_length: usize,
+}
+
+impl Generator {
+    pub(crate) fn generate(&self, typ: &MoveType, rng: &mut StdRng) -> Value {
+        match typ {
+            MoveType::Bool => Value::Bool(rng.gen()),
+            MoveType::U8 => Value::U8(edge_case(rng, u8::MAX as u128) as u8),
+            MoveType::U64 => Value::U64(edge_case(rng, u64::MAX as u128) as u64),
+            MoveType::U128 => Value::U128(edge_case(rng, u128::MAX)),
+            MoveType::Address => Value::Address(match rng.gen_range(0, 4) {
+                0 => self.sender,
+                1 => AccountAddress::ONE,
+                2 => AccountAddress::ZERO,
+                _ => AccountAddress::random(),
+            }),
+            MoveType::Vector { items } => {
+                let length = rng.gen_range(0, self.max_length + 1);
+                Value::Vector((0..length).map(|_| self.generate(items, rng)).collect())
+            }
+            // Strings must be valid UTF-8, which `char`s are
+            _ => {
+                let length = rng.gen_range(0, self.max_length + 1);
+                Value::String(
+                    (0..length)
+                        .map(|_| {
+                            if rng.gen_bool(0.8) {
+                                rng.gen_range(b' ', b'~' + 1) as char
+                            } else {
+                                rng.gen::<char>()
+                            }
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+/// An integer up to `max`, often one at the bounds
+fn edge_case(rng: &mut StdRng, max: u128) -> u128 {
+    match rng.gen_range(0, 8) {
+        0 => 0,
+        1 => 1,
+        2 => max,
+        3 => max - 1,
+        4 => rng.gen_range(0, 256).min(max),
+        _ => rng.gen::<u128>() % max.saturating_add(1).max(1),
+    }
+}
+
+/// The function under test, in its executor
+struct Target {
+    executor: LocalExecutor,
+    sender: LocalAccount,
+    module_id: ModuleId,
+    function: Identifier,
+    type_args: Vec<TypeTag>,
+    expected_aborts: Vec<u64>,
+}
+
+impl Target {
+    /// Calls the function, returning how it failed if it did
+    fn failure(&self, args: &[Value]) -> Option<String> {
+        let args = args
+            .iter()
+            .map(|arg| bcs::to_bytes(arg).expect("arguments serialize"))
+            .collect();
+        let txn = entry_function_transaction(
+            &self.sender,
+            EntryFunction::new(
+                self.module_id.clone(),
+                self.function.clone(),
+                self.type_args.clone(),
+                args,
+            ),
+        );
+        // The transaction isn't applied, so every call starts from the same state
+        let output = match self.executor.execute(txn) {
+            Ok(output) => output,
+            Err(err) => return Some(err.to_string()),
+        };
+        match output.status() {
+            TransactionStatus::Keep(ExecutionStatus::Success) => None,
+            TransactionStatus::Keep(ExecutionStatus::MoveAbort { code, .. })
+                if self.expected_aborts.is_empty() || self.expected_aborts.contains(code) =>
+            {
+                None
+            }
+            status => Some(format!("{:?}", status)),
+        }
+    }
+
+    /// Shrinks arguments to simpler ones failing the same way
+    fn shrink(&self, mut args: Vec<Value>, failure: &str) -> Vec<Value> {
+        let mut runs = 0;
+        'shrinking: loop {
+            for index in 0..args.len() {
+                for simpler in args[index].shrink() {
+                    if runs == MAX_SHRINK_RUNS {
+                        break 'shrinking;
+                    }
+                    runs += 1;
+                    let mut candidate = args.clone();
+                    candidate[index] = simpler;
+                    if self.failure(&candidate).as_deref() == Some(failure) {
+                        args = candidate;
+                        continue 'shrinking;
+                    }
+                }
+            }
+            break;
+        }
+        args
+    }
+
+    fn reproducer(
+        &self,
+        seed: u64,
+        params: &[MoveType],
+        args: &[Value],
+        failure: &str,
+    ) -> serde_json::Value {
+        let function = format!(
+            "{}::{}::{}",
+            self.module_id.address().to_hex_literal(),
+            self.module_id.name(),
+            self.function
+        );
+        let type_args: Vec<_> = self.type_args.iter().map(|tag| tag.to_string()).collect();
+        let command = args
+            .iter()
+            .map(Value::cli_arg)
+            .collect::<Option<Vec<_>>>()
+            .map(|cli_args| {
+                let mut command = format!("aptos move run --function-id {}", function);
+                if !type_args.is_empty() {
+                    let _ = write!(command, " --type-args {}", type_args.join(" "));
+                }
+                if !cli_args.is_empty() {
+                    let _ = write!(command, " --args {}", cli_args.join(" "));
+                }
+                command
+            });
+        serde_json::json!({
+            "function": function,
+            "seed": seed,
+            "failure": failure,
+            "type_args": type_args,
+            "args": args
+                .iter()
+                .zip(params)
+                .map(|(arg, typ)| serde_json::json!({"type": typ.to_string(), "value": arg.to_json()}))
+                .collect::<Vec<_>>(),
+            "bcs_args": args
+                .iter()
+                .map(|arg| hex::encode(bcs::to_bytes(arg).expect("arguments serialize")))
+                .collect::<Vec<_>>(),
+            "command": command,
+        })
+    }
+}
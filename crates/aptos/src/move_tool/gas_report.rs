@@ -0,0 +1,167 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Gas used by each Move unit test, compared with a saved baseline
+//!
+//! The gas is as the unit test runner meters it, read from its statistics table.  It isn't the
+//! gas a transaction would be charged on chain, but it changes with the code each test runs, so
+//! it shows which changes make code more or less expensive.
+
+use crate::{
+    common::{
+        types::{CliError, CliTypedResult},
+        utils::{read_from_file, write_to_file},
+    },
+    move_tool::test_report::TestCaseResult,
+};
+use clap::ArgEnum;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Write},
+    path::Path,
+    str::FromStr,
+};
+
+pub const GAS_BASELINE_FILE: &str = "gas-baseline.json";
+
+/// Gas used by each test, by qualified name
+pub type GasBaseline = BTreeMap<String, u64>;
+
+#[derive(ArgEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GasSort {
+    Name,
+    /// Most gas first
+    Gas,
+    /// Largest increase over the baseline first
+    Delta,
+}
+
+impl Display for GasSort {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GasSort::Name => "name",
+            GasSort::Gas => "gas",
+            GasSort::Delta => "delta",
+        })
+    }
+}
+
+impl FromStr for GasSort {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "name" => Ok(GasSort::Name),
+            "gas" => Ok(GasSort::Gas),
+            "delta" => Ok(GasSort::Delta),
+            _ => Err("Invalid sort. Valid values are name, gas, delta"),
+        }
+    }
+}
+
+/// The gas of each test that reported it
+pub fn gas_used(results: &[TestCaseResult]) -> GasBaseline {
+    results
+        .iter()
+        .filter_map(|result| Some((result.qualified_name(), result.gas?)))
+        .collect()
+}
+
+pub fn load_baseline(path: &Path) -> CliTypedResult<Option<GasBaseline>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    serde_json::from_slice(&read_from_file(path)?)
+        .map(Some)
+        .map_err(|err| CliError::UnableToParse(GAS_BASELINE_FILE, err.to_string()))
+}
+
+pub fn save_baseline(path: &Path, gas: &GasBaseline) -> CliTypedResult<()> {
+    let json = serde_json::to_string_pretty(gas)
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+    write_to_file(path, GAS_BASELINE_FILE, json.as_bytes())
+}
+
+/// A table of the gas of each test, and its change from the baseline
+pub fn gas_report(gas: &GasBaseline, baseline: Option<&GasBaseline>, sort: GasSort) -> String {
+    let mut rows: Vec<_> = gas
+        .iter()
+        .map(|(name, used)| {
+            let previous = baseline.and_then(|baseline| baseline.get(name)).copied();
+            let delta = previous.map(|previous| *used as i128 - previous as i128);
+            (name, *used, previous, delta)
+        })
+        .collect();
+    match sort {
+        GasSort::Name => {}
+        GasSort::Gas => rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0))),
+        GasSort::Delta => rows.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.0.cmp(b.0))),
+    }
+
+    let cells: Vec<[String; 4]> = rows
+        .into_iter()
+        .map(|(name, used, previous, delta)| {
+            [
+                name.clone(),
+                used.to_string(),
+                previous
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                match (previous, delta) {
+                    (Some(previous), Some(delta)) if previous > 0 => format!(
+                        "{:+} ({:+.1}%)",
+                        delta,
+                        delta as f64 * 100.0 / previous as f64
+                    ),
+                    (_, Some(delta)) => format!("{:+}", delta),
+                    _ => "new".to_string(),
+                },
+            ]
+        })
+        .collect();
+    let header = ["Test", "Gas", "Baseline", "Delta"];
+    let mut widths = header.map(str::len);
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut table = String::new();
+    let mut write_row = |row: [&str; 4]| {
+        let _ = writeln!(
+            table,
+            "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3]
+        );
+    };
+    write_row(header);
+    for row in &cells {
+        write_row([&row[0], &row[1], &row[2], &row[3]]);
+    }
+    if let Some(baseline) = baseline {
+        let removed: Vec<_> = baseline
+            .keys()
+            .filter(|name| !gas.contains_key(*name))
+            .collect();
+        if !removed.is_empty() {
+            let _ = writeln!(
+                table,
+                "Not run, but in the baseline: {}",
+                removed
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
+    table
+}
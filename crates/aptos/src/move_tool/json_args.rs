@@ -0,0 +1,116 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Arguments of entry functions given as JSON, typed by the function's ABI
+//!
+//! Each value is encoded as BCS with the layout of its parameter, as `aptos util bcs encode`
+//! does, so structs, vectors of them, options and strings can be passed as they're shown by the
+//! REST API.
+
+use crate::{
+    account::resources::{substitute, Abis},
+    common::types::{CliError, CliTypedResult},
+    move_tool::MemberId,
+    util::bcs_tool::{layout_structs, Layout},
+};
+use aptos_rest_client::{
+    aptos_api_types::{MoveFunction, MoveType},
+    Client,
+};
+
+/// Encodes a JSON array of arguments for the function's parameters, its signers left out
+pub(crate) async fn encode_json_args(
+    client: &Client,
+    function_id: &MemberId,
+    type_args: &[MoveType],
+    json_args: &str,
+) -> CliTypedResult<Vec<Vec<u8>>> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(json_args)
+        .map_err(|err| CliError::UnableToParse("--json-args", err.to_string()))?;
+
+    let address = *function_id.module_id.address();
+    let module_name = function_id.module_id.name().as_str();
+    let module = client
+        .get_account_module(address, module_name)
+        .await?
+        .into_inner()
+        .try_parse_abi()
+        .ok()
+        .and_then(|module| module.abi)
+        .ok_or_else(|| {
+            CliError::UnableToParse("module", format!("{}::{}", address, module_name))
+        })?;
+    let function = module
+        .exposed_functions
+        .iter()
+        .find(|function| function.name.as_str() == function_id.member_id.as_str())
+        .ok_or_else(|| {
+            CliError::CommandArgumentError(format!(
+                "No function {} found in {}",
+                function_id.member_id, function_id.module_id
+            ))
+        })?;
+    let params = params(function, type_args, values.len())?;
+
+    let mut abis = Abis::default();
+    abis.insert(module.clone());
+    let mut structs = vec![];
+    for param in &params {
+        layout_structs(param, &mut structs);
+    }
+    abis.load(client, structs).await?;
+
+    let mut args = vec![];
+    for (index, (param, value)) in params.iter().zip(values).enumerate() {
+        let mut bytes = vec![];
+        Layout::new(&abis, param)?
+            .encode(&value, &mut bytes)
+            .map_err(|err| {
+                CliError::UnableToParse(
+                    "--json-args",
+                    format!("Argument {} of type {}: {}", index + 1, param, err),
+                )
+            })?;
+        args.push(bytes);
+    }
+    Ok(args)
+}
+
+/// The types of the parameters taking arguments, with the type arguments substituted
+fn params(
+    function: &MoveFunction,
+    type_args: &[MoveType],
+    num_args: usize,
+) -> CliTypedResult<Vec<MoveType>> {
+    if type_args.len() != function.generic_type_params.len() {
+        return Err(CliError::CommandArgumentError(format!(
+            "Function {} takes {} type arguments, {} given",
+            function.name,
+            function.generic_type_params.len(),
+            type_args.len()
+        )));
+    }
+    let params: Vec<_> = function
+        .params
+        .iter()
+        .filter(|param| !is_signer(param))
+        .map(|param| substitute(param, type_args))
+        .collect();
+    if params.len() != num_args {
+        return Err(CliError::CommandArgumentError(format!(
+            "Function {} takes {} arguments, {} given",
+            function.name,
+            params.len(),
+            num_args
+        )));
+    }
+    Ok(params)
+}
+
+fn is_signer(typ: &MoveType) -> bool {
+    match typ {
+        MoveType::Signer => true,
+        MoveType::Reference { to, .. } => is_signer(to),
+        _ => false,
+    }
+}
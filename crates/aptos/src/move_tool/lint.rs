@@ -0,0 +1,282 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lints of a package's Move code
+//!
+//! Functions and fields are checked in the bytecode: candidates come from the package as it's
+//! published, and references from its test build, so code only tests use isn't reported.
+//! Constants are inlined by the compiler and don't survive in the bytecode, so they're checked
+//! in the sources.
+
+use crate::common::{
+    types::{CliCommand, CliError, CliTypedResult, MovePackageDir},
+    utils::read_from_file,
+};
+use async_trait::async_trait;
+use clap::Parser;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode, StructFieldInformation, Visibility},
+    CompiledModule,
+};
+use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
+use move_package::{compilation::compiled_package::CompiledPackage, BuildConfig};
+use regex::Regex;
+use serde::Serialize;
+use std::{collections::BTreeSet, path::PathBuf};
+
+/// Lint a package
+///
+/// Lints are selected by flag.  `--unused`, the only one so far, reports code never
+/// referenced in the package:
+/// - private and friend functions that aren't entry functions or `init_module`
+/// - constants
+/// - fields never borrowed, of structs never unpacked and without `key` or `store`, as values
+///   stored on chain are read off-chain
+///
+/// Code referenced only by tests counts as used.  Fails if there's anything to report
+#[derive(Parser)]
+pub struct LintPackage {
+    /// Report unused functions, constants, and struct fields
+    #[clap(long)]
+    pub(crate) unused: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[derive(Serialize)]
+pub struct LintWarning {
+    pub lint: &'static str,
+    /// The unused item, e.g. `coin::mint`, or `coin::Coin.value` for a field
+    pub item: String,
+    /// Source file the item is in
+    pub file: PathBuf,
+}
+
+#[async_trait]
+impl CliCommand<Vec<LintWarning>> for LintPackage {
+    fn command_name(&self) -> &'static str {
+        "LintPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<LintWarning>> {
+        if !self.unused {
+            return Err(CliError::CommandArgumentError(
+                "Select the lints to run, e.g. --unused".to_string(),
+            ));
+        }
+        let published = self.compile(false)?;
+        let tested = self.compile(true)?;
+
+        let mut warnings = unused_code(&root_modules(&published), &root_modules(&tested));
+        let mut files = BTreeSet::new();
+        for unit in published.root_modules() {
+            files.insert(PathBuf::from(&unit.source_path));
+        }
+        for file in files {
+            warnings.extend(unused_constants(&file)?);
+        }
+
+        if warnings.is_empty() {
+            return Ok(warnings);
+        }
+        for warning in &warnings {
+            eprintln!(
+                "{}: {} is never used ({})",
+                warning.file.display(),
+                warning.item,
+                warning.lint
+            );
+        }
+        Err(CliError::UnexpectedError(format!(
+            "{} unused items",
+            warnings.len()
+        )))
+    }
+}
+
+impl LintPackage {
+    fn compile(&self, test_mode: bool) -> CliTypedResult<CompiledPackage> {
+        BuildConfig {
+            additional_named_addresses: self.move_options.named_addresses(),
+            install_dir: self.move_options.output_dir.clone(),
+            test_mode,
+            ..Default::default()
+        }
+        .compile_package_no_exit(
+            &self.move_options.get_package_path()?,
+            &mut std::io::stderr(),
+        )
+        .map_err(|err| CliError::MoveCompilationError(format!("{:#}", err)))
+    }
+}
+
+/// The package's own modules, with their source files
+fn root_modules(package: &CompiledPackage) -> Vec<(&CompiledModule, PathBuf)> {
+    package
+        .root_modules()
+        .filter_map(|unit| match &unit.unit {
+            CompiledUnit::Module(NamedCompiledModule { module, .. }) => {
+                Some((module, PathBuf::from(&unit.source_path)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Functions and fields of the published modules that no module references
+fn unused_code(
+    published: &[(&CompiledModule, PathBuf)],
+    tested: &[(&CompiledModule, PathBuf)],
+) -> Vec<LintWarning> {
+    // Functions as `<module>::<function>`, fields as `<module>::<struct>.<field>`
+    let mut called = BTreeSet::new();
+    let mut borrowed = BTreeSet::new();
+    let mut unpacked = BTreeSet::new();
+    for (module, _) in tested {
+        let function_name = |index| {
+            let handle = module.function_handle_at(index);
+            format!(
+                "{}::{}",
+                module.module_id_for_handle(module.module_handle_at(handle.module)),
+                module.identifier_at(handle.name)
+            )
+        };
+        let struct_name = |index| {
+            let definition = module.struct_def_at(index);
+            let handle = module.struct_handle_at(definition.struct_handle);
+            format!(
+                "{}::{}",
+                module.self_id(),
+                module.identifier_at(handle.name)
+            )
+        };
+        let field_name = |index| {
+            let handle = module.field_handle_at(index);
+            let definition = module.struct_def_at(handle.owner);
+            let field = match &definition.field_information {
+                StructFieldInformation::Declared(fields) => &fields[handle.field as usize],
+                StructFieldInformation::Native => unreachable!("native structs have no fields"),
+            };
+            format!(
+                "{}.{}",
+                struct_name(handle.owner),
+                module.identifier_at(field.name)
+            )
+        };
+
+        for definition in module.function_defs() {
+            let code = match &definition.code {
+                Some(code) => code,
+                None => continue,
+            };
+            for instruction in &code.code {
+                match instruction {
+                    Bytecode::Call(index) => {
+                        called.insert(function_name(*index));
+                    }
+                    Bytecode::CallGeneric(index) => {
+                        called.insert(function_name(
+                            module.function_instantiation_at(*index).handle,
+                        ));
+                    }
+                    Bytecode::ImmBorrowField(index) | Bytecode::MutBorrowField(index) => {
+                        borrowed.insert(field_name(*index));
+                    }
+                    Bytecode::ImmBorrowFieldGeneric(index)
+                    | Bytecode::MutBorrowFieldGeneric(index) => {
+                        borrowed.insert(field_name(module.field_instantiation_at(*index).handle));
+                    }
+                    Bytecode::Unpack(index) => {
+                        unpacked.insert(struct_name(*index));
+                    }
+                    Bytecode::UnpackGeneric(index) => {
+                        unpacked.insert(struct_name(module.struct_instantiation_at(*index).def));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let mut warnings = vec![];
+    for (module, file) in published {
+        let module_name = module.self_id().name().to_string();
+        for definition in module.function_defs() {
+            let name = module.identifier_at(module.function_handle_at(definition.function).name);
+            if definition.visibility == Visibility::Public
+                || definition.is_entry
+                || name.as_str() == "init_module"
+                || called.contains(&format!("{}::{}", module.self_id(), name))
+            {
+                continue;
+            }
+            warnings.push(LintWarning {
+                lint: "unused function",
+                item: format!("{}::{}", module_name, name),
+                file: file.clone(),
+            });
+        }
+
+        for definition in module.struct_defs() {
+            let handle = module.struct_handle_at(definition.struct_handle);
+            let name = module.identifier_at(handle.name);
+            let fields = match &definition.field_information {
+                StructFieldInformation::Declared(fields) => fields,
+                StructFieldInformation::Native => continue,
+            };
+            if handle.abilities.has_key()
+                || handle.abilities.has_store()
+                || unpacked.contains(&format!("{}::{}", module.self_id(), name))
+            {
+                continue;
+            }
+            for field in fields {
+                let field_name = module.identifier_at(field.name);
+                if !borrowed.contains(&format!("{}::{}.{}", module.self_id(), name, field_name)) {
+                    warnings.push(LintWarning {
+                        lint: "unused field",
+                        item: format!("{}::{}.{}", module_name, name, field_name),
+                        file: file.clone(),
+                    });
+                }
+            }
+        }
+    }
+    warnings
+}
+
+/// Constants of a source file whose names appear nowhere else in it, comments aside
+fn unused_constants(file: &PathBuf) -> CliTypedResult<Vec<LintWarning>> {
+    let text = String::from_utf8(read_from_file(file)?)
+        .map_err(|err| CliError::UnableToReadFile(file.display().to_string(), err.to_string()))?;
+    let comments = Regex::new(r"(?s)//[^\n]*|/\*.*?\*/").expect("valid regex");
+    let code = comments.replace_all(&text, "");
+    let module = Regex::new(r"module\s+(?:\w+::)?(\w+)\s*\{").expect("valid regex");
+    let constant = Regex::new(r"\bconst\s+(\w+)\s*:").expect("valid regex");
+
+    let mut warnings = vec![];
+    for declaration in constant.captures_iter(&code) {
+        let name = &declaration[1];
+        let uses = Regex::new(&format!(r"\b{}\b", name))
+            .expect("valid regex")
+            .find_iter(&code)
+            .count();
+        if uses > 1 {
+            continue;
+        }
+        let start = declaration.get(0).expect("match").start();
+        let module_name = module
+            .captures_iter(&code[..start])
+            .last()
+            .map(|captures| captures[1].to_string())
+            .unwrap_or_default();
+        warnings.push(LintWarning {
+            lint: "unused constant",
+            item: format!("{}::{}", module_name, name),
+            file: file.clone(),
+        });
+    }
+    Ok(warnings)
+}
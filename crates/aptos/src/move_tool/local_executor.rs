@@ -0,0 +1,150 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! An in-memory chain for running a package's entry functions locally
+//!
+//! The state starts from a genesis of the current framework, with the package's modules and its
+//! dependencies' added.
+//! Transactions run through the VM directly, and aren't applied, so each runs from the same
+//! state.
+
+use crate::common::types::{account_address_from_public_key, CliError, CliTypedResult};
+use anyhow::Result;
+use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey};
+use aptos_keygen::KeyGen;
+use aptos_sdk::{transaction_builder::TransactionFactory, types::LocalAccount};
+use aptos_state_view::StateView;
+use aptos_types::{
+    access_path::AccessPath,
+    account_config::aptos_test_root_address,
+    chain_id::ChainId,
+    state_store::{state_key::StateKey, state_storage_usage::StateStorageUsage},
+    transaction::{
+        EntryFunction, ExecutionStatus, SignedTransaction, Transaction, TransactionOutput,
+        TransactionStatus,
+    },
+    write_set::{WriteOp, WriteSet},
+};
+use aptos_vm::{AptosVM, VMExecutor};
+use cached_packages::aptos_stdlib;
+use framework::BuiltPackage;
+use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
+use std::{collections::HashMap, convert::TryFrom};
+use vm_genesis::{generate_genesis_change_set_for_testing, GenesisOptions, GENESIS_KEYPAIR};
+
+/// Octas the sender is funded with
+const SENDER_BALANCE: u64 = 1_000_000_000_000;
+/// Max gas of a call to an entry function
+const MAX_GAS_AMOUNT: u64 = 1_000_000;
+
+/// The state of a local chain
+pub(crate) struct LocalExecutor {
+    state: HashMap<StateKey, Vec<u8>>,
+}
+
+impl LocalExecutor {
+    /// Executes a transaction without applying it
+    pub(crate) fn execute(&self, txn: SignedTransaction) -> CliTypedResult<TransactionOutput> {
+        AptosVM::execute_block(vec![Transaction::UserTransaction(txn)], self)
+            .map_err(|err| CliError::UnexpectedError(format!("Failed to execute: {:?}", err)))?
+            .pop()
+            .ok_or_else(|| CliError::UnexpectedError("Execution had no output".to_string()))
+    }
+
+    fn apply(&mut self, write_set: &WriteSet) {
+        for (state_key, write_op) in write_set {
+            match write_op {
+                WriteOp::Modification(blob) | WriteOp::Creation(blob) => {
+                    self.state.insert(state_key.clone(), blob.clone());
+                }
+                WriteOp::Deletion => {
+                    self.state.remove(state_key);
+                }
+            }
+        }
+    }
+}
+
+impl StateView for LocalExecutor {
+    fn get_state_value(&self, state_key: &StateKey) -> Result<Option<Vec<u8>>> {
+        Ok(self.state.get(state_key).cloned())
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.state.is_empty()
+    }
+
+    fn get_usage(&self) -> Result<StateStorageUsage> {
+        let mut usage = StateStorageUsage::new_untracked();
+        for (key, value) in self.state.iter() {
+            usage.add_item(key.size() + value.len())
+        }
+        Ok(usage)
+    }
+}
+
+/// An executor of the current framework with the package's and its dependencies' modules added,
+/// and a funded sender
+///
+/// The sender is funded by the test genesis's core resources account.
+pub(crate) fn package_executor(
+    pack: &BuiltPackage,
+) -> CliTypedResult<(LocalExecutor, LocalAccount)> {
+    let mut executor = LocalExecutor {
+        state: HashMap::new(),
+    };
+    executor.apply(generate_genesis_change_set_for_testing(GenesisOptions::Head).write_set());
+    for (module, code) in pack.modules().zip(pack.extract_code()) {
+        executor.state.insert(
+            StateKey::AccessPath(AccessPath::from(&module.self_id())),
+            code,
+        );
+    }
+    // Dependencies are added too so the package links, but the genesis's framework is kept
+    for (_, unit) in &pack.package().deps_compiled_units {
+        if let CompiledUnit::Module(NamedCompiledModule { module, .. }) = &unit.unit {
+            executor
+                .state
+                .entry(StateKey::AccessPath(AccessPath::from(&module.self_id())))
+                .or_insert_with(|| unit.unit.serialize(None));
+        }
+    }
+
+    let root_key = Ed25519PrivateKey::try_from(&GENESIS_KEYPAIR.0.to_bytes()[..])
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+    let mut root = LocalAccount::new(aptos_test_root_address(), root_key, 0);
+    let sender_key = KeyGen::from_os_rng().generate_ed25519_private_key();
+    let sender = LocalAccount::new(
+        account_address_from_public_key(&sender_key.public_key()),
+        sender_key,
+        0,
+    );
+    let fund =
+        root.sign_with_transaction_builder(TransactionFactory::new(ChainId::test()).payload(
+            aptos_stdlib::aptos_account_transfer(sender.address(), SENDER_BALANCE),
+        ));
+    let output = executor.execute(fund)?;
+    if output.status() != &TransactionStatus::Keep(ExecutionStatus::Success) {
+        return Err(CliError::UnexpectedError(format!(
+            "Failed to fund the sender: {:?}",
+            output.status()
+        )));
+    }
+    executor.apply(output.write_set());
+    Ok((executor, sender))
+}
+
+/// A call of an entry function by the sender, at its first sequence number
+pub(crate) fn entry_function_transaction(
+    sender: &LocalAccount,
+    function: EntryFunction,
+) -> SignedTransaction {
+    sender.sign_transaction(
+        TransactionFactory::new(ChainId::test())
+            .entry_function(function)
+            .sender(sender.address())
+            .sequence_number(0)
+            .max_gas_amount(MAX_GAS_AMOUNT)
+            .build(),
+    )
+}
@@ -0,0 +1,269 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Move.lock, pinning every git dependency of a package to an exact commit
+//!
+//! The package system checks out a git dependency's `rev` when it first downloads it, so a
+//! branch rev builds against whatever commit the branch was at then.  The lockfile records
+//! the commits, and `--locked` builds fail unless the dependencies still resolve to them.
+
+use crate::{
+    common::{
+        types::{CliError, CliTypedResult, MovePackageDir},
+        utils::{read_from_file, write_to_file},
+    },
+    move_tool::dependencies::{
+        git_cache_dir, git_head, git_ls_remote, is_commit_hash, load_manifest, lookup_ref,
+    },
+    CliCommand,
+};
+use async_trait::async_trait;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+pub const LOCKFILE: &str = "Move.lock";
+
+/// A git dependency, as declared in a Move.toml
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GitDependency {
+    pub git: String,
+    pub rev: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subdir: Option<String>,
+}
+
+/// A git dependency pinned to a commit
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LockedDependency {
+    #[serde(flatten)]
+    pub dependency: GitDependency,
+    pub commit: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MoveLock {
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, LockedDependency>,
+}
+
+impl MoveLock {
+    fn path(package_dir: &Path) -> PathBuf {
+        package_dir.join(LOCKFILE)
+    }
+
+    pub fn load(package_dir: &Path) -> CliTypedResult<Option<Self>> {
+        let path = Self::path(package_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = read_from_file(&path)?;
+        toml::from_slice(&bytes)
+            .map(Some)
+            .map_err(|err| CliError::UnableToParse(LOCKFILE, err.to_string()))
+    }
+
+    pub fn save(&self, package_dir: &Path) -> CliTypedResult<()> {
+        let contents = toml::to_string_pretty(self)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        write_to_file(
+            &Self::path(package_dir),
+            LOCKFILE,
+            format!(
+                "# Generated by `aptos move lock`, update with `aptos move update`\n\n{}",
+                contents
+            )
+            .as_bytes(),
+        )
+    }
+}
+
+/// Resolve every git dependency to an exact commit in Move.lock
+///
+/// Includes the dependencies of dependencies, as far as they've been downloaded or are local.
+/// Build with `--locked` to fail if the dependencies no longer match Move.lock
+#[derive(Parser)]
+pub struct LockPackage {
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<BTreeMap<String, LockedDependency>> for LockPackage {
+    fn command_name(&self) -> &'static str {
+        "LockPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<BTreeMap<String, LockedDependency>> {
+        let package_dir = self.move_options.get_package_path()?;
+        let mut lock = MoveLock::default();
+        for (name, dependency) in git_dependencies(&package_dir)? {
+            let commit = resolve_commit(&dependency)?;
+            lock.dependencies
+                .insert(name, LockedDependency { dependency, commit });
+        }
+        lock.save(&package_dir)?;
+        Ok(lock.dependencies)
+    }
+}
+
+/// Advance dependencies in Move.lock to the latest commit of their rev
+///
+/// Downloads of the old commits are removed from the package cache, so the next build
+/// fetches the locked commits
+#[derive(Parser)]
+pub struct UpdateLockedDependencies {
+    /// Dependencies to update, all of them if none are given
+    pub(crate) names: Vec<String>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<BTreeMap<String, LockedDependency>> for UpdateLockedDependencies {
+    fn command_name(&self) -> &'static str {
+        "UpdateLockedDependencies"
+    }
+
+    async fn execute(self) -> CliTypedResult<BTreeMap<String, LockedDependency>> {
+        let package_dir = self.move_options.get_package_path()?;
+        let mut lock = MoveLock::load(&package_dir)?.ok_or_else(missing_lockfile)?;
+        let dependencies = git_dependencies(&package_dir)?;
+        if let Some(name) = self
+            .names
+            .iter()
+            .find(|name| !dependencies.contains_key(*name))
+        {
+            return Err(CliError::CommandArgumentError(format!(
+                "No git dependency named {}",
+                name
+            )));
+        }
+
+        let mut updated = BTreeMap::new();
+        for (name, dependency) in dependencies {
+            if !self.names.is_empty() && !self.names.contains(&name) {
+                continue;
+            }
+            let commit = resolve_commit(&dependency)?;
+            let cache_dir = git_cache_dir(&dependency.git, &dependency.rev);
+            if git_head(&cache_dir).map_or(false, |cached| cached != commit) {
+                std::fs::remove_dir_all(&cache_dir)
+                    .map_err(|err| CliError::IO("package cache".to_string(), err))?;
+            }
+            let locked = LockedDependency { dependency, commit };
+            lock.dependencies.insert(name.clone(), locked.clone());
+            updated.insert(name, locked);
+        }
+        lock.save(&package_dir)?;
+        Ok(updated)
+    }
+}
+
+/// Fails unless every git dependency is in Move.lock, and resolves to its locked commit
+pub fn check_lockfile(package_dir: &Path) -> CliTypedResult<()> {
+    let lock = MoveLock::load(package_dir)?.ok_or_else(missing_lockfile)?;
+    let stale = |reason: String| {
+        CliError::CommandArgumentError(format!(
+            "{} is out of date: {}.  Run `aptos move lock` or `aptos move update`",
+            LOCKFILE, reason
+        ))
+    };
+
+    for (name, dependency) in git_dependencies(package_dir)? {
+        let locked = lock
+            .dependencies
+            .get(&name)
+            .ok_or_else(|| stale(format!("{} isn't locked", name)))?;
+        if locked.dependency != dependency {
+            return Err(stale(format!("{} changed in Move.toml", name)));
+        }
+        // The download is what gets built, otherwise it's what will be downloaded
+        let commit = match git_head(&git_cache_dir(&dependency.git, &dependency.rev)) {
+            Some(commit) => commit,
+            None => resolve_commit(&dependency)?,
+        };
+        if !commit.starts_with(&locked.commit) {
+            return Err(stale(format!(
+                "{} is at commit {}, not the locked {}",
+                name, commit, locked.commit
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn missing_lockfile() -> CliError {
+    CliError::CommandArgumentError(format!(
+        "No {} found, create it with `aptos move lock`",
+        LOCKFILE
+    ))
+}
+
+/// Resolves a dependency's rev to a commit with the remote
+fn resolve_commit(dependency: &GitDependency) -> CliTypedResult<String> {
+    if is_commit_hash(&dependency.rev) {
+        return Ok(dependency.rev.clone());
+    }
+    lookup_ref(&git_ls_remote(&dependency.git)?, &dependency.rev).ok_or_else(|| {
+        CliError::UnexpectedError(format!("{} has no ref {}", dependency.git, dependency.rev))
+    })
+}
+
+/// Collects the git dependencies of a package and of its dependencies, as far as they're
+/// available locally
+pub(crate) fn git_dependencies(
+    package_dir: &Path,
+) -> CliTypedResult<BTreeMap<String, GitDependency>> {
+    let mut dependencies = BTreeMap::new();
+    collect_git_dependencies(package_dir, &mut dependencies, true)?;
+    Ok(dependencies)
+}
+
+fn collect_git_dependencies(
+    package_dir: &Path,
+    dependencies: &mut BTreeMap<String, GitDependency>,
+    root: bool,
+) -> CliTypedResult<()> {
+    let manifest = match load_manifest(package_dir) {
+        Ok((_, manifest)) => manifest,
+        // Dependencies that haven't been downloaded can't be followed
+        Err(_) if !root => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let declared = match manifest
+        .get("dependencies")
+        .and_then(|dependencies| dependencies.as_table_like())
+    {
+        Some(declared) => declared,
+        None => return Ok(()),
+    };
+
+    for (name, dependency) in declared.iter() {
+        let field = |key: &str| {
+            dependency
+                .get(key)
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_string())
+        };
+        if let Some(local) = field("local") {
+            collect_git_dependencies(&package_dir.join(local), dependencies, false)?;
+        } else if let (Some(git), Some(rev)) = (field("git"), field("rev")) {
+            if dependencies.contains_key(name) {
+                continue;
+            }
+            let subdir = field("subdir");
+            let mut dir = git_cache_dir(&git, &rev);
+            if let Some(ref subdir) = subdir {
+                dir = dir.join(subdir);
+            }
+            dependencies.insert(name.to_string(), GitDependency { git, rev, subdir });
+            collect_git_dependencies(&dir, dependencies, false)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,199 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Upgrading Move.toml from older layouts
+//!
+//! Edits go through `toml_edit` like those of `aptos move add`, so what isn't migrated keeps its
+//! formatting and comments.
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, MovePackageDir, PromptOptions},
+        utils::prompt_yes_with_override,
+    },
+    move_tool::{
+        dependencies::{
+            framework_dependency, known_package, load_manifest, save_manifest, APTOS_GIT_PATH,
+        },
+        new_module::DEV_ADDRESS,
+    },
+};
+use async_trait::async_trait;
+use clap::Parser;
+use toml_edit::{Document, Item, Table, Value};
+
+/// Repositories the Move standard library was published from before it moved to aptos-core
+const LEGACY_STDLIB_REPOSITORIES: &[&str] = &[
+    "https://github.com/diem/move.git",
+    "https://github.com/move-language/move.git",
+];
+const LEGACY_STDLIB_SUBDIR: &str = "language/move-stdlib";
+
+/// Upgrade Move.toml to the current layout
+///
+/// The migrations are:
+/// - dependencies written as `[dependencies.<name>]` tables become inline tables
+/// - the aptos-core repository is given by its canonical URL, ending in `.git`
+/// - MoveStdlib from the diem or move-language repositories comes from aptos-core, at the
+///   revision of the other framework dependencies
+/// - named address placeholders `_` get a `[dev-addresses]` entry, so the package can be tested
+///
+/// The changes are shown as a diff, and applied once confirmed.  With `--dry-run`, only the
+/// diff is shown.
+#[derive(Parser)]
+pub struct MigrateManifest {
+    /// Show the changes without applying them
+    #[clap(long)]
+    pub(crate) dry_run: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<Vec<String>> for MigrateManifest {
+    fn command_name(&self) -> &'static str {
+        "MigrateManifest"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<String>> {
+        let package_dir = self.move_options.get_package_path()?;
+        let (original, mut manifest) = load_manifest(&package_dir)?;
+        let migrations = migrate(&mut manifest)?;
+        if migrations.is_empty() {
+            eprintln!("Move.toml is up to date");
+            return Ok(migrations);
+        }
+
+        let migrated = manifest.to_string();
+        eprintln!("{}", line_diff(&original, &migrated));
+        for migration in &migrations {
+            eprintln!("- {}", migration);
+        }
+        if !self.dry_run {
+            prompt_yes_with_override("Apply these changes to Move.toml?", self.prompt_options)?;
+            save_manifest(&package_dir, &migrated)?;
+        }
+        Ok(migrations)
+    }
+}
+
+/// Applies the migrations to the manifest, describing each one applied
+pub(crate) fn migrate(manifest: &mut Document) -> CliTypedResult<Vec<String>> {
+    let mut migrations = vec![];
+    for section in ["dependencies", "dev-dependencies"] {
+        let names: Vec<String> = match manifest.get(section).and_then(|t| t.as_table_like()) {
+            Some(dependencies) => dependencies
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            None => continue,
+        };
+        for name in names {
+            let dependency = &manifest[section][&name];
+            if dependency.is_table() {
+                let inline = dependency.clone().into_value().map_err(|_| {
+                    CliError::UnableToParse(
+                        "Move.toml",
+                        format!("[{}.{}] can't be made inline", section, name),
+                    )
+                })?;
+                manifest[section][&name] = Item::Value(inline);
+                migrations.push(format!("{}.{}: written as an inline table", section, name));
+            }
+
+            let git = match manifest[section][&name]
+                .get("git")
+                .and_then(|git| git.as_str())
+            {
+                Some(git) => git.to_string(),
+                None => continue,
+            };
+            let subdir = manifest[section][&name]
+                .get("subdir")
+                .and_then(|subdir| subdir.as_str())
+                .unwrap_or_default()
+                .to_string();
+            if LEGACY_STDLIB_REPOSITORIES.contains(&git.as_str()) && subdir == LEGACY_STDLIB_SUBDIR
+            {
+                let (_, stdlib_subdir) =
+                    known_package("MoveStdlib").expect("MoveStdlib is a known package");
+                let dependency = framework_dependency(manifest, stdlib_subdir, None);
+                manifest[section][&name] = Item::Value(Value::InlineTable(dependency));
+                migrations.push(format!(
+                    "{}.{}: the Move standard library from {}",
+                    section, name, APTOS_GIT_PATH
+                ));
+            } else if git != APTOS_GIT_PATH && is_aptos_core(&git) {
+                manifest[section][&name]["git"] = toml_edit::value(APTOS_GIT_PATH);
+                migrations.push(format!("{}.{}.git: {}", section, name, APTOS_GIT_PATH));
+            }
+        }
+    }
+
+    let placeholders: Vec<String> = manifest
+        .get("addresses")
+        .and_then(|addresses| addresses.as_table_like())
+        .into_iter()
+        .flat_map(|addresses| addresses.iter())
+        .filter(|(_, value)| value.as_str() == Some("_"))
+        .map(|(name, _)| name.to_string())
+        .collect();
+    for name in placeholders {
+        let dev_addresses = manifest
+            .as_table_mut()
+            .entry("dev-addresses")
+            .or_insert(Item::Table(Table::new()))
+            .as_table_like_mut()
+            .ok_or_else(|| {
+                CliError::UnableToParse("Move.toml", "[dev-addresses] is not a table".to_string())
+            })?;
+        if !dev_addresses.contains_key(&name) {
+            dev_addresses.insert(&name, toml_edit::value(DEV_ADDRESS));
+            migrations.push(format!("dev-addresses.{}: {}", name, DEV_ADDRESS));
+        }
+    }
+    Ok(migrations)
+}
+
+/// Whether the URL is of the aptos-core repository, written differently
+fn is_aptos_core(git: &str) -> bool {
+    let normalized = git.trim_end_matches('/').trim_end_matches(".git");
+    normalized.eq_ignore_ascii_case(APTOS_GIT_PATH.trim_end_matches(".git"))
+}
+
+/// A diff of the lines of two texts, with `-` for removed lines and `+` for added ones
+pub(crate) fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+    // Longest common subsequence of lines from each position on, manifests are short
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push_str(&format!("  {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || common[i][j + 1] >= common[i + 1][j]) {
+            diff.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        } else {
+            diff.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        }
+    }
+    diff
+}
@@ -1,19 +1,51 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod abi;
 mod aptos_debug_natives;
+mod args_file;
+pub mod attestation;
+pub mod audit;
+pub mod bench;
+pub mod bindings;
+pub mod build_report;
+pub mod bytecode;
+pub mod check_manifest;
+mod chunked_publish;
+pub mod clean;
+pub mod compose;
+pub mod coverage;
+mod dependencies;
+pub mod dry_run;
+pub mod fuzz;
+pub mod gas_report;
+pub mod json_args;
+pub mod lint;
+mod local_executor;
+pub mod lockfile;
 mod manifest;
+pub mod migrate_manifest;
+pub mod new_module;
+pub mod new_script;
 pub mod package_hooks;
 pub use package_hooks::*;
+pub mod random_test;
+mod resolved_package;
+pub mod spec_stub;
 pub mod stored_package;
+pub mod test_report;
 mod transactional_tests_runner;
+pub mod upgrade_policy;
+pub mod verify_source;
+mod watch;
 
 pub use stored_package::*;
 
 use crate::common::types::MoveManifestAccountWrapper;
 use crate::common::types::{CliConfig, ConfigSearchMode, ProfileOptions, RestOptions};
 use crate::common::utils::{
-    create_dir_if_not_exist, dir_default_to_current, prompt_yes_with_override, write_to_file,
+    create_dir_if_not_exist, dir_default_to_current, prompt_yes_with_override, read_from_file,
+    read_line, write_to_file,
 };
 use crate::governance::CompileScriptFunction;
 use crate::move_tool::manifest::{
@@ -22,8 +54,8 @@ use crate::move_tool::manifest::{
 use crate::{
     common::{
         types::{
-            load_account_arg, CliError, CliTypedResult, MovePackageDir, PromptOptions,
-            TransactionOptions, TransactionSummary,
+            load_account_arg, load_manifest_account_arg, CliError, CliTypedResult, MovePackageDir,
+            PromptOptions, TransactionOptions, TransactionSummary,
         },
         utils::check_if_file_exists,
     },
@@ -35,24 +67,30 @@ use aptos_rest_client::aptos_api_types::MoveType;
 use aptos_transactional_test_harness::run_aptos_test;
 use aptos_types::account_address::{create_resource_address, AccountAddress};
 use aptos_types::transaction::{EntryFunction, Script, TransactionArgument, TransactionPayload};
+use args_file::ArgsFile;
 use async_trait::async_trait;
 use clap::{ArgEnum, Parser, Subcommand};
+use dry_run::PackageSources;
 use framework::docgen::DocgenOptions;
 use framework::natives::code::UpgradePolicy;
 use framework::prover::ProverOptions;
 use framework::{BuildOptions, BuiltPackage};
+use gas_report::GasSort;
 use itertools::Itertools;
+use move_binary_format::CompiledModule;
 use move_cli::base::test::UnitTestResult;
-use move_command_line_common::env::MOVE_HOME;
+use random_test::expand_random_tests;
+use resolved_package::resolve_package;
 use std::fmt::{Display, Formatter};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     convert::TryFrom,
     path::{Path, PathBuf},
     str::FromStr,
 };
+use test_report::{TestFormat, TestOutput, TestStatus};
 use tokio::task;
-use transactional_tests_runner::TransactionalTestOpts;
+use transactional_tests_runner::{run_snapshot_tests, TransactionalTestOpts};
 use {
     move_cli,
     move_core_types::{
@@ -70,41 +108,97 @@ use {
 /// about this code.
 #[derive(Subcommand)]
 pub enum MoveTool {
+    Abi(abi::ExportPackageAbi),
+    Add(dependencies::AddDependency),
+    Audit(audit::AuditPackage),
+    Bench(bench::BenchPackage),
+    BuildReport(build_report::BuildReport),
+    CheckManifest(check_manifest::CheckManifest),
     Compile(CompilePackage),
+    Compose(compose::ComposeScript),
+    Coverage(coverage::CoveragePackage),
+    Decompile(bytecode::DecompileModules),
+    Disassemble(bytecode::DisassembleModule),
     Init(InitPackage),
     Publish(PublishPackage),
     Download(DownloadPackage),
+    Fuzz(fuzz::FuzzFunction),
+    GeneratePython(bindings::GeneratePython),
+    GenerateRust(bindings::GenerateRust),
+    #[clap(name = "generate-ts")]
+    GenerateTypescript(bindings::GenerateTypescript),
+    Lint(lint::LintPackage),
     List(ListPackage),
-    Clean(CleanPackage),
+    Lock(lockfile::LockPackage),
+    MigrateManifest(migrate_manifest::MigrateManifest),
+    NewModule(new_module::NewModule),
+    NewScript(new_script::NewScript),
+    Outdated(dependencies::OutdatedDependencies),
+    Clean(clean::CleanPackage),
+    VerifyAttestation(attestation::VerifyAttestation),
     VerifyPackage(VerifyPackage),
+    VerifySource(verify_source::VerifySource),
     Run(RunFunction),
     RunScript(RunScript),
+    Remove(dependencies::RemoveDependency),
+    SpecStub(spec_stub::SpecStub),
     Test(TestPackage),
+    Update(lockfile::UpdateLockedDependencies),
+    #[clap(subcommand)]
+    UpgradePolicy(upgrade_policy::UpgradePolicyTool),
     Prove(ProvePackage),
     Document(DocumentPackage),
     TransactionalTest(TransactionalTestOpts),
     CreateResourceAccountAndPublishPackage(CreateResourceAccountAndPublishPackage),
+    Watch(watch::WatchPackage),
 }
 
 impl MoveTool {
     pub async fn execute(self) -> CliResult {
         match self {
+            MoveTool::Abi(tool) => tool.execute_serialized_success().await,
+            MoveTool::Add(tool) => tool.execute_serialized().await,
+            MoveTool::Audit(tool) => tool.execute_serialized().await,
+            MoveTool::Bench(tool) => tool.execute_serialized().await,
+            MoveTool::BuildReport(tool) => tool.execute_serialized().await,
+            MoveTool::CheckManifest(tool) => tool.execute_serialized().await,
             MoveTool::Compile(tool) => tool.execute_serialized().await,
+            MoveTool::Compose(tool) => tool.execute_serialized().await,
+            MoveTool::Coverage(tool) => tool.execute_serialized().await,
+            MoveTool::Decompile(tool) => tool.execute_serialized().await,
+            MoveTool::Disassemble(tool) => tool.execute_serialized_success().await,
             MoveTool::Init(tool) => tool.execute_serialized_success().await,
             MoveTool::Publish(tool) => tool.execute_serialized().await,
             MoveTool::Download(tool) => tool.execute_serialized().await,
+            MoveTool::Fuzz(tool) => tool.execute_serialized().await,
+            MoveTool::GeneratePython(tool) => tool.execute_serialized().await,
+            MoveTool::GenerateRust(tool) => tool.execute_serialized().await,
+            MoveTool::GenerateTypescript(tool) => tool.execute_serialized().await,
+            MoveTool::Lint(tool) => tool.execute_serialized().await,
             MoveTool::List(tool) => tool.execute_serialized().await,
+            MoveTool::Lock(tool) => tool.execute_serialized().await,
+            MoveTool::MigrateManifest(tool) => tool.execute_serialized().await,
+            MoveTool::NewModule(tool) => tool.execute_serialized().await,
+            MoveTool::NewScript(tool) => tool.execute_serialized().await,
+            MoveTool::Outdated(tool) => tool.execute_serialized().await,
             MoveTool::Clean(tool) => tool.execute_serialized().await,
+            MoveTool::VerifyAttestation(tool) => tool.execute_serialized().await,
             MoveTool::VerifyPackage(tool) => tool.execute_serialized().await,
+            MoveTool::VerifySource(tool) => tool.execute_serialized().await,
             MoveTool::Run(tool) => tool.execute_serialized().await,
             MoveTool::RunScript(tool) => tool.execute_serialized().await,
+            MoveTool::Remove(tool) => tool.execute_serialized().await,
+            MoveTool::SpecStub(tool) => tool.execute_serialized().await,
             MoveTool::Test(tool) => tool.execute_serialized().await,
+            MoveTool::Update(tool) => tool.execute_serialized().await,
+            MoveTool::UpgradePolicy(tool) => tool.execute().await,
             MoveTool::Prove(tool) => tool.execute_serialized().await,
             MoveTool::Document(tool) => tool.execute_serialized().await,
             MoveTool::TransactionalTest(tool) => tool.execute_serialized_success().await,
             MoveTool::CreateResourceAccountAndPublishPackage(tool) => {
                 tool.execute_serialized_success().await
             }
+            MoveTool::Watch(tool) => tool.execute_serialized_success().await,
         }
     }
 }
@@ -218,6 +312,15 @@ pub struct InitPackage {
     #[clap(long, parse(try_from_str = crate::common::utils::parse_map), default_value = "")]
     pub(crate) named_addresses: BTreeMap<String, MoveManifestAccountWrapper>,
 
+    /// Adopt existing Move source files in the package directory
+    ///
+    /// Any `.move` files at the top of the package directory are moved into `sources/`, and
+    /// the named addresses declared by `module <address>::<name>` headers are added to the
+    /// Move.toml.  Addresses not given by `--named-addresses` are prompted for, and can be an
+    /// address, a profile name, or `_` for a placeholder.
+    #[clap(long)]
+    pub(crate) existing: bool,
+
     #[clap(flatten)]
     pub(crate) prompt_options: PromptOptions,
 
@@ -233,21 +336,124 @@ impl CliCommand<()> for InitPackage {
 
     async fn execute(self) -> CliTypedResult<()> {
         let package_dir = dir_default_to_current(self.package_dir.clone())?;
-        let addresses = self
+        let mut addresses: BTreeMap<String, ManifestNamedAddress> = self
             .named_addresses
             .into_iter()
             .map(|(key, value)| (key, value.account_address.into()))
             .collect();
 
+        let mut prompt_options = self.prompt_options;
+        if self.existing {
+            // Confirm overwriting the Move.toml before any sources are moved, so declining
+            // leaves the directory as it was
+            let move_toml = package_dir.join(SourcePackageLayout::Manifest.path());
+            check_if_file_exists(move_toml.as_path(), prompt_options)?;
+            adopt_existing_sources(package_dir.as_path(), &mut addresses, prompt_options)?;
+            // Overwriting was already confirmed
+            prompt_options = PromptOptions::yes();
+        }
+
         self.framework_package_args.init_move_dir(
             package_dir.as_path(),
             &self.name,
             addresses,
-            self.prompt_options,
+            prompt_options,
         )
     }
 }
 
+/// Moves top level `.move` files into `sources/` and fills in the named addresses they declare
+fn adopt_existing_sources(
+    package_dir: &Path,
+    addresses: &mut BTreeMap<String, ManifestNamedAddress>,
+    prompt_options: PromptOptions,
+) -> CliTypedResult<()> {
+    let sources_dir = package_dir.join(SourcePackageLayout::Sources.path());
+    let top_level_files: Vec<PathBuf> = std::fs::read_dir(package_dir)
+        .map_err(|err| CliError::IO(package_dir.display().to_string(), err))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "move"))
+        .collect();
+
+    if !top_level_files.is_empty() {
+        prompt_yes_with_override(
+            &format!(
+                "Move {} source file(s) from {} into {}?",
+                top_level_files.len(),
+                package_dir.display(),
+                sources_dir.display()
+            ),
+            prompt_options,
+        )?;
+        create_dir_if_not_exist(sources_dir.as_path())?;
+        for file in top_level_files {
+            let destination = sources_dir.join(file.file_name().unwrap());
+            std::fs::rename(&file, &destination)
+                .map_err(|err| CliError::IO(file.display().to_string(), err))?;
+        }
+    }
+
+    let source_files: Vec<PathBuf> = walkdir::WalkDir::new(&sources_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "move"))
+        .collect();
+    if source_files.is_empty() {
+        return Err(CliError::CommandArgumentError(format!(
+            "No Move source files found in {}",
+            package_dir.display()
+        )));
+    }
+
+    let mut declared_addresses = BTreeSet::new();
+    for file in source_files {
+        let source = String::from_utf8(read_from_file(file.as_path())?)?;
+        declared_addresses.extend(declared_named_addresses(&source));
+    }
+
+    for name in declared_addresses {
+        if addresses.contains_key(&name) {
+            continue;
+        }
+        let address = if prompt_options.assume_yes || prompt_options.assume_no {
+            None
+        } else {
+            eprintln!(
+                "Enter an address or profile name for named address `{}` [No input: Use the placeholder `_`]",
+                name
+            );
+            let input = read_line("Named address")?;
+            let input = input.trim();
+            if input.is_empty() {
+                None
+            } else {
+                load_manifest_account_arg(input)?
+            }
+        };
+        addresses.insert(name, address.into());
+    }
+
+    create_dir_if_not_exist(
+        package_dir
+            .join(SourcePackageLayout::Tests.path())
+            .as_path(),
+    )
+}
+
+/// Finds the named addresses used to declare modules e.g. `module my_addr::my_module`
+pub(crate) fn declared_named_addresses(source: &str) -> BTreeSet<String> {
+    let module_header =
+        regex::Regex::new(r"(?m)^\s*module\s+([A-Za-z_][A-Za-z0-9_]*)\s*::").unwrap();
+    let address_block =
+        regex::Regex::new(r"(?m)^\s*address\s+([A-Za-z_][A-Za-z0-9_]*)\s*\{").unwrap();
+    module_header
+        .captures_iter(source)
+        .chain(address_block.captures_iter(source))
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
 /// Compiles a package and returns the associated ModuleIds
 #[derive(Parser)]
 pub struct CompilePackage {
@@ -258,6 +464,19 @@ pub struct CompilePackage {
     #[clap(long)]
     pub(crate) save_metadata: bool,
 
+    /// Write a build attestation, for `aptos move verify-attestation` to check a rebuild against
+    ///
+    /// It pins the CLI version, the build options, and the locked commits of the git
+    /// dependencies, which must be locked in Move.lock, along with the digests of the bytecode.
+    #[clap(long)]
+    pub(crate) reproducible: bool,
+
+    /// Where to write the attestation with `--reproducible`
+    ///
+    /// Defaults to `<package_dir>/build-info.json`
+    #[clap(long, parse(from_os_str), requires = "reproducible")]
+    pub(crate) attestation_file: Option<PathBuf>,
+
     #[clap(flatten)]
     pub(crate) included_artifacts_args: IncludedArtifactsArgs,
     #[clap(flatten)]
@@ -271,18 +490,40 @@ impl CliCommand<Vec<String>> for CompilePackage {
     }
 
     async fn execute(self) -> CliTypedResult<Vec<String>> {
+        let package_path = self.move_options.get_package_path()?;
+        // An attested build must resolve its git dependencies the same way again
+        if self.reproducible
+            && !self.move_options.locked
+            && !lockfile::git_dependencies(&package_path)?.is_empty()
+        {
+            lockfile::check_lockfile(&package_path)?;
+        }
+        let package = resolve_package(&package_path, self.move_options.env.as_deref())?;
         let build_options = BuildOptions {
-            install_dir: self.move_options.output_dir.clone(),
+            install_dir: package.install_dir(self.move_options.output_dir.clone()),
             ..self
                 .included_artifacts_args
                 .included_artifacts
                 .build_options(self.move_options.named_addresses())
         };
-        let pack = BuiltPackage::build(self.move_options.get_package_path()?, build_options)
+        let pack = BuiltPackage::build(package.path(), build_options.clone())
             .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
         if self.save_metadata {
             pack.extract_metadata_and_save()?;
         }
+        if self.reproducible {
+            let attestation = attestation::BuildAttestation::new(
+                &pack,
+                &package_path,
+                self.move_options.env.clone(),
+                build_options,
+            )?;
+            attestation.save(
+                &self
+                    .attestation_file
+                    .unwrap_or_else(|| package_path.join(attestation::ATTESTATION_FILE)),
+            )?;
+        }
         let mut ids = Vec::new();
         for module in pack.modules() {
             verify_module_init_function(module)
@@ -317,6 +558,71 @@ pub struct TestPackage {
         long = "instructions"
     )]
     pub instruction_execution_bound: u64,
+
+    /// Save the coverage of the tests, for `aptos move coverage`
+    #[clap(long)]
+    pub(crate) coverage: bool,
+
+    /// Only run the test named exactly by the filter
+    ///
+    /// The name is the test's function, qualified by as much of its module as needed, e.g.
+    /// `coin::test_mint`.  The runner selects tests by substring, so tests whose names extend
+    /// the filter still run, but they're left out of the output and don't fail the command
+    #[clap(long, requires = "filter")]
+    pub(crate) exact: bool,
+
+    /// Output format: [pretty, junit, json]
+    ///
+    /// JSON is an object per line for each test event: `started`, then `ok` or `failed` with
+    /// the abort location and code, and lastly the `suite` summary
+    #[clap(long, default_value_t = TestFormat::Pretty)]
+    pub(crate) format: TestFormat,
+
+    /// File to write the report to, instead of printing it
+    ///
+    /// The test output is then still printed, as with the pretty format
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Rewrite the expected output of the snapshot tests, rather than checking against it
+    ///
+    /// Snapshot tests are transactional test scripts in the package's `snapshot_tests`
+    /// directory, which publish the modules they exercise and record the events, aborts, and
+    /// return values of each step in the `.exp` file next to the script.  They run after the
+    /// unit tests, unless a report is printed instead of the test output
+    #[clap(long)]
+    pub(crate) update_snapshots: bool,
+
+    /// Seed of the arguments of random tests, to replay a failed run
+    ///
+    /// Random tests are functions annotated `#[random_test]` instead of `#[test]`, taking
+    /// arguments of primitive, `String`, and vector types, and signers.  Each runs as the tests
+    /// `<function>_random_<n>`, with generated arguments that are printed if it fails.  Without
+    /// a seed, a random one is used and printed with the failures
+    #[clap(long)]
+    pub(crate) seed: Option<u64>,
+
+    /// Number of times each random test runs, with different arguments
+    #[clap(long, default_value_t = 32)]
+    pub(crate) random_runs: usize,
+
+    /// Print the gas each test used, and how it changed from the baseline if there's one
+    ///
+    /// The gas is as the test runner meters it, not as a transaction would be charged
+    #[clap(long)]
+    pub(crate) gas_report: bool,
+
+    /// Sort the gas report by: [name, gas, delta]
+    #[clap(long, default_value_t = GasSort::Gas, requires = "gas-report")]
+    pub(crate) gas_sort: GasSort,
+
+    /// File of the gas baseline, defaults to `<package_dir>/gas-baseline.json`
+    #[clap(long, parse(from_os_str), requires = "gas-report")]
+    pub(crate) gas_baseline: Option<PathBuf>,
+
+    /// Save the gas of the tests run as the new baseline
+    #[clap(long, requires = "gas-report")]
+    pub(crate) save_gas_baseline: bool,
 }
 
 #[async_trait]
@@ -326,18 +632,40 @@ impl CliCommand<&'static str> for TestPackage {
     }
 
     async fn execute(self) -> CliTypedResult<&'static str> {
+        let package_path = self.move_options.get_package_path()?;
+        let package = resolve_package(&package_path, self.move_options.env.as_deref())?;
+        let random_tests = expand_random_tests(
+            &package.path(),
+            self.random_runs,
+            self.seed.unwrap_or_else(rand::random),
+        )?;
+        let test_path = match random_tests {
+            Some(ref random_tests) => random_tests.path(),
+            None => package.path(),
+        };
         let config = BuildConfig {
             additional_named_addresses: self.move_options.named_addresses(),
             test_mode: true,
-            install_dir: self.move_options.output_dir.clone(),
+            // A package with random tests or Move.toml settings applied is tested from a copy,
+            // its build stays in the package
+            install_dir: self
+                .move_options
+                .output_dir
+                .clone()
+                .or_else(|| Some(package_path.clone())),
             ..Default::default()
         };
+        let filter = self.filter.clone();
+        let exact = if self.exact { filter.clone() } else { None };
+        // Reports are read back from the runner's output, statistics give the durations and gas
+        let mut output = TestOutput::new(self.format, exact.clone(), self.output.is_some());
         let result = move_cli::base::test::run_move_unit_tests(
-            self.move_options.get_package_path()?.as_path(),
+            test_path.as_path(),
             config,
             UnitTestingConfig {
                 filter: self.filter,
                 report_stacktrace_on_abort: true,
+                report_statistics: self.format != TestFormat::Pretty || self.gas_report,
                 ..UnitTestingConfig::default_with_bound(None)
             },
             // TODO(Gas): we may want to switch to non-zero costs in the future
@@ -346,14 +674,61 @@ impl CliCommand<&'static str> for TestPackage {
                 AbstractValueSizeGasParameters::zeros(),
             ),
             None,
-            false,
-            &mut std::io::stdout(),
+            self.coverage,
+            &mut output,
         )
         .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
 
-        match result {
-            UnitTestResult::Success => Ok("Success"),
-            UnitTestResult::Failure => Err(CliError::MoveTestError),
+        let (results, report) = output.finish(&local_package_name(&package_path)?);
+        if let Some(ref random_tests) = random_tests {
+            random_tests.report_failures(&results);
+        }
+        if self.gas_report {
+            let baseline_path = self
+                .gas_baseline
+                .clone()
+                .unwrap_or_else(|| package_path.join(gas_report::GAS_BASELINE_FILE));
+            let gas = gas_report::gas_used(&results);
+            let baseline = gas_report::load_baseline(&baseline_path)?;
+            eprint!(
+                "{}",
+                gas_report::gas_report(&gas, baseline.as_ref(), self.gas_sort)
+            );
+            if self.save_gas_baseline {
+                gas_report::save_baseline(&baseline_path, &gas)?;
+            }
+        }
+        let snapshots_passed = match (report, &self.output) {
+            (Some(report), Some(path)) => {
+                write_to_file(path, "test report", report.as_bytes())?;
+                run_snapshot_tests(&package_path, filter, self.exact, self.update_snapshots)?
+            }
+            (Some(report), None) => {
+                print!("{}", report);
+                true
+            }
+            (None, _) => {
+                run_snapshot_tests(&package_path, filter, self.exact, self.update_snapshots)?
+            }
+        };
+
+        // Tests extending the exact name also ran, their results don't count
+        let success = match exact {
+            Some(filter) if results.is_empty() => {
+                return Err(CliError::CommandArgumentError(format!(
+                    "No test is named {}",
+                    filter
+                )))
+            }
+            Some(_) => results
+                .iter()
+                .all(|result| result.status == TestStatus::Pass),
+            None => matches!(result, UnitTestResult::Success),
+        };
+        if success && snapshots_passed {
+            Ok("Success")
+        } else {
+            Err(CliError::MoveTestError)
         }
     }
 }
@@ -478,6 +853,27 @@ pub struct PublishPackage {
     #[clap(long)]
     pub(crate) override_size_check: bool,
 
+    /// Publish over several transactions, for packages larger than the size limit
+    ///
+    /// The package is staged under the sender in chunks by the `large_packages` module, whose
+    /// source is in `aptos-move/move-examples/large_packages`, and the last transaction
+    /// publishes it.  If a chunk fails, running the command again resumes after the chunks
+    /// already staged.  If publishing fails, the staged package is removed
+    #[clap(long, requires = "large-packages-address")]
+    pub(crate) chunked_publish: bool,
+
+    /// Address the `large_packages` module is published at, for `--chunked-publish`
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) large_packages_address: Option<AccountAddress>,
+
+    /// Simulate the publish and print what it would change, without submitting it
+    ///
+    /// Lists the modules that are new or changed from what's published, the resources it
+    /// writes with their changed fields, its events, and the gas it uses.  If it fails in one
+    /// of the package's modules, e.g. in `init_module`, the failure is placed in its source.
+    #[clap(long, conflicts_with = "chunked-publish")]
+    pub(crate) dry_run: bool,
+
     #[clap(flatten)]
     pub(crate) included_artifacts_args: IncludedArtifactsArgs,
     #[clap(flatten)]
@@ -567,31 +963,52 @@ impl CliCommand<TransactionSummary> for PublishPackage {
             move_options,
             txn_options,
             override_size_check,
+            chunked_publish,
+            large_packages_address,
+            dry_run,
             included_artifacts_args,
         } = self;
-        let package_path = move_options.get_package_path()?;
-        let options = included_artifacts_args
-            .included_artifacts
-            .build_options(move_options.named_addresses());
-        let package = BuiltPackage::build(package_path, options)?;
+        let resolved = resolve_package(
+            &move_options.get_package_path()?,
+            move_options.env.as_deref(),
+        )?;
+        let options = BuildOptions {
+            install_dir: resolved.install_dir(move_options.output_dir.clone()),
+            ..included_artifacts_args
+                .included_artifacts
+                .build_options(move_options.named_addresses())
+        };
+        let package = BuiltPackage::build(resolved.path(), options)?;
         let compiled_units = package.extract_code();
 
         // Send the compiled module and metadata using the code::publish_package_txn.
         let metadata = package.extract_metadata()?;
-        let payload = cached_packages::aptos_stdlib::code_publish_package_txn(
-            bcs::to_bytes(&metadata).expect("PackageMetadata has BCS"),
-            compiled_units,
-        );
+        let metadata = bcs::to_bytes(&metadata).expect("PackageMetadata has BCS");
+        if let (true, Some(large_packages)) = (chunked_publish, large_packages_address) {
+            return chunked_publish::publish_chunked(
+                &txn_options,
+                large_packages,
+                metadata,
+                compiled_units,
+            )
+            .await;
+        }
+        let payload =
+            cached_packages::aptos_stdlib::code_publish_package_txn(metadata, compiled_units);
         let size = bcs::serialized_size(&payload)?;
         println!("package size {} bytes", size);
         if !override_size_check && size > MAX_PUBLISH_PACKAGE_SIZE {
             return Err(CliError::UnexpectedError(format!(
                 "The package is larger than {} bytes ({} bytes)! To lower the size \
                 you may want to include less artifacts via `--included_artifacts`. \
-                You can also override this check with `--override-size-check",
+                You can also override this check with `--override-size-check`, or publish \
+                in several transactions with `--chunked-publish`",
                 MAX_PUBLISH_PACKAGE_SIZE, size
             )));
         }
+        if dry_run {
+            return dry_run::dry_run(&txn_options, payload, &PackageSources::new(&package)).await;
+        }
         txn_options
             .submit_transaction(payload)
             .await
@@ -751,13 +1168,22 @@ impl CliCommand<&'static str> for DownloadPackage {
 }
 
 /// Downloads a package and verifies that the bytecode matches a local compilation of the Move code
+///
+/// The package's metadata, source digest, and the bytecode of every module are compared, and
+/// all the mismatches are reported
 #[derive(Parser)]
 pub struct VerifyPackage {
     /// Address of the account containing the package
     #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
     pub(crate) account: AccountAddress,
 
+    /// Name of the on-chain package, defaults to the name in Move.toml
+    #[clap(long)]
+    pub(crate) package: Option<String>,
+
     /// Artifacts to be generated when building this package.
+    ///
+    /// Sources and source maps are included if the on-chain package has them
     #[clap(long, default_value_t = IncludedArtifacts::Sparse)]
     pub(crate) included_artifacts: IncludedArtifacts,
 
@@ -772,26 +1198,21 @@ pub struct VerifyPackage {
 #[async_trait]
 impl CliCommand<&'static str> for VerifyPackage {
     fn command_name(&self) -> &'static str {
-        "DownloadPackage"
+        "VerifyPackage"
     }
 
     async fn execute(self) -> CliTypedResult<&'static str> {
-        // First build the package locally to get the package metadata
-        let build_options = BuildOptions {
-            install_dir: self.move_options.output_dir.clone(),
-            ..self
-                .included_artifacts
-                .build_options(self.move_options.named_addresses())
+        let package_dir = self.move_options.get_package_path()?;
+        let package_name = match self.package.clone() {
+            Some(package_name) => package_name,
+            None => local_package_name(&package_dir)?,
         };
-        let pack = BuiltPackage::build(self.move_options.get_package_path()?, build_options)
-            .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
-        let compiled_metadata = pack.extract_metadata()?;
 
-        // Now pull the compiled package
+        // First pull the on-chain package, to build with the same artifacts
         let url = self.rest_options.url(&self.profile_options)?;
-        let registry = CachedPackageRegistry::create(url, self.account).await?;
+        let registry = CachedPackageRegistry::create(url.clone(), self.account).await?;
         let package = registry
-            .get_package(pack.name())
+            .get_package(&package_name)
             .await
             .map_err(|s| CliError::CommandArgumentError(s.to_string()))?;
 
@@ -804,13 +1225,114 @@ impl CliCommand<&'static str> for VerifyPackage {
             ));
         }
 
-        // Verify that the source digest matches
-        package.verify(&compiled_metadata)?;
+        // Now build the package locally to get the package metadata
+        let build_options = BuildOptions {
+            install_dir: self.move_options.output_dir.clone(),
+            with_srcs: package.has_sources(),
+            with_source_maps: package.has_source_maps(),
+            ..self
+                .included_artifacts
+                .build_options(self.move_options.named_addresses())
+        };
+        let pack = BuiltPackage::build(package_dir, build_options)
+            .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
+        let compiled_metadata = pack.extract_metadata()?;
+        let mut mismatches = package.mismatches(&compiled_metadata);
 
-        Ok("Successfully verified source of package")
+        // And compare the bytecode of each module
+        let client = aptos_rest_client::Client::new(url);
+        for module in pack.modules() {
+            let module_id = module.self_id();
+            let name = module_id.name().as_str();
+            if *module_id.address() != self.account {
+                mismatches.push(format!(
+                    "Module {}: compiled for address {}, not {}",
+                    name,
+                    module_id.address(),
+                    self.account
+                ));
+                continue;
+            }
+            let mut local = vec![];
+            module
+                .serialize(&mut local)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            match client.get_account_module(self.account, name).await {
+                Ok(response) => {
+                    let on_chain = response.into_inner().bytecode.0;
+                    if let Some(mismatch) = compare_bytecode(&local, &on_chain) {
+                        mismatches.push(format!("Module {}: {}", name, mismatch));
+                    }
+                }
+                Err(err) => mismatches.push(format!(
+                    "Module {}: failed to fetch the on-chain bytecode: {}",
+                    name, err
+                )),
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok("Successfully verified source of package")
+        } else {
+            Err(CliError::UnexpectedError(format!(
+                "Package {} at {} doesn't match the local source:\n{}",
+                package_name,
+                self.account,
+                mismatches.join("\n")
+            )))
+        }
     }
 }
 
+/// Reads the package name from a package's Move.toml
+fn local_package_name(package_dir: &Path) -> CliTypedResult<String> {
+    let (_, manifest) = dependencies::load_manifest(package_dir)?;
+    manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .map(|name| name.to_string())
+        .ok_or_else(|| CliError::UnableToParse("Move.toml", "missing package name".to_string()))
+}
+
+/// Describes how a local module's bytecode differs from the on-chain one, if it does
+fn compare_bytecode(local: &[u8], on_chain: &[u8]) -> Option<String> {
+    if local == on_chain {
+        return None;
+    }
+    let (mut local_module, mut on_chain_module) = match (
+        CompiledModule::deserialize(local),
+        CompiledModule::deserialize(on_chain),
+    ) {
+        (Ok(local_module), Ok(on_chain_module)) => (local_module, on_chain_module),
+        (_, Err(err)) => {
+            return Some(format!(
+                "the on-chain bytecode doesn't deserialize: {}",
+                err
+            ))
+        }
+        (Err(err), _) => return Some(format!("the local bytecode doesn't deserialize: {}", err)),
+    };
+    if local_module.version != on_chain_module.version {
+        return Some(format!(
+            "bytecode version {} doesn't match {}",
+            local_module.version, on_chain_module.version
+        ));
+    }
+    if local_module.metadata != on_chain_module.metadata {
+        local_module.metadata.clear();
+        on_chain_module.metadata.clear();
+        if local_module == on_chain_module {
+            return Some("metadata doesn't match, the code does".to_string());
+        }
+    }
+    Some(format!(
+        "bytecode doesn't match ({} bytes locally, {} on chain)",
+        local.len(),
+        on_chain.len()
+    ))
+}
+
 /// Lists information about packages and modules on-chain for an account
 #[derive(Parser)]
 pub struct ListPackage {
@@ -879,47 +1401,50 @@ impl CliCommand<&'static str> for ListPackage {
     }
 }
 
-/// Cleans derived artifacts of a package.
+/// Run a Move function
 #[derive(Parser)]
-pub struct CleanPackage {
+pub struct RunFunction {
     #[clap(flatten)]
-    pub(crate) move_options: MovePackageDir,
+    pub(crate) entry_function_args: EntryFunctionArguments,
+
+    /// Simulate the call and print what it would change, without submitting it
+    ///
+    /// Lists the resources it writes with their changed fields, its events decoded with the
+    /// ABIs of their modules, and the gas it uses
+    #[clap(long)]
+    pub(crate) dry_run: bool,
+
     #[clap(flatten)]
-    pub(crate) prompt_options: PromptOptions,
+    pub(crate) txn_options: TransactionOptions,
 }
 
 #[async_trait]
-impl CliCommand<&'static str> for CleanPackage {
+impl CliCommand<TransactionSummary> for RunFunction {
     fn command_name(&self) -> &'static str {
-        "CleanPackage"
+        "RunFunction"
     }
 
-    async fn execute(self) -> CliTypedResult<&'static str> {
-        let path = self.move_options.get_package_path()?;
-        let build_dir = path.join("build");
-        std::fs::remove_dir_all(build_dir)
-            .map_err(|e| CliError::IO("Removing Move build dir".to_string(), e))?;
-
-        let move_dir = &*MOVE_HOME;
-        if prompt_yes_with_override(
-            &format!(
-                "Do you also want to delete the local package download cache at `{}`?",
-                move_dir
-            ),
-            self.prompt_options,
-        )
-        .is_ok()
-        {
-            std::fs::remove_dir_all(PathBuf::from(move_dir))
-                .map_err(|e| CliError::IO("Removing Move cache dir".to_string(), e))?;
+    async fn execute(self) -> CliTypedResult<TransactionSummary> {
+        let payload = self
+            .entry_function_args
+            .create_payload(
+                &self.txn_options.rest_options,
+                &self.txn_options.profile_options,
+            )
+            .await?;
+        if self.dry_run {
+            return dry_run::dry_run(&self.txn_options, payload, &PackageSources::default()).await;
         }
-        Ok("succeeded")
+        self.txn_options
+            .submit_transaction(payload)
+            .await
+            .map(TransactionSummary::from)
     }
 }
 
-/// Run a Move function
+/// The function to call and its arguments, for `aptos move run` and `aptos transaction build`
 #[derive(Parser)]
-pub struct RunFunction {
+pub struct EntryFunctionArguments {
     /// Function name as `<ADDRESS>::<MODULE_ID>::<FUNCTION_NAME>`
     ///
     /// Example: `0x842ed41fad9640a2ad08fdd7d3e4f7f505319aac7d67e1c0dd6a7cce8732c7e3::message::set_message`
@@ -934,46 +1459,74 @@ pub struct RunFunction {
     #[clap(long, multiple_values = true)]
     pub(crate) args: Vec<ArgWithType>,
 
+    /// Arguments as a JSON array, typed by the function's ABI
+    ///
+    /// Structs are objects of their fields, options `null` or their value, objects their
+    /// address, and numbers can be strings.  Signer parameters are left out.
+    ///
+    /// Example: `'[[{"name": "a", "amount": "5"}], null, "0x1"]'`
+    #[clap(long, conflicts_with = "args")]
+    pub(crate) json_args: Option<String>,
+
     /// TypeTag arguments separated by spaces.
     ///
     /// Example: `u8 u64 u128 bool address vector signer`
     #[clap(long, multiple_values = true)]
     pub(crate) type_args: Vec<MoveType>,
 
-    #[clap(flatten)]
-    pub(crate) txn_options: TransactionOptions,
+    /// JSON file of the type arguments and arguments, instead of giving them as options
+    ///
+    /// Example: `{"type_args": ["u64"], "args": [{"type": "u64", "value": 5}]}`
+    #[clap(long, parse(from_os_str), conflicts_with_all = &["args", "json-args", "type-args"])]
+    pub(crate) args_file: Option<PathBuf>,
 }
 
-#[async_trait]
-impl CliCommand<TransactionSummary> for RunFunction {
-    fn command_name(&self) -> &'static str {
-        "RunFunction"
-    }
-
-    async fn execute(self) -> CliTypedResult<TransactionSummary> {
-        let args: Vec<Vec<u8>> = self
-            .args
-            .into_iter()
-            .map(|arg_with_type| arg_with_type.arg)
-            .collect();
+impl EntryFunctionArguments {
+    /// Builds the payload calling the function
+    ///
+    /// The node is only queried for the function's ABI, when there are JSON arguments
+    pub(crate) async fn create_payload(
+        self,
+        rest_options: &RestOptions,
+        profile_options: &ProfileOptions,
+    ) -> CliTypedResult<TransactionPayload> {
+        let (args, json_args, move_type_args) = match &self.args_file {
+            Some(path) => {
+                let args_file = ArgsFile::load(path)?;
+                (
+                    args_file.args()?,
+                    args_file.json_args.as_ref().map(|json| json.to_string()),
+                    args_file.type_args()?,
+                )
+            }
+            None => (self.args, self.json_args, self.type_args),
+        };
+        let args: Vec<Vec<u8>> = match &json_args {
+            Some(json_args) => {
+                let client = rest_options.client(profile_options)?;
+                json_args::encode_json_args(&client, &self.function_id, &move_type_args, json_args)
+                    .await?
+            }
+            None => args
+                .into_iter()
+                .map(|arg_with_type| arg_with_type.arg)
+                .collect(),
+        };
         let mut type_args: Vec<TypeTag> = Vec::new();
 
         // These TypeArgs are used for generics
-        for type_arg in self.type_args.into_iter() {
+        for type_arg in move_type_args.into_iter() {
             let type_tag = TypeTag::try_from(type_arg)
                 .map_err(|err| CliError::UnableToParse("--type-args", err.to_string()))?;
             type_args.push(type_tag)
         }
 
-        self.txn_options
-            .submit_transaction(TransactionPayload::EntryFunction(EntryFunction::new(
-                self.function_id.module_id,
-                self.function_id.member_id,
-                type_args,
-                args,
-            )))
-            .await
-            .map(TransactionSummary::from)
+        Ok(TransactionPayload::EntryFunction(EntryFunction::new(
+            self.function_id.module_id,
+            self.function_id.member_id,
+            type_args,
+            args,
+        )))
     }
 }
 
@@ -996,6 +1549,11 @@ pub struct RunScript {
     /// Example: `u8 u64 u128 bool address vector signer`
     #[clap(long, multiple_values = true)]
     pub(crate) type_args: Vec<MoveType>,
+    /// JSON file of the type arguments and arguments, instead of giving them as options
+    ///
+    /// Example: `{"type_args": ["u64"], "args": [{"type": "u64", "value": 5}]}`
+    #[clap(long, parse(from_os_str), conflicts_with_all = &["args", "type-args"])]
+    pub(crate) args_file: Option<PathBuf>,
 }
 
 #[async_trait]
@@ -1009,15 +1567,28 @@ impl CliCommand<TransactionSummary> for RunScript {
             .compile_proposal_args
             .compile("RunScript", self.txn_options.prompt_options)?;
 
+        let (file_args, move_type_args) = match &self.args_file {
+            Some(path) => {
+                let args_file = ArgsFile::load(path)?;
+                if args_file.json_args.is_some() {
+                    return Err(CliError::UnableToParse(
+                        "--args-file",
+                        "scripts take args, not json_args".to_string(),
+                    ));
+                }
+                (args_file.args()?, args_file.type_args()?)
+            }
+            None => (self.args, self.type_args),
+        };
         let mut args: Vec<TransactionArgument> = vec![];
-        for arg in self.args {
+        for arg in file_args {
             args.push(arg.try_into()?);
         }
 
         let mut type_args: Vec<TypeTag> = Vec::new();
 
         // These TypeArgs are used for generics
-        for type_arg in self.type_args.into_iter() {
+        for type_arg in move_type_args.into_iter() {
             let type_tag = TypeTag::try_from(type_arg)
                 .map_err(|err| CliError::UnableToParse("--type-args", err.to_string()))?;
             type_args.push(type_tag)
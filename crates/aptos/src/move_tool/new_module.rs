@@ -0,0 +1,398 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scaffolding for Move modules in an existing package
+//!
+//! Each kind of module is a small example of a common pattern, with a test module exercising it.
+//! The templates are filled in by replacing `{address}` and `{module}`.
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, MovePackageDir, PromptOptions},
+        utils::{create_dir_if_not_exist, prompt_yes_with_override, read_from_file, write_to_file},
+    },
+    move_tool::{
+        declared_named_addresses,
+        dependencies::{ensure_framework_dependency, load_manifest, save_manifest},
+    },
+};
+use async_trait::async_trait;
+use clap::{ArgEnum, Parser};
+use move_core_types::identifier::Identifier;
+use move_package::source_package::layout::SourcePackageLayout;
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+    path::Path,
+    str::FromStr,
+};
+use toml_edit::{Item, Table};
+
+/// Dev address given to a new named address that has no address yet, so the tests can run
+pub(crate) const DEV_ADDRESS: &str = "0xcafe";
+
+#[derive(ArgEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ModuleKind {
+    /// Emitting events from an event handle stored under the account
+    Events,
+    /// A resource stored under the account, created on first use
+    Storage,
+    /// A capability only the publisher is granted, checked by a guarded function
+    Capability,
+}
+
+impl Display for ModuleKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ModuleKind::Events => "events",
+            ModuleKind::Storage => "storage",
+            ModuleKind::Capability => "capability",
+        })
+    }
+}
+
+impl FromStr for ModuleKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "events" => Ok(ModuleKind::Events),
+            "storage" => Ok(ModuleKind::Storage),
+            "capability" => Ok(ModuleKind::Capability),
+            _ => Err("Invalid kind. Valid values are events, storage, capability"),
+        }
+    }
+}
+
+impl ModuleKind {
+    /// The framework package the module's code uses
+    fn dependency(&self) -> &'static str {
+        match self {
+            ModuleKind::Events => "AptosFramework",
+            ModuleKind::Storage | ModuleKind::Capability => "MoveStdlib",
+        }
+    }
+
+    fn templates(&self) -> (&'static str, &'static str) {
+        match self {
+            ModuleKind::Events => (EVENTS_MODULE, EVENTS_TESTS),
+            ModuleKind::Storage => (STORAGE_MODULE, STORAGE_TESTS),
+            ModuleKind::Capability => (CAPABILITY_MODULE, CAPABILITY_TESTS),
+        }
+    }
+}
+
+/// Create a Move module in the package, from a template of a common pattern
+///
+/// The module is written to `sources/<name>.move`, and a test module for it to
+/// `tests/<name>_tests.move`.  The kinds of module are:
+/// - events: emits events from an event handle stored under the account
+/// - storage: stores a resource under the account, created on first use
+/// - capability: grants a capability to the publisher, and checks it in a guarded function
+///
+/// The module is declared under `--address`.  If Move.toml doesn't declare that named address,
+/// it's added with its address from `--named-addresses`, or as a placeholder `_` with a dev
+/// address for the tests.  The framework package the module uses is added to the dependencies
+/// if it's missing.
+///
+/// Example: `aptos move new-module vault --kind capability`
+#[derive(Parser)]
+pub struct NewModule {
+    /// Name of the module, and of its file
+    pub(crate) name: Identifier,
+
+    /// Kind of module: [events, storage, capability]
+    #[clap(long, default_value_t = ModuleKind::Storage)]
+    pub(crate) kind: ModuleKind,
+
+    /// Named address to declare the module under
+    ///
+    /// Defaults to the named address the package's modules are declared under, if there's
+    /// exactly one
+    #[clap(long)]
+    pub(crate) address: Option<Identifier>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<Vec<String>> for NewModule {
+    fn command_name(&self) -> &'static str {
+        "NewModule"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<String>> {
+        let package_dir = self.move_options.get_package_path()?;
+        let address = match self.address {
+            Some(ref address) => address.to_string(),
+            None => package_address(&package_dir)?,
+        };
+        let module_path = package_dir
+            .join(SourcePackageLayout::Sources.path())
+            .join(format!("{}.move", self.name));
+        let tests_dir = package_dir.join(SourcePackageLayout::Tests.path());
+        let tests_path = tests_dir.join(format!("{}_tests.move", self.name));
+        for path in [&module_path, &tests_path] {
+            if path.exists() {
+                prompt_yes_with_override(
+                    &format!("{} already exists, overwrite it?", path.display()),
+                    self.prompt_options,
+                )?;
+            }
+        }
+
+        let mut changed = vec![];
+        let manifest_path = package_dir.join(SourcePackageLayout::Manifest.path());
+        if self.add_named_address(&package_dir, &address)? {
+            eprintln!("Added named address {} to Move.toml", address);
+            changed.push(manifest_path.display().to_string());
+        }
+        if ensure_framework_dependency(&package_dir, self.kind.dependency())? {
+            eprintln!("Added {} to the dependencies", self.kind.dependency());
+            changed.push(manifest_path.display().to_string());
+        }
+
+        let (module, tests) = self.kind.templates();
+        let fill = |template: &str| {
+            template
+                .replace("{address}", &address)
+                .replace("{module}", self.name.as_str())
+        };
+        create_dir_if_not_exist(&tests_dir)?;
+        for (path, template) in [(&module_path, module), (&tests_path, tests)] {
+            write_to_file(path, &path.display().to_string(), fill(template).as_bytes())?;
+            changed.push(path.display().to_string());
+        }
+        changed.dedup();
+        Ok(changed)
+    }
+}
+
+impl NewModule {
+    /// Declares the named address in Move.toml if it isn't, returning whether it was added
+    fn add_named_address(&self, package_dir: &Path, address: &str) -> CliTypedResult<bool> {
+        let (_, mut manifest) = load_manifest(package_dir)?;
+        let declared = manifest
+            .get("addresses")
+            .and_then(|addresses| addresses.get(address))
+            .is_some();
+        if declared {
+            return Ok(false);
+        }
+
+        let value = self.move_options.named_addresses().get(address).copied();
+        address_table(&mut manifest, "addresses")?.insert(
+            address,
+            toml_edit::value(value.map_or_else(|| "_".to_string(), |value| value.to_hex_literal())),
+        );
+        if value.is_none() {
+            let dev_addresses = address_table(&mut manifest, "dev-addresses")?;
+            if !dev_addresses.contains_key(address) {
+                dev_addresses.insert(address, toml_edit::value(DEV_ADDRESS));
+            }
+        }
+        save_manifest(package_dir, &manifest.to_string())?;
+        Ok(true)
+    }
+}
+
+fn address_table<'a>(
+    manifest: &'a mut toml_edit::Document,
+    name: &str,
+) -> CliTypedResult<&'a mut Table> {
+    manifest
+        .as_table_mut()
+        .entry(name)
+        .or_insert(Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| CliError::UnableToParse("Move.toml", format!("[{}] is not a table", name)))
+}
+
+/// The one named address the package's modules are declared under
+fn package_address(package_dir: &Path) -> CliTypedResult<String> {
+    let mut addresses = BTreeSet::new();
+    let sources_dir = package_dir.join(SourcePackageLayout::Sources.path());
+    for entry in walkdir::WalkDir::new(&sources_dir).into_iter().flatten() {
+        let path = entry.path();
+        if entry.file_type().is_file() && path.extension().map_or(false, |ext| ext == "move") {
+            addresses.extend(declared_named_addresses(&String::from_utf8(
+                read_from_file(path)?,
+            )?));
+        }
+    }
+    if addresses.len() == 1 {
+        Ok(addresses.into_iter().next().unwrap())
+    } else {
+        Err(CliError::CommandArgumentError(format!(
+            "Please provide --address, the package's modules are declared under {}",
+            if addresses.is_empty() {
+                "no named address".to_string()
+            } else {
+                addresses.into_iter().collect::<Vec<_>>().join(", ")
+            }
+        )))
+    }
+}
+
+const STORAGE_MODULE: &str = r#"module {address}::{module} {
+    use std::error;
+    use std::signer;
+
+    /// The account has no counter
+    const ENO_COUNTER: u64 = 1;
+
+    struct Counter has key {
+        value: u64,
+    }
+
+    /// Increments the account's counter, creating it on first use
+    public entry fun increment(account: &signer) acquires Counter {
+        let addr = signer::address_of(account);
+        if (!exists<Counter>(addr)) {
+            move_to(account, Counter { value: 0 });
+        };
+        let counter = borrow_global_mut<Counter>(addr);
+        counter.value = counter.value + 1;
+    }
+
+    public fun value(addr: address): u64 acquires Counter {
+        assert!(exists<Counter>(addr), error::not_found(ENO_COUNTER));
+        borrow_global<Counter>(addr).value
+    }
+}
+"#;
+
+const STORAGE_TESTS: &str = r#"#[test_only]
+module {address}::{module}_tests {
+    use std::signer;
+    use {address}::{module};
+
+    #[test(account = @{address})]
+    fun test_increment(account: &signer) {
+        {module}::increment(account);
+        {module}::increment(account);
+        assert!({module}::value(signer::address_of(account)) == 2, 0);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 0x60001)]
+    fun test_value_without_counter() {
+        {module}::value(@0x123);
+    }
+}
+"#;
+
+const EVENTS_MODULE: &str = r#"module {address}::{module} {
+    use std::signer;
+    use aptos_framework::account;
+    use aptos_framework::event::{Self, EventHandle};
+
+    struct MessageEvent has drop, store {
+        message: vector<u8>,
+    }
+
+    struct Events has key {
+        message_events: EventHandle<MessageEvent>,
+    }
+
+    /// Emits a message event, creating the account's event handle on first use
+    public entry fun post(account: &signer, message: vector<u8>) acquires Events {
+        let addr = signer::address_of(account);
+        if (!exists<Events>(addr)) {
+            move_to(account, Events {
+                message_events: account::new_event_handle<MessageEvent>(account),
+            });
+        };
+        let events = borrow_global_mut<Events>(addr);
+        event::emit_event(&mut events.message_events, MessageEvent { message });
+    }
+
+    public fun event_count(addr: address): u64 acquires Events {
+        if (!exists<Events>(addr)) {
+            return 0
+        };
+        event::counter(&borrow_global<Events>(addr).message_events)
+    }
+}
+"#;
+
+const EVENTS_TESTS: &str = r#"#[test_only]
+module {address}::{module}_tests {
+    use std::signer;
+    use aptos_framework::account;
+    use {address}::{module};
+
+    #[test(account = @{address})]
+    fun test_post(account: &signer) {
+        let addr = signer::address_of(account);
+        account::create_account_for_test(addr);
+        {module}::post(account, b"hello");
+        assert!({module}::event_count(addr) == 1, 0);
+    }
+}
+"#;
+
+const CAPABILITY_MODULE: &str = r#"module {address}::{module} {
+    use std::error;
+    use std::signer;
+
+    /// Only the publisher of the module can be granted the capability
+    const ENOT_PUBLISHER: u64 = 1;
+    /// The account doesn't have the capability
+    const ENO_CAPABILITY: u64 = 2;
+
+    /// Allows its holder to call `guarded`.  It has no `store`, so it can't be transferred
+    struct AdminCapability has key {}
+
+    /// Grants the capability to the publisher
+    public entry fun initialize(publisher: &signer) {
+        assert!(
+            signer::address_of(publisher) == @{address},
+            error::permission_denied(ENOT_PUBLISHER)
+        );
+        move_to(publisher, AdminCapability {});
+    }
+
+    public fun has_capability(addr: address): bool {
+        exists<AdminCapability>(addr)
+    }
+
+    /// Can only be called by holders of the capability
+    public entry fun guarded(account: &signer) {
+        assert!(
+            has_capability(signer::address_of(account)),
+            error::permission_denied(ENO_CAPABILITY)
+        );
+        // TODO: what holders of the capability can do
+    }
+}
+"#;
+
+const CAPABILITY_TESTS: &str = r#"#[test_only]
+module {address}::{module}_tests {
+    use std::signer;
+    use {address}::{module};
+
+    #[test(publisher = @{address})]
+    fun test_publisher_is_granted(publisher: &signer) {
+        {module}::initialize(publisher);
+        assert!({module}::has_capability(signer::address_of(publisher)), 0);
+        {module}::guarded(publisher);
+    }
+
+    #[test(other = @0x123)]
+    #[expected_failure(abort_code = 0x50001)]
+    fun test_others_are_not_granted(other: &signer) {
+        {module}::initialize(other);
+    }
+
+    #[test(other = @0x123)]
+    #[expected_failure(abort_code = 0x50002)]
+    fun test_guarded_without_capability(other: &signer) {
+        {module}::guarded(other);
+    }
+}
+"#;
@@ -0,0 +1,217 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scaffolding for Move scripts in a package's `scripts` directory
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, MovePackageDir, PromptOptions},
+        utils::{create_dir_if_not_exist, prompt_yes_with_override, write_to_file},
+    },
+    move_tool::{dependencies::ensure_framework_dependency, FunctionArgType},
+};
+use async_trait::async_trait;
+use clap::Parser;
+use move_core_types::identifier::Identifier;
+use move_package::source_package::layout::SourcePackageLayout;
+use std::{collections::BTreeSet, str::FromStr};
+
+/// Create a Move script with a `main` function to fill in
+///
+/// The script is written to `scripts/<name>.move`, with a `main` taking `--signers` signers
+/// followed by the `--params`.  If a parameter needs the Move standard library, and the package
+/// doesn't depend on a framework package, MoveStdlib is added to its dependencies.
+///
+/// Prints the `aptos move run-script` command to run the script with.
+///
+/// Example: `aptos move new-script transfer --params recipient:address amount:u64`
+#[derive(Parser)]
+pub struct NewScript {
+    /// Name of the script, and of its file
+    pub(crate) name: Identifier,
+
+    /// Number of signers `main` takes
+    ///
+    /// `aptos move run-script` submits with a single signer, the sender
+    #[clap(long, default_value_t = 1)]
+    pub(crate) signers: usize,
+
+    /// Parameters of `main`, after its signers, as `<name>:<type>` separated by spaces
+    ///
+    /// Types are those of `aptos move run-script`'s `--args` [address, bool, hex, string, u8,
+    /// u64, u128]
+    ///
+    /// Example: `recipient:address amount:u64`
+    #[clap(long, multiple_values = true)]
+    pub(crate) params: Vec<ScriptParam>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+/// A parameter of a script's `main`
+#[derive(Clone, Debug)]
+pub struct ScriptParam {
+    pub(crate) name: Identifier,
+    pub(crate) ty: FunctionArgType,
+}
+
+impl ScriptParam {
+    /// The parameter's type in Move
+    fn move_type(&self) -> &'static str {
+        match self.ty {
+            FunctionArgType::Address => "address",
+            FunctionArgType::Bool => "bool",
+            FunctionArgType::Hex => "vector<u8>",
+            FunctionArgType::String => "String",
+            FunctionArgType::U8 => "u8",
+            FunctionArgType::U64 => "u64",
+            FunctionArgType::U128 => "u128",
+            FunctionArgType::HexArray | FunctionArgType::Raw => {
+                unreachable!("Rejected when parsing")
+            }
+        }
+    }
+
+    /// The parameter's argument type for `aptos move run-script`
+    fn arg_type(&self) -> &'static str {
+        match self.ty {
+            FunctionArgType::Address => "address",
+            FunctionArgType::Bool => "bool",
+            FunctionArgType::Hex => "hex",
+            FunctionArgType::String => "string",
+            FunctionArgType::U8 => "u8",
+            FunctionArgType::U64 => "u64",
+            FunctionArgType::U128 => "u128",
+            FunctionArgType::HexArray | FunctionArgType::Raw => {
+                unreachable!("Rejected when parsing")
+            }
+        }
+    }
+}
+
+impl FromStr for ScriptParam {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, type_name) = s.split_once(':').ok_or_else(|| {
+            CliError::CommandArgumentError(
+                "Parameters must be pairs of <name>:<type> e.g. amount:u64".to_string(),
+            )
+        })?;
+        let name = Identifier::from_str(name)
+            .map_err(|err| CliError::UnableToParse("--params", err.to_string()))?;
+        let ty = FunctionArgType::from_str(type_name)?;
+        if matches!(ty, FunctionArgType::HexArray | FunctionArgType::Raw) {
+            return Err(CliError::CommandArgumentError(format!(
+                "Parameter {} can't be of type {}, scripts take [address, bool, hex, string, u8, \
+                u64, u128]",
+                name, type_name
+            )));
+        }
+        Ok(ScriptParam { name, ty })
+    }
+}
+
+#[async_trait]
+impl CliCommand<String> for NewScript {
+    fn command_name(&self) -> &'static str {
+        "NewScript"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        let mut names = BTreeSet::new();
+        for param in &self.params {
+            if !names.insert(param.name.as_str()) {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Parameter {} is given more than once",
+                    param.name
+                )));
+            }
+        }
+
+        let package_dir = self.move_options.get_package_path()?;
+        let scripts_dir = package_dir.join(SourcePackageLayout::Scripts.path());
+        let script_path = scripts_dir.join(format!("{}.move", self.name));
+        if script_path.exists() {
+            prompt_yes_with_override(
+                &format!("{} already exists, overwrite it?", script_path.display()),
+                self.prompt_options,
+            )?;
+        }
+
+        if self
+            .params
+            .iter()
+            .any(|param| matches!(param.ty, FunctionArgType::String))
+            && ensure_framework_dependency(&package_dir, "MoveStdlib")?
+        {
+            eprintln!("Added MoveStdlib to the dependencies, for `std::string`");
+        }
+        create_dir_if_not_exist(&scripts_dir)?;
+        write_to_file(
+            &script_path,
+            &script_path.display().to_string(),
+            script_source(self.signers, &self.params).as_bytes(),
+        )?;
+        eprintln!("Created {}", script_path.display());
+        if self.signers > 1 {
+            eprintln!(
+                "Warning: `aptos move run-script` submits with one signer, the script takes {}",
+                self.signers
+            );
+        }
+
+        // Relative to the working directory if the package is under it, as the user would type it
+        let shown_path = std::env::current_dir()
+            .ok()
+            .and_then(|dir| {
+                script_path
+                    .strip_prefix(dir)
+                    .ok()
+                    .map(|path| path.to_path_buf())
+            })
+            .unwrap_or_else(|| script_path.clone());
+        let mut command = format!(
+            "aptos move run-script --script-path {}",
+            shown_path.display()
+        );
+        if !self.params.is_empty() {
+            command.push_str(" --args");
+            for param in &self.params {
+                command.push_str(&format!(" {}:<{}>", param.arg_type(), param.name));
+            }
+        }
+        Ok(command)
+    }
+}
+
+/// The script, with an empty `main` of the signers and parameters
+pub(crate) fn script_source(signers: usize, params: &[ScriptParam]) -> String {
+    let mut arguments: Vec<String> = match signers {
+        1 => vec!["account: &signer".to_string()],
+        _ => (1..=signers)
+            .map(|index| format!("account_{}: &signer", index))
+            .collect(),
+    };
+    arguments.extend(
+        params
+            .iter()
+            .map(|param| format!("{}: {}", param.name, param.move_type())),
+    );
+
+    let mut source = "script {\n".to_string();
+    if params
+        .iter()
+        .any(|param| matches!(param.ty, FunctionArgType::String))
+    {
+        source.push_str("    use std::string::String;\n\n");
+    }
+    source.push_str(&format!(
+        "    fun main({}) {{\n        // TODO: call the package's functions\n    }}\n}}\n",
+        arguments.join(", ")
+    ));
+    source
+}
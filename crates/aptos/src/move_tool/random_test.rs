@@ -0,0 +1,288 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Randomized unit tests, functions annotated `#[random_test]` that take generated arguments
+//!
+//! The unit test runner only knows `#[test]` functions, which take nothing but signers.  So the
+//! package is copied to a temporary directory, where each random test is made `#[test_only]`
+//! and preceded by `#[test]` functions calling it with arguments generated from the seed.
+
+use crate::{
+    common::{
+        types::{CliError, CliTypedResult},
+        utils::{read_from_file, write_to_file},
+    },
+    move_tool::{
+        dependencies::copy_package,
+        fuzz::{is_string, Generator, Value},
+        test_report::{TestCaseResult, TestStatus},
+    },
+};
+use aptos_rest_client::aptos_api_types::MoveType;
+use aptos_types::account_address::AccountAddress;
+use move_package::source_package::layout::SourcePackageLayout;
+use rand::{rngs::StdRng, SeedableRng};
+use regex::Regex;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tempfile::TempDir;
+
+/// Longest vector or string generated for a random test
+const MAX_LENGTH: usize = 16;
+
+/// A package with its random tests expanded into unit tests
+pub struct RandomTests {
+    /// The copy of the package, removed when dropped
+    package: TempDir,
+    seed: u64,
+    /// The call each generated test makes, by `<module>::<test>`
+    calls: BTreeMap<String, String>,
+}
+
+impl RandomTests {
+    pub fn path(&self) -> PathBuf {
+        self.package.path().to_path_buf()
+    }
+
+    /// Prints the arguments of the failed random tests, and how to replay them
+    pub fn report_failures(&self, results: &[TestCaseResult]) {
+        let failures: Vec<_> = results
+            .iter()
+            .filter(|result| result.status != TestStatus::Pass)
+            .filter_map(|result| {
+                let module = result.module.rsplit("::").next().unwrap_or(&result.module);
+                self.calls
+                    .get(&format!("{}::{}", module, result.name))
+                    .map(|call| (result.qualified_name(), call))
+            })
+            .collect();
+        if failures.is_empty() {
+            return;
+        }
+        eprintln!("Random tests failed with seed {}:", self.seed);
+        for (name, call) in failures {
+            eprintln!("  {}: {}", name, call);
+        }
+        eprintln!(
+            "Replay them with `--seed {}`, and one of them with `--filter` on its name",
+            self.seed
+        );
+    }
+}
+
+/// Copies a package with its random tests expanded, if it has any
+///
+/// Each random test gets `runs` tests named `<function>_random_<n>`, and attributes following
+/// `#[random_test]`, like `#[expected_failure]`, move to them
+pub fn expand_random_tests(
+    package_path: &Path,
+    runs: usize,
+    seed: u64,
+) -> CliTypedResult<Option<RandomTests>> {
+    let source_dirs = [SourcePackageLayout::Sources, SourcePackageLayout::Tests];
+    let mut sources = BTreeMap::new();
+    for dir in source_dirs {
+        for entry in walkdir::WalkDir::new(package_path.join(dir.path()))
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "move"))
+        {
+            let text = String::from_utf8(read_from_file(entry.path())?).map_err(|err| {
+                CliError::UnableToReadFile(entry.path().display().to_string(), err.to_string())
+            })?;
+            if text.contains("#[random_test]") {
+                let relative = entry
+                    .path()
+                    .strip_prefix(package_path)
+                    .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                sources.insert(relative.to_path_buf(), text);
+            }
+        }
+    }
+    if sources.is_empty() {
+        return Ok(None);
+    }
+
+    let package = TempDir::new().map_err(|err| {
+        CliError::UnexpectedError(format!("Failed to create temporary directory {}", err))
+    })?;
+    copy_package(package_path, package.path())?;
+
+    let mut calls = BTreeMap::new();
+    for (relative, text) in sources {
+        let expanded = expand(&text, runs, seed, &mut calls).map_err(|err| {
+            CliError::CommandArgumentError(format!("{}: {}", relative.display(), err))
+        })?;
+        write_to_file(
+            &package.path().join(&relative),
+            &relative.display().to_string(),
+            expanded.as_bytes(),
+        )?;
+    }
+
+    Ok(Some(RandomTests {
+        package,
+        seed,
+        calls,
+    }))
+}
+
+/// Expands the random tests of a source file
+fn expand(
+    text: &str,
+    runs: usize,
+    seed: u64,
+    calls: &mut BTreeMap<String, String>,
+) -> Result<String, String> {
+    let random_test = Regex::new(
+        r"#\[random_test\]((?:\s*#\[[^\]]*\])*)\s*((?:public(?:\s*\(\s*\w+\s*\))?\s+)?fun\s+(\w+)\s*(<[^>]*>)?\s*\(([^)]*)\))",
+    )
+    .expect("valid regex");
+    let module = Regex::new(r"module\s+(?:\w+::)?(\w+)\s*\{").expect("valid regex");
+
+    let mut expanded = String::new();
+    let mut end = 0;
+    for captures in random_test.captures_iter(text) {
+        let whole = captures.get(0).expect("match");
+        let name = &captures[3];
+        if captures.get(4).is_some() {
+            return Err(format!("random test {} can't be generic", name));
+        }
+        let module_name = module
+            .captures_iter(&text[..whole.start()])
+            .last()
+            .map(|captures| captures[1].to_string())
+            .ok_or_else(|| format!("random test {} isn't in a module", name))?;
+        let indent: String = text[..whole.start()]
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+
+        let params = parameters(&captures[5])
+            .map_err(|typ| format!("random test {} can't take arguments of type {}", name, typ))?;
+        let attributes: Vec<_> = captures[1]
+            .split('\n')
+            .map(str::trim)
+            .filter(|attribute| !attribute.is_empty())
+            .collect();
+        // Every random test gets the same arguments for a seed, whatever else changes
+        let mut rng = StdRng::seed_from_u64(seed);
+        let generator = Generator {
+            sender: AccountAddress::ONE,
+            max_length: MAX_LENGTH,
+        };
+
+        expanded.push_str(&text[end..whole.start()]);
+        for run in 0..runs {
+            let test = format!("{}_random_{}", name, run);
+            let mut signers = vec![];
+            let mut args = vec![];
+            for param in &params {
+                match param {
+                    Param::Signer { reference } => {
+                        let address = generator.generate(&MoveType::Address, &mut rng);
+                        let signer = format!("s{}", signers.len());
+                        args.push(if *reference {
+                            format!("&{}", signer)
+                        } else {
+                            signer.clone()
+                        });
+                        signers.push(format!("{} = {}", signer, address.move_literal()));
+                    }
+                    Param::Value(typ) => {
+                        args.push(generator.generate(typ, &mut rng).move_literal());
+                    }
+                }
+            }
+            let call = format!("{}({})", name, args.join(", "));
+
+            if signers.is_empty() {
+                expanded.push_str("#[test]\n");
+            } else {
+                let _ = writeln!(expanded, "#[test({})]", signers.join(", "));
+            }
+            for attribute in &attributes {
+                let _ = writeln!(expanded, "{}{}", indent, attribute);
+            }
+            let _ = write!(
+                expanded,
+                "{0}fun {1}({2}) {{\n{0}    {3};\n{0}}}\n\n{0}",
+                indent,
+                test,
+                (0..signers.len())
+                    .map(|index| format!("s{}: signer", index))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                call
+            );
+            calls.insert(format!("{}::{}", module_name, test), call);
+        }
+        let _ = write!(expanded, "#[test_only]\n{}{}", indent, &captures[2]);
+        end = whole.end();
+    }
+    expanded.push_str(&text[end..]);
+    Ok(expanded)
+}
+
+enum Param {
+    Signer { reference: bool },
+    Value(MoveType),
+}
+
+/// Parses the parameters of a random test, returning the first unsupported type on failure
+fn parameters(text: &str) -> Result<Vec<Param>, String> {
+    let mut params = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ',')))
+    {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                let param = &text[start..index];
+                start = index + 1;
+                let typ = match param.split_once(':') {
+                    Some((_, typ)) => typ.split_whitespace().collect::<String>(),
+                    None if param.trim().is_empty() => continue,
+                    None => return Err(param.trim().to_string()),
+                };
+                params.push(match typ.as_str() {
+                    "signer" => Param::Signer { reference: false },
+                    "&signer" => Param::Signer { reference: true },
+                    _ => Param::Value(parse_type(&typ).ok_or(typ)?),
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(params)
+}
+
+/// Parses a type of the arguments that can be generated
+fn parse_type(typ: &str) -> Option<MoveType> {
+    match typ {
+        "bool" | "u8" | "u64" | "u128" | "address" => MoveType::from_str(typ).ok(),
+        "String" | "string::String" | "std::string::String" => {
+            MoveType::from_str("0x1::string::String")
+                .ok()
+                .filter(is_string)
+        }
+        _ => typ
+            .strip_prefix("vector<")
+            .and_then(|items| items.strip_suffix('>'))
+            .and_then(parse_type)
+            .map(|items| MoveType::Vector {
+                items: Box::new(items),
+            }),
+    }
+}
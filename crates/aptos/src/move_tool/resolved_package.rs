@@ -0,0 +1,163 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Move.toml settings the package system doesn't know about
+//!
+//! - Addresses given by profile, e.g. `my_addr = "profile:default"`
+//! - Environments, e.g. `[env.testnet.addresses]`, whose addresses and dependencies replace
+//!   those of the same names when building with `--env testnet`
+//!
+//! The package system only reads the plain settings, so a package using any of these is built
+//! from a copy whose Move.toml has them applied.
+
+use crate::{
+    common::types::{load_account_arg, CliError, CliTypedResult, PROFILE_PREFIX},
+    move_tool::dependencies::{
+        copy_package, load_manifest, make_local_dependencies_absolute, save_manifest,
+    },
+};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use toml_edit::{Document, Item, Table};
+
+pub(crate) const ENV: &str = "env";
+const ADDRESS_SECTIONS: [&str; 2] = ["addresses", "dev-addresses"];
+pub(crate) const ENV_SECTIONS: [&str; 4] = [
+    "addresses",
+    "dev-addresses",
+    "dependencies",
+    "dev-dependencies",
+];
+
+/// A package ready to build, with its Move.toml settings applied
+pub struct ResolvedPackage {
+    package_path: PathBuf,
+    /// The copy with the settings applied, removed when dropped
+    copy: Option<TempDir>,
+}
+
+impl ResolvedPackage {
+    /// Where to build the package from
+    pub fn path(&self) -> PathBuf {
+        match &self.copy {
+            Some(copy) => copy.path().to_path_buf(),
+            None => self.package_path.clone(),
+        }
+    }
+
+    /// Where the build goes, the package's own `build` even when built from a copy
+    pub fn install_dir(&self, output_dir: Option<PathBuf>) -> Option<PathBuf> {
+        output_dir.or_else(|| self.copy.as_ref().map(|_| self.package_path.clone()))
+    }
+}
+
+/// Applies the environment and resolves the profile addresses, copying the package if needed
+pub fn resolve_package(package_path: &Path, env: Option<&str>) -> CliTypedResult<ResolvedPackage> {
+    let (_, mut manifest) = load_manifest(package_path)?;
+    let mut changed = apply_env(&mut manifest, env)?;
+    changed |= resolve_profile_addresses(&mut manifest)?;
+
+    if !changed {
+        return Ok(ResolvedPackage {
+            package_path: package_path.to_path_buf(),
+            copy: None,
+        });
+    }
+    let copy = TempDir::new().map_err(|err| {
+        CliError::UnexpectedError(format!("Failed to create temporary directory {}", err))
+    })?;
+    copy_package(package_path, copy.path())?;
+    make_local_dependencies_absolute(&mut manifest, package_path);
+    save_manifest(copy.path(), &manifest.to_string())?;
+    Ok(ResolvedPackage {
+        package_path: package_path.to_path_buf(),
+        copy: Some(copy),
+    })
+}
+
+/// Replaces addresses and dependencies with the environment's, and removes the environments
+fn apply_env(manifest: &mut Document, env: Option<&str>) -> CliTypedResult<bool> {
+    let envs = match manifest.as_table_mut().remove(ENV) {
+        Some(envs) => envs,
+        None => {
+            return match env {
+                Some(env) => Err(no_env(env)),
+                None => Ok(false),
+            }
+        }
+    };
+    let env = match env {
+        Some(env) => env,
+        None => return Ok(true),
+    };
+    let overlay = envs
+        .get(env)
+        .and_then(|overlay| overlay.as_table_like())
+        .ok_or_else(|| no_env(env))?;
+
+    for (section, entries) in overlay.iter() {
+        if !ENV_SECTIONS.contains(&section) {
+            return Err(CliError::UnableToParse(
+                "Move.toml",
+                format!(
+                    "[{}.{}.{}] isn't one of {}",
+                    ENV,
+                    env,
+                    section,
+                    ENV_SECTIONS.join(", ")
+                ),
+            ));
+        }
+        let entries = entries.as_table_like().ok_or_else(|| {
+            CliError::UnableToParse(
+                "Move.toml",
+                format!("[{}.{}.{}] is not a table", ENV, env, section),
+            )
+        })?;
+        let target = manifest
+            .as_table_mut()
+            .entry(section)
+            .or_insert(Item::Table(Table::new()))
+            .as_table_like_mut()
+            .ok_or_else(|| {
+                CliError::UnableToParse("Move.toml", format!("[{}] is not a table", section))
+            })?;
+        for (name, value) in entries.iter() {
+            target.insert(name, value.clone());
+        }
+    }
+    Ok(true)
+}
+
+fn no_env(env: &str) -> CliError {
+    CliError::CommandArgumentError(format!("Move.toml has no [{}.{}]", ENV, env))
+}
+
+/// Replaces addresses given by profile with the profiles' addresses
+fn resolve_profile_addresses(manifest: &mut Document) -> CliTypedResult<bool> {
+    let mut resolved = false;
+    for section in ADDRESS_SECTIONS {
+        let addresses = match manifest
+            .get_mut(section)
+            .and_then(|addresses| addresses.as_table_like_mut())
+        {
+            Some(addresses) => addresses,
+            None => continue,
+        };
+        for (name, value) in addresses.iter_mut() {
+            let profile = match value.as_str() {
+                Some(profile) if profile.starts_with(PROFILE_PREFIX) => profile,
+                _ => continue,
+            };
+            let address = load_account_arg(profile).map_err(|err| {
+                CliError::CommandArgumentError(format!(
+                    "Address {} in [{}] of Move.toml: {}",
+                    name, section, err
+                ))
+            })?;
+            *value = toml_edit::value(address.to_hex_literal());
+            resolved = true;
+        }
+    }
+    Ok(resolved)
+}
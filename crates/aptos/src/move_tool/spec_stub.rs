@@ -0,0 +1,163 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generating placeholder specs for the public functions of a package
+//!
+//! The public functions are found in the compiled modules.  Whether a function already has a
+//! spec is decided from the sources: a `spec <function>` block in the module's source file, or
+//! in the `<module>.spec.move` next to it, where the stubs are written.
+
+use crate::common::{
+    types::{CliCommand, CliError, CliTypedResult, MovePackageDir},
+    utils::{read_from_file, write_to_file},
+};
+use async_trait::async_trait;
+use clap::Parser;
+use move_binary_format::{access::ModuleAccess, file_format::Visibility};
+use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
+use move_package::BuildConfig;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+/// Generate spec stubs for public functions without specs
+///
+/// For each module, a `spec` block with `aborts_if` and `ensures` placeholders is added for
+/// every public function that has no spec yet.  The stubs are appended to `<module>.spec.move`
+/// next to the module's source, which is created if it doesn't exist.  The placeholders claim
+/// the function never aborts, so the prover points at the stubs still to be filled in.
+#[derive(Parser)]
+pub struct SpecStub {
+    /// Print the stubs instead of writing them
+    #[clap(long)]
+    pub(crate) dry_run: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[async_trait]
+impl CliCommand<BTreeMap<String, Vec<String>>> for SpecStub {
+    fn command_name(&self) -> &'static str {
+        "SpecStub"
+    }
+
+    async fn execute(self) -> CliTypedResult<BTreeMap<String, Vec<String>>> {
+        let package = BuildConfig {
+            additional_named_addresses: self.move_options.named_addresses(),
+            install_dir: self.move_options.output_dir.clone(),
+            ..Default::default()
+        }
+        .compile_package_no_exit(
+            &self.move_options.get_package_path()?,
+            &mut std::io::stderr(),
+        )
+        .map_err(|err| CliError::MoveCompilationError(format!("{:#}", err)))?;
+
+        let mut stubbed = BTreeMap::new();
+        for unit in package.root_modules() {
+            let module = match &unit.unit {
+                CompiledUnit::Module(NamedCompiledModule { module, .. }) => module,
+                CompiledUnit::Script(_) => continue,
+            };
+            let module_name = module.self_id().name().to_string();
+            let source = read_text(&unit.source_path)?;
+            let spec_path = unit.source_path.with_extension("spec.move");
+            let existing_specs = if spec_path.exists() {
+                read_text(&spec_path)?
+            } else {
+                String::new()
+            };
+            let specified: BTreeSet<_> = specified_functions(&source)
+                .into_iter()
+                .chain(specified_functions(&existing_specs))
+                .collect();
+
+            let functions: Vec<String> = module
+                .function_defs()
+                .iter()
+                .filter(|definition| {
+                    definition.visibility == Visibility::Public && definition.code.is_some()
+                })
+                .map(|definition| {
+                    let handle = module.function_handle_at(definition.function);
+                    module.identifier_at(handle.name).to_string()
+                })
+                .filter(|name| !specified.contains(name))
+                .collect();
+            if functions.is_empty() {
+                continue;
+            }
+
+            let address = module_address(&source, &module_name)
+                .unwrap_or_else(|| module.self_id().address().to_hex_literal());
+            let stubs = spec_stubs(&address, &module_name, &functions);
+            if self.dry_run {
+                println!("// {}\n{}", spec_path.display(), stubs);
+            } else {
+                let mut text = existing_specs;
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&stubs);
+                write_to_file(
+                    &spec_path,
+                    &spec_path.display().to_string(),
+                    text.as_bytes(),
+                )?;
+            }
+            stubbed.insert(spec_path.display().to_string(), functions);
+        }
+        Ok(stubbed)
+    }
+}
+
+/// A `spec` block of the module, with a stub for each function
+pub(crate) fn spec_stubs(address: &str, module: &str, functions: &[String]) -> String {
+    let mut stubs = format!("spec {}::{} {{\n", address, module);
+    for (index, function) in functions.iter().enumerate() {
+        if index > 0 {
+            stubs.push('\n');
+        }
+        stubs.push_str(&format!(
+            "    spec {function} {{\n        \
+            // TODO: the conditions under which `{function}` aborts\n        \
+            aborts_if false;\n        \
+            // TODO: what `{function}` guarantees when it returns\n        \
+            ensures true;\n    \
+            }}\n",
+            function = function
+        ));
+    }
+    stubs.push_str("}\n");
+    stubs
+}
+
+/// Names of the functions with `spec <function>` blocks in the source
+pub(crate) fn specified_functions(source: &str) -> BTreeSet<String> {
+    // Not `spec module`, `spec schema`, or `spec fun`, which don't specify a function
+    let spec_block = regex::Regex::new(r"(?m)^\s*spec\s+([A-Za-z_][A-Za-z0-9_]*)\s*[{(<]").unwrap();
+    spec_block
+        .captures_iter(source)
+        .map(|captures| captures[1].to_string())
+        .filter(|name| name != "module")
+        .collect()
+}
+
+/// The address of the module as its source declares it, e.g. a named address
+fn module_address(source: &str, module: &str) -> Option<String> {
+    let header = regex::Regex::new(&format!(
+        r"(?m)^\s*module\s+([A-Za-z0-9_]+)\s*::\s*{}\b",
+        regex::escape(module)
+    ))
+    .unwrap();
+    header
+        .captures(source)
+        .map(|captures| captures[1].to_string())
+}
+
+fn read_text(path: &Path) -> CliTypedResult<String> {
+    String::from_utf8(read_from_file(path)?)
+        .map_err(|err| CliError::UnableToReadFile(path.display().to_string(), err.to_string()))
+}
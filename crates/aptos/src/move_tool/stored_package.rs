@@ -141,60 +141,111 @@ impl<'a> CachedPackageMetadata<'a> {
         Ok(())
     }
 
+    /// Whether the package was published with the source of its modules
+    pub fn has_sources(&self) -> bool {
+        self.metadata
+            .modules
+            .iter()
+            .any(|module| !module.source.is_empty())
+    }
+
+    /// Whether the package was published with the source maps of its modules
+    pub fn has_source_maps(&self) -> bool {
+        self.metadata
+            .modules
+            .iter()
+            .any(|module| !module.source_map.is_empty())
+    }
+
     pub fn verify(&self, package_metadata: &PackageMetadata) -> anyhow::Result<()> {
+        let mismatches = self.mismatches(package_metadata);
+        if !mismatches.is_empty() {
+            bail!("{}", mismatches.join("\n"))
+        }
+        Ok(())
+    }
+
+    /// Lists every difference between this package's metadata and a locally built package's
+    ///
+    /// The upgrade number isn't compared, as it's assigned on chain when publishing
+    pub fn mismatches(&self, package_metadata: &PackageMetadata) -> Vec<String> {
         let self_metadata = self.metadata;
+        let mut mismatches = vec![];
 
         if self_metadata.name != package_metadata.name {
-            bail!(
+            mismatches.push(format!(
                 "Package name doesn't match {} : {}",
-                package_metadata.name,
-                self_metadata.name
-            )
-        } else if self_metadata.deps != package_metadata.deps {
-            bail!(
+                package_metadata.name, self_metadata.name
+            ));
+        }
+        if self_metadata.deps != package_metadata.deps {
+            mismatches.push(format!(
                 "Dependencies don't match {:?} : {:?}",
-                package_metadata.deps,
-                self_metadata.deps
-            )
-        } else if self_metadata.modules != package_metadata.modules {
-            bail!(
-                "Modules don't match {:?} : {:?}",
-                package_metadata.modules,
-                self_metadata.modules
-            )
-        } else if self_metadata.manifest != package_metadata.manifest {
-            bail!(
-                "Manifest doesn't match {:?} : {:?}",
-                package_metadata.manifest,
-                self_metadata.manifest
-            )
-        } else if self_metadata.upgrade_policy != package_metadata.upgrade_policy {
-            bail!(
-                "Upgrade policy doesn't match {:?} : {:?}",
-                package_metadata.upgrade_policy,
-                self_metadata.upgrade_policy
-            )
-        } else if self_metadata.upgrade_number != package_metadata.upgrade_number {
-            bail!(
-                "Upgrade number doesn't match {:?} : {:?}",
-                package_metadata.upgrade_number,
-                self_metadata.upgrade_number
-            )
-        } else if self_metadata.extension != package_metadata.extension {
-            bail!(
+                package_metadata.deps, self_metadata.deps
+            ));
+        }
+        if self_metadata.manifest != package_metadata.manifest {
+            mismatches.push(format!(
+                "Manifest doesn't match:\n{}\n:\n{}",
+                unzip_metadata_str(&package_metadata.manifest).unwrap_or_default(),
+                self.manifest().unwrap_or_default()
+            ));
+        }
+        if self_metadata.upgrade_policy != package_metadata.upgrade_policy {
+            mismatches.push(format!(
+                "Upgrade policy doesn't match {} : {}",
+                package_metadata.upgrade_policy, self_metadata.upgrade_policy
+            ));
+        }
+        if self_metadata.extension != package_metadata.extension {
+            mismatches.push(format!(
                 "Extensions doesn't match {:?} : {:?}",
-                package_metadata.extension,
-                self_metadata.extension
-            )
-        } else if self_metadata.source_digest != package_metadata.source_digest {
-            bail!(
-                "Source digests doesn't match {:?} : {:?}",
-                package_metadata.source_digest,
-                self_metadata.source_digest
-            )
+                package_metadata.extension, self_metadata.extension
+            ));
+        }
+        if self_metadata.source_digest != package_metadata.source_digest {
+            mismatches.push(format!(
+                "Source digests doesn't match {} : {}",
+                package_metadata.source_digest, self_metadata.source_digest
+            ));
         }
 
-        Ok(())
+        for module in &package_metadata.modules {
+            let on_chain = match self_metadata
+                .modules
+                .iter()
+                .find(|on_chain| on_chain.name == module.name)
+            {
+                Some(on_chain) => on_chain,
+                None => {
+                    mismatches.push(format!("Module {} isn't in the package", module.name));
+                    continue;
+                }
+            };
+            if on_chain.source != module.source {
+                mismatches.push(format!("Module {}: source doesn't match", module.name));
+            }
+            if on_chain.source_map != module.source_map {
+                mismatches.push(format!("Module {}: source map doesn't match", module.name));
+            }
+            if on_chain.extension != module.extension {
+                mismatches.push(format!("Module {}: extension doesn't match", module.name));
+            }
+        }
+        for on_chain in &self_metadata.modules {
+            if !package_metadata
+                .modules
+                .iter()
+                .any(|module| module.name == on_chain.name)
+            {
+                mismatches.push(format!(
+                    "Module {} is only in the on-chain package",
+                    on_chain.name
+                ));
+            }
+        }
+
+        mismatches
     }
 }
 
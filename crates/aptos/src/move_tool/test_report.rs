@@ -0,0 +1,446 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Machine readable reports of Move unit tests
+//!
+//! The unit test runner only reports results as text, so the report is built by reading back
+//! its output: the result line of each test, the statistics table for durations and gas, and
+//! the failures section for what went wrong.
+
+use clap::ArgEnum;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Write},
+    io,
+    str::FromStr,
+};
+
+#[derive(ArgEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TestFormat {
+    /// The test runner's output
+    Pretty,
+    /// A JUnit XML report
+    Junit,
+    /// A JSON object per line for each test event, like `cargo test`'s JSON output
+    Json,
+}
+
+impl Display for TestFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TestFormat::Pretty => "pretty",
+            TestFormat::Junit => "junit",
+            TestFormat::Json => "json",
+        })
+    }
+}
+
+impl FromStr for TestFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pretty" => Ok(TestFormat::Pretty),
+            "junit" => Ok(TestFormat::Junit),
+            "json" => Ok(TestFormat::Json),
+            _ => Err("Invalid format. Valid values are pretty, junit, json"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TestStatus {
+    Pass,
+    Fail,
+    Timeout,
+}
+
+#[derive(Clone, Debug)]
+pub struct TestCaseResult {
+    /// The test's module, e.g. `0x1::coin`
+    pub module: String,
+    /// The test's function
+    pub name: String,
+    pub status: TestStatus,
+    /// Seconds the test took
+    pub duration: Option<f64>,
+    /// Gas the test used, as the runner meters it
+    pub gas: Option<u64>,
+    /// Why the test failed, as the runner reported it
+    pub failure: Option<String>,
+}
+
+/// Collects the output of the unit test runner, for reports, as it's written
+///
+/// The runner's text is printed as it goes for the pretty format, or when the report goes to
+/// a file.  JSON events of tests starting and passing are streamed as their result lines are
+/// written, while failures are sent at the end, once the runner has described them.  Tests
+/// not selected with `--exact` are left out of all of it.
+pub struct TestOutput {
+    format: TestFormat,
+    echo: bool,
+    stream_events: bool,
+    exact: Option<String>,
+    buffer: Vec<u8>,
+    /// Where the line being written starts
+    line_start: usize,
+    events: Vec<serde_json::Value>,
+}
+
+impl TestOutput {
+    pub fn new(format: TestFormat, exact: Option<String>, to_file: bool) -> Self {
+        Self {
+            format,
+            echo: format == TestFormat::Pretty || to_file,
+            stream_events: format == TestFormat::Json && !to_file,
+            exact,
+            buffer: vec![],
+            line_start: 0,
+            events: vec![],
+        }
+    }
+
+    fn is_selected(&self, qualified_name: &str) -> bool {
+        self.exact
+            .as_ref()
+            .map_or(true, |filter| is_exact_match(qualified_name, filter))
+    }
+
+    fn send(&mut self, event: serde_json::Value) {
+        if self.stream_events {
+            println!("{}", event);
+        }
+        self.events.push(event);
+    }
+
+    fn on_line(&mut self, line: &str) {
+        let stripped = strip_ansi(line);
+        if let Some((module, name, status)) = parse_result_line(&stripped) {
+            let qualified_name = format!("{}::{}", module, name);
+            if !self.is_selected(&qualified_name) {
+                return;
+            }
+            if self.format == TestFormat::Json {
+                self.send(json!({"type": "test", "event": "started", "name": qualified_name}));
+                if status == TestStatus::Pass {
+                    self.send(json!({"type": "test", "event": "ok", "name": qualified_name}));
+                }
+            }
+        }
+        if self.echo {
+            println!("{}", line);
+        }
+    }
+
+    /// The results of the selected tests, and the report if the format has one
+    ///
+    /// For JSON, the report is the events not streamed yet
+    pub fn finish(mut self, package: &str) -> (Vec<TestCaseResult>, Option<String>) {
+        if self.line_start < self.buffer.len() {
+            let line = String::from_utf8_lossy(&self.buffer[self.line_start..]).to_string();
+            self.line_start = self.buffer.len();
+            self.on_line(&line);
+        }
+        let mut results = parse_test_output(&String::from_utf8_lossy(&self.buffer));
+        results.retain(|result| self.is_selected(&result.qualified_name()));
+        let report = match self.format {
+            TestFormat::Pretty => None,
+            TestFormat::Junit => Some(junit_report(package, &results)),
+            TestFormat::Json => {
+                let streamed = if self.stream_events {
+                    self.events.len()
+                } else {
+                    0
+                };
+                for result in &results {
+                    if result.status != TestStatus::Pass {
+                        self.events.push(result.json_event());
+                    }
+                }
+                let failed = results
+                    .iter()
+                    .filter(|result| result.status != TestStatus::Pass)
+                    .count();
+                let event = if failed == 0 { "ok" } else { "failed" };
+                let exec_time: f64 = results.iter().filter_map(|result| result.duration).sum();
+                self.events.push(json!({
+                    "type": "suite",
+                    "event": event,
+                    "passed": results.len() - failed,
+                    "failed": failed,
+                    "exec_time": exec_time,
+                }));
+                Some(
+                    self.events[streamed..]
+                        .iter()
+                        .map(|event| format!("{}\n", event))
+                        .collect(),
+                )
+            }
+        };
+        (results, report)
+    }
+}
+
+impl io::Write for TestOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while let Some(end) = self.buffer[self.line_start..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+        {
+            let line =
+                String::from_utf8_lossy(&self.buffer[self.line_start..self.line_start + end])
+                    .to_string();
+            self.line_start += end + 1;
+            self.on_line(&line);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+/// Whether a test's qualified name, e.g. `0x1::coin::test_mint`, is selected by `--filter`
+/// with `--exact`: the whole name, or its last components, must be the filter
+pub fn is_exact_match(qualified_name: &str, filter: &str) -> bool {
+    qualified_name == filter || qualified_name.ends_with(&format!("::{}", filter))
+}
+
+/// The module, function, and status of a test result line of the runner
+pub fn parse_result_line(line: &str) -> Option<(&str, &str, TestStatus)> {
+    let (status, name) = line.strip_prefix("[ ")?.split_once(" ] ")?;
+    let status = match status.trim() {
+        "PASS" => TestStatus::Pass,
+        "FAIL" => TestStatus::Fail,
+        "TIMEOUT" => TestStatus::Timeout,
+        _ => return None,
+    };
+    let (module, name) = name.trim().rsplit_once("::")?;
+    Some((module, name, status))
+}
+
+/// Reads the results of each test from the output of the unit test runner
+///
+/// The output must include the statistics table for durations and gas to be known
+fn parse_test_output(output: &str) -> Vec<TestCaseResult> {
+    let output = strip_ansi(output);
+    let mut results = vec![];
+    let mut durations = BTreeMap::new();
+    let mut gas = BTreeMap::new();
+    let mut failures = BTreeMap::new();
+    let mut failures_module = None;
+    let mut failure: Option<(String, String)> = None;
+
+    for line in output.lines() {
+        if let Some((name, mut text)) = failure.take() {
+            if line.starts_with('└') {
+                if let Some(module) = &failures_module {
+                    failures.insert(format!("{}::{}", module, name), text);
+                }
+            } else {
+                let line = line
+                    .strip_prefix("│ ")
+                    .or_else(|| line.strip_prefix('│'))
+                    .unwrap_or(line);
+                text.push_str(line);
+                text.push('\n');
+                failure = Some((name, text));
+            }
+            continue;
+        }
+
+        if let Some((module, name, status)) = parse_result_line(line) {
+            results.push(TestCaseResult {
+                module: module.to_string(),
+                name: name.to_string(),
+                status,
+                duration: None,
+                gas: None,
+                failure: None,
+            });
+        } else if let Some(module) = line
+            .strip_prefix("Failures in ")
+            .and_then(|rest| rest.strip_suffix(':'))
+        {
+            failures_module = Some(module.to_string());
+        } else if let Some(rest) = line.strip_prefix("┌── ") {
+            let name = rest.trim_end_matches(|c| c == '─' || c == ' ');
+            failure = Some((name.to_string(), String::new()));
+        } else if line.starts_with('│') {
+            // A row of the statistics table: name, time, and gas
+            let cells: Vec<_> = line.split('│').map(str::trim).collect();
+            if let (Some(name), Some(Ok(time))) =
+                (cells.get(1), cells.get(2).map(|t| t.parse::<f64>()))
+            {
+                durations.insert(name.to_string(), time);
+                if let Some(Ok(used)) = cells.get(3).map(|g| g.parse::<u64>()) {
+                    gas.insert(name.to_string(), used);
+                }
+            }
+        }
+    }
+
+    for result in &mut results {
+        let qualified_name = format!("{}::{}", result.module, result.name);
+        result.duration = durations.get(&qualified_name).copied();
+        result.gas = gas.get(&qualified_name).copied();
+        result.failure = failures.remove(&qualified_name);
+    }
+    results
+}
+
+/// A JUnit XML report, with a test suite per module
+fn junit_report(package: &str, results: &[TestCaseResult]) -> String {
+    let mut suites: BTreeMap<&str, Vec<&TestCaseResult>> = BTreeMap::new();
+    for result in results {
+        suites.entry(&result.module).or_default().push(result);
+    }
+    let failures = |results: &[&TestCaseResult]| {
+        results
+            .iter()
+            .filter(|result| result.status != TestStatus::Pass)
+            .count()
+    };
+    let time = |results: &[&TestCaseResult]| {
+        results
+            .iter()
+            .filter_map(|result| result.duration)
+            .sum::<f64>()
+    };
+
+    let all: Vec<_> = results.iter().collect();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+        escape(package),
+        all.len(),
+        failures(&all),
+        time(&all)
+    );
+    for (module, results) in suites {
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+            escape(module),
+            results.len(),
+            failures(&results),
+            time(&results)
+        );
+        for result in results {
+            let _ = write!(
+                out,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape(&result.name),
+                escape(module),
+                result.duration.unwrap_or_default()
+            );
+            if result.status == TestStatus::Pass {
+                out.push_str("/>\n");
+                continue;
+            }
+            let text = result.failure.clone().unwrap_or_default();
+            let message = match result.status {
+                TestStatus::Timeout => "test timed out".to_string(),
+                _ => text
+                    .lines()
+                    .find(|line| !line.trim().is_empty())
+                    .unwrap_or("test failed")
+                    .trim()
+                    .to_string(),
+            };
+            let _ = writeln!(
+                out,
+                ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                escape(&message),
+                escape(&text)
+            );
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+impl TestCaseResult {
+    pub fn qualified_name(&self) -> String {
+        format!("{}::{}", self.module, self.name)
+    }
+
+    /// Where the test failed, as `<file>:<line>:<column>`
+    pub fn failure_location(&self) -> Option<String> {
+        self.failure.as_ref()?.lines().find_map(|line| {
+            line.split_once("┌─ ")
+                .map(|(_, location)| location.trim().to_string())
+        })
+    }
+
+    /// The code the test aborted with, if it aborted
+    pub fn abort_code(&self) -> Option<u64> {
+        let failure = self.failure.as_ref()?;
+        let (_, rest) = failure.split_once("aborted with ")?;
+        let code: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        code.parse().ok()
+    }
+
+    /// The JSON event of the test's result
+    pub fn json_event(&self) -> serde_json::Value {
+        match self.status {
+            TestStatus::Pass => json!({
+                "type": "test",
+                "event": "ok",
+                "name": self.qualified_name(),
+                "exec_time": self.duration,
+            }),
+            TestStatus::Fail | TestStatus::Timeout => json!({
+                "type": "test",
+                "event": "failed",
+                "name": self.qualified_name(),
+                "exec_time": self.duration,
+                "timeout": self.status == TestStatus::Timeout,
+                "location": self.failure_location(),
+                "abort_code": self.abort_code(),
+                "message": self.failure,
+            }),
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Removes the terminal color codes the runner adds
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip to the end of the escape sequence, its first letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
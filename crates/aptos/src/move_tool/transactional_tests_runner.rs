@@ -8,6 +8,8 @@
 use clap::Parser;
 
 use crate::common::types::{CliError, CliTypedResult};
+use aptos_transactional_test_harness::run_aptos_test;
+use move_command_line_common::testing::UPDATE_BASELINE;
 
 use std::{
     io::{self, Write},
@@ -108,14 +110,71 @@ pub(crate) fn runner(options: &TransactionalTestOpts, reqs: &[Requirements]) ->
         return Ok(());
     }
 
-    match run_tests(options, tests) {
-        Ok(true) => Ok(()),
-        Ok(false) => process::exit(101),
-        Err(e) => Err(CliError::UnexpectedError(format!(
-            "error: io error when running tests: {:?}",
-            e
-        ))),
+    if !checked_run_tests(options, tests)? {
+        process::exit(101)
     }
+    Ok(())
+}
+
+/// Directory of a package with its snapshot tests, transactional test scripts whose output is
+/// checked against the `.exp` file next to each
+pub(crate) const SNAPSHOT_TESTS_DIR: &str = "snapshot_tests";
+
+/// Runs the snapshot tests of a package, if it has any, and returns whether they passed
+///
+/// With `update`, the `.exp` files are rewritten with the output of the scripts instead
+pub(crate) fn run_snapshot_tests(
+    package_path: &Path,
+    filter: Option<String>,
+    filter_exact: bool,
+    update: bool,
+) -> CliTypedResult<bool> {
+    let root_path = package_path.join(SNAPSHOT_TESTS_DIR);
+    let options = TransactionalTestOpts {
+        filter,
+        filter_exact,
+        test_threads: NonZeroUsize::new(32).unwrap(),
+        quiet: false,
+        list: false,
+        root_path,
+        pattern: r".*\.(mvir|move)$".to_string(),
+    };
+    let requirements = Requirements::new(
+        run_aptos_test,
+        "snapshot".to_string(),
+        options.root_path.display().to_string(),
+        options.pattern.clone(),
+    );
+    let re = regex::Regex::new(&options.pattern).expect("valid pattern");
+    if !options.root_path.is_dir()
+        || !iterate_directory(&options.root_path).any(|path| re.is_match(&path.to_string_lossy()))
+    {
+        return Ok(true);
+    }
+
+    let mut tests = requirements.expand();
+    tests.sort_by(|a, b| a.name.cmp(&b.name));
+    if !update {
+        return checked_run_tests(&options, tests);
+    }
+
+    // The harness only takes the update from the environment, writing the output as the
+    // expected output when this is set.  Every test has finished once the run returns, so
+    // the variable is restored then, to not leak into the rest of the command.
+    let previous = std::env::var_os(UPDATE_BASELINE);
+    std::env::set_var(UPDATE_BASELINE, "1");
+    let result = checked_run_tests(&options, tests);
+    match previous {
+        Some(previous) => std::env::set_var(UPDATE_BASELINE, previous),
+        None => std::env::remove_var(UPDATE_BASELINE),
+    }
+    result
+}
+
+fn checked_run_tests(options: &TransactionalTestOpts, tests: Vec<Test>) -> CliTypedResult<bool> {
+    run_tests(options, tests).map_err(|e| {
+        CliError::UnexpectedError(format!("error: io error when running tests: {:?}", e))
+    })
 }
 
 fn run_tests(options: &TransactionalTestOpts, tests: Vec<Test>) -> io::Result<bool> {
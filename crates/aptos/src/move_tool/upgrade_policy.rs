@@ -0,0 +1,188 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inspecting and tightening the upgrade policy of published packages
+//!
+//! The policy is part of a package's metadata in `0x1::code::PackageRegistry`, and only changes
+//! when the package is published again.  `code::publish_package_txn` accepts a policy at least
+//! as strong as the current one, so `set` republishes the package unchanged but for the policy.
+
+use crate::{
+    common::{
+        types::{
+            CliCommand, CliError, CliResult, CliTypedResult, MovePackageDir, ProfileOptions,
+            RestOptions, TransactionOptions, TransactionSummary,
+        },
+        utils::prompt_yes_with_override,
+    },
+    move_tool::{CachedPackageRegistry, IncludedArtifactsArgs},
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::{Parser, Subcommand};
+use framework::{natives::code::UpgradePolicy, BuiltPackage};
+use serde::Serialize;
+
+/// Show or tighten the upgrade policy of published packages
+#[derive(Subcommand)]
+pub enum UpgradePolicyTool {
+    Show(ShowUpgradePolicy),
+    Set(SetUpgradePolicy),
+}
+
+impl UpgradePolicyTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            UpgradePolicyTool::Show(tool) => tool.execute_serialized().await,
+            UpgradePolicyTool::Set(tool) => tool.execute_serialized().await,
+        }
+    }
+}
+
+/// Show the upgrade policy and source digest of an account's published packages
+#[derive(Parser)]
+pub struct ShowUpgradePolicy {
+    /// Address of the account the packages are published under
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) account: AccountAddress,
+
+    /// Package to show, all of the account's packages if not given
+    #[clap(long)]
+    pub(crate) package: Option<String>,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[derive(Serialize)]
+pub struct PackageUpgradePolicy {
+    pub package: String,
+    pub upgrade_policy: String,
+    pub upgrade_number: u64,
+    pub source_digest: String,
+}
+
+#[async_trait]
+impl CliCommand<Vec<PackageUpgradePolicy>> for ShowUpgradePolicy {
+    fn command_name(&self) -> &'static str {
+        "ShowUpgradePolicy"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<PackageUpgradePolicy>> {
+        let url = self.rest_options.url(&self.profile_options)?;
+        let registry = CachedPackageRegistry::create(url, self.account).await?;
+        let names: Vec<String> = match self.package {
+            Some(package) => vec![package],
+            None => registry
+                .package_names()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        };
+
+        let mut packages = vec![];
+        for name in names {
+            let data = registry.get_package(&name).await?;
+            packages.push(PackageUpgradePolicy {
+                package: data.name().to_string(),
+                upgrade_policy: data.upgrade_policy().to_string(),
+                upgrade_number: data.upgrade_number(),
+                source_digest: data.source_digest().to_string(),
+            });
+        }
+        Ok(packages)
+    }
+}
+
+/// Tighten the upgrade policy of a published package
+///
+/// The package is built from its local sources and published again with the new policy, so the
+/// sources must be the ones published.  A policy can only be made stronger, from `arbitrary` to
+/// `compatible` to `immutable`, and making a package `immutable` can't be undone
+#[derive(Parser)]
+pub struct SetUpgradePolicy {
+    /// The new policy, `compatible` or `immutable`
+    #[clap(long)]
+    pub(crate) policy: UpgradePolicy,
+
+    #[clap(flatten)]
+    pub(crate) included_artifacts_args: IncludedArtifactsArgs,
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+}
+
+#[async_trait]
+impl CliCommand<TransactionSummary> for SetUpgradePolicy {
+    fn command_name(&self) -> &'static str {
+        "SetUpgradePolicy"
+    }
+
+    async fn execute(self) -> CliTypedResult<TransactionSummary> {
+        let options = self
+            .included_artifacts_args
+            .included_artifacts
+            .build_options(self.move_options.named_addresses());
+        let package = BuiltPackage::build(self.move_options.get_package_path()?, options)?;
+        let mut metadata = package.extract_metadata()?;
+
+        let url = self
+            .txn_options
+            .rest_options
+            .url(&self.txn_options.profile_options)?;
+        let sender = self.txn_options.sender_address()?;
+        let registry = CachedPackageRegistry::create(url, sender).await?;
+        let published = registry.get_package(&metadata.name).await.map_err(|_| {
+            CliError::CommandArgumentError(format!(
+                "Package {} isn't published under {}",
+                metadata.name, sender
+            ))
+        })?;
+
+        let current = published.upgrade_policy();
+        if self.policy.policy <= current.policy {
+            return Err(CliError::CommandArgumentError(format!(
+                "Package {} is {}, its policy can only be made stronger than that",
+                metadata.name, current
+            )));
+        }
+        if published.source_digest() != metadata.source_digest {
+            return Err(CliError::UnexpectedError(format!(
+                "The local sources of {} (digest {}) aren't the published ones (digest {}), \
+                publish them first or check out the published version",
+                metadata.name,
+                metadata.source_digest,
+                published.source_digest()
+            )));
+        }
+
+        eprintln!(
+            "Package {} at {}, upgrade number {}: {} -> {}",
+            metadata.name,
+            sender,
+            published.upgrade_number(),
+            current,
+            self.policy
+        );
+        let prompt = if self.policy == UpgradePolicy::immutable() {
+            "The package can never be upgraded again. Do you want to make it immutable?".to_string()
+        } else {
+            format!("Do you want to make the package {}?", self.policy)
+        };
+        prompt_yes_with_override(&prompt, self.txn_options.prompt_options)?;
+
+        metadata.upgrade_policy = self.policy;
+        let metadata = bcs::to_bytes(&metadata).expect("PackageMetadata has BCS");
+        let payload = cached_packages::aptos_stdlib::code_publish_package_txn(
+            metadata,
+            package.extract_code(),
+        );
+        self.txn_options
+            .submit_transaction(payload)
+            .await
+            .map(TransactionSummary::from)
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Submitting a package's sources to a source verification service
+//!
+//! The service is given the sources, the manifest and lockfile, and the build attestation of a
+//! local build, to rebuild the package with and compare against the bytecode on chain:
+//!
+//! - `POST <url>/verifications` with the submission returns `{"id": "..."}`
+//! - `GET <url>/verifications/<id>` returns `{"status": "pending" | "verified" | "failed"}`,
+//!   with a `message` explaining a failure
+
+use crate::{
+    account::list::account_or_profile,
+    common::types::{
+        CliCommand, CliError, CliTypedResult, MovePackageDir, ProfileOptions, RestOptions,
+    },
+    config::GlobalConfig,
+    move_tool::{
+        attestation::BuildAttestation, lockfile::LOCKFILE, resolved_package::resolve_package,
+        IncludedArtifactsArgs,
+    },
+};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::Parser;
+use framework::{BuildOptions, BuiltPackage};
+use move_package::source_package::layout::SourcePackageLayout;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+
+/// Submit a package's sources for verification against the published package
+///
+/// The sources, manifest, and a build attestation are packaged up and, with `--submit`, sent to
+/// the verification service, whose verdict is waited for.  Without `--submit`, the submission
+/// is printed for review.  The service is given with `--verification-url`, or set with
+/// `aptos config set-global-config --verification-url`.
+#[derive(Parser)]
+pub struct VerifySource {
+    /// Address of the account the package is published under
+    ///
+    /// Defaults to the profile's account
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) account: Option<AccountAddress>,
+
+    /// Send the submission to the verification service, and wait for its verdict
+    #[clap(long)]
+    pub(crate) submit: bool,
+
+    /// URL of the verification service, instead of the one in the global config
+    #[clap(long)]
+    pub(crate) verification_url: Option<Url>,
+
+    /// Seconds to wait for the verdict
+    #[clap(long, default_value_t = 300)]
+    pub(crate) timeout_secs: u64,
+
+    /// Seconds between checks of the verification's status
+    #[clap(long, default_value_t = 5)]
+    pub(crate) poll_interval_secs: u64,
+
+    #[clap(flatten)]
+    pub(crate) included_artifacts_args: IncludedArtifactsArgs,
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[derive(Serialize)]
+pub struct SourceSubmission {
+    pub chain_id: u8,
+    pub account: AccountAddress,
+    pub package: String,
+    /// Move.toml
+    pub manifest: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lockfile: Option<String>,
+    /// The Move files under `sources`, by their paths in the package
+    pub sources: BTreeMap<String, String>,
+    pub build_info: BuildAttestation,
+}
+
+#[derive(Deserialize)]
+struct SubmissionResponse {
+    id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct VerificationStatus {
+    pub id: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum VerifySourceResult {
+    Submission(Box<SourceSubmission>),
+    Status(VerificationStatus),
+}
+
+#[async_trait]
+impl CliCommand<VerifySourceResult> for VerifySource {
+    fn command_name(&self) -> &'static str {
+        "VerifySource"
+    }
+
+    async fn execute(self) -> CliTypedResult<VerifySourceResult> {
+        let submission = self.submission().await?;
+        if !self.submit {
+            return Ok(VerifySourceResult::Submission(Box::new(submission)));
+        }
+
+        let url = match &self.verification_url {
+            Some(url) => url.clone(),
+            None => GlobalConfig::load()?
+                .verification_url
+                .ok_or_else(|| {
+                    CliError::CommandArgumentError(
+                        "Please provide --verification-url, or set it with \
+                        `aptos config set-global-config --verification-url`"
+                            .to_string(),
+                    )
+                })?
+                .parse::<Url>()
+                .map_err(|err| CliError::UnableToParse("verification_url", err.to_string()))?,
+        };
+        let client = reqwest::Client::new();
+        let response = client
+            .post(endpoint(&url, "verifications")?)
+            .json(&submission)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| CliError::ApiError(err.to_string()))?;
+        let id = response
+            .json::<SubmissionResponse>()
+            .await
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+            .id;
+        eprintln!(
+            "Submitted {} for verification as {}",
+            submission.package, id
+        );
+
+        let status_url = endpoint(&url, &format!("verifications/{}", id))?;
+        let start = Instant::now();
+        loop {
+            let status: VerificationStatus = client
+                .get(status_url.clone())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|err| CliError::ApiError(err.to_string()))?
+                .json()
+                .await
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            match status.status.as_str() {
+                "verified" => return Ok(VerifySourceResult::Status(status)),
+                "failed" => {
+                    return Err(CliError::UnexpectedError(format!(
+                        "Verification {} failed: {}",
+                        id,
+                        status.message.unwrap_or_default()
+                    )))
+                }
+                _ if start.elapsed() >= Duration::from_secs(self.timeout_secs) => {
+                    return Err(CliError::UnexpectedError(format!(
+                        "Verification {} is still {} after {} seconds",
+                        id, status.status, self.timeout_secs
+                    )))
+                }
+                _ => tokio::time::sleep(Duration::from_secs(self.poll_interval_secs)).await,
+            }
+        }
+    }
+}
+
+impl VerifySource {
+    /// Builds the package for its attestation, and collects its files
+    async fn submission(&self) -> CliTypedResult<SourceSubmission> {
+        let account = account_or_profile(self.account, &self.profile_options)?;
+        let chain_id = self
+            .rest_options
+            .client(&self.profile_options)?
+            .get_ledger_information()
+            .await?
+            .into_inner()
+            .chain_id;
+
+        let package_dir = self.move_options.get_package_path()?;
+        let resolved = resolve_package(&package_dir, self.move_options.env.as_deref())?;
+        let build_dir = TempDir::new().map_err(|err| {
+            CliError::UnexpectedError(format!("Failed to create temporary directory {}", err))
+        })?;
+        let options = BuildOptions {
+            install_dir: Some(build_dir.path().to_path_buf()),
+            ..self
+                .included_artifacts_args
+                .included_artifacts
+                .build_options(self.move_options.named_addresses())
+        };
+        let package = BuiltPackage::build(resolved.path(), options.clone())
+            .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
+        let build_info = BuildAttestation::new(
+            &package,
+            &package_dir,
+            self.move_options.env.clone(),
+            options,
+        )?;
+
+        let lockfile_path = package_dir.join(LOCKFILE);
+        let lockfile = if lockfile_path.exists() {
+            Some(read_text(&lockfile_path)?)
+        } else {
+            None
+        };
+        let mut sources = BTreeMap::new();
+        let sources_dir = package_dir.join(SourcePackageLayout::Sources.path());
+        for entry in walkdir::WalkDir::new(&sources_dir).into_iter().flatten() {
+            let path = entry.path();
+            if !entry.file_type().is_file()
+                || path.extension().and_then(|ext| ext.to_str()) != Some("move")
+            {
+                continue;
+            }
+            let relative = path
+                .strip_prefix(&package_dir)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            sources.insert(relative.display().to_string(), read_text(path)?);
+        }
+
+        Ok(SourceSubmission {
+            chain_id,
+            account,
+            package: package.name().to_string(),
+            manifest: read_text(&package_dir.join(SourcePackageLayout::Manifest.path()))?,
+            lockfile,
+            sources,
+            build_info,
+        })
+    }
+}
+
+fn endpoint(url: &Url, path: &str) -> CliTypedResult<Url> {
+    let mut base = url.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+    base.join(path)
+        .map_err(|err| CliError::UnableToParse("verification_url", err.to_string()))
+}
+
+fn read_text(path: &Path) -> CliTypedResult<String> {
+    fs::read_to_string(path).map_err(|err| CliError::IO(path.display().to_string(), err))
+}
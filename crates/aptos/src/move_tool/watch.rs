@@ -0,0 +1,174 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult},
+        utils::dir_default_to_current,
+    },
+    Tool,
+};
+use async_trait::async_trait;
+use clap::Parser;
+use move_package::source_package::layout::SourcePackageLayout;
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+/// Time to wait for more changes before rerunning, so a save of several files reruns once
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Rerun compile, test, or prove whenever the package changes
+///
+/// Watches `sources/`, `tests/`, `scripts/`, and Move.toml, and reruns the command after the
+/// files stop changing.  Arguments after `--` are passed to the command, e.g.
+/// `aptos move watch --test -- --filter my_test`
+#[derive(Parser)]
+pub struct WatchPackage {
+    /// Rerun `aptos move test`
+    #[clap(long, group = "watch_command")]
+    pub(crate) test: bool,
+
+    /// Rerun `aptos move compile`, the default
+    #[clap(long, group = "watch_command")]
+    pub(crate) compile: bool,
+
+    /// Rerun `aptos move prove`
+    #[clap(long, group = "watch_command")]
+    pub(crate) prove: bool,
+
+    /// Path to a move package (the folder with a Move.toml file)
+    #[clap(long, parse(from_os_str))]
+    pub(crate) package_dir: Option<PathBuf>,
+
+    /// Arguments for the command
+    #[clap(last = true)]
+    pub(crate) args: Vec<String>,
+}
+
+impl WatchPackage {
+    fn command(&self) -> &'static str {
+        if self.test {
+            "test"
+        } else if self.prove {
+            "prove"
+        } else {
+            "compile"
+        }
+    }
+
+    /// Runs the command once, printing its result the way `aptos` would
+    async fn run(&self, package_dir: &Path) -> CliTypedResult<()> {
+        let mut args = vec![
+            "aptos".to_string(),
+            "move".to_string(),
+            self.command().to_string(),
+            "--package-dir".to_string(),
+            package_dir.display().to_string(),
+        ];
+        args.extend(self.args.iter().cloned());
+        let tool = Tool::try_parse_from(args)
+            .map_err(|err| CliError::CommandArgumentError(err.to_string()))?;
+        match tool.execute().await {
+            Ok(output) => println!("{}", output),
+            Err(output) => println!("{}", output),
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CliCommand<()> for WatchPackage {
+    fn command_name(&self) -> &'static str {
+        "WatchPackage"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        let package_dir = dir_default_to_current(self.package_dir.clone())?;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let mut watcher = RecommendedWatcher::new(
+            move |event: notify::Result<Event>| {
+                if let Ok(event) = event {
+                    let _ = sender.send(event);
+                }
+            },
+            Config::default(),
+        )
+        .map_err(watch_error)?;
+
+        watcher
+            .watch(
+                &package_dir.join(SourcePackageLayout::Manifest.path()),
+                RecursiveMode::NonRecursive,
+            )
+            .map_err(watch_error)?;
+        for layout in [
+            SourcePackageLayout::Sources,
+            SourcePackageLayout::Tests,
+            SourcePackageLayout::Scripts,
+        ] {
+            let dir = package_dir.join(layout.path());
+            if dir.exists() {
+                watcher
+                    .watch(&dir, RecursiveMode::Recursive)
+                    .map_err(watch_error)?;
+            }
+        }
+
+        self.run(&package_dir).await?;
+        loop {
+            eprintln!(
+                "\nWatching {} for changes, press Ctrl-C to stop",
+                package_dir.display()
+            );
+            let mut changed = BTreeSet::new();
+            match receiver.recv().await {
+                Some(event) => add_changes(&mut changed, event),
+                None => {
+                    return Err(CliError::UnexpectedError(
+                        "File watcher stopped".to_string(),
+                    ))
+                }
+            }
+            while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, receiver.recv()).await {
+                add_changes(&mut changed, event);
+            }
+            if changed.is_empty() {
+                continue;
+            }
+
+            eprintln!("\n---");
+            for path in &changed {
+                let path = path.strip_prefix(&package_dir).unwrap_or(path);
+                eprintln!("Changed: {}", path.display());
+            }
+            self.run(&package_dir).await?;
+        }
+    }
+}
+
+/// Adds the Move files and manifests changed by an event, ignoring editor temporary files
+fn add_changes(changed: &mut BTreeSet<PathBuf>, event: Event) {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    for path in event.paths {
+        let relevant = path
+            .extension()
+            .map_or(false, |extension| extension == "move")
+            || path.file_name().map_or(false, |name| {
+                name == SourcePackageLayout::Manifest.location_str()
+            });
+        if relevant {
+            changed.insert(path);
+        }
+    }
+}
+
+fn watch_error(err: notify::Error) -> CliError {
+    CliError::UnexpectedError(format!("Failed to watch the package: {}", err))
+}
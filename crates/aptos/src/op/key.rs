@@ -3,21 +3,40 @@
 
 use crate::{
     common::{
+        key_import::load_ed25519_private_key_file,
+        mnemonic::{
+            derive_ed25519_private_key, expand_derivation_path, generate_mnemonic, parse_mnemonic,
+            APTOS_DERIVATION_PATH,
+        },
         types::{
-            CliError, CliTypedResult, EncodingOptions, EncodingType, KeyType, RngArgs, SaveFile,
+            account_address_from_public_key, CliConfig, CliError, CliTypedResult, ConfigSearchMode,
+            EncodingOptions, EncodingType, KeyType, ProfileConfig, ProfileOptions, PromptOptions,
+            RestOptions, RngArgs, SaveFile,
+        },
+        utils::{
+            append_file_extension, check_if_file_exists, generate_vanity_ed25519_private_key,
+            prompt_yes_with_override, read_from_file, write_to_file, write_to_user_only_file,
         },
-        utils::{append_file_extension, check_if_file_exists, write_to_file},
+    },
+    op::multikey::{
+        AggregateMultikeySignatures, BuildMultikeyTransaction, GenerateMultikey,
+        SignMultikeyTransaction,
     },
     CliCommand, CliResult,
 };
 use aptos_config::config::{Peer, PeerRole};
 use aptos_crypto::{bls12381, ed25519, x25519, PrivateKey, ValidCryptoMaterial};
 use aptos_genesis::config::HostAndPort;
+use aptos_rest_client::{
+    aptos_api_types::AptosErrorCode,
+    error::{AptosErrorResponse, RestError},
+};
 use aptos_types::account_address::{from_identity_public_key, AccountAddress};
 use async_trait::async_trait;
 use clap::{Parser, Subcommand};
+use serde::Serialize;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
@@ -31,6 +50,12 @@ pub const PUBLIC_KEY_EXTENSION: &str = "pub";
 pub enum KeyTool {
     Generate(GenerateKey),
     ExtractPeer(ExtractPeer),
+    Derive(DeriveKeys),
+    Import(ImportKey),
+    GenerateMultikey(GenerateMultikey),
+    BuildMultikeyTransaction(BuildMultikeyTransaction),
+    SignMultikeyTransaction(SignMultikeyTransaction),
+    AggregateMultikeySignatures(AggregateMultikeySignatures),
 }
 
 impl KeyTool {
@@ -38,6 +63,12 @@ impl KeyTool {
         match self {
             KeyTool::Generate(tool) => tool.execute_serialized().await,
             KeyTool::ExtractPeer(tool) => tool.execute_serialized().await,
+            KeyTool::Derive(tool) => tool.execute_serialized().await,
+            KeyTool::Import(tool) => tool.execute_serialized().await,
+            KeyTool::GenerateMultikey(tool) => tool.execute_serialized().await,
+            KeyTool::BuildMultikeyTransaction(tool) => tool.execute_serialized().await,
+            KeyTool::SignMultikeyTransaction(tool) => tool.execute_serialized().await,
+            KeyTool::AggregateMultikeySignatures(tool) => tool.execute_serialized().await,
         }
     }
 }
@@ -105,6 +136,199 @@ impl CliCommand<HashMap<AccountAddress, Peer>> for ExtractPeer {
     }
 }
 
+/// Import an ed25519 private key from another tool's key file
+///
+/// The format of `--private-key-file` is detected from its contents: PKCS#8 PEM (e.g. from
+/// `openssl genpkey`), unencrypted OpenSSH (e.g. from `ssh-keygen -t ed25519`), a passphrase
+/// encrypted key, or a key encoded with `--encoding`.  The key is saved like
+/// `aptos key generate` saves keys
+#[derive(Debug, Parser)]
+pub struct ImportKey {
+    /// Key file to import
+    #[clap(long, parse(from_os_str))]
+    pub(crate) private_key_file: PathBuf,
+
+    #[clap(flatten)]
+    pub(crate) save_params: SaveKey,
+}
+
+#[async_trait]
+impl CliCommand<HashMap<&'static str, PathBuf>> for ImportKey {
+    fn command_name(&self) -> &'static str {
+        "ImportKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
+        self.save_params.check_key_file()?;
+        let private_key = load_ed25519_private_key_file(
+            self.save_params.encoding_options.encoding,
+            "--private-key-file",
+            &self.private_key_file,
+        )?;
+        self.save_params.save_key(&private_key, "ed25519")
+    }
+}
+
+/// List the accounts derived from a mnemonic
+///
+/// Derives the ed25519 key for each index in the `--path` range and looks up whether its
+/// account exists on chain, and its balance.  This is for finding the accounts a wallet
+/// created from the mnemonic, which can then be added as profiles with `--create-profiles`.
+#[derive(Debug, Parser)]
+pub struct DeriveKeys {
+    /// File containing the BIP-39 mnemonic
+    #[clap(long, parse(from_os_str))]
+    pub(crate) mnemonic_file: PathBuf,
+
+    /// Derivation path, with an optional inclusive range of account indices e.g. {0..9}
+    #[clap(long, default_value = "m/44'/637'/{0..9}'/0'/0'")]
+    pub(crate) path: String,
+
+    /// Indices in the `--path` range to create profiles for, e.g. 0,2
+    ///
+    /// The profiles are named `<profile-prefix><index>`, and use the network and URLs of
+    /// the profile from `--profile`
+    #[clap(long, value_delimiter = ',')]
+    pub(crate) create_profiles: Vec<u32>,
+
+    /// Prefix for the names of profiles created with `--create-profiles`
+    #[clap(long, default_value = "derived-")]
+    pub(crate) profile_prefix: String,
+
+    /// Skip looking up the accounts on chain
+    #[clap(long)]
+    pub(crate) skip_lookup: bool,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+/// An account derived from a mnemonic
+#[derive(Debug, Serialize)]
+pub struct DerivedAccount {
+    pub index: u32,
+    pub derivation_path: String,
+    pub account: AccountAddress,
+    /// Whether the account exists on chain, if it was looked up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    /// Balance in Octas, if the account exists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<u64>,
+    /// Profile created for the account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+#[async_trait]
+impl CliCommand<Vec<DerivedAccount>> for DeriveKeys {
+    fn command_name(&self) -> &'static str {
+        "DeriveKeys"
+    }
+
+    async fn execute(self) -> CliTypedResult<Vec<DerivedAccount>> {
+        let phrase = String::from_utf8(read_from_file(&self.mnemonic_file)?)
+            .map_err(|err| CliError::UnableToParse("mnemonic", err.to_string()))?;
+        let mnemonic = parse_mnemonic(&phrase)?;
+        let paths = expand_derivation_path(&self.path)?;
+        if let Some(index) = self
+            .create_profiles
+            .iter()
+            .find(|index| !paths.iter().any(|(path_index, _)| path_index == *index))
+        {
+            return Err(CliError::CommandArgumentError(format!(
+                "Index {} is not in the --path range",
+                index
+            )));
+        }
+
+        let client = if self.skip_lookup {
+            None
+        } else {
+            Some(self.rest_options.client(&self.profile_options)?)
+        };
+        let mut config = if self.create_profiles.is_empty() {
+            None
+        } else if CliConfig::config_exists(ConfigSearchMode::CurrentDir) {
+            Some(CliConfig::load(ConfigSearchMode::CurrentDir)?)
+        } else {
+            Some(CliConfig::default())
+        };
+        let base_profile = self.profile_options.profile().unwrap_or_default();
+
+        let mut accounts = Vec::new();
+        for (index, derivation_path) in paths {
+            let private_key = derive_ed25519_private_key(&mnemonic, &derivation_path)?;
+            let public_key = private_key.public_key();
+            let account = account_address_from_public_key(&public_key);
+
+            let (exists, balance) = if let Some(ref client) = client {
+                // Only a missing account or coin store means no balance, other errors are
+                // reported rather than taken as the account not existing
+                match client.get_account_balance(account).await {
+                    Ok(balance) => (Some(true), Some(balance.into_inner().get())),
+                    Err(err) if is_not_found(&err) => match client.get_account(account).await {
+                        Ok(_) => (Some(true), None),
+                        Err(err) if is_not_found(&err) => (Some(false), None),
+                        Err(err) => return Err(err.into()),
+                    },
+                    Err(err) => return Err(err.into()),
+                }
+            } else {
+                (None, None)
+            };
+
+            let profile = if let (Some(config), true) =
+                (config.as_mut(), self.create_profiles.contains(&index))
+            {
+                let profile_name = format!("{}{}", self.profile_prefix, index);
+                let mut profile_config = ProfileConfig {
+                    network: base_profile.network,
+                    rest_url: base_profile.rest_url.clone(),
+                    faucet_url: base_profile.faucet_url.clone(),
+                    public_key: Some(public_key),
+                    account: Some(account),
+                    ..Default::default()
+                };
+                profile_config.set_private_key(private_key, false)?;
+
+                let profiles = config.profiles.get_or_insert_with(BTreeMap::new);
+                if profiles.contains_key(&profile_name) {
+                    prompt_yes_with_override(
+                        &format!(
+                            "Profile {} already exists, do you want to overwrite it?",
+                            profile_name
+                        ),
+                        self.prompt_options,
+                    )?;
+                }
+                profiles.insert(profile_name.clone(), profile_config);
+                Some(profile_name)
+            } else {
+                None
+            };
+
+            accounts.push(DerivedAccount {
+                index,
+                derivation_path,
+                account,
+                exists,
+                balance,
+                profile,
+            });
+        }
+
+        if let Some(config) = config {
+            config.save()?;
+        }
+        Ok(accounts)
+    }
+}
+
 #[derive(Debug, Default, Parser)]
 pub struct NetworkKeyInputOptions {
     /// x25519 Private key input file name
@@ -167,6 +391,29 @@ pub struct GenerateKey {
     #[clap(long, default_value_t = KeyType::Ed25519)]
     pub(crate) key_type: KeyType,
 
+    /// Generate an ed25519 key whose account address starts with this hex prefix
+    ///
+    /// Keys are generated in parallel until one matches.  Each extra character makes the
+    /// search 16 times slower.  With `--random-seed`, the search runs on a single thread, so
+    /// the same seed always finds the same key
+    #[clap(long)]
+    pub(crate) vanity_prefix: Option<String>,
+
+    /// Generate a new BIP-39 mnemonic with this many words [12, 24], and derive the ed25519
+    /// key from it
+    ///
+    /// The mnemonic is saved to `output_file.mnemonic`
+    #[clap(long, conflicts_with_all = &["vanity-prefix", "mnemonic"])]
+    pub(crate) mnemonic_words: Option<usize>,
+
+    /// Derive the ed25519 key from an existing BIP-39 mnemonic
+    #[clap(long, conflicts_with = "vanity-prefix")]
+    pub(crate) mnemonic: Option<String>,
+
+    /// Derivation path for keys derived from a mnemonic
+    #[clap(long, default_value = APTOS_DERIVATION_PATH)]
+    pub(crate) derivation_path: String,
+
     #[clap(flatten)]
     pub rng_args: RngArgs,
     #[clap(flatten)]
@@ -181,6 +428,23 @@ impl CliCommand<HashMap<&'static str, PathBuf>> for GenerateKey {
 
     async fn execute(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
         self.save_params.check_key_file()?;
+        if let Some(ref prefix) = self.vanity_prefix {
+            if !matches!(self.key_type, KeyType::Ed25519) {
+                return Err(CliError::CommandArgumentError(
+                    "--vanity-prefix can only be used with ed25519 keys".to_string(),
+                ));
+            }
+            let private_key = generate_vanity_ed25519_private_key(&self.rng_args, prefix)?;
+            return self.save_params.save_key(&private_key, "ed25519");
+        }
+        if self.mnemonic_words.is_some() || self.mnemonic.is_some() {
+            if !matches!(self.key_type, KeyType::Ed25519) {
+                return Err(CliError::CommandArgumentError(
+                    "Mnemonics can only be used with ed25519 keys".to_string(),
+                ));
+            }
+            return self.generate_from_mnemonic();
+        }
         let mut keygen = self.rng_args.key_generator()?;
 
         match self.key_type {
@@ -206,6 +470,35 @@ impl CliCommand<HashMap<&'static str, PathBuf>> for GenerateKey {
 }
 
 impl GenerateKey {
+    /// Derives the key from a given or newly generated mnemonic, saving a new mnemonic
+    /// alongside the key
+    fn generate_from_mnemonic(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
+        let (mnemonic, mnemonic_file) = if let Some(ref phrase) = self.mnemonic {
+            (parse_mnemonic(phrase)?, None)
+        } else {
+            let mnemonic_file = append_file_extension(
+                self.save_params.file_options.output_file.as_path(),
+                "mnemonic",
+            )?;
+            check_if_file_exists(&mnemonic_file, self.save_params.file_options.prompt_options)?;
+            let word_count = self.mnemonic_words.expect("Checked by caller");
+            (
+                generate_mnemonic(&self.rng_args, word_count)?,
+                Some(mnemonic_file),
+            )
+        };
+        let private_key = derive_ed25519_private_key(&mnemonic, &self.derivation_path)?;
+
+        if let Some(ref mnemonic_file) = mnemonic_file {
+            write_to_user_only_file(mnemonic_file, "mnemonic", mnemonic.phrase().as_bytes())?;
+        }
+        let mut map = self.save_params.save_key(&private_key, "ed25519")?;
+        if let Some(mnemonic_file) = mnemonic_file {
+            map.insert("Mnemonic Path", mnemonic_file);
+        }
+        Ok(map)
+    }
+
     /// A test friendly typed key generation for x25519 keys.
     pub async fn generate_x25519(
         encoding: EncodingType,
@@ -339,3 +632,15 @@ impl SaveKey {
         Ok(map)
     }
 }
+
+/// Whether the REST API reported the account or resource as missing
+fn is_not_found(err: &RestError) -> bool {
+    matches!(
+        err,
+        RestError::Api(AptosErrorResponse { error, .. })
+            if matches!(
+                error.error_code,
+                AptosErrorCode::AccountNotFound | AptosErrorCode::ResourceNotFound
+            )
+    )
+}
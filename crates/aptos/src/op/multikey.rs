@@ -0,0 +1,444 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! K-of-N multi-ed25519 accounts
+//!
+//! The flow for a multikey account is:
+//! 1. `generate-multikey` combines the public keys of the group into the account's multikey
+//! 2. `build-multikey-transaction` builds an unsigned transaction for the account
+//! 3. Each signer runs `sign-multikey-transaction` with their own key
+//! 4. `aggregate-multikey-signatures` combines at least K signatures and submits the transaction
+
+use crate::{
+    common::{
+        types::{
+            CliCommand, CliError, CliTypedResult, EncodingOptions, GasOptions,
+            PrivateKeyInputOptions, ProfileOptions, PromptOptions, RestOptions, SaveFile,
+            TransactionSummary,
+        },
+        utils::{chain_id, get_sequence_number, prompt_yes_with_override, read_from_file},
+    },
+    move_tool::{ArgWithType, MemberId},
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    PrivateKey, SigningKey, ValidCryptoMaterialStringExt,
+};
+use aptos_global_constants::adjust_gas_headroom;
+use aptos_rest_client::aptos_api_types::{ExplainVMStatus, MoveType};
+use aptos_sdk::transaction_builder::TransactionFactory;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{
+        authenticator::AuthenticationKey, EntryFunction, RawTransaction, SignedTransaction,
+        TransactionPayload,
+    },
+};
+use async_trait::async_trait;
+use clap::Parser;
+use move_core_types::language_storage::TypeTag;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+};
+
+/// Default time a multikey transaction is valid for, long enough to collect signatures
+const DEFAULT_EXPIRATION_SECS: u64 = 3600;
+
+/// Combine public keys into a K-of-N multi-ed25519 key
+///
+/// The multikey is saved hex encoded to `output_file`.  Its authentication key is the
+/// address of the multikey account, which can be funded like any other address.
+#[derive(Debug, Parser)]
+pub struct GenerateMultikey {
+    /// Public keys of the group in order, encoded in the type from `--encoding`
+    ///
+    /// Example: 0x1234,0x5678
+    #[clap(long, value_delimiter = ',', required = true)]
+    pub(crate) public_keys: Vec<String>,
+
+    /// Number of signatures required to sign for the account
+    #[clap(long)]
+    pub(crate) threshold: u8,
+
+    #[clap(flatten)]
+    pub(crate) encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    pub(crate) output_file_options: SaveFile,
+}
+
+/// Summary of a multikey account
+#[derive(Debug, Serialize)]
+pub struct MultikeySummary {
+    pub account: AccountAddress,
+    pub authentication_key: String,
+    pub threshold: u8,
+    pub num_keys: usize,
+}
+
+#[async_trait]
+impl CliCommand<MultikeySummary> for GenerateMultikey {
+    fn command_name(&self) -> &'static str {
+        "GenerateMultikey"
+    }
+
+    async fn execute(self) -> CliTypedResult<MultikeySummary> {
+        self.output_file_options.check_file()?;
+        let public_keys = self
+            .public_keys
+            .iter()
+            .map(|key| {
+                self.encoding_options
+                    .encoding
+                    .decode_key::<Ed25519PublicKey>("--public-keys", key.as_bytes().to_vec())
+            })
+            .collect::<CliTypedResult<Vec<_>>>()?;
+        let num_keys = public_keys.len();
+        let multikey = MultiEd25519PublicKey::new(public_keys, self.threshold).map_err(|err| {
+            CliError::CommandArgumentError(format!(
+                "Invalid multikey, the threshold must be between 1 and the number of keys: {}",
+                err
+            ))
+        })?;
+        self.output_file_options.save_to_file(
+            "Multikey",
+            multikey
+                .to_encoded_string()
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+                .as_bytes(),
+        )?;
+
+        let authentication_key = AuthenticationKey::multi_ed25519(&multikey);
+        Ok(MultikeySummary {
+            account: authentication_key.derived_address(),
+            authentication_key: authentication_key.to_string(),
+            threshold: self.threshold,
+            num_keys,
+        })
+    }
+}
+
+/// Build an unsigned transaction for a multikey account
+///
+/// The transaction is saved hex encoded to `output_file`, to be passed to each signer
+#[derive(Debug, Parser)]
+pub struct BuildMultikeyTransaction {
+    /// File with the multikey from `generate-multikey`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) multikey_file: PathBuf,
+
+    /// Address of the multikey account
+    ///
+    /// Defaults to the address derived from the multikey, override it if the account's key
+    /// was rotated to the multikey
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    pub(crate) sender_account: Option<AccountAddress>,
+
+    /// Function name as `<ADDRESS>::<MODULE_ID>::<FUNCTION_NAME>`
+    ///
+    /// Example: `0x1::coin::transfer`
+    #[clap(long)]
+    pub(crate) function_id: MemberId,
+
+    /// Arguments combined with their type separated by spaces.
+    ///
+    /// Supported types [u8, u64, u128, bool, hex, string, address, raw]
+    ///
+    /// Example: `address:0x1 bool:true u8:0`
+    #[clap(long, multiple_values = true)]
+    pub(crate) args: Vec<ArgWithType>,
+
+    /// TypeTag arguments separated by spaces.
+    ///
+    /// Example: `u8 u64 u128 bool address vector signer`
+    #[clap(long, multiple_values = true)]
+    pub(crate) type_args: Vec<MoveType>,
+
+    /// Number of seconds the transaction is valid for
+    ///
+    /// All signatures have to be collected and the transaction submitted before then
+    #[clap(long, default_value_t = DEFAULT_EXPIRATION_SECS)]
+    pub(crate) expiration_secs: u64,
+
+    #[clap(flatten)]
+    pub(crate) gas_options: GasOptions,
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+    #[clap(flatten)]
+    pub(crate) output_file_options: SaveFile,
+}
+
+#[async_trait]
+impl CliCommand<RawTransaction> for BuildMultikeyTransaction {
+    fn command_name(&self) -> &'static str {
+        "BuildMultikeyTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<RawTransaction> {
+        self.output_file_options.check_file()?;
+        let multikey = load_multikey(&self.multikey_file)?;
+        let sender = self
+            .sender_account
+            .unwrap_or_else(|| AuthenticationKey::multi_ed25519(&multikey).derived_address());
+
+        let mut type_args = Vec::new();
+        for type_arg in self.type_args {
+            type_args.push(
+                TypeTag::try_from(type_arg)
+                    .map_err(|err| CliError::UnableToParse("--type-args", err.to_string()))?,
+            );
+        }
+        let payload = TransactionPayload::EntryFunction(EntryFunction::new(
+            self.function_id.module_id,
+            self.function_id.member_id,
+            type_args,
+            self.args.into_iter().map(|arg| arg.arg).collect(),
+        ));
+
+        let client = self.rest_options.client(&self.profile_options)?;
+        let sequence_number = get_sequence_number(&client, sender).await?;
+        let gas_unit_price = if let Some(gas_unit_price) = self.gas_options.gas_unit_price {
+            gas_unit_price
+        } else {
+            client.estimate_gas_price().await?.into_inner().gas_estimate
+        };
+        let transaction_factory = TransactionFactory::new(chain_id(&client).await?)
+            .with_gas_unit_price(gas_unit_price)
+            .with_transaction_expiration_time(self.expiration_secs);
+
+        let max_gas = if let Some(max_gas) = self.gas_options.max_gas {
+            max_gas
+        } else {
+            // Simulate with empty signatures from the first K keys to estimate the gas
+            let unsigned_transaction = transaction_factory
+                .payload(payload.clone())
+                .sender(sender)
+                .sequence_number(sequence_number)
+                .build();
+            let empty_signature = Ed25519Signature::try_from([0u8; 64].as_ref()).unwrap();
+            let signature = MultiEd25519Signature::new(
+                (0..*multikey.threshold())
+                    .map(|index| (empty_signature.clone(), index))
+                    .collect(),
+            )
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            let signed_transaction =
+                SignedTransaction::new_multisig(unsigned_transaction, multikey, signature);
+            let simulated_txn = client
+                .simulate_bcs_with_gas_estimation(&signed_transaction, true, false)
+                .await?
+                .into_inner();
+            let status = simulated_txn.info.status();
+            if !status.is_success() {
+                let status = client.explain_vm_status(status);
+                return Err(CliError::SimulationError(status));
+            }
+            adjust_gas_headroom(
+                simulated_txn.info.gas_used(),
+                simulated_txn
+                    .transaction
+                    .as_signed_user_txn()
+                    .expect("Should be signed user transaction")
+                    .max_gas_amount(),
+            )
+        };
+
+        let raw_transaction = transaction_factory
+            .with_max_gas_amount(max_gas)
+            .payload(payload)
+            .sender(sender)
+            .sequence_number(sequence_number)
+            .build();
+        save_hex_file(
+            &self.output_file_options,
+            "Raw transaction",
+            &raw_transaction,
+        )?;
+        Ok(raw_transaction)
+    }
+}
+
+/// Sign a multikey transaction with one of the group's keys
+///
+/// The signature is saved to `output_file`, to be combined with the other signers' with
+/// `aggregate-multikey-signatures`
+#[derive(Debug, Parser)]
+pub struct SignMultikeyTransaction {
+    /// File with the unsigned transaction from `build-multikey-transaction`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) raw_transaction_file: PathBuf,
+
+    #[clap(flatten)]
+    pub(crate) private_key_options: PrivateKeyInputOptions,
+    #[clap(flatten)]
+    pub(crate) encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+    #[clap(flatten)]
+    pub(crate) output_file_options: SaveFile,
+}
+
+/// A signature on a multikey transaction by one of the group's keys
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PartialSignature {
+    pub public_key: Ed25519PublicKey,
+    pub signature: Ed25519Signature,
+}
+
+#[async_trait]
+impl CliCommand<PartialSignature> for SignMultikeyTransaction {
+    fn command_name(&self) -> &'static str {
+        "SignMultikeyTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<PartialSignature> {
+        self.output_file_options.check_file()?;
+        let raw_transaction: RawTransaction =
+            load_hex_file(&self.raw_transaction_file, "raw transaction")?;
+        let private_key = self
+            .private_key_options
+            .extract_private_key(self.encoding_options.encoding, &self.profile_options)?;
+        let signature = private_key
+            .sign(&raw_transaction)
+            .map_err(|err| CliError::UnexpectedError(format!("Failed to sign: {}", err)))?;
+
+        let partial_signature = PartialSignature {
+            public_key: private_key.public_key(),
+            signature,
+        };
+        let json = serde_json::to_string_pretty(&partial_signature)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        self.output_file_options
+            .save_to_file("Signature", json.as_bytes())?;
+        Ok(partial_signature)
+    }
+}
+
+/// Combine signatures on a multikey transaction and submit it
+#[derive(Debug, Parser)]
+pub struct AggregateMultikeySignatures {
+    /// File with the multikey from `generate-multikey`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) multikey_file: PathBuf,
+
+    /// File with the unsigned transaction from `build-multikey-transaction`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) raw_transaction_file: PathBuf,
+
+    /// Files with the signatures from `sign-multikey-transaction`, at least the threshold
+    #[clap(long, value_delimiter = ',', required = true, parse(from_os_str))]
+    pub(crate) signature_files: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<TransactionSummary> for AggregateMultikeySignatures {
+    fn command_name(&self) -> &'static str {
+        "AggregateMultikeySignatures"
+    }
+
+    async fn execute(self) -> CliTypedResult<TransactionSummary> {
+        let multikey = load_multikey(&self.multikey_file)?;
+        let raw_transaction: RawTransaction =
+            load_hex_file(&self.raw_transaction_file, "raw transaction")?;
+
+        let mut signatures = Vec::new();
+        for signature_file in &self.signature_files {
+            let partial_signature: PartialSignature =
+                serde_json::from_slice(&read_from_file(signature_file)?)
+                    .map_err(|err| CliError::UnableToParse("signature file", err.to_string()))?;
+            let index = multikey
+                .public_keys()
+                .iter()
+                .position(|key| key == &partial_signature.public_key)
+                .ok_or_else(|| {
+                    CliError::CommandArgumentError(format!(
+                        "Signature in {} is from a key that isn't in the multikey",
+                        signature_file.display()
+                    ))
+                })?;
+            if signatures
+                .iter()
+                .any(|(_, existing)| *existing == index as u8)
+            {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Signature in {} is from a key that already signed",
+                    signature_file.display()
+                )));
+            }
+            signatures.push((partial_signature.signature, index as u8));
+        }
+        if signatures.len() < *multikey.threshold() as usize {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} signatures are required, but only {} were given",
+                multikey.threshold(),
+                signatures.len()
+            )));
+        }
+        let signature = MultiEd25519Signature::new(signatures)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+
+        let signed_transaction =
+            SignedTransaction::new_multisig(raw_transaction, multikey, signature);
+        signed_transaction
+            .clone()
+            .check_signature()
+            .map_err(|err| {
+                CliError::CommandArgumentError(format!(
+                    "Signatures don't match the transaction: {}",
+                    err
+                ))
+            })?;
+
+        prompt_yes_with_override(
+            &format!(
+                "Do you want to submit a transaction for a maximum of {} Octas at a gas unit price of {} Octas?",
+                signed_transaction.max_gas_amount() * signed_transaction.gas_unit_price(),
+                signed_transaction.gas_unit_price()
+            ),
+            self.prompt_options,
+        )?;
+        let client = self.rest_options.client(&self.profile_options)?;
+        client
+            .submit_and_wait(&signed_transaction)
+            .await
+            .map(|response| TransactionSummary::from(response.into_inner()))
+            .map_err(|err| CliError::ApiError(err.to_string()))
+    }
+}
+
+fn load_multikey(path: &Path) -> CliTypedResult<MultiEd25519PublicKey> {
+    let contents = String::from_utf8(read_from_file(path)?)
+        .map_err(|err| CliError::UnableToParse("multikey", err.to_string()))?;
+    MultiEd25519PublicKey::from_encoded_string(contents.trim())
+        .map_err(|err| CliError::UnableToParse("multikey", err.to_string()))
+}
+
+/// Loads a hex encoded BCS value
+fn load_hex_file<T: DeserializeOwned>(path: &Path, name: &'static str) -> CliTypedResult<T> {
+    let contents = String::from_utf8(read_from_file(path)?)
+        .map_err(|err| CliError::UnableToParse(name, err.to_string()))?;
+    let contents = contents.trim();
+    let bytes = hex::decode(contents.strip_prefix("0x").unwrap_or(contents))?;
+    bcs::from_bytes(&bytes).map_err(|err| CliError::UnableToParse(name, err.to_string()))
+}
+
+/// Saves a value as hex encoded BCS
+fn save_hex_file<T: Serialize>(
+    output_file_options: &SaveFile,
+    name: &'static str,
+    value: &T,
+) -> CliTypedResult<()> {
+    let bytes = bcs::to_bytes(value).map_err(|err| CliError::BCS(name, err))?;
+    output_file_options.save_to_file(name, hex::encode(bytes).as_bytes())
+}
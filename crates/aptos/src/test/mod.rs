@@ -10,6 +10,7 @@ use crate::account::{
     transfer::{TransferCoins, TransferSummary},
 };
 use crate::common::init::{InitTool, Network};
+use crate::common::mnemonic::APTOS_DERIVATION_PATH;
 use crate::common::types::{
     account_address_from_public_key, AccountAddressWrapper, CliError, CliTypedResult,
     EncodingOptions, FaucetOptions, GasOptions, KeyType, MoveManifestAccountWrapper,
@@ -20,10 +21,11 @@ use crate::common::types::{
 use crate::common::utils::write_to_file;
 
 use crate::governance::CompileScriptFunction;
+use crate::move_tool::test_report::TestFormat;
 use crate::move_tool::{
-    ArgWithType, CompilePackage, DownloadPackage, FrameworkPackageArgs, IncludedArtifacts,
-    IncludedArtifactsArgs, InitPackage, MemberId, PublishPackage, RunFunction, RunScript,
-    TestPackage,
+    ArgWithType, CompilePackage, DownloadPackage, EntryFunctionArguments, FrameworkPackageArgs,
+    IncludedArtifacts, IncludedArtifactsArgs, InitPackage, MemberId, PublishPackage, RunFunction,
+    RunScript, TestPackage,
 };
 use crate::node::{
     AnalyzeMode, AnalyzeValidatorPerformance, GetStakePool, InitializeValidator, JoinValidatorSet,
@@ -287,23 +289,28 @@ impl CliTestFramework {
         gas_options: Option<GasOptions>,
     ) -> CliTypedResult<TransactionSummary> {
         RunFunction {
-            function_id: MemberId {
-                module_id: ModuleId::new(
-                    AccountAddress::ONE,
-                    Identifier::from_str("coin").unwrap(),
-                ),
-                member_id: Identifier::from_str("transfer").unwrap(),
+            entry_function_args: EntryFunctionArguments {
+                function_id: MemberId {
+                    module_id: ModuleId::new(
+                        AccountAddress::ONE,
+                        Identifier::from_str("coin").unwrap(),
+                    ),
+                    member_id: Identifier::from_str("transfer").unwrap(),
+                },
+                args: vec![
+                    ArgWithType::from_str("address:0xdeadbeefcafebabe").unwrap(),
+                    ArgWithType::from_str(&format!("u64:{}", amount)).unwrap(),
+                ],
+                type_args: vec![MoveType::Struct(MoveStructTag::new(
+                    AccountAddress::ONE.into(),
+                    IdentifierWrapper::from_str("aptos_coin").unwrap(),
+                    IdentifierWrapper::from_str("AptosCoin").unwrap(),
+                    vec![],
+                ))],
+                json_args: None,
+                args_file: None,
             },
-            args: vec![
-                ArgWithType::from_str("address:0xdeadbeefcafebabe").unwrap(),
-                ArgWithType::from_str(&format!("u64:{}", amount)).unwrap(),
-            ],
-            type_args: vec![MoveType::Struct(MoveStructTag::new(
-                AccountAddress::ONE.into(),
-                IdentifierWrapper::from_str("aptos_coin").unwrap(),
-                IdentifierWrapper::from_str("AptosCoin").unwrap(),
-                vec![],
-            ))],
+            dry_run: false,
             txn_options: self.transaction_options(sender_index, gas_options),
         }
         .execute()
@@ -518,6 +525,8 @@ impl CliTestFramework {
     pub async fn init(&self, private_key: &Ed25519PrivateKey) -> CliTypedResult<()> {
         InitTool {
             network: Some(Network::Custom),
+            networks: vec![],
+            separate_keys: false,
             rest_url: Some(self.endpoint.clone()),
             faucet_url: Some(self.faucet_endpoint.clone()),
             rng_args: RngArgs::from_seed([0; 32]),
@@ -526,6 +535,15 @@ impl CliTestFramework {
             prompt_options: PromptOptions::yes(),
             encoding_options: EncodingOptions::default(),
             skip_faucet: false,
+            faucet_amount: 100000000,
+            faucet_auth_token: None,
+            encrypt_private_key: false,
+            key_store: None,
+            vanity_prefix: None,
+            mnemonic: None,
+            derivation_path: APTOS_DERIVATION_PATH.to_string(),
+            watch_address: None,
+            watch_public_key: None,
         }
         .execute()
         .await
@@ -577,16 +595,21 @@ impl CliTestFramework {
         commission_percentage: u64,
     ) -> CliTypedResult<TransactionSummary> {
         RunFunction {
-            function_id: MemberId::from_str("0x1::staking_contract::create_staking_contract")
-                .unwrap(),
-            args: vec![
-                ArgWithType::address(self.account_id(operator_index)),
-                ArgWithType::address(self.account_id(voter_index)),
-                ArgWithType::u64(amount),
-                ArgWithType::u64(commission_percentage),
-                ArgWithType::bytes(vec![]),
-            ],
-            type_args: vec![],
+            entry_function_args: EntryFunctionArguments {
+                function_id: MemberId::from_str("0x1::staking_contract::create_staking_contract")
+                    .unwrap(),
+                args: vec![
+                    ArgWithType::address(self.account_id(operator_index)),
+                    ArgWithType::address(self.account_id(voter_index)),
+                    ArgWithType::u64(amount),
+                    ArgWithType::u64(commission_percentage),
+                    ArgWithType::bytes(vec![]),
+                ],
+                type_args: vec![],
+                json_args: None,
+                args_file: None,
+            },
+            dry_run: false,
             txn_options: self.transaction_options(owner_index, None),
         }
         .execute()
@@ -703,6 +726,10 @@ impl CliTestFramework {
     ) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
         GenerateKey {
             key_type: KeyType::X25519,
+            vanity_prefix: None,
+            mnemonic_words: None,
+            mnemonic: None,
+            derivation_path: APTOS_DERIVATION_PATH.to_string(),
             rng_args: RngArgs::from_seed(seed),
             save_params: SaveKey {
                 file_options: SaveFile {
@@ -786,6 +813,7 @@ impl CliTestFramework {
             name,
             package_dir: Some(self.move_dir()),
             named_addresses: Self::move_manifest_named_addresses(account_strs),
+            existing: false,
             prompt_options: PromptOptions {
                 assume_yes: false,
                 assume_no: true,
@@ -824,6 +852,13 @@ impl CliTestFramework {
             instruction_execution_bound: 100_000,
             move_options: self.move_options(account_strs),
             filter: filter.map(|str| str.to_string()),
+            coverage: false,
+            exact: false,
+            format: TestFormat::Pretty,
+            output: None,
+            update_snapshots: false,
+            seed: None,
+            random_runs: 32,
         }
         .execute()
         .await
@@ -840,6 +875,9 @@ impl CliTestFramework {
             move_options: self.move_options(account_strs),
             txn_options: self.transaction_options(index, gas_options),
             override_size_check: false,
+            chunked_publish: false,
+            large_packages_address: None,
+            dry_run: false,
             included_artifacts_args: IncludedArtifactsArgs {
                 included_artifacts: included_artifacts.unwrap_or(IncludedArtifacts::Sparse),
             },
@@ -890,10 +928,15 @@ impl CliTestFramework {
         }
 
         RunFunction {
+            entry_function_args: EntryFunctionArguments {
+                function_id,
+                args: parsed_args,
+                json_args: None,
+                type_args: parsed_type_args,
+                args_file: None,
+            },
+            dry_run: false,
             txn_options: self.transaction_options(index, gas_options),
-            function_id,
-            args: parsed_args,
-            type_args: parsed_type_args,
         }
         .execute()
         .await
@@ -948,6 +991,8 @@ impl CliTestFramework {
             package_dir: Some(self.move_dir()),
             output_dir: None,
             named_addresses: Self::named_addresses(account_strs),
+            locked: false,
+            env: None,
         }
     }
 
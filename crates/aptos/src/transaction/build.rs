@@ -0,0 +1,137 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, TransactionOptions},
+        utils::{chain_id, check_if_file_exists, get_sequence_number, write_to_file},
+    },
+    move_tool::EntryFunctionArguments,
+};
+use aptos_global_constants::adjust_gas_headroom;
+use aptos_types::{account_address::AccountAddress, transaction::RawTransaction};
+use async_trait::async_trait;
+use clap::Parser;
+use serde::Serialize;
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Build an unsigned transaction calling a function, and write it to a file
+///
+/// This is the first step of signing offline: the transaction is built on a host with network
+/// access, signed with `aptos transaction sign` on one holding the key, e.g. an airgapped
+/// machine, and submitted with `aptos transaction submit`.
+///
+/// The profile only needs the sender's public key, so it can be a read-only profile.  Without
+/// `--max-gas`, the transaction is simulated to estimate it.  The file is the BCS of the raw
+/// transaction.
+#[derive(Parser)]
+pub struct BuildTransaction {
+    #[clap(flatten)]
+    pub(crate) entry_function_args: EntryFunctionArguments,
+
+    /// File to write the unsigned transaction to
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_file: PathBuf,
+
+    /// Sequence number of the transaction
+    ///
+    /// Defaults to the sender's current sequence number.  Give it to build several transactions
+    /// to be signed together.
+    #[clap(long)]
+    pub(crate) sequence_number: Option<u64>,
+
+    /// Seconds from now until the transaction expires
+    ///
+    /// This is the time there is to sign and submit it
+    #[clap(long, default_value_t = 600)]
+    pub(crate) expiration_secs: u64,
+
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+}
+
+/// An unsigned transaction written to a file
+#[derive(Serialize)]
+pub struct UnsignedTransaction {
+    pub file: PathBuf,
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+    pub expiration_timestamp_secs: u64,
+    pub chain_id: u8,
+}
+
+#[async_trait]
+impl CliCommand<UnsignedTransaction> for BuildTransaction {
+    fn command_name(&self) -> &'static str {
+        "BuildTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<UnsignedTransaction> {
+        let txn_options = &self.txn_options;
+        check_if_file_exists(&self.output_file, txn_options.prompt_options)?;
+        let client = txn_options
+            .rest_options
+            .client(&txn_options.profile_options)?;
+        let payload = self
+            .entry_function_args
+            .create_payload(&txn_options.rest_options, &txn_options.profile_options)
+            .await?;
+        let (_, sender) = txn_options.get_public_key_and_address()?;
+        let sequence_number = match self.sequence_number {
+            Some(sequence_number) => sequence_number,
+            None => get_sequence_number(&client, sender).await?,
+        };
+        let gas_unit_price = match txn_options.gas_options.gas_unit_price {
+            Some(gas_unit_price) => gas_unit_price,
+            None => txn_options.estimate_gas_price().await?,
+        };
+        let max_gas_amount = match txn_options.gas_options.max_gas {
+            Some(max_gas) => max_gas,
+            None => {
+                let simulated = txn_options
+                    .simulate_transaction(payload.clone(), Some(gas_unit_price), None)
+                    .await?;
+                if !simulated.info.success {
+                    return Err(CliError::SimulationError(simulated.info.vm_status));
+                }
+                adjust_gas_headroom(
+                    simulated.info.gas_used.0,
+                    simulated.request.max_gas_amount.0,
+                )
+            }
+        };
+        let expiration_timestamp_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+            .as_secs()
+            + self.expiration_secs;
+        let chain_id = chain_id(&client).await?;
+
+        let transaction = RawTransaction::new(
+            sender,
+            sequence_number,
+            payload,
+            max_gas_amount,
+            gas_unit_price,
+            expiration_timestamp_secs,
+            chain_id,
+        );
+        let bytes = bcs::to_bytes(&transaction).map_err(|err| CliError::BCS("transaction", err))?;
+        write_to_file(&self.output_file, "Unsigned transaction", &bytes)?;
+
+        Ok(UnsignedTransaction {
+            file: self.output_file,
+            sender,
+            sequence_number,
+            max_gas_amount,
+            gas_unit_price,
+            expiration_timestamp_secs,
+            chain_id: chain_id.id(),
+        })
+    }
+}
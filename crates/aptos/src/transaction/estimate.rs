@@ -0,0 +1,172 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    common::{
+        types::{CliCommand, CliError, CliTypedResult, TransactionOptions},
+        utils::read_from_file,
+    },
+    move_tool::{json_args::encode_json_args, MemberId},
+};
+use aptos_global_constants::adjust_gas_headroom;
+use aptos_rest_client::{aptos_api_types::MoveType, Client};
+use aptos_types::transaction::{EntryFunction, TransactionPayload};
+use async_trait::async_trait;
+use clap::Parser;
+use move_core_types::language_storage::TypeTag;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, str::FromStr};
+
+const OCTAS_PER_APT: u64 = 100_000_000;
+
+/// Estimate the gas unit price and gas of a transaction, and what it can cost
+///
+/// Gas unit prices are the node's estimates for three priorities: `low` for transactions that
+/// can wait, `median` for the current price, and `aggressive` to be prioritized.  The gas is
+/// estimated by simulating the transaction, with headroom as when submitting it.  The maximum
+/// fee for each priority is the maximum gas at its price.
+///
+/// The payload file is an entry function payload as the REST API shows it:
+///
+/// `{"function": "0x1::coin::transfer", "type_arguments": ["0x1::aptos_coin::AptosCoin"],
+/// "arguments": ["0xcafe", "100"]}`
+#[derive(Parser)]
+pub struct EstimateGas {
+    /// JSON file of the entry function payload
+    #[clap(long, parse(from_os_str))]
+    pub(crate) payload: PathBuf,
+
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+}
+
+/// An entry function payload, as in the REST API's JSON
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PayloadFile {
+    /// Optional, for payloads copied from the API, where it's `entry_function_payload`
+    #[serde(rename = "type")]
+    typ: Option<String>,
+    function: String,
+    #[serde(default)]
+    type_arguments: Vec<String>,
+    /// Arguments typed by the function's ABI, as for `aptos move run --json-args`
+    #[serde(default)]
+    arguments: Vec<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+pub struct GasEstimate {
+    /// Gas used by the simulation
+    pub gas_used: u64,
+    /// Gas used with headroom, as `--max-gas` would be set when submitting
+    pub max_gas: u64,
+    pub low: PriorityEstimate,
+    pub median: PriorityEstimate,
+    pub aggressive: PriorityEstimate,
+}
+
+#[derive(Serialize)]
+pub struct PriorityEstimate {
+    pub gas_unit_price: u64,
+    pub max_fee_octas: u64,
+    pub max_fee_apt: String,
+}
+
+#[async_trait]
+impl CliCommand<GasEstimate> for EstimateGas {
+    fn command_name(&self) -> &'static str {
+        "EstimateGas"
+    }
+
+    async fn execute(self) -> CliTypedResult<GasEstimate> {
+        let txn_options = &self.txn_options;
+        let client = txn_options
+            .rest_options
+            .client(&txn_options.profile_options)?;
+        let payload = self.load_payload(&client).await?;
+
+        let prices = client
+            .estimate_gas_price()
+            .await
+            .map_err(|err| CliError::ApiError(err.to_string()))?
+            .into_inner();
+        let median = prices.gas_estimate;
+        let low = prices.deprioritized_gas_estimate.unwrap_or(median);
+        let aggressive = prices.prioritized_gas_estimate.unwrap_or(median);
+
+        let simulated = txn_options
+            .simulate_transaction(payload, Some(median), None)
+            .await?;
+        if !simulated.info.success {
+            return Err(CliError::SimulationError(simulated.info.vm_status));
+        }
+        let gas_used = simulated.info.gas_used.0;
+        let max_gas = adjust_gas_headroom(gas_used, simulated.request.max_gas_amount.0);
+
+        let estimate = |gas_unit_price: u64| {
+            let max_fee_octas = max_gas.saturating_mul(gas_unit_price);
+            PriorityEstimate {
+                gas_unit_price,
+                max_fee_octas,
+                max_fee_apt: octas_to_apt(max_fee_octas),
+            }
+        };
+        Ok(GasEstimate {
+            gas_used,
+            max_gas,
+            low: estimate(low),
+            median: estimate(median),
+            aggressive: estimate(aggressive),
+        })
+    }
+}
+
+impl EstimateGas {
+    /// Reads the payload, encoding its arguments with the function's ABI
+    async fn load_payload(&self, client: &Client) -> CliTypedResult<TransactionPayload> {
+        let file: PayloadFile = serde_json::from_slice(&read_from_file(&self.payload)?)
+            .map_err(|err| CliError::UnableToParse("--payload", err.to_string()))?;
+        if let Some(typ) = &file.typ {
+            if typ != "entry_function_payload" {
+                return Err(CliError::UnableToParse(
+                    "--payload",
+                    format!("only entry function payloads are supported, not {}", typ),
+                ));
+            }
+        }
+        let function = MemberId::from_str(&file.function)?;
+        let mut move_type_args = vec![];
+        let mut type_args = vec![];
+        for type_arg in &file.type_arguments {
+            let move_type = MoveType::from_str(type_arg)
+                .map_err(|err| CliError::UnableToParse("type_arguments", err.to_string()))?;
+            type_args.push(
+                TypeTag::try_from(move_type.clone())
+                    .map_err(|err| CliError::UnableToParse("type_arguments", err.to_string()))?,
+            );
+            move_type_args.push(move_type);
+        }
+        let arguments = serde_json::to_string(&file.arguments)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        let args = encode_json_args(client, &function, &move_type_args, &arguments).await?;
+        Ok(TransactionPayload::EntryFunction(EntryFunction::new(
+            function.module_id,
+            function.member_id,
+            type_args,
+            args,
+        )))
+    }
+}
+
+/// Octas as APT, e.g. `0.0015` for 150000
+pub(crate) fn octas_to_apt(octas: u64) -> String {
+    let whole = octas / OCTAS_PER_APT;
+    let fraction = format!("{:08}", octas % OCTAS_PER_APT);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::types::{CliCommand, CliResult};
+use clap::Subcommand;
+
+pub mod build;
+pub mod estimate;
+pub mod replay;
+pub mod sign;
+pub mod submit;
+
+/// Tool for building, estimating, signing offline, submitting, and replaying transactions
+#[derive(Subcommand)]
+pub enum TransactionTool {
+    Build(build::BuildTransaction),
+    Estimate(estimate::EstimateGas),
+    Replay(replay::ReplayTransaction),
+    Sign(sign::SignTransaction),
+    Submit(submit::SubmitTransaction),
+}
+
+impl TransactionTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            TransactionTool::Build(tool) => tool.execute_serialized().await,
+            TransactionTool::Estimate(tool) => tool.execute_serialized().await,
+            TransactionTool::Replay(tool) => tool.execute_serialized().await,
+            TransactionTool::Sign(tool) => tool.execute_serialized().await,
+            TransactionTool::Submit(tool) => tool.execute_serialized().await,
+        }
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::watch::describe_payload,
+    common::types::{CliCommand, CliError, CliTypedResult, ProfileOptions, RestOptions},
+};
+use aptos_crypto::HashValue;
+use aptos_debugger::AptosDebugger;
+use aptos_rest_client::{
+    aptos_api_types::{ExplainVMStatus, TransactionData},
+    Transaction,
+};
+use aptos_types::transaction::TransactionStatus;
+use async_trait::async_trait;
+use clap::Parser;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Re-execute a committed transaction locally, against the state before it
+///
+/// The state is read from the node at the transaction's version, and the transaction is run by
+/// the VM of this CLI.  The output is compared with what was committed: the status, the gas
+/// used, the events, and the write set.  This shows whether an abort or a gas anomaly
+/// reproduces, e.g. with the VM of a newer release.
+///
+/// The trace has the transaction's call, with its status and gas.  Events and writes are
+/// decoded as committed when the replay reproduces them, and shown raw otherwise.
+#[derive(Parser)]
+pub struct ReplayTransaction {
+    /// Version or hash of the transaction
+    pub(crate) transaction: TransactionRef,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+/// A committed transaction, by version or hash
+#[derive(Clone, Copy, Debug)]
+pub enum TransactionRef {
+    Version(u64),
+    Hash(HashValue),
+}
+
+impl FromStr for TransactionRef {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(version) = u64::from_str(s) {
+            return Ok(TransactionRef::Version(version));
+        }
+        HashValue::from_hex(s.trim_start_matches("0x"))
+            .map(TransactionRef::Hash)
+            .map_err(|_| {
+                CliError::CommandArgumentError(format!(
+                    "{} is neither a transaction version nor a transaction hash",
+                    s
+                ))
+            })
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReplaySummary {
+    pub version: u64,
+    pub hash: String,
+    /// Whether the replay reproduced the committed status, gas, events, and write set
+    pub reproduced: bool,
+    /// How the replay differs from what was committed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub differences: Vec<String>,
+    pub trace: Vec<Frame>,
+    pub events: Vec<serde_json::Value>,
+    pub write_set: Vec<serde_json::Value>,
+}
+
+/// A call of the transaction
+#[derive(Serialize)]
+pub struct Frame {
+    pub call: String,
+    pub status: String,
+    pub gas_used: u64,
+}
+
+#[async_trait]
+impl CliCommand<ReplaySummary> for ReplayTransaction {
+    fn command_name(&self) -> &'static str {
+        "ReplayTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<ReplaySummary> {
+        let client = self.rest_options.client(&self.profile_options)?;
+        let committed = match self.transaction {
+            TransactionRef::Version(version) => client.get_transaction_by_version(version).await?,
+            TransactionRef::Hash(hash) => client.get_transaction_by_hash(hash).await?,
+        }
+        .into_inner();
+        let version = committed.version().ok_or_else(|| {
+            CliError::CommandArgumentError("The transaction is still pending".to_string())
+        })?;
+        let committed_data = match client
+            .get_transaction_by_version_bcs(version)
+            .await?
+            .into_inner()
+        {
+            TransactionData::OnChain(data) => data,
+            TransactionData::Pending(_) => {
+                return Err(CliError::UnexpectedError(format!(
+                    "Transaction {} is committed, but the node returned it as pending",
+                    version
+                )))
+            }
+        };
+
+        let debugger = AptosDebugger::rest_client(client.clone())
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        let transaction = committed_data.transaction.clone();
+        // State is fetched from the node as the VM reads it, blocking this thread
+        let output = tokio::task::block_in_place(|| {
+            debugger.execute_transactions_at_version(version, vec![transaction])
+        })
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to replay: {:#}", err)))?
+        .pop()
+        .ok_or_else(|| CliError::UnexpectedError("The replay had no output".to_string()))?;
+
+        let committed_status = committed_data.info.status();
+        let status = match output.status() {
+            TransactionStatus::Keep(status) => client.explain_vm_status(status),
+            TransactionStatus::Discard(status) => format!("Discarded: {:?}", status),
+            TransactionStatus::Retry => "Retry".to_string(),
+        };
+        let mut differences = vec![];
+        if output.status() != &TransactionStatus::Keep(committed_status.clone()) {
+            differences.push(format!(
+                "status: {} committed, {} replayed",
+                client.explain_vm_status(committed_status),
+                status
+            ));
+        }
+        if output.gas_used() != committed_data.info.gas_used() {
+            differences.push(format!(
+                "gas used: {} committed, {} replayed",
+                committed_data.info.gas_used(),
+                output.gas_used()
+            ));
+        }
+        let same_events = output.events() == committed_data.events.as_slice();
+        if !same_events {
+            differences.push(format!(
+                "events: {} committed, {} replayed, which differ",
+                committed_data.events.len(),
+                output.events().len()
+            ));
+        }
+        let same_write_set = output.write_set() == &committed_data.changes;
+        if !same_write_set {
+            differences.push("write set: the replayed writes differ".to_string());
+        }
+
+        let (call, committed_events, committed_changes) = match &committed {
+            Transaction::UserTransaction(transaction) => (
+                describe_payload(&transaction.request.payload),
+                to_values(&transaction.events)?,
+                to_values(&transaction.info.changes)?,
+            ),
+            Transaction::GenesisTransaction(transaction) => (
+                committed.type_str().to_string(),
+                to_values(&transaction.events)?,
+                to_values(&transaction.info.changes)?,
+            ),
+            Transaction::BlockMetadataTransaction(transaction) => (
+                committed.type_str().to_string(),
+                to_values(&transaction.events)?,
+                to_values(&transaction.info.changes)?,
+            ),
+            transaction => (
+                transaction.type_str().to_string(),
+                vec![],
+                to_values(
+                    &transaction
+                        .transaction_info()
+                        .map(|info| &info.changes)
+                        .ok(),
+                )?,
+            ),
+        };
+        let events = if same_events {
+            committed_events
+        } else {
+            output
+                .events()
+                .iter()
+                .map(|event| {
+                    serde_json::json!({
+                        "type": event.type_tag().to_string(),
+                        "data": hex::encode(event.event_data()),
+                    })
+                })
+                .collect()
+        };
+        let write_set = if same_write_set {
+            committed_changes
+        } else {
+            output
+                .write_set()
+                .iter()
+                .map(|(key, op)| {
+                    serde_json::json!({
+                        "key": format!("{:?}", key),
+                        "deleted": op.is_deletion(),
+                    })
+                })
+                .collect()
+        };
+
+        Ok(ReplaySummary {
+            version,
+            hash: committed_data.info.transaction_hash().to_hex_literal(),
+            reproduced: differences.is_empty(),
+            differences,
+            trace: vec![Frame {
+                call,
+                status,
+                gas_used: output.gas_used(),
+            }],
+            events,
+            write_set,
+        })
+    }
+}
+
+/// The committed events or changes as JSON, as the node shows them
+fn to_values<T: Serialize>(items: &T) -> CliTypedResult<Vec<serde_json::Value>> {
+    match serde_json::to_value(items) {
+        Ok(serde_json::Value::Array(values)) => Ok(values),
+        Ok(serde_json::Value::Null) => Ok(vec![]),
+        Ok(value) => Ok(vec![value]),
+        Err(err) => Err(CliError::UnexpectedError(err.to_string())),
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::{
+    types::{
+        CliCommand, CliError, CliTypedResult, EncodingOptions, PrivateKeyInputOptions,
+        ProfileOptions, PromptOptions,
+    },
+    utils::{check_if_file_exists, prompt_yes_with_override, read_from_file, write_to_file},
+};
+use aptos_crypto::PrivateKey;
+use aptos_types::{account_address::AccountAddress, transaction::RawTransaction};
+use async_trait::async_trait;
+use clap::Parser;
+use serde::Serialize;
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Sign a transaction built by `aptos transaction build`
+///
+/// Nothing is read from the network, so this can be run on an airgapped machine.  The
+/// transaction is shown for review before it's signed.  The signed transaction is written as
+/// BCS, for `aptos transaction submit`.
+#[derive(Parser)]
+pub struct SignTransaction {
+    /// File of the unsigned transaction
+    #[clap(long, parse(from_os_str))]
+    pub(crate) file: PathBuf,
+
+    /// File to write the signed transaction to
+    ///
+    /// Defaults to the unsigned transaction's file, with the extension `signed`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) private_key_options: PrivateKeyInputOptions,
+    #[clap(flatten)]
+    pub(crate) encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+    #[clap(flatten)]
+    pub(crate) prompt_options: PromptOptions,
+}
+
+/// A signed transaction written to a file
+#[derive(Serialize)]
+pub struct SignedTransactionFile {
+    pub file: PathBuf,
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    /// Hash the transaction will have once submitted
+    pub transaction_hash: String,
+}
+
+#[async_trait]
+impl CliCommand<SignedTransactionFile> for SignTransaction {
+    fn command_name(&self) -> &'static str {
+        "SignTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<SignedTransactionFile> {
+        let output_file = self
+            .output_file
+            .clone()
+            .unwrap_or_else(|| self.file.with_extension("signed"));
+        let transaction: RawTransaction = bcs::from_bytes(&read_from_file(&self.file)?)
+            .map_err(|err| CliError::UnableToParse("--file", err.to_string()))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+            .as_secs();
+        let (private_key, address) = self.private_key_options.extract_private_key_and_address(
+            self.encoding_options.encoding,
+            &self.profile_options,
+            None,
+        )?;
+
+        eprintln!(
+            "{}",
+            transaction.format_for_client(|_| "script".to_string())
+        );
+        // Signed before review only to read the expiration, it's written once confirmed
+        let signed = transaction
+            .clone()
+            .sign(&private_key, private_key.public_key())
+            .map_err(|err| CliError::UnexpectedError(format!("Failed to sign: {}", err)))?
+            .into_inner();
+        if signed.expiration_timestamp_secs() <= now {
+            return Err(CliError::CommandArgumentError(
+                "The transaction has expired, build it again".to_string(),
+            ));
+        }
+        if transaction.sender() != address {
+            prompt_yes_with_override(
+                &format!(
+                    "The key is for account {}, but the transaction is sent by {}, which only \
+                     works if the account's key was rotated to it.  Sign anyway?",
+                    address,
+                    transaction.sender()
+                ),
+                self.prompt_options,
+            )?;
+        }
+        prompt_yes_with_override("Do you want to sign this transaction?", self.prompt_options)?;
+
+        check_if_file_exists(&output_file, self.prompt_options)?;
+        let bytes = bcs::to_bytes(&signed).map_err(|err| CliError::BCS("transaction", err))?;
+        write_to_file(&output_file, "Signed transaction", &bytes)?;
+
+        Ok(SignedTransactionFile {
+            file: output_file,
+            sender: signed.sender(),
+            sequence_number: signed.sequence_number(),
+            transaction_hash: signed.committed_hash().to_hex_literal(),
+        })
+    }
+}
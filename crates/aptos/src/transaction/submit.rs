@@ -0,0 +1,55 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::{
+    types::{
+        CliCommand, CliError, CliTypedResult, ProfileOptions, RestOptions, TransactionSummary,
+    },
+    utils::read_from_file,
+};
+use aptos_types::transaction::SignedTransaction;
+use async_trait::async_trait;
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Submit a transaction signed by `aptos transaction sign`, and wait for it to be committed
+#[derive(Parser)]
+pub struct SubmitTransaction {
+    /// File of the signed transaction
+    #[clap(long, parse(from_os_str))]
+    pub(crate) file: PathBuf,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+#[async_trait]
+impl CliCommand<TransactionSummary> for SubmitTransaction {
+    fn command_name(&self) -> &'static str {
+        "SubmitTransaction"
+    }
+
+    async fn execute(self) -> CliTypedResult<TransactionSummary> {
+        let transaction: SignedTransaction = bcs::from_bytes(&read_from_file(&self.file)?)
+            .map_err(|err| CliError::UnableToParse("--file", err.to_string()))?;
+        // Caught here rather than by the node, which only says the signature is invalid
+        let transaction = transaction
+            .check_signature()
+            .map_err(|err| {
+                CliError::CommandArgumentError(format!(
+                    "The transaction's signature is invalid: {}",
+                    err
+                ))
+            })?
+            .into_inner();
+
+        let client = self.rest_options.client(&self.profile_options)?;
+        client
+            .submit_and_wait(&transaction)
+            .await
+            .map(|response| TransactionSummary::from(response.into_inner()))
+            .map_err(|err| CliError::ApiError(err.to_string()))
+    }
+}
@@ -0,0 +1,400 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Converting values of Move types between JSON and BCS
+//!
+//! Values are in JSON as the REST API shows them: `u64` and `u128` as strings, `vector<u8>` as
+//! hex, and `0x1::string::String` as a string.  Structs are objects of their fields, with the
+//! fields' types from the ABI of the struct's module.  An `0x1::option::Option` can also be given
+//! as `null` or its value, and an `0x1::object::Object` as its address.
+
+use crate::{
+    account::resources::Abis,
+    common::types::{
+        CliCommand, CliError, CliResult, CliTypedResult, MovePackageDir, ProfileOptions,
+        RestOptions,
+    },
+    move_tool::IncludedArtifacts,
+};
+use aptos_rest_client::aptos_api_types::{MoveModule, MoveStructTag, MoveType};
+use aptos_types::account_address::AccountAddress;
+use async_trait::async_trait;
+use clap::{Parser, Subcommand};
+use framework::BuiltPackage;
+
+/// Convert values of Move types to and from BCS
+#[derive(Subcommand)]
+pub enum BcsTool {
+    Decode(DecodeBcs),
+    Encode(EncodeBcs),
+}
+
+impl BcsTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            BcsTool::Decode(tool) => tool.execute_serialized().await,
+            BcsTool::Encode(tool) => tool.execute_serialized().await,
+        }
+    }
+}
+
+/// The type of the value, and where to find the structs in it
+#[derive(Parser)]
+pub struct BcsTypeArgs {
+    /// Move type of the value
+    ///
+    /// Example: `vector<address>` or `0x1::coin::Coin<0x1::aptos_coin::AptosCoin>`
+    #[clap(long = "type")]
+    pub(crate) typ: MoveType,
+
+    /// Structs are looked up in the package given with `--package-dir`, and on chain otherwise
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+impl BcsTypeArgs {
+    async fn layout(&self) -> CliTypedResult<Layout> {
+        let mut abis = Abis::default();
+        if self.move_options.package_dir.is_some() {
+            let options =
+                IncludedArtifacts::None.build_options(self.move_options.named_addresses());
+            let package = BuiltPackage::build(self.move_options.get_package_path()?, options)?;
+            for module in package.modules() {
+                abis.insert(MoveModule::from(module.clone()));
+            }
+        }
+
+        let mut structs = vec![];
+        layout_structs(&self.typ, &mut structs);
+        if !structs.is_empty() {
+            let client = self.rest_options.client(&self.profile_options)?;
+            abis.load(&client, structs).await?;
+        }
+        Layout::new(&abis, &self.typ)
+    }
+}
+
+/// Encode a JSON value as BCS, printed as hex
+#[derive(Parser)]
+pub struct EncodeBcs {
+    /// The value as JSON, e.g. `{"value": "100"}`, or a string without quotes
+    #[clap(long)]
+    pub(crate) value: String,
+
+    #[clap(flatten)]
+    pub(crate) type_args: BcsTypeArgs,
+}
+
+#[async_trait]
+impl CliCommand<String> for EncodeBcs {
+    fn command_name(&self) -> &'static str {
+        "EncodeBcs"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        let layout = self.type_args.layout().await?;
+        let value =
+            serde_json::from_str(&self.value).unwrap_or(serde_json::Value::String(self.value));
+        let mut bytes = vec![];
+        layout
+            .encode(&value, &mut bytes)
+            .map_err(|err| CliError::UnableToParse("value", err))?;
+        Ok(format!("0x{}", hex::encode(bytes)))
+    }
+}
+
+/// Decode BCS, given as hex, to a JSON value
+#[derive(Parser)]
+pub struct DecodeBcs {
+    /// The BCS bytes as hex, e.g. `0x6400000000000000`
+    #[clap(long)]
+    pub(crate) bytes: String,
+
+    #[clap(flatten)]
+    pub(crate) type_args: BcsTypeArgs,
+}
+
+#[async_trait]
+impl CliCommand<serde_json::Value> for DecodeBcs {
+    fn command_name(&self) -> &'static str {
+        "DecodeBcs"
+    }
+
+    async fn execute(self) -> CliTypedResult<serde_json::Value> {
+        let layout = self.type_args.layout().await?;
+        let bytes = hex::decode(self.bytes.trim_start_matches("0x"))
+            .map_err(|err| CliError::UnableToParse("bytes", err.to_string()))?;
+        let mut input = bytes.as_slice();
+        let value = layout
+            .decode(&mut input)
+            .map_err(|err| CliError::UnableToParse("bytes", err))?;
+        if !input.is_empty() {
+            return Err(CliError::UnableToParse(
+                "bytes",
+                format!("{} bytes left over after the value", input.len()),
+            ));
+        }
+        Ok(value)
+    }
+}
+
+/// How a value of a type is laid out in BCS
+#[derive(Debug)]
+pub(crate) enum Layout {
+    Bool,
+    U8,
+    U64,
+    U128,
+    Address,
+    Vector(Box<Layout>),
+    /// `0x1::string::String`, its bytes as a string
+    String,
+    /// `0x1::option::Option`, a vector of at most one value
+    Option(Box<Layout>),
+    /// `0x1::object::Object`, its address
+    Object,
+    Struct(Vec<(String, Layout)>),
+}
+
+impl Layout {
+    pub(crate) fn new(abis: &Abis, typ: &MoveType) -> CliTypedResult<Layout> {
+        Ok(match typ {
+            MoveType::Bool => Layout::Bool,
+            MoveType::U8 => Layout::U8,
+            MoveType::U64 => Layout::U64,
+            MoveType::U128 => Layout::U128,
+            MoveType::Address | MoveType::Signer => Layout::Address,
+            MoveType::Vector { items } => Layout::Vector(Box::new(Layout::new(abis, items)?)),
+            MoveType::Struct(tag) if is_framework_struct(tag, "string", "String") => Layout::String,
+            MoveType::Struct(tag) if is_framework_struct(tag, "option", "Option") => {
+                let value = tag.generic_type_params.first().ok_or_else(|| {
+                    CliError::UnableToParse("type", format!("{} has no type argument", tag))
+                })?;
+                Layout::Option(Box::new(Layout::new(abis, value)?))
+            }
+            MoveType::Struct(tag) if is_framework_struct(tag, "object", "Object") => Layout::Object,
+            MoveType::Struct(tag) => {
+                let fields = abis.fields(tag).ok_or_else(|| {
+                    CliError::UnableToParse("type", format!("No struct {} found", tag))
+                })?;
+                let mut layouts = vec![];
+                for (name, typ) in fields {
+                    layouts.push((name, Layout::new(abis, &typ)?));
+                }
+                Layout::Struct(layouts)
+            }
+            MoveType::GenericTypeParam { .. }
+            | MoveType::Reference { .. }
+            | MoveType::Unparsable(_) => {
+                return Err(CliError::UnableToParse(
+                    "type",
+                    format!("{} has no values", typ),
+                ))
+            }
+        })
+    }
+
+    pub(crate) fn encode(
+        &self,
+        value: &serde_json::Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        let unexpected = || format!("Expected a value for {:?}, got {}", self, value);
+        match self {
+            Layout::Bool => out.push(value.as_bool().ok_or_else(unexpected)? as u8),
+            Layout::U8 => out.push(number(value).ok_or_else(unexpected)?),
+            Layout::U64 => {
+                let number: u64 = number(value).ok_or_else(unexpected)?;
+                out.extend(number.to_le_bytes())
+            }
+            Layout::U128 => {
+                let number: u128 = number(value).ok_or_else(unexpected)?;
+                out.extend(number.to_le_bytes())
+            }
+            Layout::Address => {
+                let address = value
+                    .as_str()
+                    .and_then(|address| AccountAddress::from_hex_literal(address).ok())
+                    .ok_or_else(unexpected)?;
+                out.extend(address.to_vec());
+            }
+            Layout::Object => Layout::Address.encode(value.get("inner").unwrap_or(value), out)?,
+            Layout::Vector(items) => match (items.as_ref(), value) {
+                (Layout::U8, serde_json::Value::String(hex)) => {
+                    let bytes =
+                        hex::decode(hex.trim_start_matches("0x")).map_err(|err| err.to_string())?;
+                    write_length(bytes.len(), out);
+                    out.extend(bytes);
+                }
+                (_, serde_json::Value::Array(values)) => {
+                    write_length(values.len(), out);
+                    for value in values {
+                        items.encode(value, out)?;
+                    }
+                }
+                _ => return Err(unexpected()),
+            },
+            Layout::String => {
+                let string = value.as_str().ok_or_else(unexpected)?;
+                write_length(string.len(), out);
+                out.extend(string.as_bytes());
+            }
+            Layout::Option(inner) => match value {
+                serde_json::Value::Null => write_length(0, out),
+                serde_json::Value::Object(object)
+                    if object.len() == 1 && object.contains_key("vec") =>
+                {
+                    let values = object["vec"].as_array().ok_or_else(unexpected)?;
+                    if values.len() > 1 {
+                        return Err(unexpected());
+                    }
+                    write_length(values.len(), out);
+                    for value in values {
+                        inner.encode(value, out)?;
+                    }
+                }
+                _ => {
+                    write_length(1, out);
+                    inner.encode(value, out)?;
+                }
+            },
+            Layout::Struct(fields) => {
+                let object = value.as_object().ok_or_else(unexpected)?;
+                for (name, layout) in fields {
+                    let field = object
+                        .get(name)
+                        .ok_or_else(|| format!("Missing field {} in {}", name, value))?;
+                    layout.encode(field, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn decode(&self, input: &mut &[u8]) -> Result<serde_json::Value, String> {
+        Ok(match self {
+            Layout::Bool => match take(input, 1)? {
+                [0] => false.into(),
+                [1] => true.into(),
+                _ => return Err("Invalid bool".to_string()),
+            },
+            Layout::U8 => take(input, 1)?[0].into(),
+            Layout::U64 => {
+                let bytes = take(input, 8)?.try_into().expect("8 bytes");
+                u64::from_le_bytes(bytes).to_string().into()
+            }
+            Layout::U128 => {
+                let bytes = take(input, 16)?.try_into().expect("16 bytes");
+                u128::from_le_bytes(bytes).to_string().into()
+            }
+            Layout::Address => {
+                let bytes = take(input, AccountAddress::LENGTH)?;
+                AccountAddress::from_bytes(bytes)
+                    .map_err(|err| err.to_string())?
+                    .to_hex_literal()
+                    .into()
+            }
+            Layout::Vector(items) => {
+                let length = read_length(input)?;
+                if let Layout::U8 = items.as_ref() {
+                    format!("0x{}", hex::encode(take(input, length)?)).into()
+                } else {
+                    let mut values = vec![];
+                    for _ in 0..length {
+                        values.push(items.decode(input)?);
+                    }
+                    values.into()
+                }
+            }
+            Layout::String => {
+                let length = read_length(input)?;
+                String::from_utf8(take(input, length)?.to_vec())
+                    .map_err(|err| err.to_string())?
+                    .into()
+            }
+            // As the REST API shows them
+            Layout::Option(inner) => {
+                let values = match read_length(input)? {
+                    0 => vec![],
+                    1 => vec![inner.decode(input)?],
+                    _ => return Err("Invalid option".to_string()),
+                };
+                serde_json::json!({ "vec": values })
+            }
+            Layout::Object => serde_json::json!({ "inner": Layout::Address.decode(input)? }),
+            Layout::Struct(fields) => {
+                let mut object = serde_json::Map::new();
+                for (name, layout) in fields {
+                    object.insert(name.clone(), layout.decode(input)?);
+                }
+                object.into()
+            }
+        })
+    }
+}
+
+fn is_framework_struct(tag: &MoveStructTag, module: &str, name: &str) -> bool {
+    AccountAddress::from(tag.address) == AccountAddress::ONE
+        && tag.module.as_str() == module
+        && tag.name.as_str() == name
+}
+
+/// The structs whose ABIs are needed for the layout of a type
+pub(crate) fn layout_structs(typ: &MoveType, structs: &mut Vec<MoveStructTag>) {
+    match typ {
+        MoveType::Vector { items } => layout_structs(items, structs),
+        MoveType::Struct(tag)
+            if is_framework_struct(tag, "string", "String")
+                || is_framework_struct(tag, "object", "Object") => {}
+        MoveType::Struct(tag) if is_framework_struct(tag, "option", "Option") => {
+            for typ in &tag.generic_type_params {
+                layout_structs(typ, structs);
+            }
+        }
+        MoveType::Struct(tag) => structs.push(tag.clone()),
+        _ => {}
+    }
+}
+
+/// A number, given as a JSON number or a string
+fn number<T: TryFrom<u64> + std::str::FromStr>(value: &serde_json::Value) -> Option<T> {
+    match value {
+        serde_json::Value::Number(number) => T::try_from(number.as_u64()?).ok(),
+        serde_json::Value::String(string) => string.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Writes a vector's length, as ULEB128
+fn write_length(mut length: usize, out: &mut Vec<u8>) {
+    while length >= 0x80 {
+        out.push((length as u8 & 0x7f) | 0x80);
+        length >>= 7;
+    }
+    out.push(length as u8);
+}
+
+fn read_length(input: &mut &[u8]) -> Result<usize, String> {
+    let mut length = 0usize;
+    for shift in (0..32).step_by(7) {
+        let byte = take(input, 1)?[0];
+        length |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(length);
+        }
+    }
+    Err("Invalid vector length".to_string())
+}
+
+fn take<'a>(input: &mut &'a [u8], count: usize) -> Result<&'a [u8], String> {
+    if input.len() < count {
+        return Err("Unexpected end of bytes".to_string());
+    }
+    let (taken, rest) = input.split_at(count);
+    *input = rest;
+    Ok(taken)
+}
@@ -0,0 +1,22 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::types::CliResult;
+use clap::Subcommand;
+
+pub mod bcs_tool;
+
+/// Tool for utilities, like converting values to and from BCS
+#[derive(Subcommand)]
+pub enum UtilTool {
+    #[clap(subcommand)]
+    Bcs(bcs_tool::BcsTool),
+}
+
+impl UtilTool {
+    pub async fn execute(self) -> CliResult {
+        match self {
+            UtilTool::Bcs(tool) => tool.execute().await,
+        }
+    }
+}